@@ -1,47 +1,101 @@
 complete -c larashell -n "__fish_use_subcommand" -l embed -d 'X11 window ID to embed LaraShell within (decimal or hexadecimal with "0x" prefix)' -r
 complete -c larashell -n "__fish_use_subcommand" -l config-file -d 'Specify alternative configuration file [default: $XDG_CONFIG_HOME/larashell/larashell.toml]' -r -F
 complete -c larashell -n "__fish_use_subcommand" -l socket -d 'Path for IPC socket creation' -r -F
+complete -c larashell -n "__fish_use_subcommand" -l restore-session -d 'Restore windows from a saved session [default: most recent session]' -r -F
 complete -c larashell -n "__fish_use_subcommand" -l working-directory -d 'Start the shell in the specified working directory' -r -F
 complete -c larashell -n "__fish_use_subcommand" -s e -l command -d 'Command and args to execute (must be last argument)' -r
 complete -c larashell -n "__fish_use_subcommand" -s T -l title -d 'Defines the window title [default: LaraShell]' -r
 complete -c larashell -n "__fish_use_subcommand" -l class -d 'Defines window class/app_id on X11/Wayland [default: LaraShell]' -r
+complete -c larashell -n "__fish_use_subcommand" -l group -d 'Group for broadcasting input between windows [default: window class]' -r
+complete -c larashell -n "__fish_use_subcommand" -l attach -d 'Attach to a session of `larashell server`, creating it if necessary' -r
 complete -c larashell -n "__fish_use_subcommand" -s o -l option -d 'Override configuration file options [example: \'cursor.style="Beam"\']' -r
 complete -c larashell -n "__fish_use_subcommand" -l print-events -d 'Print all events to STDOUT'
 complete -c larashell -n "__fish_use_subcommand" -l ref-test -d 'Generates ref test'
 complete -c larashell -n "__fish_use_subcommand" -s q -d 'Reduces the level of verbosity (the min level is -qq)'
 complete -c larashell -n "__fish_use_subcommand" -s v -d 'Increases the level of verbosity (the max level is -vvv)'
 complete -c larashell -n "__fish_use_subcommand" -l hold -d 'Remain open after child process exit'
+complete -c larashell -n "__fish_use_subcommand" -l tab -d 'Open a tab in the focused window instead of a new window'
 complete -c larashell -n "__fish_use_subcommand" -s h -l help -d 'Print help'
 complete -c larashell -n "__fish_use_subcommand" -s V -l version -d 'Print version'
 complete -c larashell -n "__fish_use_subcommand" -f -a "msg" -d 'Send a message to the LaraShell socket'
 complete -c larashell -n "__fish_use_subcommand" -f -a "migrate" -d 'Migrate the configuration file'
+complete -c larashell -n "__fish_use_subcommand" -f -a "server" -d 'Run a headless server keeping terminal sessions alive while no window is attached'
 complete -c larashell -n "__fish_use_subcommand" -f -a "help" -d 'Print this message or the help of the given subcommand(s)'
-complete -c larashell -n "__fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from help" -s s -l socket -d 'IPC socket connection path override' -r -F
-complete -c larashell -n "__fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from help" -s h -l help -d 'Print help'
-complete -c larashell -n "__fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from help" -f -a "create-window" -d 'Create a new window in the same LaraShell process'
-complete -c larashell -n "__fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from help" -f -a "config" -d 'Update the LaraShell configuration'
-complete -c larashell -n "__fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from help" -f -a "help" -d 'Print this message or the help of the given subcommand(s)'
+complete -c larashell -n "__fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from export; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from send-text; and not __fish_seen_subcommand_from send-keys; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from toggle-visibility; and not __fish_seen_subcommand_from help" -s s -l socket -d 'IPC socket connection path override' -r -F
+complete -c larashell -n "__fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from export; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from send-text; and not __fish_seen_subcommand_from send-keys; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from toggle-visibility; and not __fish_seen_subcommand_from help" -s h -l help -d 'Print help'
+complete -c larashell -n "__fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from export; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from send-text; and not __fish_seen_subcommand_from send-keys; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from toggle-visibility; and not __fish_seen_subcommand_from help" -f -a "create-window" -d 'Create a new window in the same LaraShell process'
+complete -c larashell -n "__fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from export; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from send-text; and not __fish_seen_subcommand_from send-keys; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from toggle-visibility; and not __fish_seen_subcommand_from help" -f -a "config" -d 'Update the LaraShell configuration'
+complete -c larashell -n "__fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from export; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from send-text; and not __fish_seen_subcommand_from send-keys; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from toggle-visibility; and not __fish_seen_subcommand_from help" -f -a "export" -d 'Write terminal content to a file'
+complete -c larashell -n "__fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from export; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from send-text; and not __fish_seen_subcommand_from send-keys; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from toggle-visibility; and not __fish_seen_subcommand_from help" -f -a "list-windows" -d 'List all windows of the LaraShell process'
+complete -c larashell -n "__fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from export; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from send-text; and not __fish_seen_subcommand_from send-keys; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from toggle-visibility; and not __fish_seen_subcommand_from help" -f -a "send-text" -d 'Write text to a terminal, as if it was pasted'
+complete -c larashell -n "__fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from export; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from send-text; and not __fish_seen_subcommand_from send-keys; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from toggle-visibility; and not __fish_seen_subcommand_from help" -f -a "send-keys" -d 'Send key presses to a terminal'
+complete -c larashell -n "__fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from export; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from send-text; and not __fish_seen_subcommand_from send-keys; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from toggle-visibility; and not __fish_seen_subcommand_from help" -f -a "get-text" -d 'Print the text content of a terminal'
+complete -c larashell -n "__fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from export; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from send-text; and not __fish_seen_subcommand_from send-keys; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from toggle-visibility; and not __fish_seen_subcommand_from help" -f -a "subscribe" -d 'Print events of all windows as they happen'
+complete -c larashell -n "__fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from export; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from send-text; and not __fish_seen_subcommand_from send-keys; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from toggle-visibility; and not __fish_seen_subcommand_from help" -f -a "toggle-visibility" -d 'Show or hide windows, sliding in drop-down windows'
+complete -c larashell -n "__fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from export; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from send-text; and not __fish_seen_subcommand_from send-keys; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from toggle-visibility; and not __fish_seen_subcommand_from help" -f -a "help" -d 'Print this message or the help of the given subcommand(s)'
 complete -c larashell -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from create-window" -l working-directory -d 'Start the shell in the specified working directory' -r -F
 complete -c larashell -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from create-window" -s e -l command -d 'Command and args to execute (must be last argument)' -r
 complete -c larashell -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from create-window" -s T -l title -d 'Defines the window title [default: LaraShell]' -r
 complete -c larashell -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from create-window" -l class -d 'Defines window class/app_id on X11/Wayland [default: LaraShell]' -r
+complete -c larashell -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from create-window" -l group -d 'Group for broadcasting input between windows [default: window class]' -r
+complete -c larashell -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from create-window" -l attach -d 'Attach to a session of `larashell server`, creating it if necessary' -r
 complete -c larashell -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from create-window" -s o -l option -d 'Override configuration file options [example: \'cursor.style="Beam"\']' -r
 complete -c larashell -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from create-window" -l hold -d 'Remain open after child process exit'
+complete -c larashell -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from create-window" -l tab -d 'Open a tab in the focused window instead of a new window'
 complete -c larashell -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from create-window" -s h -l help -d 'Print help'
 complete -c larashell -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from config" -s w -l window-id -d 'Window ID for the new config' -r
 complete -c larashell -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from config" -s r -l reset -d 'Clear all runtime configuration changes'
 complete -c larashell -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from config" -s h -l help -d 'Print help (see more with \'--help\')'
-complete -c larashell -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from help; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from help" -f -a "create-window" -d 'Create a new window in the same LaraShell process'
-complete -c larashell -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from help; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from help" -f -a "config" -d 'Update the LaraShell configuration'
-complete -c larashell -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from help; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from help" -f -a "help" -d 'Print this message or the help of the given subcommand(s)'
+complete -c larashell -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from export" -s f -l format -d 'Output format [default: derived from the file extension]' -r -f -a "{plain	'Text without any formatting',ansi	'Text with ANSI escape sequences',html	'Self-contained HTML document'}"
+complete -c larashell -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from export" -s w -l window-id -d 'Window ID of the exported terminal [default: focused window]' -r
+complete -c larashell -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from export" -l selection -d 'Export the selection instead of the scrollback buffer'
+complete -c larashell -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from export" -s h -l help -d 'Print help (see more with \'--help\')'
+complete -c larashell -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from list-windows" -s h -l help -d 'Print help'
+complete -c larashell -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from send-text" -s w -l window-id -d 'Window ID of the receiving terminal [default: focused window]' -r
+complete -c larashell -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from send-text" -s b -l bracketed -d 'Use bracketed paste if the application requested it'
+complete -c larashell -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from send-text" -s h -l help -d 'Print help'
+complete -c larashell -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from send-keys" -s w -l window-id -d 'Window ID of the receiving terminal [default: focused window]' -r
+complete -c larashell -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from send-keys" -s h -l help -d 'Print help'
+complete -c larashell -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from get-text" -s e -l extent -d 'Part of the terminal content which should be read' -r -f -a "{screen	'Lines visible in the window',scrollback	'The entire scrollback buffer',selection	'The active selection',last-output	'Output of the most recent shell command'}"
+complete -c larashell -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from get-text" -s l -l lines -d 'Maximum number of scrollback lines which should be read' -r
+complete -c larashell -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from get-text" -s o -l offset -d 'Number of scrollback lines skipped, counting up from the bottom' -r
+complete -c larashell -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from get-text" -s w -l window-id -d 'Window ID of the terminal [default: focused window]' -r
+complete -c larashell -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from get-text" -l ansi -d 'Keep colors and text attributes as escape sequences'
+complete -c larashell -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from get-text" -s h -l help -d 'Print help (see more with \'--help\')'
+complete -c larashell -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from subscribe" -s e -l events -d 'Events which should be reported [example: \'title,bell\']' -r -f -a "{title	'The window title changed',bell	'The terminal bell rang',exit	'The window was closed',focus	'The window gained or lost keyboard focus',cwd	'The working directory of the foreground process changed',command-finished	'A shell command finished, as reported through OSC 133'}"
+complete -c larashell -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from subscribe" -s h -l help -d 'Print help (see more with \'--help\')'
+complete -c larashell -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from toggle-visibility" -l class -d 'Toggle all windows with this class [default: all drop-down windows]' -r
+complete -c larashell -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from toggle-visibility" -s w -l window-id -d 'Window ID of the terminal, instead of matching by class' -r
+complete -c larashell -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from toggle-visibility" -s h -l help -d 'Print help'
+complete -c larashell -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from help; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from export; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from send-text; and not __fish_seen_subcommand_from send-keys; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from toggle-visibility; and not __fish_seen_subcommand_from help" -f -a "create-window" -d 'Create a new window in the same LaraShell process'
+complete -c larashell -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from help; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from export; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from send-text; and not __fish_seen_subcommand_from send-keys; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from toggle-visibility; and not __fish_seen_subcommand_from help" -f -a "config" -d 'Update the LaraShell configuration'
+complete -c larashell -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from help; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from export; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from send-text; and not __fish_seen_subcommand_from send-keys; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from toggle-visibility; and not __fish_seen_subcommand_from help" -f -a "export" -d 'Write terminal content to a file'
+complete -c larashell -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from help; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from export; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from send-text; and not __fish_seen_subcommand_from send-keys; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from toggle-visibility; and not __fish_seen_subcommand_from help" -f -a "list-windows" -d 'List all windows of the LaraShell process'
+complete -c larashell -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from help; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from export; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from send-text; and not __fish_seen_subcommand_from send-keys; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from toggle-visibility; and not __fish_seen_subcommand_from help" -f -a "send-text" -d 'Write text to a terminal, as if it was pasted'
+complete -c larashell -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from help; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from export; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from send-text; and not __fish_seen_subcommand_from send-keys; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from toggle-visibility; and not __fish_seen_subcommand_from help" -f -a "send-keys" -d 'Send key presses to a terminal'
+complete -c larashell -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from help; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from export; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from send-text; and not __fish_seen_subcommand_from send-keys; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from toggle-visibility; and not __fish_seen_subcommand_from help" -f -a "get-text" -d 'Print the text content of a terminal'
+complete -c larashell -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from help; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from export; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from send-text; and not __fish_seen_subcommand_from send-keys; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from toggle-visibility; and not __fish_seen_subcommand_from help" -f -a "subscribe" -d 'Print events of all windows as they happen'
+complete -c larashell -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from help; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from export; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from send-text; and not __fish_seen_subcommand_from send-keys; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from toggle-visibility; and not __fish_seen_subcommand_from help" -f -a "toggle-visibility" -d 'Show or hide windows, sliding in drop-down windows'
+complete -c larashell -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from help; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from export; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from send-text; and not __fish_seen_subcommand_from send-keys; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from toggle-visibility; and not __fish_seen_subcommand_from help" -f -a "help" -d 'Print this message or the help of the given subcommand(s)'
 complete -c larashell -n "__fish_seen_subcommand_from migrate" -s c -l config-file -d 'Path to the configuration file' -r -F
 complete -c larashell -n "__fish_seen_subcommand_from migrate" -s d -l dry-run -d 'Only output TOML config to STDOUT'
 complete -c larashell -n "__fish_seen_subcommand_from migrate" -s i -l skip-imports -d 'Do not recurse over imports'
 complete -c larashell -n "__fish_seen_subcommand_from migrate" -l skip-renames -d 'Do not move renamed fields to their new location'
 complete -c larashell -n "__fish_seen_subcommand_from migrate" -s s -l silent -d 'Do not output to STDOUT'
 complete -c larashell -n "__fish_seen_subcommand_from migrate" -s h -l help -d 'Print help'
-complete -c larashell -n "__fish_seen_subcommand_from help; and not __fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from migrate; and not __fish_seen_subcommand_from help" -f -a "msg" -d 'Send a message to the LaraShell socket'
-complete -c larashell -n "__fish_seen_subcommand_from help; and not __fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from migrate; and not __fish_seen_subcommand_from help" -f -a "migrate" -d 'Migrate the configuration file'
-complete -c larashell -n "__fish_seen_subcommand_from help; and not __fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from migrate; and not __fish_seen_subcommand_from help" -f -a "help" -d 'Print this message or the help of the given subcommand(s)'
-complete -c larashell -n "__fish_seen_subcommand_from help; and __fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config" -f -a "create-window" -d 'Create a new window in the same LaraShell process'
-complete -c larashell -n "__fish_seen_subcommand_from help; and __fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config" -f -a "config" -d 'Update the LaraShell configuration'
+complete -c larashell -n "__fish_seen_subcommand_from server" -s s -l socket -d 'Server socket path override' -r -F
+complete -c larashell -n "__fish_seen_subcommand_from server" -s l -l list -d 'List the sessions of the running server instead of starting one'
+complete -c larashell -n "__fish_seen_subcommand_from server" -s h -l help -d 'Print help'
+complete -c larashell -n "__fish_seen_subcommand_from help; and not __fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from migrate; and not __fish_seen_subcommand_from server; and not __fish_seen_subcommand_from help" -f -a "msg" -d 'Send a message to the LaraShell socket'
+complete -c larashell -n "__fish_seen_subcommand_from help; and not __fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from migrate; and not __fish_seen_subcommand_from server; and not __fish_seen_subcommand_from help" -f -a "migrate" -d 'Migrate the configuration file'
+complete -c larashell -n "__fish_seen_subcommand_from help; and not __fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from migrate; and not __fish_seen_subcommand_from server; and not __fish_seen_subcommand_from help" -f -a "server" -d 'Run a headless server keeping terminal sessions alive while no window is attached'
+complete -c larashell -n "__fish_seen_subcommand_from help; and not __fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from migrate; and not __fish_seen_subcommand_from server; and not __fish_seen_subcommand_from help" -f -a "help" -d 'Print this message or the help of the given subcommand(s)'
+complete -c larashell -n "__fish_seen_subcommand_from help; and __fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from export; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from send-text; and not __fish_seen_subcommand_from send-keys; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from toggle-visibility" -f -a "create-window" -d 'Create a new window in the same LaraShell process'
+complete -c larashell -n "__fish_seen_subcommand_from help; and __fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from export; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from send-text; and not __fish_seen_subcommand_from send-keys; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from toggle-visibility" -f -a "config" -d 'Update the LaraShell configuration'
+complete -c larashell -n "__fish_seen_subcommand_from help; and __fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from export; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from send-text; and not __fish_seen_subcommand_from send-keys; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from toggle-visibility" -f -a "export" -d 'Write terminal content to a file'
+complete -c larashell -n "__fish_seen_subcommand_from help; and __fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from export; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from send-text; and not __fish_seen_subcommand_from send-keys; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from toggle-visibility" -f -a "list-windows" -d 'List all windows of the LaraShell process'
+complete -c larashell -n "__fish_seen_subcommand_from help; and __fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from export; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from send-text; and not __fish_seen_subcommand_from send-keys; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from toggle-visibility" -f -a "send-text" -d 'Write text to a terminal, as if it was pasted'
+complete -c larashell -n "__fish_seen_subcommand_from help; and __fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from export; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from send-text; and not __fish_seen_subcommand_from send-keys; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from toggle-visibility" -f -a "send-keys" -d 'Send key presses to a terminal'
+complete -c larashell -n "__fish_seen_subcommand_from help; and __fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from export; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from send-text; and not __fish_seen_subcommand_from send-keys; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from toggle-visibility" -f -a "get-text" -d 'Print the text content of a terminal'
+complete -c larashell -n "__fish_seen_subcommand_from help; and __fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from export; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from send-text; and not __fish_seen_subcommand_from send-keys; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from toggle-visibility" -f -a "subscribe" -d 'Print events of all windows as they happen'
+complete -c larashell -n "__fish_seen_subcommand_from help; and __fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from export; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from send-text; and not __fish_seen_subcommand_from send-keys; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from toggle-visibility" -f -a "toggle-visibility" -d 'Show or hide windows, sliding in drop-down windows'
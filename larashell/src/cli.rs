@@ -4,14 +4,17 @@ use std::ops::{Deref, DerefMut};
 use std::path::PathBuf;
 use std::rc::Rc;
 
+use clap::{ArgAction, Args, Parser, Subcommand, ValueEnum, ValueHint};
 use larashell_config::SerdeReplace;
-use clap::{ArgAction, Args, Parser, Subcommand, ValueHint};
 use log::{error, LevelFilter};
 use serde::{Deserialize, Serialize};
 use toml::Value;
 
+use larashell_terminal::term::TermSnapshot;
 use larashell_terminal::tty::Options as PtyOptions;
 
+#[cfg(unix)]
+use crate::config::export::ExportFormat;
 use crate::config::ui_config::Program;
 use crate::config::window::{Class, Identity};
 use crate::config::UiConfig;
@@ -54,6 +57,10 @@ pub struct Options {
     #[clap(long, value_hint = ValueHint::FilePath)]
     pub socket: Option<PathBuf>,
 
+    /// Restore windows from a saved session [default: most recent session].
+    #[clap(long, value_name = "SESSION", num_args = 0..=1, value_hint = ValueHint::FilePath)]
+    pub restore_session: Option<Option<PathBuf>>,
+
     /// Reduces the level of verbosity (the min level is -qq).
     #[clap(short, conflicts_with("verbose"), action = ArgAction::Count)]
     quiet: u8,
@@ -229,8 +236,10 @@ impl WindowIdentity {
 #[derive(Subcommand, Debug)]
 pub enum Subcommands {
     #[cfg(unix)]
-    Msg(MessageOptions),
+    Msg(Box<MessageOptions>),
     Migrate(MigrateOptions),
+    #[cfg(unix)]
+    Server(ServerOptions),
 }
 
 /// Send a message to the LaraShell socket.
@@ -255,6 +264,27 @@ pub enum SocketMessage {
 
     /// Update the LaraShell configuration.
     Config(IpcConfig),
+
+    /// Write terminal content to a file.
+    Export(IpcExport),
+
+    /// List all windows of the LaraShell process.
+    ListWindows,
+
+    /// Write text to a terminal, as if it was pasted.
+    SendText(IpcSendText),
+
+    /// Send key presses to a terminal.
+    SendKeys(IpcSendKeys),
+
+    /// Print the text content of a terminal.
+    GetText(IpcGetText),
+
+    /// Print events of all windows as they happen.
+    Subscribe(IpcSubscribe),
+
+    /// Show or hide windows, sliding in drop-down windows.
+    ToggleVisibility(IpcToggleVisibility),
 }
 
 /// Migrate the configuration file.
@@ -281,6 +311,19 @@ pub struct MigrateOptions {
     pub silent: bool,
 }
 
+/// Run a headless server keeping terminal sessions alive while no window is attached.
+#[cfg(unix)]
+#[derive(Args, Clone, Debug)]
+pub struct ServerOptions {
+    /// Server socket path override.
+    #[clap(short, long, value_hint = ValueHint::FilePath)]
+    pub socket: Option<PathBuf>,
+
+    /// List the sessions of the running server instead of starting one.
+    #[clap(short, long)]
+    pub list: bool,
+}
+
 /// Subset of options that we pass to 'create-window' IPC subcommand.
 #[derive(Serialize, Deserialize, Args, Default, Clone, Debug, PartialEq, Eq)]
 pub struct WindowOptions {
@@ -297,6 +340,28 @@ pub struct WindowOptions {
     /// The window tabbing identifier to use when building a window.
     pub window_tabbing_id: Option<String>,
 
+    /// Open a tab in the focused window instead of a new window.
+    #[clap(long)]
+    #[serde(default)]
+    #[cfg(not(target_os = "macos"))]
+    pub tab: bool,
+
+    /// Group for broadcasting input between windows [default: window class].
+    #[clap(long, value_name = "GROUP")]
+    #[serde(default)]
+    pub group: Option<String>,
+
+    /// Attach to a session of `larashell server`, creating it if necessary.
+    #[clap(long, value_name = "SESSION")]
+    #[serde(default)]
+    #[cfg(unix)]
+    pub attach: Option<String>,
+
+    #[clap(skip)]
+    #[serde(skip)]
+    /// Terminal content restored from a saved session.
+    pub snapshot: Option<Box<TermSnapshot<'static>>>,
+
     /// Override configuration file options [example: 'cursor.style="Beam"'].
     #[clap(short = 'o', long, num_args = 1..)]
     option: Vec<String>,
@@ -328,8 +393,157 @@ pub struct IpcConfig {
     pub reset: bool,
 }
 
+/// Parameters to the `export` IPC subcommand.
+#[cfg(unix)]
+#[derive(Args, Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+pub struct IpcExport {
+    /// Path of the exported file.
+    #[clap(value_hint = ValueHint::FilePath)]
+    pub path: PathBuf,
+
+    /// Output format [default: derived from the file extension].
+    #[clap(short, long, value_enum)]
+    pub format: Option<ExportFormat>,
+
+    /// Export the selection instead of the scrollback buffer.
+    #[clap(long)]
+    pub selection: bool,
+
+    /// Window ID of the exported terminal [default: focused window].
+    #[clap(short, long, env = "LARASHELL_WINDOW_ID")]
+    pub window_id: Option<u64>,
+}
+
+/// Parameters to the `send-text` IPC subcommand.
+#[cfg(unix)]
+#[derive(Args, Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+pub struct IpcSendText {
+    /// Text which should be written.
+    pub text: String,
+
+    /// Use bracketed paste if the application requested it.
+    #[clap(short, long)]
+    pub bracketed: bool,
+
+    /// Window ID of the receiving terminal [default: focused window].
+    #[clap(short, long, env = "LARASHELL_WINDOW_ID")]
+    pub window_id: Option<u64>,
+}
+
+/// Parameters to the `send-keys` IPC subcommand.
+#[cfg(unix)]
+#[derive(Args, Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+pub struct IpcSendKeys {
+    /// Keys which should be pressed [example: 'Control+c' 'Enter'].
+    #[clap(required = true)]
+    pub keys: Vec<String>,
+
+    /// Window ID of the receiving terminal [default: focused window].
+    #[clap(short, long, env = "LARASHELL_WINDOW_ID")]
+    pub window_id: Option<u64>,
+}
+
+/// Maximum number of scrollback lines read by a single `get-text` IPC message.
+#[cfg(unix)]
+pub const MAX_TEXT_LINES: usize = 10_000;
+
+/// Parameters to the `get-text` IPC subcommand.
+#[cfg(unix)]
+#[derive(Args, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct IpcGetText {
+    /// Part of the terminal content which should be read.
+    #[clap(short, long, value_enum, default_value_t)]
+    pub extent: TextExtent,
+
+    /// Keep colors and text attributes as escape sequences.
+    #[clap(long)]
+    pub ansi: bool,
+
+    /// Maximum number of scrollback lines which should be read.
+    #[clap(short, long, default_value_t = MAX_TEXT_LINES)]
+    #[serde(default = "default_text_lines")]
+    pub lines: usize,
+
+    /// Number of scrollback lines skipped, counting up from the bottom.
+    #[clap(short, long, default_value_t)]
+    #[serde(default)]
+    pub offset: usize,
+
+    /// Window ID of the terminal [default: focused window].
+    #[clap(short, long, env = "LARASHELL_WINDOW_ID")]
+    pub window_id: Option<u64>,
+}
+
+#[cfg(unix)]
+fn default_text_lines() -> usize {
+    MAX_TEXT_LINES
+}
+
+/// Part of the terminal content read by the `get-text` IPC subcommand.
+#[cfg(unix)]
+#[derive(ValueEnum, Serialize, Deserialize, Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TextExtent {
+    /// Lines visible in the window.
+    #[default]
+    Screen,
+
+    /// The entire scrollback buffer.
+    Scrollback,
+
+    /// The active selection.
+    Selection,
+
+    /// Output of the most recent shell command.
+    LastOutput,
+}
+
+/// Parameters to the `subscribe` IPC subcommand.
+#[cfg(unix)]
+#[derive(Args, Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+pub struct IpcSubscribe {
+    /// Events which should be reported [example: 'title,bell'].
+    #[clap(short, long, value_enum, value_delimiter = ',', required = true)]
+    pub events: Vec<SubscriptionEvent>,
+}
+
+/// Parameters to the `toggle-visibility` IPC subcommand.
+#[cfg(unix)]
+#[derive(Args, Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+pub struct IpcToggleVisibility {
+    /// Toggle all windows with this class [default: all drop-down windows].
+    #[clap(long, value_name = "general> | <general>,<instance", value_parser = parse_class)]
+    pub class: Option<Class>,
+
+    /// Window ID of the terminal, instead of matching by class.
+    #[clap(short, long, conflicts_with = "class")]
+    pub window_id: Option<u64>,
+}
+
+/// Event reported by the `subscribe` IPC subcommand.
+#[cfg(unix)]
+#[derive(ValueEnum, Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SubscriptionEvent {
+    /// The window title changed.
+    Title,
+
+    /// The terminal bell rang.
+    Bell,
+
+    /// The window was closed.
+    Exit,
+
+    /// The window gained or lost keyboard focus.
+    Focus,
+
+    /// The working directory of the foreground process changed.
+    Cwd,
+
+    /// A shell command finished, as reported through OSC 133.
+    CommandFinished,
+}
+
 /// Parsed CLI config overrides.
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct ParsedOptions {
     config_options: Vec<(String, Value)>,
 }
@@ -505,6 +719,17 @@ mod tests {
         assert!(class.is_err());
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn toggle_visibility_class_roundtrip() {
+        let class = Some(parse_class("dropdown").unwrap());
+        let message =
+            SocketMessage::ToggleVisibility(IpcToggleVisibility { class, window_id: None });
+
+        let json = serde_json::to_string(&message).unwrap();
+        assert_eq!(serde_json::from_str::<SocketMessage>(&json).unwrap(), message);
+    }
+
     #[test]
     fn valid_decimal() {
         let value = parse_hex_or_decimal("10485773");
@@ -523,6 +748,15 @@ mod tests {
         assert_eq!(value, None);
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn get_text_defaults() {
+        let ipc_text: IpcGetText =
+            serde_json::from_str(r#"{"extent":"Scrollback","ansi":false}"#).unwrap();
+        assert_eq!(ipc_text.lines, MAX_TEXT_LINES);
+        assert_eq!(ipc_text.offset, 0);
+    }
+
     #[cfg(target_os = "linux")]
     #[test]
     fn completions() {
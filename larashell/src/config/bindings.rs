@@ -1,9 +1,10 @@
 #![allow(clippy::enum_glob_use)]
 
 use std::fmt::{self, Debug, Display};
+use std::str::FromStr;
 
 use bitflags::bitflags;
-use serde::de::{self, Error as SerdeError, MapAccess, Unexpected, Visitor};
+use serde::de::{self, Error as SerdeError, IntoDeserializer, MapAccess, Unexpected, Visitor};
 use serde::{Deserialize, Deserializer};
 use toml::Value as SerdeValue;
 use winit::event::MouseButton;
@@ -123,6 +124,12 @@ pub enum Action {
     /// Store current selection into selection buffer.
     CopySelection,
 
+    /// Select and copy the output of the last shell command.
+    CopyLastCommandOutput,
+
+    /// Select the command output below the mouse or vi mode cursor.
+    SelectCommandOutput,
+
     /// Paste contents of selection buffer.
     PasteSelection,
 
@@ -159,9 +166,30 @@ pub enum Action {
     /// Scroll all the way to the bottom.
     ScrollToBottom,
 
+    /// Scroll to the closest marked line above the viewport or vi mode cursor.
+    ScrollToPreviousMark,
+
+    /// Scroll to the closest marked line below the viewport or vi mode cursor.
+    ScrollToNextMark,
+
+    /// Scroll to the closest shell prompt above the viewport or vi mode cursor.
+    ScrollToPreviousPrompt,
+
+    /// Scroll to the closest shell prompt below the viewport or vi mode cursor.
+    ScrollToNextPrompt,
+
     /// Clear the display buffer(s) to remove history.
     ClearHistory,
 
+    /// Write the scrollback buffer to a file.
+    ExportScrollback,
+
+    /// Write the current selection to a file.
+    ExportSelection,
+
+    /// Save the state of all windows for restoring them later.
+    SaveSession,
+
     /// Hide the LaraShell window.
     Hide,
 
@@ -246,6 +274,57 @@ pub enum Action {
     /// Start a backward buffer search.
     SearchBackward,
 
+    /// Start a fuzzy search through all lines of the buffer.
+    FuzzySearch,
+
+    /// Show only lines matching a regex, or return to the unfiltered view.
+    FilterLines,
+
+    /// Split the focused pane, placing the new pane to its right.
+    SplitRight,
+
+    /// Split the focused pane, placing the new pane below it.
+    SplitDown,
+
+    /// Close the focused pane.
+    ClosePane,
+
+    /// Focus the next pane.
+    FocusNextPane,
+
+    /// Focus the previous pane.
+    FocusPreviousPane,
+
+    /// Focus the pane to the left of the focused pane.
+    FocusPaneLeft,
+
+    /// Focus the pane to the right of the focused pane.
+    FocusPaneRight,
+
+    /// Focus the pane above the focused pane.
+    FocusPaneUp,
+
+    /// Focus the pane below the focused pane.
+    FocusPaneDown,
+
+    /// Move the divider left of the focused pane.
+    ResizePaneLeft,
+
+    /// Move the divider right of the focused pane.
+    ResizePaneRight,
+
+    /// Move the divider above the focused pane.
+    ResizePaneUp,
+
+    /// Move the divider below the focused pane.
+    ResizePaneDown,
+
+    /// Let the focused pane fill the window, or restore the split layout.
+    TogglePaneZoom,
+
+    /// Mirror keyboard input and pastes to all windows of the same group.
+    ToggleBroadcastInput,
+
     /// No action.
     None,
 }
@@ -327,6 +406,12 @@ pub enum ViAction {
     InlineSearchNext,
     /// Jump to the previous inline search match.
     InlineSearchPrevious,
+    /// Mark the vi mode cursor's line with a named mark.
+    #[config(skip)]
+    SetMark(char),
+    /// Jump to the line with a named mark.
+    #[config(skip)]
+    JumpToMark(char),
 }
 
 /// Search mode specific actions.
@@ -349,6 +434,12 @@ pub enum SearchAction {
     SearchHistoryPrevious,
     /// Go to the next regex in the search history.
     SearchHistoryNext,
+    /// Cycle between smart case, case sensitive and case insensitive matching.
+    SearchToggleCaseSensitivity,
+    /// Toggle matching only whole words.
+    SearchToggleWholeWord,
+    /// Toggle matching the search text literally instead of as a regex.
+    SearchToggleLiteral,
 }
 
 /// Mouse binding specific actions.
@@ -356,6 +447,8 @@ pub enum SearchAction {
 pub enum MouseAction {
     /// Expand the selection to the current mouse cursor position.
     ExpandSelection,
+    /// Add or remove a mark on the line below the mouse cursor.
+    ToggleMark,
 }
 
 macro_rules! bindings {
@@ -418,6 +511,7 @@ pub fn default_mouse_bindings() -> Vec<MouseBinding> {
         MouseButton::Right;                            MouseAction::ExpandSelection;
         MouseButton::Right,   ModifiersState::CONTROL; MouseAction::ExpandSelection;
         MouseButton::Middle, ~BindingMode::VI;         Action::PasteSelection;
+        MouseButton::Middle,  ModifiersState::CONTROL; MouseAction::ToggleMark;
     )
 }
 
@@ -487,6 +581,11 @@ pub fn default_key_bindings() -> Vec<KeyBinding> {
         "t",      ModifiersState::SHIFT,    +BindingMode::VI, ~BindingMode::SEARCH; ViAction::InlineSearchBackwardShort;
         ";",                                +BindingMode::VI, ~BindingMode::SEARCH; ViAction::InlineSearchNext;
         ",",                                +BindingMode::VI, ~BindingMode::SEARCH; ViAction::InlineSearchPrevious;
+        "[",                                +BindingMode::VI, ~BindingMode::SEARCH; Action::ScrollToPreviousMark;
+        "]",                                +BindingMode::VI, ~BindingMode::SEARCH; Action::ScrollToNextMark;
+        "{",      ModifiersState::SHIFT,    +BindingMode::VI, ~BindingMode::SEARCH; Action::ScrollToPreviousPrompt;
+        "}",      ModifiersState::SHIFT,    +BindingMode::VI, ~BindingMode::SEARCH; Action::ScrollToNextPrompt;
+        "o",                                +BindingMode::VI, ~BindingMode::SEARCH; Action::SelectCommandOutput;
         "k",                                +BindingMode::VI, ~BindingMode::SEARCH; ViMotion::Up;
         "j",                                +BindingMode::VI, ~BindingMode::SEARCH; ViMotion::Down;
         "h",                                +BindingMode::VI, ~BindingMode::SEARCH; ViMotion::Left;
@@ -520,10 +619,31 @@ pub fn default_key_bindings() -> Vec<KeyBinding> {
         "n",      ModifiersState::CONTROL,  +BindingMode::SEARCH; SearchAction::SearchHistoryNext;
         ArrowUp,                            +BindingMode::SEARCH; SearchAction::SearchHistoryPrevious;
         ArrowDown,                          +BindingMode::SEARCH; SearchAction::SearchHistoryNext;
+        "c",      ModifiersState::ALT,      +BindingMode::SEARCH; SearchAction::SearchToggleCaseSensitivity;
+        "w",      ModifiersState::ALT,      +BindingMode::SEARCH; SearchAction::SearchToggleWholeWord;
+        "l",      ModifiersState::ALT,      +BindingMode::SEARCH; SearchAction::SearchToggleLiteral;
         Enter,                              +BindingMode::SEARCH, ~BindingMode::VI; SearchAction::SearchFocusNext;
         Enter, ModifiersState::SHIFT,       +BindingMode::SEARCH, ~BindingMode::VI; SearchAction::SearchFocusPrevious;
     );
 
+    // Named vi marks for every digit.
+    for name in '1'..='9' {
+        let trigger = BindingKey::Keycode {
+            key: Key::Character(name.to_string().into()),
+            location: KeyLocation::Any,
+        };
+        let binding = |mods, action: ViAction| KeyBinding {
+            trigger: trigger.clone(),
+            mods,
+            mode: BindingMode::VI,
+            notmode: BindingMode::SEARCH,
+            action: action.into(),
+        };
+
+        bindings.push(binding(ModifiersState::ALT, ViAction::SetMark(name)));
+        bindings.push(binding(ModifiersState::empty(), ViAction::JumpToMark(name)));
+    }
+
     bindings.extend(platform_key_bindings());
 
     bindings
@@ -537,6 +657,11 @@ fn common_keybindings() -> Vec<KeyBinding> {
         "v",    ModifiersState::CONTROL | ModifiersState::SHIFT, +BindingMode::VI, +BindingMode::SEARCH; Action::Paste;
         "f",    ModifiersState::CONTROL | ModifiersState::SHIFT, ~BindingMode::SEARCH;                   Action::SearchForward;
         "b",    ModifiersState::CONTROL | ModifiersState::SHIFT, ~BindingMode::SEARCH;                   Action::SearchBackward;
+        "r",    ModifiersState::CONTROL | ModifiersState::SHIFT, ~BindingMode::SEARCH;                   Action::FuzzySearch;
+        "l",    ModifiersState::CONTROL | ModifiersState::SHIFT, ~BindingMode::SEARCH;                   Action::FilterLines;
+        "z",    ModifiersState::CONTROL | ModifiersState::SHIFT, ~BindingMode::SEARCH;                   Action::ScrollToPreviousPrompt;
+        "x",    ModifiersState::CONTROL | ModifiersState::SHIFT, ~BindingMode::SEARCH;                   Action::ScrollToNextPrompt;
+        "g",    ModifiersState::CONTROL | ModifiersState::SHIFT, ~BindingMode::SEARCH;                   Action::CopyLastCommandOutput;
         Insert, ModifiersState::SHIFT,                           ~BindingMode::VI;                       Action::PasteSelection;
         "c",    ModifiersState::CONTROL | ModifiersState::SHIFT;                                         Action::Copy;
         "c",    ModifiersState::CONTROL | ModifiersState::SHIFT, +BindingMode::VI, ~BindingMode::SEARCH; Action::ClearSelection;
@@ -603,6 +728,11 @@ pub fn platform_key_bindings() -> Vec<KeyBinding> {
         "w",    ModifiersState::SUPER;                                         Action::Quit;
         "f",    ModifiersState::SUPER, ~BindingMode::SEARCH;                   Action::SearchForward;
         "b",    ModifiersState::SUPER, ~BindingMode::SEARCH;                   Action::SearchBackward;
+        "r",    ModifiersState::SUPER, ~BindingMode::SEARCH;                   Action::FuzzySearch;
+        "l",    ModifiersState::SUPER, ~BindingMode::SEARCH;                   Action::FilterLines;
+        ArrowUp, ModifiersState::SUPER | ModifiersState::SHIFT;                Action::ScrollToPreviousPrompt;
+        ArrowDown, ModifiersState::SUPER | ModifiersState::SHIFT;              Action::ScrollToNextPrompt;
+        "g",    ModifiersState::SUPER | ModifiersState::SHIFT;                 Action::CopyLastCommandOutput;
         "+" => KeyLocation::Numpad, ModifiersState::SUPER;                     Action::IncreaseFontSize;
         "-" => KeyLocation::Numpad, ModifiersState::SUPER;                     Action::DecreaseFontSize;
     )
@@ -737,6 +867,82 @@ impl<'a> Deserialize<'a> for BindingKey {
     }
 }
 
+/// Key press with modifiers, written like `Control+Shift+c`.
+///
+/// The key and modifiers use the same names as key bindings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyPress {
+    pub key: Key,
+    pub location: KeyLocation,
+    pub mods: ModifiersState,
+}
+
+impl KeyPress {
+    /// Text produced by the key with all its modifiers applied.
+    pub fn text(&self) -> Option<String> {
+        let character = match self.key.as_ref() {
+            Key::Character(character) => character,
+            Key::Named(named) => return named.to_text().map(String::from),
+            _ => return None,
+        };
+
+        // Translate control characters like `Control+c` to their C0 code.
+        if self.mods.control_key() {
+            let control = match character {
+                "@" | " " => Some(0),
+                "[" => Some(0x1b),
+                "\\" => Some(0x1c),
+                "]" => Some(0x1d),
+                "^" => Some(0x1e),
+                "_" => Some(0x1f),
+                _ if character.len() == 1 => {
+                    character.bytes().next().filter(|byte| byte.is_ascii_alphabetic())
+                },
+                _ => None,
+            };
+
+            if let Some(control) = control {
+                return Some(char::from(control & 0x1f).to_string());
+            }
+        }
+
+        if self.mods.shift_key() {
+            Some(character.to_uppercase())
+        } else {
+            Some(character.to_owned())
+        }
+    }
+}
+
+impl FromStr for KeyPress {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        // The key is the last component, which allows pressing `+` using `Control++`.
+        let (mods, key) = match value.strip_suffix("++") {
+            Some(mods) => (mods, "+"),
+            None if value == "+" => ("", "+"),
+            None => value.rsplit_once('+').unwrap_or(("", value)),
+        };
+
+        let mods = if mods.is_empty() {
+            ModifiersState::empty()
+        } else {
+            let mods = mods.replace('+', "|");
+            ModsWrapper::deserialize(mods.as_str().into_deserializer())
+                .map_err(|err: de::value::Error| err.to_string())?
+                .into_inner()
+        };
+
+        let binding_key = BindingKey::deserialize(key.into_deserializer())
+            .map_err(|err: de::value::Error| err.to_string())?;
+        match binding_key {
+            BindingKey::Keycode { key, location } => Ok(Self { key, location, mods }),
+            BindingKey::Scancode(_) => Err(format!("unsupported key {key:?}")),
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct ModeWrapper {
     pub mode: BindingMode,
@@ -1071,6 +1277,10 @@ impl<'a> Deserialize<'a> for RawBinding {
                             } else if let Ok(mouse_action) = MouseAction::deserialize(value.clone())
                             {
                                 Some(mouse_action.into())
+                            } else if let Ok(mark_action) =
+                                SerdeMarkAction::deserialize(value.clone())
+                            {
+                                Some(mark_action.into())
                             } else {
                                 match Action::deserialize(value.clone()).map_err(V::Error::custom) {
                                     Ok(action) => Some(action),
@@ -1187,6 +1397,26 @@ impl<'de> Deserialize<'de> for SerdeViMotion {
     }
 }
 
+/// Vi mark actions, which are configured together with the mark's name.
+///
+/// ```toml
+/// { key = "M", mods = "Alt", mode = "Vi", action = { SetMark = "m" } }
+/// ```
+#[derive(Deserialize, Debug, Copy, Clone, Eq, PartialEq)]
+enum SerdeMarkAction {
+    SetMark(char),
+    JumpToMark(char),
+}
+
+impl From<SerdeMarkAction> for Action {
+    fn from(action: SerdeMarkAction) -> Self {
+        match action {
+            SerdeMarkAction::SetMark(name) => ViAction::SetMark(name).into(),
+            SerdeMarkAction::JumpToMark(name) => ViAction::JumpToMark(name).into(),
+        }
+    }
+}
+
 /// Newtype for implementing deserialize on winit Mods.
 ///
 /// Our deserialize impl wouldn't be covered by a derive(Deserialize); see the
@@ -1443,4 +1673,38 @@ mod tests {
         assert!(!binding.is_triggered_by(BindingMode::ALT_SCREEN, mods, &t));
         assert!(!binding.is_triggered_by(BindingMode::ALT_SCREEN | BindingMode::VI, mods, &t));
     }
+
+    #[test]
+    fn parse_key_press() {
+        let key: KeyPress = "Control+Shift+c".parse().unwrap();
+        assert_eq!(key.key, Key::Character("c".into()));
+        assert_eq!(key.mods, ModifiersState::CONTROL | ModifiersState::SHIFT);
+        assert_eq!(key.text().as_deref(), Some("\x03"));
+
+        let key: KeyPress = "Shift+a".parse().unwrap();
+        assert_eq!(key.text().as_deref(), Some("A"));
+
+        let key: KeyPress = "Enter".parse().unwrap();
+        assert_eq!(key.key, Key::Named(NamedKey::Enter));
+        assert_eq!(key.mods, ModifiersState::empty());
+        assert_eq!(key.text().as_deref(), Some("\r"));
+
+        let key: KeyPress = "Alt++".parse().unwrap();
+        assert_eq!(key.key, Key::Character("+".into()));
+        assert_eq!(key.mods, ModifiersState::ALT);
+
+        assert!("Hyper+c".parse::<KeyPress>().is_err());
+        assert!("Control+NotAKey".parse::<KeyPress>().is_err());
+    }
+
+    #[test]
+    fn deserialize_mark_action() {
+        let value: SerdeValue =
+            toml::from_str("key = 'M'\nmode = 'Vi'\naction = { SetMark = 'm' }").unwrap();
+        let binding = KeyBinding::deserialize(value).unwrap();
+        assert_eq!(binding.action, Action::Vi(ViAction::SetMark('m')));
+
+        let value: SerdeValue = toml::from_str("key = 'M'\naction = 'SetMark'").unwrap();
+        assert!(KeyBinding::deserialize(value).is_err());
+    }
 }
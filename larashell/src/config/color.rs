@@ -21,6 +21,8 @@ pub struct Colors {
     pub transparent_background_colors: bool,
     pub draw_bold_text_with_bright_colors: bool,
     footer_bar: BarColors,
+    tab_bar: BarColors,
+    pane_border: PaneBorderColors,
 }
 
 impl Colors {
@@ -31,6 +33,28 @@ impl Colors {
     pub fn footer_bar_background(&self) -> Rgb {
         self.footer_bar.background.unwrap_or(self.primary.foreground)
     }
+
+    pub fn tab_bar_foreground(&self) -> Rgb {
+        self.tab_bar.foreground.unwrap_or_else(|| self.footer_bar_foreground())
+    }
+
+    pub fn tab_bar_background(&self) -> Rgb {
+        self.tab_bar.background.unwrap_or_else(|| self.footer_bar_background())
+    }
+
+    pub fn pane_border_active(&self) -> Rgb {
+        self.pane_border.active.unwrap_or(self.normal.blue)
+    }
+
+    pub fn pane_border_inactive(&self) -> Rgb {
+        self.pane_border.inactive.unwrap_or(self.bright.black)
+    }
+}
+
+#[derive(ConfigDeserialize, Copy, Clone, Default, Debug, PartialEq, Eq)]
+struct PaneBorderColors {
+    active: Option<Rgb>,
+    inactive: Option<Rgb>,
 }
 
 #[derive(ConfigDeserialize, Copy, Clone, Default, Debug, PartialEq, Eq)]
@@ -0,0 +1,40 @@
+use std::path::PathBuf;
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+use larashell_config_derive::ConfigDeserialize;
+use larashell_terminal::term::export::ExportFormat as TermExportFormat;
+
+/// Exporting of terminal content to files.
+#[derive(ConfigDeserialize, Default, Clone, Debug, PartialEq, Eq)]
+pub struct Export {
+    /// Format used by the export bindings.
+    pub format: ExportFormat,
+
+    /// Directory for exported files.
+    pub directory: Option<PathBuf>,
+}
+
+#[derive(ConfigDeserialize, ValueEnum, Serialize, Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Text without any formatting.
+    #[default]
+    Plain,
+
+    /// Text with ANSI escape sequences.
+    Ansi,
+
+    /// Self-contained HTML document.
+    Html,
+}
+
+impl From<ExportFormat> for TermExportFormat {
+    fn from(format: ExportFormat) -> Self {
+        match format {
+            ExportFormat::Plain => Self::Plain,
+            ExportFormat::Ansi => Self::Ansi,
+            ExportFormat::Html => Self::Html,
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::fmt;
 
 use crossfont::Size as FontSize;
@@ -42,6 +43,9 @@ pub struct Font {
 
     /// Whether to use the built-in font for box drawing characters.
     pub builtin_box_drawing: bool,
+
+    /// Text shaping for programming ligatures.
+    pub ligatures: Ligatures,
 }
 
 impl Font {
@@ -88,10 +92,21 @@ impl Default for Font {
             normal: Default::default(),
             bold: Default::default(),
             size: Default::default(),
+            ligatures: Default::default(),
         }
     }
 }
 
+/// Programming ligatures config.
+#[derive(ConfigDeserialize, Default, Debug, Clone, PartialEq, Eq)]
+pub struct Ligatures {
+    /// Shape runs of text with the same style to render the font's ligatures.
+    pub enabled: bool,
+
+    /// OpenType features toggled while shaping, like `calt`, `liga` or `ss01`.
+    pub features: HashMap<String, bool>,
+}
+
 /// Description of the normal font.
 #[derive(ConfigDeserialize, Debug, Clone, PartialEq, Eq)]
 pub struct FontDescription {
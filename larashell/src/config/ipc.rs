@@ -0,0 +1,78 @@
+use serde::Deserialize;
+
+use larashell_config_derive::ConfigDeserialize;
+
+use crate::cli::SocketMessage;
+
+/// IPC socket configuration.
+#[derive(ConfigDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Ipc {
+    /// Messages accepted through the IPC socket.
+    pub allow: Vec<IpcScope>,
+}
+
+impl Default for Ipc {
+    fn default() -> Self {
+        Self {
+            allow: vec![
+                IpcScope::CreateWindow,
+                IpcScope::Config,
+                IpcScope::Export,
+                IpcScope::ListWindows,
+                IpcScope::Subscribe,
+                IpcScope::ToggleVisibility,
+            ],
+        }
+    }
+}
+
+/// IPC message which can be allowed in the config.
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum IpcScope {
+    CreateWindow,
+    Config,
+    Export,
+    ListWindows,
+    SendText,
+    SendKeys,
+    GetText,
+    Subscribe,
+    ToggleVisibility,
+}
+
+impl From<&SocketMessage> for IpcScope {
+    fn from(message: &SocketMessage) -> Self {
+        match message {
+            SocketMessage::CreateWindow(_) => Self::CreateWindow,
+            SocketMessage::Config(_) => Self::Config,
+            SocketMessage::Export(_) => Self::Export,
+            SocketMessage::ListWindows => Self::ListWindows,
+            SocketMessage::SendText(_) => Self::SendText,
+            SocketMessage::SendKeys(_) => Self::SendKeys,
+            SocketMessage::GetText(_) => Self::GetText,
+            SocketMessage::Subscribe(_) => Self::Subscribe,
+            SocketMessage::ToggleVisibility(_) => Self::ToggleVisibility,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::config::UiConfig;
+
+    #[test]
+    fn deserialize_allow() {
+        let config: UiConfig =
+            toml::from_str("ipc.allow = [\"list-windows\", \"send-text\"]").unwrap();
+        assert_eq!(config.ipc.allow, vec![IpcScope::ListWindows, IpcScope::SendText]);
+
+        // Reading from and writing to terminals must be allowed explicitly.
+        let config: UiConfig = toml::from_str("").unwrap();
+        assert!(!config.ipc.allow.contains(&IpcScope::SendText));
+        assert!(!config.ipc.allow.contains(&IpcScope::SendKeys));
+        assert!(!config.ipc.allow.contains(&IpcScope::GetText));
+    }
+}
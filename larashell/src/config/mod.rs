@@ -14,7 +14,10 @@ pub mod bell;
 pub mod color;
 pub mod cursor;
 pub mod debug;
+pub mod export;
 pub mod font;
+#[cfg(unix)]
+pub mod ipc;
 pub mod monitor;
 pub mod scrolling;
 pub mod selection;
@@ -30,7 +33,7 @@ use crate::cli::Options;
 #[cfg(test)]
 pub use crate::config::bindings::Binding;
 pub use crate::config::bindings::{
-    Action, BindingKey, BindingMode, MouseAction, SearchAction, ViAction,
+    Action, BindingKey, BindingMode, KeyLocation, KeyPress, MouseAction, SearchAction, ViAction,
 };
 pub use crate::config::ui_config::UiConfig;
 use crate::logging::LOG_TARGET_CONFIG;
@@ -12,11 +12,26 @@ pub struct Scrolling {
     pub multiplier: u8,
 
     history: ScrollingHistory,
+
+    /// Memory in bytes for compressed history beyond `history` lines.
+    pub history_memory: usize,
+
+    /// Store history exceeding `history_memory` in a temporary file of up to 1 GiB.
+    pub history_spill: bool,
+
+    /// Number of lines shown around every match in the filter view.
+    pub filter_context: usize,
 }
 
 impl Default for Scrolling {
     fn default() -> Self {
-        Self { multiplier: 3, history: Default::default() }
+        Self {
+            multiplier: 3,
+            history: Default::default(),
+            history_memory: 0,
+            history_spill: false,
+            filter_context: 0,
+        }
     }
 }
 
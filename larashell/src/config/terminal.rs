@@ -4,12 +4,15 @@ use toml::Value;
 use larashell_config_derive::{ConfigDeserialize, SerdeReplace};
 use larashell_terminal::term::Osc52;
 
-use crate::config::ui_config::StringVisitor;
+use crate::config::ui_config::{LazyRegex, StringVisitor};
 
-#[derive(ConfigDeserialize, Default, Copy, Clone, Debug, PartialEq)]
+#[derive(ConfigDeserialize, Default, Clone, Debug, PartialEq)]
 pub struct Terminal {
     /// OSC52 support mode.
     pub osc52: SerdeOsc52,
+
+    /// Regex matching the start of shell prompts without OSC 133 support.
+    pub prompt_regex: Option<LazyRegex>,
 }
 
 #[derive(SerdeReplace, Default, Copy, Clone, Debug, PartialEq)]
@@ -25,7 +25,10 @@ use crate::config::bindings::{
 use crate::config::color::Colors;
 use crate::config::cursor::Cursor;
 use crate::config::debug::Debug;
+use crate::config::export::Export;
 use crate::config::font::Font;
+#[cfg(unix)]
+use crate::config::ipc::Ipc;
 use crate::config::mouse::{Mouse, MouseBindings};
 use crate::config::scrolling::Scrolling;
 use crate::config::selection::Selection;
@@ -52,6 +55,9 @@ pub struct UiConfig {
     /// Selection configuration.
     pub selection: Selection,
 
+    /// Export configuration.
+    pub export: Export,
+
     /// Font configuration.
     pub font: Font,
 
@@ -89,6 +95,10 @@ pub struct UiConfig {
     #[cfg(unix)]
     pub ipc_socket: bool,
 
+    /// IPC socket configuration.
+    #[cfg(unix)]
+    pub ipc: Ipc,
+
     /// Config for the larashell_terminal itself.
     pub terminal: Terminal,
 
@@ -126,6 +136,8 @@ impl Default for UiConfig {
             live_config_reload: true,
             #[cfg(unix)]
             ipc_socket: true,
+            #[cfg(unix)]
+            ipc: Default::default(),
             draw_bold_text_with_bright_colors: Default::default(),
             working_directory: Default::default(),
             mouse_bindings: Default::default(),
@@ -134,6 +146,7 @@ impl Default for UiConfig {
             alt_send_esc: Default::default(),
             scrolling: Default::default(),
             selection: Default::default(),
+            export: Default::default(),
             keyboard: Default::default(),
             terminal: Default::default(),
             import: Default::default(),
@@ -157,6 +170,8 @@ impl UiConfig {
         TermConfig {
             semantic_escape_chars: self.selection.semantic_escape_chars.clone(),
             scrolling_history: self.scrolling.history() as usize,
+            scrolling_history_memory: self.scrolling.history_memory,
+            scrolling_history_spill: self.scrolling.history_spill,
             vi_mode_cursor_style: self.cursor.vi_mode_style(),
             default_cursor_style: self.cursor.style(),
             osc52: self.terminal.osc52.0,
@@ -534,7 +549,7 @@ pub struct HintMouse {
 }
 
 /// Lazy regex with interior mutability.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(SerdeReplace, Clone, Debug, PartialEq, Eq)]
 pub struct LazyRegex(Rc<RefCell<LazyRegexVariant>>);
 
 impl LazyRegex {
@@ -27,6 +27,9 @@ pub struct WindowConfig {
     /// Startup mode.
     pub startup_mode: StartupMode,
 
+    /// Drop-down window settings.
+    pub dropdown: Dropdown,
+
     /// XEmbed parent.
     #[config(skip)]
     pub embed: Option<u32>,
@@ -76,6 +79,7 @@ impl Default for WindowConfig {
             dimensions: Default::default(),
             decorations: Default::default(),
             startup_mode: Default::default(),
+            dropdown: Default::default(),
             dynamic_padding: Default::default(),
             resize_increments: Default::default(),
             decorations_theme_variant: Default::default(),
@@ -138,6 +142,11 @@ impl WindowConfig {
         self.startup_mode == StartupMode::Maximized
     }
 
+    #[inline]
+    pub fn dropdown(&self) -> bool {
+        self.startup_mode == StartupMode::Dropdown
+    }
+
     #[cfg(target_os = "macos")]
     pub fn option_as_alt(&self) -> WinitOptionAsAlt {
         match self.option_as_alt {
@@ -175,6 +184,23 @@ pub enum StartupMode {
     Maximized,
     Fullscreen,
     SimpleFullscreen,
+    Dropdown,
+}
+
+/// Settings of windows using the `Dropdown` startup mode.
+#[derive(ConfigDeserialize, Debug, Copy, Clone, PartialEq)]
+pub struct Dropdown {
+    /// Window height relative to the height of the monitor.
+    pub height: Percentage,
+
+    /// Hide the window when it loses focus.
+    pub hide_on_focus_loss: bool,
+}
+
+impl Default for Dropdown {
+    fn default() -> Self {
+        Self { height: Percentage::new(0.4), hide_on_focus_loss: false }
+    }
 }
 
 #[derive(ConfigDeserialize, Default, Debug, Copy, Clone, PartialEq, Eq)]
@@ -3,19 +3,24 @@ use std::ops::Deref;
 use std::{cmp, mem};
 
 use larashell_terminal::event::EventListener;
-use larashell_terminal::grid::{Dimensions, Indexed};
+use larashell_terminal::grid::{Dimensions, Indexed, LineAttribute};
 use larashell_terminal::index::{Column, Line, Point};
 use larashell_terminal::selection::SelectionRange;
 use larashell_terminal::term::cell::{Cell, Flags, Hyperlink};
 use larashell_terminal::term::search::{Match, RegexSearch};
+#[cfg(not(any(target_os = "macos", windows)))]
+use larashell_terminal::term::LineDamageBounds;
 use larashell_terminal::term::{self, RenderableContent as TerminalContent, Term, TermMode};
 use larashell_terminal::vte::ansi::{Color, CursorShape, NamedColor};
 
 use crate::config::UiConfig;
 use crate::display::color::{CellRgb, List, Rgb, DIM_FACTOR};
+use crate::display::filter::FilterIter;
 use crate::display::hint::{self, HintState};
 use crate::display::{Display, SizeInfo};
 use crate::event::SearchState;
+#[cfg(not(any(target_os = "macos", windows)))]
+use crate::renderer::Shaper;
 
 /// Minimum contrast between a fixed cursor color and the cell's background.
 pub const MIN_CURSOR_CONTRAST: f64 = 1.5;
@@ -34,6 +39,12 @@ pub struct RenderableContent<'a> {
     colors: &'a List,
     focused_match: Option<&'a Match>,
     size: &'a SizeInfo,
+
+    /// Cells of the filtered lines, replacing the viewport while the filter view is active.
+    filter: Option<FilterIter<'a>>,
+
+    /// DECDWL/DECDHL attributes of all lines in the viewport.
+    line_attributes: Vec<LineAttribute>,
 }
 
 impl<'a> RenderableContent<'a> {
@@ -43,7 +54,9 @@ impl<'a> RenderableContent<'a> {
         term: &'a Term<T>,
         search_state: &'a mut SearchState,
     ) -> Self {
-        let search = search_state.dfas().map(|dfas| HintMatches::visible_regex_matches(term, dfas));
+        let filtering = display.filter.filtering();
+        let mut search =
+            search_state.dfas().map(|dfas| HintMatches::visible_regex_matches(term, dfas));
         let focused_match = search_state.focused_match();
         let terminal_content = term.renderable_content();
 
@@ -52,6 +65,7 @@ impl<'a> RenderableContent<'a> {
             || display.cursor_hidden
             || search_state.regex().is_some()
             || display.ime.preedit().is_some()
+            || filtering
         {
             CursorShape::Hidden
         } else if !term.is_focused && config.cursor.unfocused_hollow {
@@ -65,13 +79,37 @@ impl<'a> RenderableContent<'a> {
         let display_offset = terminal_content.display_offset;
         let cursor_point = term::point_to_viewport(display_offset, cursor_point).unwrap();
 
-        let hint = if display.hint_state.active() {
+        let hint = if display.hint_state.active() && !filtering {
             display.hint_state.update_matches(term);
             Some(Hint::from(&display.hint_state))
         } else {
             None
         };
 
+        // Show the filtered lines instead of the viewport, highlighting the filter matches.
+        let screen_lines = term.screen_lines();
+        let filter = filtering.then(|| {
+            let lines = display.filter.visible_lines(term);
+            search = Some(HintMatches::new(display.filter.visible_matches(term)));
+            FilterIter::new(term.grid(), lines)
+        });
+
+        let line_attributes = match &filter {
+            Some(filter) => {
+                let mut line_attributes: Vec<_> =
+                    filter.lines().iter().map(|line| term.grid()[*line].line_attribute()).collect();
+                line_attributes.resize(screen_lines, LineAttribute::Normal);
+                line_attributes
+            },
+            None => (0..screen_lines)
+                .map(|line| {
+                    let line =
+                        term::viewport_to_point(display_offset, Point::new(line, Column(0))).line;
+                    term.grid()[line].line_attribute()
+                })
+                .collect(),
+        };
+
         Self {
             colors: &display.colors,
             size: &display.size_info,
@@ -83,6 +121,58 @@ impl<'a> RenderableContent<'a> {
             search,
             config,
             hint,
+            filter,
+            line_attributes,
+        }
+    }
+
+    /// Renderable content of a pane without keyboard focus.
+    ///
+    /// Search, hints and the filter view only apply to the focused pane, so they are never
+    /// shown.
+    pub fn inactive<T: EventListener>(
+        config: &'a UiConfig,
+        colors: &'a List,
+        size: &'a SizeInfo,
+        term: &'a Term<T>,
+    ) -> Self {
+        let terminal_content = term.renderable_content();
+
+        // Find terminal cursor shape.
+        let cursor_shape = if terminal_content.cursor.shape == CursorShape::Hidden {
+            CursorShape::Hidden
+        } else if config.cursor.unfocused_hollow {
+            CursorShape::HollowBlock
+        } else {
+            terminal_content.cursor.shape
+        };
+
+        // Convert terminal cursor point to viewport position.
+        let display_offset = terminal_content.display_offset;
+        let cursor_point =
+            term::point_to_viewport(display_offset, terminal_content.cursor.point).unwrap();
+
+        let line_attributes = (0..term.screen_lines())
+            .map(|line| {
+                let line =
+                    term::viewport_to_point(display_offset, Point::new(line, Column(0))).line;
+                term.grid()[line].line_attribute()
+            })
+            .collect();
+
+        Self {
+            cursor: RenderableCursor::new_hidden(),
+            focused_match: None,
+            search: None,
+            hint: None,
+            filter: None,
+            terminal_content,
+            cursor_shape,
+            cursor_point,
+            line_attributes,
+            config,
+            colors,
+            size,
         }
     }
 
@@ -136,6 +226,7 @@ impl<'a> RenderableContent<'a> {
 
         RenderableCursor {
             is_wide: cell.flags.contains(Flags::WIDE_CHAR),
+            line_attribute: cell.line_attribute,
             shape: self.cursor_shape,
             point: self.cursor_point,
             cursor_color,
@@ -154,8 +245,20 @@ impl<'a> Iterator for RenderableContent<'a> {
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
         loop {
-            let cell = self.terminal_content.display_iter.next()?;
-            let mut cell = RenderableCell::new(self, cell);
+            let (cell, point) = match &mut self.filter {
+                Some(filter) => {
+                    let (line, cell) = filter.next()?;
+                    let point = Point::new(line, cell.point.column);
+                    (cell, point)
+                },
+                None => {
+                    let cell = self.terminal_content.display_iter.next()?;
+                    let display_offset = self.terminal_content.display_offset;
+                    let point = term::point_to_viewport(display_offset, cell.point).unwrap();
+                    (cell, point)
+                },
+            };
+            let mut cell = RenderableCell::new(self, cell, point);
 
             if self.cursor_point == cell.point {
                 // Store the cursor which should be rendered.
@@ -188,7 +291,20 @@ pub struct RenderableCell {
     pub bg_alpha: f32,
     pub underline: Rgb,
     pub flags: Flags,
+    pub line_attribute: LineAttribute,
     pub extra: Option<Box<RenderableCellExtra>>,
+    pub shaped: Option<ShapedGlyph>,
+}
+
+/// Glyph replacing a cell's character after text shaping.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(any(target_os = "macos", windows), allow(dead_code))]
+pub enum ShapedGlyph {
+    /// Glyph substituted by the font's OpenType features.
+    Glyph(u16),
+
+    /// Cell covered by a ligature starting in one of the preceding cells.
+    Covered,
 }
 
 /// Extra storage with rarely present fields for [`RenderableCell`], to reduce the cell size we
@@ -200,7 +316,7 @@ pub struct RenderableCellExtra {
 }
 
 impl RenderableCell {
-    fn new(content: &mut RenderableContent<'_>, cell: Indexed<&Cell>) -> Self {
+    fn new(content: &mut RenderableContent<'_>, cell: Indexed<&Cell>, point: Point<usize>) -> Self {
         // Lookup RGB values.
         let mut fg = Self::compute_fg_rgb(content, cell.fg, cell.flags);
         let mut bg = Self::compute_bg_rgb(content, cell.bg);
@@ -271,10 +387,6 @@ impl RenderableCell {
             bg_alpha = content.config.window_opacity();
         }
 
-        // Convert cell point to viewport position.
-        let cell_point = cell.point;
-        let point = term::point_to_viewport(display_offset, cell_point).unwrap();
-
         let underline = cell
             .underline_color()
             .map_or(fg, |underline| Self::compute_fg_rgb(content, underline, flags));
@@ -289,7 +401,35 @@ impl RenderableCell {
             })
         });
 
-        RenderableCell { flags, character, bg_alpha, point, fg, bg, underline, extra }
+        let line_attribute = content.line_attributes[point.line];
+
+        RenderableCell {
+            flags,
+            character,
+            bg_alpha,
+            point,
+            fg,
+            bg,
+            underline,
+            line_attribute,
+            extra,
+            shaped: None,
+        }
+    }
+
+    /// Number of columns covered by the cell on screen.
+    pub fn visual_width(&self) -> usize {
+        let width = if self.flags.contains(Flags::WIDE_CHAR) { 2 } else { 1 };
+        if self.line_attribute.is_double_width() {
+            width * 2
+        } else {
+            width
+        }
+    }
+
+    /// Viewport position of the cell's left edge on screen.
+    pub fn visual_point(&self) -> Point<usize> {
+        visual_point(self.point, self.line_attribute)
     }
 
     /// Check if cell contains any renderable content.
@@ -397,6 +537,7 @@ pub struct RenderableCursor {
     cursor_color: Rgb,
     text_color: Rgb,
     is_wide: bool,
+    line_attribute: LineAttribute,
     point: Point<usize>,
 }
 
@@ -406,14 +547,16 @@ impl RenderableCursor {
         let cursor_color = Rgb::default();
         let text_color = Rgb::default();
         let is_wide = false;
+        let line_attribute = LineAttribute::Normal;
         let point = Point::default();
-        Self { shape, cursor_color, text_color, is_wide, point }
+        Self { shape, cursor_color, text_color, is_wide, line_attribute, point }
     }
 }
 
 impl RenderableCursor {
     pub fn new(point: Point<usize>, shape: CursorShape, cursor_color: Rgb, is_wide: bool) -> Self {
-        Self { shape, cursor_color, text_color: cursor_color, is_wide, point }
+        let line_attribute = LineAttribute::Normal;
+        Self { shape, cursor_color, text_color: cursor_color, is_wide, line_attribute, point }
     }
 
     pub fn color(&self) -> Rgb {
@@ -428,11 +571,119 @@ impl RenderableCursor {
         self.is_wide
     }
 
-    pub fn point(&self) -> Point<usize> {
-        self.point
+    /// Check if the cursor is drawn with twice its usual width.
+    pub fn is_double_width(&self) -> bool {
+        self.line_attribute.is_double_width()
+    }
+
+    /// Viewport position of the cursor on screen.
+    pub fn visual_point(&self) -> Point<usize> {
+        visual_point(self.point, self.line_attribute)
+    }
+}
+
+/// Convert a viewport point to its position on screen, accounting for double width lines.
+fn visual_point(point: Point<usize>, line_attribute: LineAttribute) -> Point<usize> {
+    if line_attribute.is_double_width() {
+        Point::new(point.line, Column(point.column.0 * 2))
+    } else {
+        point
     }
 }
 
+/// Shape runs of cells with the same style, to render the font's ligatures.
+///
+/// Runs are broken up at every cell for which `split` returns `true`, so ligatures fall apart
+/// into their individual characters once the cursor or a selection enters them.
+///
+/// Returns the bounds of all cells replaced by the font, since changing any of their neighbours
+/// can change their glyphs.
+#[cfg(not(any(target_os = "macos", windows)))]
+pub fn shape_cells<F>(
+    cells: &mut [RenderableCell],
+    shaper: &mut Shaper,
+    split: F,
+) -> Vec<LineDamageBounds>
+where
+    F: Fn(&RenderableCell) -> bool,
+{
+    let mut frame = shaper.frame();
+    let mut text = String::new();
+    let mut ligatures: Vec<LineDamageBounds> = Vec::new();
+
+    let mut start = 0;
+    while start < cells.len() {
+        let end = run_end(cells, start, &split);
+
+        // Ligatures always span multiple characters.
+        if end - start > 1 {
+            text.clear();
+            text.extend(cells[start..end].iter().map(|cell| cell.character));
+
+            let shaped = frame.shape(cells[start].flags, &text);
+            for (cell, shaped) in cells[start..end].iter_mut().zip(shaped) {
+                cell.shaped = *shaped;
+
+                if cell.shaped.is_none() {
+                    continue;
+                }
+
+                // Merge adjacent replaced cells into a single span.
+                let Point { line, column } = cell.point;
+                match ligatures.last_mut() {
+                    Some(last) if last.line == line && last.right + 1 == column.0 => {
+                        last.right = column.0;
+                    },
+                    _ => ligatures.push(LineDamageBounds::new(line, column.0, column.0)),
+                }
+            }
+        }
+
+        start = end;
+    }
+
+    ligatures
+}
+
+/// Find the end of the shaping run starting at `start`.
+///
+/// A run consists of adjacent single-width cells on the same line, sharing their font style and
+/// foreground color.
+#[cfg(not(any(target_os = "macos", windows)))]
+fn run_end<F>(cells: &[RenderableCell], start: usize, split: F) -> usize
+where
+    F: Fn(&RenderableCell) -> bool,
+{
+    let shapeable = |cell: &RenderableCell| {
+        cell.line_attribute == LineAttribute::Normal
+            && !cell.flags.intersects(Flags::WIDE_CHAR | Flags::HIDDEN)
+            && cell.character != '\t'
+            && cell.extra.as_ref().map_or(true, |extra| extra.zerowidth.is_none())
+            && !split(cell)
+    };
+
+    if !shapeable(&cells[start]) {
+        return start + 1;
+    }
+
+    let first = &cells[start];
+    let mut end = start + 1;
+    while let Some(cell) = cells.get(end) {
+        let adjacent = cell.point.line == first.point.line
+            && cell.point.column == cells[end - 1].point.column + 1;
+        let same_style = cell.flags & Flags::BOLD_ITALIC == first.flags & Flags::BOLD_ITALIC
+            && cell.fg == first.fg;
+
+        if !adjacent || !same_style || !shapeable(cell) {
+            break;
+        }
+
+        end += 1;
+    }
+
+    end
+}
+
 /// Regex hints for keyboard shortcuts.
 struct Hint<'a> {
     /// Hint matches and position.
@@ -539,3 +790,67 @@ impl<'a> Deref for HintMatches<'a> {
         self.matches.deref()
     }
 }
+
+#[cfg(all(test, not(any(target_os = "macos", windows))))]
+mod tests {
+    use super::*;
+
+    fn text_cells(line: usize, text: &str) -> Vec<RenderableCell> {
+        text.chars()
+            .enumerate()
+            .map(|(column, character)| RenderableCell {
+                character,
+                point: Point::new(line, Column(column)),
+                fg: Rgb::default(),
+                bg: Rgb::default(),
+                bg_alpha: 0.,
+                underline: Rgb::default(),
+                flags: Flags::empty(),
+                line_attribute: LineAttribute::Normal,
+                extra: None,
+                shaped: None,
+            })
+            .collect()
+    }
+
+    fn runs<F: Fn(&RenderableCell) -> bool>(cells: &[RenderableCell], split: F) -> Vec<String> {
+        let mut runs = Vec::new();
+        let mut start = 0;
+        while start < cells.len() {
+            let end = run_end(cells, start, &split);
+            runs.push(cells[start..end].iter().map(|cell| cell.character).collect());
+            start = end;
+        }
+        runs
+    }
+
+    #[test]
+    fn shaping_runs() {
+        let mut cells = text_cells(0, "a->b");
+        cells.extend(text_cells(1, "=>"));
+        assert_eq!(runs(&cells, |_| false), ["a->b", "=>"]);
+
+        // Style changes break up runs.
+        cells[1].flags = Flags::BOLD;
+        cells[2].flags = Flags::BOLD;
+        assert_eq!(runs(&cells, |_| false), ["a", "->", "b", "=>"]);
+
+        cells[4].fg = Rgb::new(255, 0, 0);
+        assert_eq!(runs(&cells, |_| false), ["a", "->", "b", "=", ">"]);
+
+        // Gaps between cells break up runs.
+        let mut cells = text_cells(0, "<=>");
+        cells.remove(1);
+        assert_eq!(runs(&cells, |_| false), ["<", ">"]);
+    }
+
+    #[test]
+    fn split_runs_at_cursor() {
+        let mut cells = text_cells(0, "a==>b");
+        let cursor = Point::new(0, Column(2));
+        assert_eq!(runs(&cells, |cell| cell.point == cursor), ["a=", "=", ">b"]);
+
+        cells[1].flags = Flags::WIDE_CHAR;
+        assert_eq!(runs(&cells, |_| false), ["a", "=", "=>b"]);
+    }
+}
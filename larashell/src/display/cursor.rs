@@ -15,7 +15,7 @@ pub trait IntoRects {
 
 impl IntoRects for RenderableCursor {
     fn rects(self, size_info: &SizeInfo, thickness: f32) -> CursorRects {
-        let point = self.point();
+        let point = self.visual_point();
         let x = point.column.0 as f32 * size_info.cell_width() + size_info.padding_x();
         let y = point.line as f32 * size_info.cell_height() + size_info.padding_y();
 
@@ -28,6 +28,10 @@ impl IntoRects for RenderableCursor {
             width *= 2.;
         }
 
+        if self.is_double_width() {
+            width *= 2.;
+        }
+
         match self.shape() {
             CursorShape::Beam => beam(x, y, height, thickness, self.color()),
             CursorShape::Underline => underline(x, y, width, height, thickness, self.color()),
@@ -18,6 +18,8 @@ pub struct DamageTracker {
     pub old_vi_cursor: Option<Point<usize>>,
     /// The location of the old selection.
     pub old_selection: Option<SelectionRange>,
+    /// Cells replaced by ligatures in the previous frame.
+    pub old_ligatures: Vec<LineDamageBounds>,
     /// Highlight damage submitted for the compositor.
     pub debug: bool,
 
@@ -35,6 +37,7 @@ impl DamageTracker {
             debug: false,
             old_vi_cursor: None,
             old_selection: None,
+            old_ligatures: Vec::new(),
             frames: Default::default(),
         };
         tracker.resize(screen_lines, columns);
@@ -89,6 +92,27 @@ impl DamageTracker {
         }
     }
 
+    /// Expand the damage to the ligatures it touches, in this and the previous frame.
+    ///
+    /// Changing any cell of a ligature can change the glyphs of all its cells.
+    pub fn damage_ligatures(&mut self, mut ligatures: Vec<LineDamageBounds>) {
+        mem::swap(&mut self.old_ligatures, &mut ligatures);
+
+        if self.frame().full {
+            return;
+        }
+
+        // Damage can grow into other ligatures overlapping the expanded bounds.
+        let frame = &mut self.frames[0];
+        let mut expanded = true;
+        while expanded {
+            expanded = false;
+            for ligature in ligatures.iter().chain(&self.old_ligatures) {
+                expanded |= frame.damage_overlapping(*ligature);
+            }
+        }
+    }
+
     /// Get shaped frame damage for the active frame.
     pub fn shape_frame_damage(&self, size_info: SizeInfo<u32>) -> Vec<Rect> {
         if self.frames[0].full {
@@ -158,6 +182,20 @@ impl FrameDamage {
         self.lines[point.line].expand(point.column.0, point.column.0);
     }
 
+    /// Expand the damage of a line to a span of cells, if the damage overlaps it.
+    ///
+    /// Returns `true` if the damage was expanded.
+    fn damage_overlapping(&mut self, span: LineDamageBounds) -> bool {
+        let damage = match self.lines.get_mut(span.line) {
+            Some(damage) if damage.left <= span.right && span.left <= damage.right => damage,
+            _ => return false,
+        };
+
+        let expanded = span.left < damage.left || span.right > damage.right;
+        damage.expand(span.left, span.right);
+        expanded
+    }
+
     /// Mark the frame as fully damaged.
     #[inline]
     pub fn mark_fully_damaged(&mut self) {
@@ -289,6 +327,8 @@ fn merge_rects(lhs: Rect, rhs: Rect) -> Rect {
 
 #[cfg(test)]
 mod tests {
+    use larashell_terminal::index::Column;
+
     use super::*;
 
     #[test]
@@ -354,4 +394,26 @@ mod tests {
         assert_eq!(frame_damage.rects[0].y, viewport_y_to_damage_y(&size_info, y, height));
         assert_eq!(damage_y_to_viewport_y(&size_info, &frame_damage.rects[0]), y);
     }
+
+    #[test]
+    fn damage_ligatures() {
+        let mut tracker = DamageTracker::new(2, 20);
+        tracker.swap_damage();
+
+        // Damage grows into the old and new ligatures overlapping it.
+        tracker.damage_ligatures(vec![LineDamageBounds::new(0, 3, 5)]);
+        tracker.frame().damage_point(Point::new(0, Column(5)));
+        tracker.damage_ligatures(vec![
+            LineDamageBounds::new(0, 1, 3),
+            LineDamageBounds::new(0, 10, 11),
+            LineDamageBounds::new(1, 0, 1),
+        ]);
+        assert_eq!(tracker.frame().lines[0], LineDamageBounds::new(0, 1, 5));
+        assert!(!tracker.frame().lines[1].is_damaged());
+
+        // Ligatures without damage are left untouched.
+        tracker.swap_damage();
+        tracker.damage_ligatures(Vec::new());
+        assert!(tracker.frame().lines.iter().all(|line| !line.is_damaged()));
+    }
 }
@@ -0,0 +1,461 @@
+use std::cmp::min;
+
+use larashell_terminal::grid::{Dimensions, Grid, Indexed, Scroll};
+use larashell_terminal::index::{Column, Direction, Line, Point};
+use larashell_terminal::term::cell::{Cell, Flags};
+use larashell_terminal::term::search::{Match, RegexIter, RegexSearch};
+use larashell_terminal::term::{Term, TermMode};
+
+/// Maximum number of lines searched for matches in a single update.
+const FILTER_CHUNK_LINES: usize = 1000;
+
+/// View showing only the lines matching a regex.
+#[derive(Default)]
+pub struct FilterView {
+    /// Whether the filter view is shown.
+    active: bool,
+
+    /// Regex lines must match to be shown.
+    regex: Option<RegexSearch>,
+
+    /// All shown grid lines, from top to bottom.
+    lines: Vec<Line>,
+
+    /// All regex matches in the shown lines.
+    matches: Vec<Match>,
+
+    /// Number of shown lines scrolled up from the bottom.
+    display_offset: usize,
+
+    /// First line which has not been searched for matches yet.
+    next_line: Option<Line>,
+
+    /// Terminal state when the shown lines were last updated.
+    snapshot: Option<Snapshot>,
+}
+
+/// Terminal state the filtered lines were found in.
+#[derive(Copy, Clone, PartialEq, Eq)]
+struct Snapshot {
+    /// Lines rotated into the scrollback history.
+    rotated_lines: i64,
+
+    /// Scrollback history size.
+    history_size: usize,
+
+    /// Grid dimensions, alternate screen state and number of context lines.
+    layout: (usize, usize, bool, usize),
+}
+
+impl Snapshot {
+    fn new<T>(term: &Term<T>, context: usize) -> Self {
+        let alt_screen = term.mode().contains(TermMode::ALT_SCREEN);
+        Self {
+            rotated_lines: term.grid().rotated_lines(),
+            history_size: term.history_size(),
+            layout: (term.columns(), term.screen_lines(), alt_screen, context),
+        }
+    }
+}
+
+impl FilterView {
+    /// Check if the filter view is shown.
+    pub fn active(&self) -> bool {
+        self.active
+    }
+
+    /// Check if the filter view is shown and lines are filtered.
+    pub fn filtering(&self) -> bool {
+        self.active && self.regex.is_some()
+    }
+
+    /// Show the filter view.
+    pub fn start(&mut self) {
+        *self = Self { active: true, ..Self::default() };
+    }
+
+    /// Return to the unfiltered terminal content.
+    pub fn stop(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Replace the regex used for filtering lines.
+    ///
+    /// Filtered lines are only updated after calling [`Self::update`].
+    pub fn set_regex(&mut self, regex: Option<RegexSearch>) {
+        self.regex = regex;
+        self.display_offset = 0;
+        self.snapshot = None;
+    }
+
+    /// Update the shown lines from the terminal content.
+    ///
+    /// Every logical line containing a match is shown, together with `context` logical lines
+    /// before and after it.
+    ///
+    /// Lines in the scrollback history can't change, so only lines which were visible during
+    /// the last update are searched again. To avoid blocking on huge scrollback buffers, only a
+    /// limited number of lines is searched at once. Returns `true` if there are lines left to
+    /// be searched by another update.
+    pub fn update<T>(&mut self, term: &Term<T>, context: usize) -> bool {
+        if !self.filtering() {
+            self.lines.clear();
+            self.matches.clear();
+            self.next_line = None;
+            return false;
+        }
+
+        // Restart when lines can't be tracked, like after a resize or clearing the history.
+        let snapshot = Snapshot::new(term, context);
+        let topmost_line = term.topmost_line();
+        let scrolled = match self.snapshot {
+            Some(old) => {
+                let scrolled = snapshot.rotated_lines - old.rotated_lines;
+                let history_size = snapshot.history_size as i64 + scrolled;
+                (scrolled >= 0
+                    && old.layout == snapshot.layout
+                    && history_size >= old.history_size as i64)
+                    .then_some(scrolled)
+            },
+            None => None,
+        };
+        self.snapshot = Some(snapshot);
+
+        match scrolled {
+            Some(scrolled) => {
+                // Move lines along with the content rotated into history.
+                let scrolled = min(scrolled, i32::MAX as i64) as i32;
+                shift_lines(&mut self.lines, &mut self.matches, scrolled, topmost_line);
+                let next_line = self.next_line.map(|line| line - scrolled);
+
+                // Search the previously visible lines again.
+                let changed_line =
+                    next_line.map_or(Line(-scrolled), |line| line.min(Line(-scrolled)));
+                self.invalidate(term, changed_line.max(topmost_line), context);
+            },
+            None => {
+                self.lines.clear();
+                self.matches.clear();
+                self.next_line = Some(topmost_line);
+            },
+        }
+
+        let regex = match &mut self.regex {
+            Some(regex) => regex,
+            None => return false,
+        };
+
+        if let Some(start) = self.next_line {
+            // Extend the chunk over wrapped lines, since matches can't be split.
+            let bottommost_line = term.bottommost_line();
+            let last_column = term.last_column();
+            let mut end_line = min(start + (FILTER_CHUNK_LINES - 1), bottommost_line);
+            while end_line < bottommost_line
+                && term.grid()[end_line][last_column].flags.contains(Flags::WRAPLINE)
+            {
+                end_line += 1;
+            }
+
+            let first_match = self.matches.len();
+            let start = Point::new(start, Column(0));
+            let end = Point::new(end_line, last_column);
+            self.matches.extend(RegexIter::new(start, end, Direction::Right, term, regex));
+            extend_lines(&mut self.lines, term, &self.matches[first_match..], context);
+
+            self.next_line = (end_line < bottommost_line).then(|| end_line + 1);
+        }
+
+        let max_offset = self.max_display_offset(term.screen_lines());
+        self.display_offset = min(self.display_offset, max_offset);
+
+        self.next_line.is_some()
+    }
+
+    /// Remove all shown lines and matches which depend on the content starting at `line`.
+    ///
+    /// Searching will continue at the start of the logical line containing `line`.
+    fn invalidate<T>(&mut self, term: &Term<T>, line: Line, context: usize) {
+        if line > term.bottommost_line() {
+            return;
+        }
+
+        // Matches can't span across logical lines.
+        let start = term.line_search_left(Point::new(line, Column(0))).line;
+        let matches_end =
+            self.matches.partition_point(|regex_match| regex_match.start().line < start);
+        self.matches.truncate(matches_end);
+        self.next_line = Some(start);
+
+        // Lines are shown based on matches up to `context` logical lines away.
+        let lines_start = logical_lines_before(term, start, context);
+        let lines_end = self.lines.partition_point(|shown_line| *shown_line < lines_start);
+        self.lines.truncate(lines_end);
+
+        let matches_start = logical_lines_before(term, lines_start, context);
+        let first_match =
+            self.matches.partition_point(|regex_match| regex_match.start().line < matches_start);
+        extend_lines(&mut self.lines, term, &self.matches[first_match..], context);
+    }
+
+    /// Scroll through the shown lines.
+    pub fn scroll(&mut self, scroll: Scroll, screen_lines: usize) {
+        let max_offset = self.max_display_offset(screen_lines);
+        self.display_offset = match scroll {
+            Scroll::Delta(count) => {
+                min(self.display_offset.saturating_add_signed(count as isize), max_offset)
+            },
+            Scroll::PageUp => min(self.display_offset + screen_lines, max_offset),
+            Scroll::PageDown => self.display_offset.saturating_sub(screen_lines),
+            Scroll::Top => max_offset,
+            Scroll::Bottom => 0,
+        };
+    }
+
+    /// Lines visible in the viewport, from top to bottom.
+    ///
+    /// Lines follow the content rotated into history since the last update and lines which
+    /// are no longer part of the grid are omitted.
+    pub fn visible_lines<T>(&self, term: &Term<T>) -> Vec<Line> {
+        let scrolled = self.scrolled(term);
+        let (topmost_line, bottommost_line) = (term.topmost_line(), term.bottommost_line());
+        self.visible_range(term.screen_lines())
+            .iter()
+            .map(|line| *line - scrolled)
+            .filter(|line| (topmost_line..=bottommost_line).contains(line))
+            .collect()
+    }
+
+    /// Regex matches within the visible lines.
+    pub fn visible_matches<T>(&self, term: &Term<T>) -> Vec<Match> {
+        let lines = self.visible_range(term.screen_lines());
+        let (first, last) = match (lines.first(), lines.last()) {
+            (Some(first), Some(last)) => (*first, *last),
+            _ => return Vec::new(),
+        };
+
+        let start = self.matches.partition_point(|regex_match| regex_match.end().line < first);
+        let end = self.matches.partition_point(|regex_match| regex_match.start().line <= last);
+
+        let scrolled = self.scrolled(term);
+        let topmost_line = term.topmost_line();
+        self.matches[start..end]
+            .iter()
+            .map(|regex_match| {
+                let start =
+                    Point::new(regex_match.start().line - scrolled, regex_match.start().column);
+                let end = Point::new(regex_match.end().line - scrolled, regex_match.end().column);
+                start..=end
+            })
+            .filter(|regex_match| regex_match.start().line >= topmost_line)
+            .collect()
+    }
+
+    /// Visible lines, as of the last update.
+    fn visible_range(&self, screen_lines: usize) -> &[Line] {
+        let end = self.lines.len() - self.display_offset;
+        let start = end.saturating_sub(screen_lines);
+        &self.lines[start..end]
+    }
+
+    /// Number of lines rotated into history since the last update.
+    fn scrolled<T>(&self, term: &Term<T>) -> i32 {
+        let rotated_lines = self.snapshot.map_or(0, |snapshot| snapshot.rotated_lines);
+        let scrolled = term.grid().rotated_lines() - rotated_lines;
+        scrolled.clamp(i32::MIN as i64, i32::MAX as i64) as i32
+    }
+
+    /// Number of lines which can be scrolled up.
+    fn max_display_offset(&self, screen_lines: usize) -> usize {
+        self.lines.len().saturating_sub(screen_lines)
+    }
+}
+
+/// Move lines and matches up by `scrolled` lines, removing everything above `topmost_line`.
+fn shift_lines(lines: &mut Vec<Line>, matches: &mut Vec<Match>, scrolled: i32, topmost_line: Line) {
+    if scrolled == 0 {
+        return;
+    }
+
+    lines.retain_mut(|line| {
+        *line -= scrolled;
+        *line >= topmost_line
+    });
+
+    matches.retain_mut(|regex_match| {
+        let start = Point::new(regex_match.start().line - scrolled, regex_match.start().column);
+        let end = Point::new(regex_match.end().line - scrolled, regex_match.end().column);
+        *regex_match = start..=end;
+        start.line >= topmost_line
+    });
+}
+
+/// Start of the logical line `count` logical lines before the one starting at `line`.
+fn logical_lines_before<T>(term: &Term<T>, mut line: Line, count: usize) -> Line {
+    for _ in 0..count {
+        if line <= term.topmost_line() {
+            break;
+        }
+        line = term.line_search_left(Point::new(line - 1, Column(0))).line;
+    }
+    line
+}
+
+/// Append the logical lines of all matches and their context to the shown lines.
+fn extend_lines<T>(lines: &mut Vec<Line>, term: &Term<T>, matches: &[Match], context: usize) {
+    let topmost_line = term.topmost_line();
+    let bottommost_line = term.bottommost_line();
+
+    let mut next_line = lines.last().map_or(topmost_line, |line| *line + 1);
+    for regex_match in matches {
+        // Extend match to logical lines with surrounding context.
+        let mut start = term.line_search_left(*regex_match.start()).line;
+        let mut end = term.line_search_right(*regex_match.end()).line;
+        for _ in 0..context {
+            if start > topmost_line {
+                start = term.line_search_left(Point::new(start - 1, Column(0))).line;
+            }
+            if end < bottommost_line {
+                end = term.line_search_right(Point::new(end + 1, Column(0))).line;
+            }
+        }
+
+        // Skip lines which are already shown.
+        start = start.max(next_line);
+        if start > end {
+            continue;
+        }
+
+        lines.extend((start.0..=end.0).map(Line));
+        next_line = end + 1;
+    }
+}
+
+/// Iterator over the cells of all visible filtered lines.
+///
+/// Yields every cell together with the viewport line it is shown at.
+pub struct FilterIter<'a> {
+    grid: &'a Grid<Cell>,
+    lines: Vec<Line>,
+    viewport_line: usize,
+    column: Column,
+}
+
+impl<'a> FilterIter<'a> {
+    pub fn new(grid: &'a Grid<Cell>, lines: Vec<Line>) -> Self {
+        Self { grid, lines, viewport_line: 0, column: Column(0) }
+    }
+
+    /// Grid lines shown in the viewport, from top to bottom.
+    pub fn lines(&self) -> &[Line] {
+        &self.lines
+    }
+}
+
+impl<'a> Iterator for FilterIter<'a> {
+    type Item = (usize, Indexed<&'a Cell>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.column > self.grid.last_column() {
+            self.viewport_line += 1;
+            self.column = Column(0);
+        }
+
+        // Stop at lines which are no longer part of the grid.
+        let line = *self.lines.get(self.viewport_line)?;
+        if line < self.grid.topmost_line() || line > self.grid.bottommost_line() {
+            return None;
+        }
+
+        let point = Point::new(line, self.column);
+        self.column += 1;
+
+        Some((self.viewport_line, Indexed { point, cell: &self.grid[point] }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use larashell_terminal::term::test::mock_term;
+
+    use super::*;
+
+    #[test]
+    fn filter_lines() {
+        #[rustfmt::skip]
+        let term = mock_term("\
+            error: one\r\n\
+            info\r\n\
+            warning\r\n\
+            error: wrapped\n\
+            line\r\n\
+            info\
+        ");
+
+        let mut filter = FilterView::default();
+        filter.start();
+        filter.set_regex(RegexSearch::new("error").ok());
+        filter.update(&term, 0);
+
+        assert_eq!(filter.lines, vec![Line(0), Line(3), Line(4)]);
+        assert_eq!(filter.visible_lines(&term), vec![Line(0), Line(3), Line(4)]);
+        assert_eq!(filter.visible_matches(&term).len(), 2);
+
+        filter.scroll(Scroll::Top, 2);
+        assert_eq!(filter.visible_lines(&term), vec![Line(0), Line(3)]);
+        assert_eq!(filter.visible_matches(&term).len(), 2);
+    }
+
+    #[test]
+    fn filter_new_output() {
+        #[rustfmt::skip]
+        let mut term = mock_term("\
+            error\r\n\
+            info\r\n\
+            error\
+        ");
+
+        let mut filter = FilterView::default();
+        filter.start();
+        filter.set_regex(RegexSearch::new("error").ok());
+        filter.update(&term, 0);
+
+        assert_eq!(filter.lines, vec![Line(0), Line(2)]);
+
+        // Shown lines follow content rotated into history before the next update.
+        term.grid_mut().scroll_up(&(Line(0)..Line(3)), 1);
+        assert_eq!(filter.visible_lines(&term), vec![Line(-1), Line(1)]);
+
+        for (column, c) in "error".chars().enumerate() {
+            term.grid_mut()[Line(2)][Column(column)].c = c;
+        }
+        filter.update(&term, 0);
+
+        assert_eq!(filter.lines, vec![Line(-1), Line(1), Line(2)]);
+        assert_eq!(filter.visible_matches(&term).len(), 3);
+
+        // Lines removed from the grid are never shown.
+        term.grid_mut().clear_history();
+        assert_eq!(filter.visible_lines(&term), vec![Line(1), Line(2)]);
+        assert!(FilterIter::new(term.grid(), vec![Line(-1)]).next().is_none());
+    }
+
+    #[test]
+    fn filter_context() {
+        #[rustfmt::skip]
+        let term = mock_term("\
+            a\r\n\
+            b\r\n\
+            match\r\n\
+            c\r\n\
+            d\r\n\
+            match\
+        ");
+
+        let mut filter = FilterView::default();
+        filter.start();
+        filter.set_regex(RegexSearch::new("match").ok());
+        filter.update(&term, 1);
+
+        assert_eq!(filter.lines, vec![Line(1), Line(2), Line(3), Line(4), Line(5)]);
+    }
+}
@@ -2,6 +2,7 @@
 //! GPU drawing.
 
 use std::cmp;
+use std::collections::HashMap;
 use std::fmt::{self, Formatter};
 use std::mem::{self, ManuallyDrop};
 use std::num::NonZeroU32;
@@ -28,6 +29,7 @@ use larashell_terminal::grid::Dimensions as TermDimensions;
 use larashell_terminal::index::{Column, Direction, Line, Point};
 use larashell_terminal::selection::Selection;
 use larashell_terminal::term::cell::Flags;
+use larashell_terminal::term::search::CaseSensitivity;
 use larashell_terminal::term::{
     self, point_to_viewport, LineDamageBounds, Term, TermDamage, TermMode, MIN_COLUMNS,
     MIN_SCREEN_LINES,
@@ -44,19 +46,24 @@ use crate::display::color::{List, Rgb};
 use crate::display::content::{RenderableContent, RenderableCursor};
 use crate::display::cursor::IntoRects;
 use crate::display::damage::{damage_y_to_viewport_y, DamageTracker};
+use crate::display::filter::FilterView;
 use crate::display::hint::{HintMatch, HintState};
 use crate::display::meter::Meter;
 use crate::display::window::Window;
 use crate::event::{Event, EventType, Mouse, SearchState};
+use crate::fuzzy::FuzzySearch;
 use crate::message_bar::{MessageBuffer, MessageType};
+use crate::pane::{Pane, PaneId, PaneLayout, Region, SplitDirection};
 use crate::renderer::rects::{RenderLine, RenderLines, RenderRect};
 use crate::renderer::{self, GlyphCache, Renderer};
 use crate::scheduler::{Scheduler, TimerId, Topic};
 use crate::string::{ShortenDirection, StrShortener};
+use crate::tab::{TabBar, Tabs};
 
 pub mod color;
 pub mod content;
 pub mod cursor;
+pub mod filter;
 pub mod hint;
 pub mod window;
 
@@ -70,6 +77,12 @@ const FORWARD_SEARCH_LABEL: &str = "Search: ";
 /// Label for the backward terminal search bar.
 const BACKWARD_SEARCH_LABEL: &str = "Backward Search: ";
 
+/// Label for the fuzzy terminal search bar.
+const FUZZY_SEARCH_LABEL: &str = "Fuzzy Search: ";
+
+/// Label for the filter view regex bar.
+const FILTER_LABEL: &str = "Filter: ";
+
 /// The character used to shorten the visible text like uri preview or search regex.
 const SHORTENER: char = '…';
 
@@ -257,6 +270,42 @@ impl SizeInfo<f32> {
         }
     }
 
+    /// Size of a pane covering part of the terminal grid.
+    ///
+    /// The pane's padding is its offset from the window origin, so pixel coordinates in the
+    /// window can be used with the pane's size directly.
+    pub fn pane(&self, region: Region) -> SizeInfo {
+        if region.lines == self.screen_lines && region.columns == self.columns {
+            return *self;
+        }
+
+        let padding_x = self.cell_width.mul_add(region.column as f32, self.padding_x);
+        let padding_y = self.cell_height.mul_add(region.line as f32, self.padding_y);
+
+        SizeInfo {
+            width: self.cell_width.mul_add(region.columns as f32, 2. * padding_x),
+            height: self.cell_height.mul_add(region.lines as f32, 2. * padding_y),
+            cell_width: self.cell_width,
+            cell_height: self.cell_height,
+            padding_x,
+            padding_y,
+            screen_lines: cmp::max(region.lines, MIN_SCREEN_LINES),
+            columns: cmp::max(region.columns, MIN_COLUMNS),
+        }
+    }
+
+    /// Grid cell at a pixel position.
+    ///
+    /// Returns `None` if the position is inside the padding.
+    pub fn cell_at(&self, x: usize, y: usize) -> Option<(usize, usize)> {
+        let column = (x as f32 - self.padding_x) / self.cell_width;
+        let line = (y as f32 - self.padding_y) / self.cell_height;
+
+        let inside = column >= 0. && line >= 0.;
+        (inside && (column as usize) < self.columns && (line as usize) < self.screen_lines)
+            .then_some((line as usize, column as usize))
+    }
+
     #[inline]
     pub fn reserve_lines(&mut self, count: usize) {
         self.screen_lines = cmp::max(self.screen_lines.saturating_sub(count), MIN_SCREEN_LINES);
@@ -339,8 +388,12 @@ impl DisplayUpdate {
 pub struct Display {
     pub window: Window,
 
+    /// Size of the focused pane.
     pub size_info: SizeInfo,
 
+    /// Size of the whole window.
+    pub window_size: SizeInfo,
+
     /// Hint highlighted by the mouse.
     pub highlighted_hint: Option<HintMatch>,
 
@@ -352,6 +405,9 @@ pub struct Display {
     /// UI cursor visibility for blinking.
     pub cursor_hidden: bool,
 
+    /// Input is broadcast to the other windows of this window's group.
+    pub broadcast: bool,
+
     pub visual_bell: VisualBell,
 
     /// Mapped RGB values for each terminal color.
@@ -360,6 +416,9 @@ pub struct Display {
     /// State of the keyboard hints.
     pub hint_state: HintState,
 
+    /// View showing only lines matching a regex.
+    pub filter: FilterView,
+
     /// Unprocessed display updates.
     pub pending_update: DisplayUpdate,
 
@@ -393,7 +452,7 @@ pub struct Display {
 
 impl Display {
     pub fn new(
-        window: Window,
+        mut window: Window,
         gl_context: NotCurrentContext,
         config: &UiConfig,
         _tabbed: bool,
@@ -412,7 +471,10 @@ impl Display {
         let (cell_width, cell_height) = compute_cell_size(config, &metrics);
 
         // Resize the window to account for the user configured size.
-        if let Some(dimensions) = config.window.dimensions() {
+        //
+        // Drop-down windows are sized relative to the monitor instead.
+        let dimensions = config.window.dimensions().filter(|_| !config.window.dropdown());
+        if let Some(dimensions) = dimensions {
             let size = window_size(config, dimensions, cell_width, cell_height, scale_factor);
             window.request_inner_size(size);
         }
@@ -513,15 +575,18 @@ impl Display {
             damage_tracker,
             glyph_cache,
             hint_state,
+            window_size: size_info,
             size_info,
             font_size,
             window,
             pending_renderer_update: Default::default(),
+            filter: Default::default(),
             vi_highlighted_hint: Default::default(),
             highlighted_hint: Default::default(),
             hint_mouse_point: Default::default(),
             pending_update: Default::default(),
             cursor_hidden: Default::default(),
+            broadcast: Default::default(),
             meter: Default::default(),
             ime: Default::default(),
         })
@@ -597,6 +662,7 @@ impl Display {
         &mut self,
         terminal: &mut Term<T>,
         pty_resize_handle: &mut dyn OnResize,
+        tabs: &mut Tabs,
         message_buffer: &MessageBuffer,
         search_state: &mut SearchState,
         config: &UiConfig,
@@ -626,7 +692,7 @@ impl Display {
             self.damage_tracker.frame().mark_fully_damaged();
         }
 
-        let (mut width, mut height) = (self.size_info.width(), self.size_info.height());
+        let (mut width, mut height) = (self.window_size.width(), self.window_size.height());
         if let Some(dimensions) = pending_update.dimensions() {
             width = dimensions.width as f32;
             height = dimensions.height as f32;
@@ -655,22 +721,32 @@ impl Display {
             self.window.set_resize_increments(PhysicalSize::new(cell_width, cell_height));
         }
 
+        // Split the remaining grid between the tab bar and the panes.
+        tabs.resize(new_size.screen_lines(), new_size.columns());
+        let layout = tabs.active();
+        let pane_size =
+            layout.region(layout.focused()).map_or(new_size, |region| new_size.pane(region));
+
         // Resize when terminal when its dimensions have changed.
-        if self.size_info.screen_lines() != new_size.screen_lines
-            || self.size_info.columns() != new_size.columns()
+        if self.size_info.screen_lines() != pane_size.screen_lines
+            || self.size_info.columns() != pane_size.columns()
         {
             // Resize PTY.
-            pty_resize_handle.on_resize(new_size.into());
+            pty_resize_handle.on_resize(pane_size.into());
 
             // Resize terminal.
-            terminal.resize(new_size);
+            terminal.resize(pane_size);
+        }
 
-            // Resize damage tracking.
+        // Resize damage tracking.
+        if self.window_size.screen_lines() != new_size.screen_lines
+            || self.window_size.columns() != new_size.columns()
+        {
             self.damage_tracker.resize(new_size.screen_lines(), new_size.columns());
         }
 
         // Check if dimensions have changed.
-        if new_size != self.size_info {
+        if new_size != self.window_size || pane_size != self.size_info {
             // Queue renderer update.
             let renderer_update = self.pending_renderer_update.get_or_insert(Default::default());
             renderer_update.resize = true;
@@ -678,7 +754,8 @@ impl Display {
             // Clear focused search match.
             search_state.clear_focused_match();
         }
-        self.size_info = new_size;
+        self.window_size = new_size;
+        self.size_info = pane_size;
     }
 
     // NOTE: Renderer updates are split off, since platforms like Wayland require resize and other
@@ -694,8 +771,8 @@ impl Display {
 
         // Resize renderer.
         if renderer_update.resize {
-            let width = NonZeroU32::new(self.size_info.width() as u32).unwrap();
-            let height = NonZeroU32::new(self.size_info.height() as u32).unwrap();
+            let width = NonZeroU32::new(self.window_size.width() as u32).unwrap();
+            let height = NonZeroU32::new(self.window_size.height() as u32).unwrap();
             self.surface.resize(&self.context, width, height);
         }
 
@@ -706,10 +783,10 @@ impl Display {
             self.reset_glyph_cache();
         }
 
-        self.renderer.resize(&self.size_info);
+        self.renderer.resize(&self.window_size);
 
-        info!("Padding: {} x {}", self.size_info.padding_x(), self.size_info.padding_y());
-        info!("Width: {}, Height: {}", self.size_info.width(), self.size_info.height());
+        info!("Padding: {} x {}", self.window_size.padding_x(), self.window_size.padding_y());
+        info!("Width: {}, Height: {}", self.window_size.width(), self.window_size.height());
     }
 
     /// Draw the screen.
@@ -717,9 +794,13 @@ impl Display {
     /// A reference to Term whose state is being drawn must be provided.
     ///
     /// This call may block if vsync is enabled.
+    #[allow(clippy::too_many_arguments)]
     pub fn draw<T: EventListener>(
         &mut self,
         mut terminal: MutexGuard<'_, Term<T>>,
+        layout: &PaneLayout,
+        panes: &HashMap<PaneId, Pane>,
+        tab_bar: Option<&TabBar>,
         scheduler: &mut Scheduler,
         message_buffer: &MessageBuffer,
         config: &UiConfig,
@@ -741,12 +822,56 @@ impl Display {
         let total_lines = terminal.grid().total_lines();
         let metrics = self.glyph_cache.font_metrics();
         let size_info = self.size_info;
+        let window_size = self.window_size;
 
         let vi_mode = terminal.mode().contains(TermMode::VI);
         let vi_cursor_point = if vi_mode { Some(terminal.vi_mode_cursor.point) } else { None };
+        let vi_cursor_viewport_point =
+            vi_cursor_point.and_then(|cursor| point_to_viewport(display_offset, cursor));
+
+        // Shape ligatures, splitting them up where the cursors or the selection enter them.
+        //
+        // The text cursor splits ligatures even while it is hidden, so they don't flicker with
+        // the cursor's blinking.
+        #[cfg(not(any(target_os = "macos", windows)))]
+        let ligatures = match self.glyph_cache.shaper_mut() {
+            Some(shaper) => {
+                let text_cursor = point_to_viewport(display_offset, cursor_point);
+                content::shape_cells(&mut grid_cells, shaper, |cell| {
+                    let point = term::viewport_to_point(display_offset, cell.point);
+                    Some(cell.point) == text_cursor
+                        || Some(cell.point) == vi_cursor_viewport_point
+                        || selection_range.is_some_and(|range| range.contains(point))
+                })
+            },
+            None => Vec::new(),
+        };
+        #[cfg(any(target_os = "macos", windows))]
+        let ligatures = Vec::new();
+
+        // Collect visible lines holding a bookmark.
+        let marked = |line: Line| terminal.grid().mark(line).is_some();
+        let marked_lines: Vec<usize> = if self.filter.filtering() {
+            let lines = self.filter.visible_lines(&terminal);
+            (0..lines.len()).filter(|&line| marked(lines[line])).collect()
+        } else {
+            (0..terminal.screen_lines())
+                .filter(|&line| marked(Line(line as i32) - display_offset))
+                .collect()
+        };
 
         // Add damage from the terminal.
         if self.collect_damage() {
+            // Damage is tracked in grid columns, which do not match the screen on lines with
+            // double width characters.
+            let has_double_width_lines = (0..terminal.screen_lines()).any(|line| {
+                let line = Line(line as i32) - display_offset;
+                terminal.grid()[line].line_attribute().is_double_width()
+            });
+            if has_double_width_lines {
+                self.damage_tracker.frame().mark_fully_damaged();
+            }
+
             match terminal.damage() {
                 TermDamage::Full => self.damage_tracker.frame().mark_fully_damaged(),
                 TermDamage::Partial(damaged_lines) => {
@@ -764,7 +889,11 @@ impl Display {
         // Add damage from larashell's UI elements overlapping terminal.
         if self.collect_damage() {
             let requires_full_damage = self.visual_bell.intensity() != 0.
+                || self.broadcast
+                || layout.is_split()
+                || tab_bar.is_some()
                 || self.hint_state.active()
+                || self.filter.active()
                 || search_state.regex().is_some();
 
             if requires_full_damage {
@@ -772,11 +901,9 @@ impl Display {
                 self.damage_tracker.next_frame().mark_fully_damaged();
             }
 
-            let vi_cursor_viewport_point =
-                vi_cursor_point.and_then(|cursor| point_to_viewport(display_offset, cursor));
-
             self.damage_tracker.damage_vi_cursor(vi_cursor_viewport_point);
             self.damage_tracker.damage_selection(selection_range, display_offset);
+            self.damage_tracker.damage_ligatures(ligatures);
         }
 
         // Make sure this window's OpenGL context is active.
@@ -785,6 +912,14 @@ impl Display {
         self.renderer.clear(background_color, config.window_opacity());
         let mut lines = RenderLines::new();
 
+        // Draw the panes without keyboard focus.
+        let mut rects = Vec::new();
+        for (id, _) in layout.visible().iter().filter(|(id, _)| *id != layout.focused()) {
+            if let Some(pane) = panes.get(id) {
+                self.draw_inactive_pane(config, pane, &mut rects);
+            }
+        }
+
         // Optimize loop hint comparator.
         let has_highlighted_hint =
             self.highlighted_hint.is_some() || self.vi_highlighted_hint.is_some();
@@ -793,9 +928,9 @@ impl Display {
         {
             let _sampler = self.meter.sampler();
 
-            // Ensure macOS hasn't reset our viewport.
-            #[cfg(target_os = "macos")]
-            self.renderer.set_viewport(&size_info);
+            // Limit rendering to the focused pane, this also ensures macOS hasn't reset our
+            // viewport.
+            self.renderer.set_pane_viewport(&window_size, &size_info);
 
             let glyph_cache = &mut self.glyph_cache;
             let highlighted_hint = &self.highlighted_hint;
@@ -834,7 +969,7 @@ impl Display {
             );
         }
 
-        let mut rects = lines.rects(&metrics, &size_info);
+        rects.extend(lines.rects(&metrics, &size_info));
 
         if let Some(vi_cursor_point) = vi_cursor_point {
             // Indicate vi mode by showing the cursor's position in the top right corner.
@@ -851,14 +986,27 @@ impl Display {
         // Draw cursor.
         rects.extend(cursor.rects(&size_info, config.cursor.thickness()));
 
+        // Draw bookmark indicators.
+        if !marked_lines.is_empty() {
+            self.draw_marks(config, &marked_lines, &mut rects);
+        }
+
+        // Draw borders between the panes.
+        self.draw_pane_borders(config, layout, &mut rects);
+
+        // Frame windows broadcasting their input.
+        if self.broadcast {
+            self.draw_broadcast_frame(config, &mut rects);
+        }
+
         // Push visual bell after url/underline/strikeout rects.
         let visual_bell_intensity = self.visual_bell.intensity();
         if visual_bell_intensity != 0. {
             let visual_bell_rect = RenderRect::new(
                 0.,
                 0.,
-                size_info.width(),
-                size_info.height(),
+                window_size.width(),
+                window_size.height(),
                 config.bell.color,
                 visual_bell_intensity as f32,
             );
@@ -869,17 +1017,32 @@ impl Display {
         let ime_position = match search_state.regex() {
             Some(regex) => {
                 let search_label = match search_state.direction() {
+                    _ if search_state.fuzzy().is_some() => FUZZY_SEARCH_LABEL,
+                    _ if search_state.filter() => FILTER_LABEL,
                     Direction::Right => FORWARD_SEARCH_LABEL,
                     Direction::Left => BACKWARD_SEARCH_LABEL,
                 };
 
-                let search_text = Self::format_search(regex, search_label, size_info.columns());
+                // Hide search status when there isn't enough space for it.
+                let mut search_status = Self::format_search_status(search_state);
+                if search_label.len() + search_status.len() + 1 > window_size.columns() {
+                    search_status.clear();
+                }
+
+                let search_width = window_size.columns() - search_status.len();
+                let search_text = Self::format_search(regex, search_label, search_width);
 
                 // Render the search bar.
-                self.draw_search(config, &search_text);
+                self.renderer.set_pane_viewport(&window_size, &window_size);
+                self.draw_search(config, &search_text, &search_status);
+
+                // Render the fuzzy search results above the search bar.
+                if let Some(fuzzy) = search_state.fuzzy() {
+                    self.draw_fuzzy_results(config, fuzzy);
+                }
 
                 // Draw search bar cursor.
-                let line = size_info.screen_lines();
+                let line = window_size.screen_lines();
                 let column = Column(search_text.chars().count() - 1);
 
                 // Add cursor to search bar if IME is not active.
@@ -887,38 +1050,47 @@ impl Display {
                     let fg = config.colors.footer_bar_foreground();
                     let shape = CursorShape::Underline;
                     let cursor = RenderableCursor::new(Point::new(line, column), shape, fg, false);
-                    rects.extend(cursor.rects(&size_info, config.cursor.thickness()));
+                    rects.extend(cursor.rects(&window_size, config.cursor.thickness()));
                 }
 
-                Some(Point::new(line, column))
+                Some((Point::new(line, column), window_size))
             },
             None => {
                 let num_lines = self.size_info.screen_lines();
                 term::point_to_viewport(display_offset, cursor_point)
                     .filter(|point| point.line < num_lines)
+                    .map(|point| (point, size_info))
             },
         };
 
         // Handle IME.
         if self.ime.is_enabled() {
-            if let Some(point) = ime_position {
+            if let Some((point, ime_size)) = ime_position {
                 let (fg, bg) = if search_state.regex().is_some() {
                     (config.colors.footer_bar_foreground(), config.colors.footer_bar_background())
                 } else {
                     (foreground_color, background_color)
                 };
 
-                self.draw_ime_preview(point, fg, bg, &mut rects, config);
+                self.renderer.set_pane_viewport(&window_size, &ime_size);
+                self.draw_ime_preview(point, ime_size, fg, bg, &mut rects, config);
             }
         }
 
+        // Draw the remaining UI elements across the whole window.
+        self.renderer.set_pane_viewport(&window_size, &window_size);
+
+        if let Some(tab_bar) = tab_bar {
+            self.draw_tab_bar(config, tab_bar);
+        }
+
         if let Some(message) = message_buffer.message() {
             let search_offset = usize::from(search_state.regex().is_some());
-            let text = message.text(&size_info);
+            let text = message.text(&window_size);
 
             // Create a new rectangle for the background.
-            let start_line = size_info.screen_lines() + search_offset;
-            let y = size_info.cell_height().mul_add(start_line as f32, size_info.padding_y());
+            let start_line = window_size.screen_lines() + search_offset;
+            let y = window_size.cell_height().mul_add(start_line as f32, window_size.padding_y());
 
             let bg = match message.ty() {
                 MessageType::Error => config.colors.normal.red,
@@ -926,8 +1098,8 @@ impl Display {
             };
 
             let x = 0;
-            let width = size_info.width() as i32;
-            let height = (size_info.height() - y) as i32;
+            let width = window_size.width() as i32;
+            let height = (window_size.height() - y) as i32;
             let message_bar_rect =
                 RenderRect::new(x as f32, y, width as f32, height as f32, bg, 1.);
 
@@ -935,10 +1107,10 @@ impl Display {
             rects.push(message_bar_rect);
 
             // Always damage message bar, since it could have messages of the same size in it.
-            self.damage_tracker.frame().add_viewport_rect(&size_info, x, y as i32, width, height);
+            self.damage_tracker.frame().add_viewport_rect(&window_size, x, y as i32, width, height);
 
             // Draw rectangles.
-            self.renderer.draw_rects(&size_info, &metrics, rects);
+            self.renderer.draw_rects(&window_size, &metrics, rects);
 
             // Relay messages to the user.
             let glyph_cache = &mut self.glyph_cache;
@@ -950,13 +1122,13 @@ impl Display {
                     fg,
                     bg,
                     message_text.chars(),
-                    &size_info,
+                    &window_size,
                     glyph_cache,
                 );
             }
         } else {
             // Draw rectangles.
-            self.renderer.draw_rects(&size_info, &metrics, rects);
+            self.renderer.draw_rects(&window_size, &metrics, rects);
         }
 
         self.draw_render_timer(config);
@@ -964,6 +1136,7 @@ impl Display {
         // Draw hyperlink uri preview.
         if has_highlighted_hint {
             let cursor_point = vi_cursor_point.or(Some(cursor_point));
+            self.renderer.set_pane_viewport(&window_size, &size_info);
             self.draw_hyperlink_preview(config, cursor_point, display_offset);
         }
 
@@ -972,10 +1145,10 @@ impl Display {
 
         // Highlight damage for debugging.
         if self.damage_tracker.debug {
-            let damage = self.damage_tracker.shape_frame_damage(self.size_info.into());
+            let damage = self.damage_tracker.shape_frame_damage(self.window_size.into());
             let mut rects = Vec::with_capacity(damage.len());
             self.highlight_damage(&mut rects);
-            self.renderer.draw_rects(&self.size_info, &metrics, rects);
+            self.renderer.draw_rects(&self.window_size, &metrics, rects);
         }
 
         // Clearing debug highlights from the previous frame requires full redraw.
@@ -1033,7 +1206,7 @@ impl Display {
         }
 
         // Find highlighted hint at mouse position.
-        let point = mouse.point(&self.size_info, term.grid().display_offset());
+        let point = mouse.point(&self.size_info, term.grid());
         let highlighted_hint = hint::highlighted_at(term, config, point, modifiers);
 
         // Update cursor shape.
@@ -1059,9 +1232,11 @@ impl Display {
     }
 
     #[inline(never)]
+    #[allow(clippy::too_many_arguments)]
     fn draw_ime_preview(
         &mut self,
         point: Point<usize>,
+        size_info: SizeInfo,
         fg: Rgb,
         bg: Rgb,
         rects: &mut Vec<RenderRect>,
@@ -1071,12 +1246,12 @@ impl Display {
             Some(preedit) => preedit,
             None => {
                 // In case we don't have preedit, just set the popup point.
-                self.window.update_ime_position(point, &self.size_info);
+                self.window.update_ime_position(point, &size_info);
                 return;
             },
         };
 
-        let num_cols = self.size_info.columns();
+        let num_cols = size_info.columns();
 
         // Get the visible preedit.
         let visible_text: String = match (preedit.cursor_byte_offset, preedit.cursor_end_offset) {
@@ -1103,17 +1278,10 @@ impl Display {
         let glyph_cache = &mut self.glyph_cache;
         let metrics = glyph_cache.font_metrics();
 
-        self.renderer.draw_string(
-            start,
-            fg,
-            bg,
-            visible_text.chars(),
-            &self.size_info,
-            glyph_cache,
-        );
+        self.renderer.draw_string(start, fg, bg, visible_text.chars(), &size_info, glyph_cache);
 
         // Damage preedit inside the terminal viewport.
-        if self.collect_damage() && point.line < self.size_info.screen_lines() {
+        if self.collect_damage() && point.line < size_info.screen_lines() {
             let damage = LineDamageBounds::new(start.line, 0, num_cols);
             self.damage_tracker.frame().damage_line(damage);
             self.damage_tracker.next_frame().damage_line(damage);
@@ -1121,7 +1289,7 @@ impl Display {
 
         // Add underline for preedit text.
         let underline = RenderLine { start, end, color: fg };
-        rects.extend(underline.rects(Flags::UNDERLINE, &metrics, &self.size_info));
+        rects.extend(underline.rects(Flags::UNDERLINE, &metrics, &size_info));
 
         let ime_popup_point = match preedit.cursor_end_offset {
             Some(cursor_end_offset) if cursor_end_offset != 0 => {
@@ -1137,13 +1305,13 @@ impl Display {
                 let cursor_point = Point::new(point.line, cursor_column);
                 let cursor =
                     RenderableCursor::new(cursor_point, CursorShape::HollowBlock, fg, is_wide);
-                rects.extend(cursor.rects(&self.size_info, config.cursor.thickness()));
+                rects.extend(cursor.rects(&size_info, config.cursor.thickness()));
                 cursor_point
             },
             _ => end,
         };
 
-        self.window.update_ime_position(ime_popup_point, &self.size_info);
+        self.window.update_ime_position(ime_popup_point, &size_info);
     }
 
     /// Format search regex to account for the cursor and fullwidth characters.
@@ -1170,6 +1338,41 @@ impl Display {
         bar_text
     }
 
+    /// Format search options and match count for the right side of the search bar.
+    fn format_search_status(search_state: &SearchState) -> String {
+        let options = search_state.options();
+
+        let mut status = String::new();
+        match options.case_sensitivity {
+            CaseSensitivity::Sensitive => status.push_str("[Aa] "),
+            CaseSensitivity::Insensitive => status.push_str("[aa] "),
+            CaseSensitivity::Smart => (),
+        }
+        if options.whole_word {
+            status.push_str("[word] ");
+        }
+        if options.literal {
+            status.push_str("[literal] ");
+        }
+
+        if let Some(fuzzy) = search_state.fuzzy() {
+            let total = fuzzy.results().total;
+            let index = if total == 0 { 0 } else { fuzzy.selected() + 1 };
+            status.push_str(&format!("{index}/{total} "));
+        } else if let Some(match_count) = search_state.match_count() {
+            let total = match_count.total();
+            let index = match match_count.focused_index() {
+                Some(index) => index.to_string(),
+                None if total == 0 => String::from("0"),
+                None => String::from("?"),
+            };
+            let incomplete = if match_count.is_complete() { "" } else { "+" };
+            status.push_str(&format!("{index}/{total}{incomplete} "));
+        }
+
+        status
+    }
+
     /// Draw preview for the currently highlighted `Hyperlink`.
     #[inline(never)]
     fn draw_hyperlink_preview(
@@ -1238,12 +1441,12 @@ impl Display {
 
     /// Draw current search regex.
     #[inline(never)]
-    fn draw_search(&mut self, config: &UiConfig, text: &str) {
-        // Assure text length is at least num_cols.
-        let num_cols = self.size_info.columns();
-        let text = format!("{:<1$}", text, num_cols);
+    fn draw_search(&mut self, config: &UiConfig, text: &str, status: &str) {
+        // Assure text length is at least num_cols, with the status right-aligned.
+        let num_cols = self.window_size.columns();
+        let text = format!("{:<1$}{status}", text, num_cols - status.len());
 
-        let point = Point::new(self.size_info.screen_lines(), Column(0));
+        let point = Point::new(self.window_size.screen_lines(), Column(0));
 
         let fg = config.colors.footer_bar_foreground();
         let bg = config.colors.footer_bar_background();
@@ -1253,11 +1456,108 @@ impl Display {
             fg,
             bg,
             text.chars(),
-            &self.size_info,
+            &self.window_size,
             &mut self.glyph_cache,
         );
     }
 
+    /// Draw the tab bar at the top of the window.
+    #[inline(never)]
+    fn draw_tab_bar(&mut self, config: &UiConfig, tab_bar: &TabBar) {
+        let num_cols = self.window_size.columns();
+        let fg = config.colors.tab_bar_foreground();
+        let bg = config.colors.tab_bar_background();
+
+        let mut column = 0;
+        for index in 0..tab_bar.titles.len() {
+            if column + tab_bar.tab_width > num_cols {
+                break;
+            }
+
+            // Highlight the selected tab by inverting its colors.
+            let (fg, bg) = if index == tab_bar.active { (bg, fg) } else { (fg, bg) };
+
+            let label = tab_bar.label(index);
+            let point = Point::new(0, Column(column));
+            let glyph_cache = &mut self.glyph_cache;
+            self.renderer.draw_string(point, fg, bg, label.chars(), &self.window_size, glyph_cache);
+
+            column += tab_bar.tab_width;
+        }
+
+        // Fill the remaining line with the background color.
+        let fill = " ".repeat(num_cols - column);
+        let point = Point::new(0, Column(column));
+        let glyph_cache = &mut self.glyph_cache;
+        self.renderer.draw_string(point, fg, bg, fill.chars(), &self.window_size, glyph_cache);
+    }
+
+    /// Draw the ranked fuzzy search results, with the best match right above the search bar.
+    #[inline(never)]
+    fn draw_fuzzy_results(&mut self, config: &UiConfig, fuzzy: &FuzzySearch) {
+        let num_cols = self.window_size.columns();
+        let screen_lines = self.window_size.screen_lines();
+
+        let bar_fg = config.colors.footer_bar_foreground();
+        let bar_bg = config.colors.footer_bar_background();
+        let focused = config.colors.search.focused_match;
+        let matched = config.colors.search.matches;
+
+        let results = fuzzy.results().matches.iter().take(screen_lines).enumerate();
+        for (index, fuzzy_match) in results {
+            let line = screen_lines - 1 - index;
+
+            let (fg, bg) = if index == fuzzy.selected() {
+                (focused.foreground.color(bar_fg, bar_bg), focused.background.color(bar_fg, bar_bg))
+            } else {
+                (bar_fg, bar_bg)
+            };
+            let match_fg = matched.foreground.color(fg, bg);
+            let match_bg = matched.background.color(fg, bg);
+
+            // Split the text into runs of matched and unmatched characters.
+            let mut runs: Vec<(usize, bool, String)> = Vec::new();
+            let mut column = 0;
+            for (i, c) in fuzzy_match.text.chars().enumerate() {
+                let width = c.width().unwrap_or(0);
+                if width == 0 {
+                    continue;
+                } else if column + width > num_cols {
+                    break;
+                }
+
+                let is_match = fuzzy_match.positions.binary_search(&i).is_ok();
+                match runs.last_mut() {
+                    Some((_, run_matched, run)) if *run_matched == is_match => run.push(c),
+                    _ => runs.push((column, is_match, c.to_string())),
+                }
+
+                // Add spacer after wide characters, to keep the column offsets intact.
+                if width == 2 {
+                    runs.last_mut().unwrap().2.push(' ');
+                }
+                column += width;
+            }
+
+            // Fill the remaining line with the background color.
+            runs.push((column, false, " ".repeat(num_cols - column)));
+
+            for (column, is_match, run) in runs {
+                let (fg, bg) = if is_match { (match_fg, match_bg) } else { (fg, bg) };
+                let point = Point::new(line, Column(column));
+                let glyph_cache = &mut self.glyph_cache;
+                self.renderer.draw_string(
+                    point,
+                    fg,
+                    bg,
+                    run.chars(),
+                    &self.window_size,
+                    glyph_cache,
+                );
+            }
+        }
+    }
+
     /// Draw render timer.
     #[inline(never)]
     fn draw_render_timer(&mut self, config: &UiConfig) {
@@ -1266,7 +1566,7 @@ impl Display {
         }
 
         let timing = format!("{:.3} usec", self.meter.average());
-        let point = Point::new(self.size_info.screen_lines().saturating_sub(2), Column(0));
+        let point = Point::new(self.window_size.screen_lines().saturating_sub(2), Column(0));
         let fg = config.colors.primary.background;
         let bg = config.colors.normal.red;
 
@@ -1278,7 +1578,7 @@ impl Display {
         }
 
         let glyph_cache = &mut self.glyph_cache;
-        self.renderer.draw_string(point, fg, bg, timing.chars(), &self.size_info, glyph_cache);
+        self.renderer.draw_string(point, fg, bg, timing.chars(), &self.window_size, glyph_cache);
     }
 
     /// Draw an indicator for the position of a line in history.
@@ -1313,6 +1613,181 @@ impl Display {
         }
     }
 
+    /// Draw indicators for bookmarked lines at the left edge of the text area.
+    fn draw_marks(&mut self, config: &UiConfig, lines: &[usize], rects: &mut Vec<RenderRect>) {
+        let size_info = self.size_info;
+        let width = (size_info.cell_width() / 4.).max(2.);
+        let height = size_info.cell_height();
+
+        // Place the indicator inside the padding whenever possible.
+        let x = (size_info.padding_x() - width).max(0.);
+
+        let colors = &config.colors;
+        let color = colors.line_indicator.background.unwrap_or(colors.primary.foreground);
+
+        for &line in lines {
+            let y = height.mul_add(line as f32, size_info.padding_y());
+            rects.push(RenderRect::new(x, y, width, height, color, 1.));
+
+            if self.collect_damage() {
+                let (x, y) = (x as i32, y as i32);
+                let (width, height) = (width.ceil() as i32, height as i32);
+                let window_size = &self.window_size;
+                self.damage_tracker.frame().add_viewport_rect(window_size, x, y, width, height);
+                // Damage it on the next frame in case it goes away.
+                self.damage_tracker.next_frame().add_viewport_rect(
+                    window_size,
+                    x,
+                    y,
+                    width,
+                    height,
+                );
+            }
+        }
+    }
+
+    /// Draw the terminal of a pane without keyboard focus.
+    fn draw_inactive_pane(&mut self, config: &UiConfig, pane: &Pane, rects: &mut Vec<RenderRect>) {
+        let size_info = pane.size_info;
+
+        // Collect renderable content before the terminal is dropped.
+        let terminal = pane.terminal.lock();
+        let mut content = RenderableContent::inactive(config, &self.colors, &size_info, &terminal);
+        let mut lines = RenderLines::new();
+        let mut cells = Vec::new();
+        for cell in &mut content {
+            lines.update(&cell);
+            cells.push(cell);
+        }
+        let cursor = content.cursor();
+
+        #[cfg(not(any(target_os = "macos", windows)))]
+        if let Some(shaper) = self.glyph_cache.shaper_mut() {
+            let grid = terminal.grid();
+            let text_cursor = point_to_viewport(grid.display_offset(), grid.cursor.point);
+            content::shape_cells(&mut cells, shaper, |cell| Some(cell.point) == text_cursor);
+        }
+
+        drop(terminal);
+
+        self.renderer.set_pane_viewport(&self.window_size, &size_info);
+        self.renderer.draw_cells(&size_info, &mut self.glyph_cache, cells.into_iter());
+
+        let metrics = self.glyph_cache.font_metrics();
+        rects.extend(lines.rects(&metrics, &size_info));
+        rects.extend(cursor.rects(&size_info, config.cursor.thickness()));
+    }
+
+    /// Draw a frame around the window, indicating that input is broadcast.
+    fn draw_broadcast_frame(&self, config: &UiConfig, rects: &mut Vec<RenderRect>) {
+        let (width, height) = (self.window_size.width(), self.window_size.height());
+        let thickness = (2. * self.window.scale_factor as f32).round();
+        let color = config.colors.normal.red;
+
+        rects.push(RenderRect::new(0., 0., width, thickness, color, 1.));
+        rects.push(RenderRect::new(0., height - thickness, width, thickness, color, 1.));
+        rects.push(RenderRect::new(0., 0., thickness, height, color, 1.));
+        rects.push(RenderRect::new(width - thickness, 0., thickness, height, color, 1.));
+    }
+
+    /// Draw the borders between panes, highlighting the borders of the focused pane.
+    fn draw_pane_borders(
+        &self,
+        config: &UiConfig,
+        layout: &PaneLayout,
+        rects: &mut Vec<RenderRect>,
+    ) {
+        let size = self.window_size;
+        let (cell_width, cell_height) = (size.cell_width(), size.cell_height());
+        let thickness = (self.window.scale_factor as f32).round().max(1.);
+
+        let active = config.colors.pane_border_active();
+        let inactive = config.colors.pane_border_inactive();
+        let focused = layout.region(layout.focused());
+
+        for divider in layout.dividers() {
+            let region = divider.region;
+
+            // Range of lines or columns along the divider, and the part bordering the focused pane.
+            let (start, end, focused_range) = match divider.direction {
+                SplitDirection::Right => {
+                    let focused = focused.filter(|focused| {
+                        focused.column + focused.columns == region.column
+                            || region.column + 1 == focused.column
+                    });
+                    let range = focused.map(|focused| (focused.line, focused.line + focused.lines));
+                    (region.line, region.line + region.lines, range)
+                },
+                SplitDirection::Down => {
+                    let focused = focused.filter(|focused| {
+                        focused.line + focused.lines == region.line
+                            || region.line + 1 == focused.line
+                    });
+                    let range =
+                        focused.map(|focused| (focused.column, focused.column + focused.columns));
+                    (region.column, region.column + region.columns, range)
+                },
+            };
+            let (focused_start, focused_end) = focused_range
+                .map_or((end, end), |(s, e)| (s.clamp(start, end), e.clamp(start, end)));
+
+            let segments = [
+                (start, focused_start, inactive),
+                (focused_start, focused_end, active),
+                (focused_end, end, inactive),
+            ];
+            for (segment_start, segment_end, color) in segments {
+                if segment_start >= segment_end {
+                    continue;
+                }
+
+                // Extend the line to the center of the dividers it touches.
+                let (mut min, mut max) = (segment_start as f32, segment_end as f32);
+                if segment_start == start && start > 0 {
+                    min -= 0.5;
+                }
+
+                let rect = match divider.direction {
+                    SplitDirection::Right => {
+                        if segment_end == end && end < size.screen_lines() {
+                            max += 0.5;
+                        }
+
+                        let x = cell_width.mul_add(region.column as f32 + 0.5, size.padding_x());
+                        let y = cell_height.mul_add(min, size.padding_y());
+                        let height = (max - min) * cell_height;
+                        RenderRect::new(
+                            (x - thickness / 2.).round(),
+                            y,
+                            thickness,
+                            height,
+                            color,
+                            1.,
+                        )
+                    },
+                    SplitDirection::Down => {
+                        if segment_end == end && end < size.columns() {
+                            max += 0.5;
+                        }
+
+                        let x = cell_width.mul_add(min, size.padding_x());
+                        let y = cell_height.mul_add(region.line as f32 + 0.5, size.padding_y());
+                        let width = (max - min) * cell_width;
+                        RenderRect::new(
+                            x,
+                            (y - thickness / 2.).round(),
+                            width,
+                            thickness,
+                            color,
+                            1.,
+                        )
+                    },
+                };
+                rects.push(rect);
+            }
+        }
+    }
+
     /// Returns `true` if damage information should be collected, `false` otherwise.
     #[inline]
     fn collect_damage(&self) -> bool {
@@ -1323,11 +1798,11 @@ impl Display {
     ///
     /// This function is for debug purposes only.
     fn highlight_damage(&self, render_rects: &mut Vec<RenderRect>) {
-        for damage_rect in &self.damage_tracker.shape_frame_damage(self.size_info.into()) {
+        for damage_rect in &self.damage_tracker.shape_frame_damage(self.window_size.into()) {
             let x = damage_rect.x as f32;
             let height = damage_rect.height as f32;
             let width = damage_rect.width as f32;
-            let y = damage_y_to_viewport_y(&self.size_info, damage_rect) as f32;
+            let y = damage_y_to_viewport_y(&self.window_size, damage_rect) as f32;
             let render_rect = RenderRect::new(x, y, width, height, DAMAGE_RECT_COLOR, 0.5);
 
             render_rects.push(render_rect);
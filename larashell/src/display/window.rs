@@ -17,6 +17,7 @@ use {
 };
 
 use std::fmt::{self, Display, Formatter};
+use std::time::{Duration, Instant};
 
 #[cfg(target_os = "macos")]
 use {
@@ -34,12 +35,13 @@ use winit::monitor::MonitorHandle;
 use winit::platform::windows::IconExtWindows;
 use winit::window::{
     CursorIcon, Fullscreen, ImePurpose, Theme, UserAttentionType, Window as WinitWindow,
-    WindowBuilder, WindowId,
+    WindowBuilder, WindowId, WindowLevel,
 };
 
 use larashell_terminal::index::Point;
 
-use crate::config::window::{Decorations, Identity, WindowConfig};
+use crate::config::ui_config::Percentage;
+use crate::config::window::{Class, Decorations, Identity, WindowConfig};
 use crate::config::UiConfig;
 use crate::display::SizeInfo;
 
@@ -47,6 +49,13 @@ use crate::display::SizeInfo;
 #[cfg(all(feature = "x11", not(any(target_os = "macos", windows))))]
 static WINDOW_ICON: &[u8] = include_bytes!("../../extra/logo/compat/larashell-term.png");
 
+/// Duration of the drop-down window's slide in animation.
+const DROPDOWN_SLIDE_DURATION: Duration = Duration::from_millis(150);
+
+/// Interval between the frames of the drop-down window's slide in animation.
+#[cfg(unix)]
+pub const DROPDOWN_FRAME_INTERVAL: Duration = Duration::from_millis(16);
+
 /// This should match the definition of IDI_ICON from `larashell.rc`.
 #[cfg(windows)]
 const IDI_ICON: u16 = 0x101;
@@ -112,6 +121,13 @@ pub struct Window {
     /// Current window title.
     title: String,
 
+    /// Window class, used to find and group windows.
+    class: Class,
+
+    /// Start of the drop-down window's slide in animation.
+    slide_start: Option<Instant>,
+
+    visible: bool,
     is_x11: bool,
     current_mouse_cursor: CursorIcon,
     mouse_visible: bool,
@@ -163,6 +179,17 @@ impl Window {
             window_builder = window_builder.with_embed_parent_window(parent_window_id);
         }
 
+        // Place drop-down windows at the top of the screen, above all other windows.
+        if config.window.dropdown() {
+            window_builder =
+                window_builder.with_decorations(false).with_window_level(WindowLevel::AlwaysOnTop);
+
+            if let Some(monitor) = event_loop.primary_monitor() {
+                let (position, size) = dropdown_geometry(&monitor, config.window.dropdown.height);
+                window_builder = window_builder.with_position(position).with_inner_size(size);
+            }
+        }
+
         let window = window_builder
             .with_title(&identity.title)
             .with_theme(config.window.theme())
@@ -194,6 +221,9 @@ impl Window {
         Ok(Self {
             requested_redraw: false,
             title: identity.title,
+            class: identity.class,
+            slide_start: None,
+            visible: false,
             current_mouse_cursor,
             mouse_visible: true,
             has_frame: true,
@@ -219,10 +249,70 @@ impl Window {
     }
 
     #[inline]
-    pub fn set_visible(&self, visibility: bool) {
+    pub fn set_visible(&mut self, visibility: bool) {
+        self.visible = visibility;
         self.window.set_visible(visibility);
     }
 
+    #[cfg(unix)]
+    #[inline]
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    #[cfg(unix)]
+    #[inline]
+    pub fn focus_window(&self) {
+        self.window.focus_window();
+    }
+
+    /// Get the window class.
+    #[inline]
+    pub fn class(&self) -> &Class {
+        &self.class
+    }
+
+    /// Show a drop-down window, starting its slide in animation from above the screen.
+    #[cfg(unix)]
+    pub fn show_dropdown(&mut self, height: Percentage) {
+        if let Some(monitor) = self.current_monitor() {
+            let (position, size) = dropdown_geometry(&monitor, height);
+            self.request_inner_size(size);
+
+            let start = position.y.saturating_sub(size.height as i32);
+            self.window.set_outer_position(PhysicalPosition::new(position.x, start));
+            self.slide_start = Some(Instant::now());
+        }
+
+        self.set_visible(true);
+        self.focus_window();
+    }
+
+    /// Move a drop-down window to the next position of its slide in animation.
+    ///
+    /// Returns `false` once the animation is complete.
+    pub fn slide_dropdown(&mut self, height: Percentage) -> bool {
+        let (slide_start, monitor) = match (self.slide_start, self.current_monitor()) {
+            (Some(slide_start), Some(monitor)) if self.visible => (slide_start, monitor),
+            _ => {
+                self.slide_start = None;
+                return false;
+            },
+        };
+
+        let progress = slide_start.elapsed().as_secs_f32() / DROPDOWN_SLIDE_DURATION.as_secs_f32();
+        let progress = progress.min(1.);
+        if progress >= 1. {
+            self.slide_start = None;
+        }
+
+        let (position, size) = dropdown_geometry(&monitor, height);
+        let offset = (size.height as f32 * (1. - progress)).round() as i32;
+        self.window.set_outer_position(PhysicalPosition::new(position.x, position.y - offset));
+
+        self.slide_start.is_some()
+    }
+
     /// Set the window title.
     #[inline]
     pub fn set_title(&mut self, title: String) {
@@ -483,6 +573,16 @@ impl Window {
     }
 }
 
+/// Position and size of a drop-down window spanning the top of a monitor.
+fn dropdown_geometry(
+    monitor: &MonitorHandle,
+    height: Percentage,
+) -> (PhysicalPosition<i32>, PhysicalSize<u32>) {
+    let monitor_size = monitor.size();
+    let height = (monitor_size.height as f32 * height.as_f32()).max(1.) as u32;
+    (monitor.position(), PhysicalSize::new(monitor_size.width, height))
+}
+
 #[cfg(target_os = "macos")]
 fn use_srgb_color_space(window: &WinitWindow) {
     let raw_window = match window.raw_window_handle() {
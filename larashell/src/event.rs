@@ -1,23 +1,26 @@
 //! Process window events.
 
 use std::borrow::Cow;
-use std::cmp::min;
+use std::cmp::{max, min};
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::error::Error;
 use std::ffi::OsStr;
 use std::fmt::Debug;
 #[cfg(not(windows))]
 use std::os::unix::io::RawFd;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
-use std::{env, f32, mem};
+use std::{env, f32, io, mem};
 
 use ahash::RandomState;
 use crossfont::Size as FontSize;
 use glutin::display::{Display as GlutinDisplay, GetGlDisplay};
 use log::{debug, error, info, warn};
 use raw_window_handle::HasRawDisplayHandle;
+#[cfg(unix)]
+use serde_json::Value;
 use winit::event::{
     ElementState, Event as WinitEvent, Ime, Modifiers, MouseButton, StartCause,
     Touch as TouchEvent, WindowEvent,
@@ -29,17 +32,24 @@ use winit::window::WindowId;
 
 use larashell_terminal::event::{Event as TerminalEvent, EventListener, Notify};
 use larashell_terminal::event_loop::Notifier;
-use larashell_terminal::grid::{BidirectionalIterator, Dimensions, Scroll};
+use larashell_terminal::grid::{BidirectionalIterator, Dimensions, Grid, LineMark, Scroll};
 use larashell_terminal::index::{Boundary, Column, Direction, Line, Point, Side};
 use larashell_terminal::selection::{Selection, SelectionType};
-use larashell_terminal::term::search::{Match, RegexSearch};
+use larashell_terminal::sync::FairMutex;
+use larashell_terminal::term::cell::Flags;
+use larashell_terminal::term::search::{Match, RegexIter, RegexSearch, SearchOptions};
 use larashell_terminal::term::{self, ClipboardType, Term, TermMode};
+use larashell_terminal::vi_mode::ViMotion;
 
 #[cfg(unix)]
-use crate::cli::{IpcConfig, ParsedOptions};
+use crate::cli::{IpcExport, ParsedOptions, SocketMessage, SubscriptionEvent};
 use crate::cli::{Options as CliOptions, WindowOptions};
 use crate::clipboard::Clipboard;
+#[cfg(unix)]
+use crate::config::ipc::IpcScope;
 use crate::config::ui_config::{HintAction, HintInternalAction};
+#[cfg(unix)]
+use crate::config::KeyPress;
 use crate::config::{self, UiConfig};
 #[cfg(not(windows))]
 use crate::daemon::foreground_process_path;
@@ -47,11 +57,21 @@ use crate::daemon::spawn_daemon;
 use crate::display::color::Rgb;
 use crate::display::hint::HintMatch;
 use crate::display::window::Window;
+#[cfg(unix)]
+use crate::display::window::DROPDOWN_FRAME_INTERVAL;
 use crate::display::{Display, Preedit, SizeInfo};
+use crate::export;
+use crate::fuzzy::{FuzzyResults, FuzzySearch};
+use crate::input::keyboard::BroadcastKey;
 use crate::input::{self, ActionContext as _, FONT_SIZE_STEP};
+#[cfg(unix)]
+use crate::ipc::{IpcError, IpcErrorCode, IpcEventData, IpcReplier, IpcSubscriber, IpcSubscribers};
 use crate::logging::LOG_TARGET_CONFIG;
-use crate::message_bar::{Message, MessageBuffer};
+use crate::message_bar::{Message, MessageBuffer, MessageType};
+use crate::pane::{PaneAction, PaneId};
 use crate::scheduler::{Scheduler, TimerId, Topic};
+use crate::session;
+use crate::tab::TabAction;
 use crate::window_context::WindowContext;
 
 /// Duration after the last user input until an unlimited search is performed.
@@ -63,22 +83,43 @@ const MAX_SEARCH_WHILE_TYPING: Option<usize> = Some(1000);
 /// Maximum number of search terms stored in the history.
 const MAX_SEARCH_HISTORY_SIZE: usize = 255;
 
+/// Delay between new terminal output and updating the filter view.
+const FILTER_UPDATE_DELAY: Duration = Duration::from_millis(100);
+
+/// Maximum number of lines searched at once while counting all search matches.
+const MATCH_COUNT_CHUNK_LINES: usize = 1000;
+
 /// Touch zoom speed.
 const TOUCH_ZOOM_FACTOR: f32 = 0.01;
 
+/// Message bar target of the confirmation for pasting into a broadcast group.
+const BROADCAST_PASTE_TARGET: &str = "broadcast_paste";
+
 /// LaraShell events.
 #[derive(Debug, Clone)]
 pub struct Event {
     /// Limit event to a specific window.
     window_id: Option<WindowId>,
 
+    /// Pane of the terminal which sent the event.
+    pane_id: Option<PaneId>,
+
     /// Event payload.
     payload: EventType,
 }
 
 impl Event {
     pub fn new<I: Into<Option<WindowId>>>(payload: EventType, window_id: I) -> Self {
-        Self { window_id: window_id.into(), payload }
+        Self { window_id: window_id.into(), pane_id: None, payload }
+    }
+
+    /// Pane of the terminal which sent the event.
+    pub fn pane_id(&self) -> Option<PaneId> {
+        self.pane_id
+    }
+
+    pub fn payload(&self) -> &EventType {
+        &self.payload
     }
 }
 
@@ -96,14 +137,42 @@ pub enum EventType {
     Message(Message),
     Scroll(Scroll),
     CreateWindow(WindowOptions),
+    CreateTab(WindowOptions),
+    Pane(PaneAction),
+    Tab(TabAction),
+    #[cfg(unix)]
+    Ipc(SocketMessage, IpcReplier),
+    #[cfg(unix)]
+    IpcSubscribe(IpcSubscriber, IpcReplier),
+    #[cfg(unix)]
+    SendText(String, bool),
     #[cfg(unix)]
-    IpcConfig(IpcConfig),
+    SendKeys(Vec<KeyPress>),
+    #[cfg(unix)]
+    ToggleVisibility,
+    SaveSession,
     BlinkCursor,
     BlinkCursorTimeout,
     SearchNext,
+    SearchCount,
+    FilterUpdate,
+    FuzzyResults(FuzzyResults),
+    DropdownSlide,
+    ToggleBroadcastInput,
+    BroadcastInput(BroadcastInput),
     Frame,
 }
 
+/// Input mirrored to the other windows of a broadcast group.
+#[derive(Debug, Clone)]
+pub enum BroadcastInput {
+    /// Key press or release, encoded for each window's keyboard modes.
+    Key(BroadcastKey),
+
+    /// Pasted text, with its bracketed paste flag.
+    Paste(String, bool),
+}
+
 impl From<TerminalEvent> for EventType {
     fn from(event: TerminalEvent) -> Self {
         Self::Terminal(event)
@@ -137,6 +206,18 @@ pub struct SearchState {
 
     /// Compiled search automatons.
     dfas: Option<RegexSearch>,
+
+    /// Options for matching the search regex.
+    options: SearchOptions,
+
+    /// Number of matches for the active search.
+    match_count: MatchCount,
+
+    /// Fuzzy search replacing the regex search.
+    fuzzy: Option<FuzzySearch>,
+
+    /// Whether the search regex is used for the filter view.
+    filter: bool,
 }
 
 impl SearchState {
@@ -165,6 +246,53 @@ impl SearchState {
         self.dfas.as_mut()
     }
 
+    /// Options for matching the search regex.
+    pub fn options(&self) -> SearchOptions {
+        self.options
+    }
+
+    /// Active fuzzy search.
+    pub fn fuzzy(&self) -> Option<&FuzzySearch> {
+        self.fuzzy.as_ref()
+    }
+
+    /// Whether the search regex is used for the filter view.
+    pub fn filter(&self) -> bool {
+        self.filter
+    }
+
+    /// Match count of the active search, if the search regex is valid.
+    pub fn match_count(&self) -> Option<&MatchCount> {
+        self.dfas.as_ref().map(|_| &self.match_count)
+    }
+
+    /// Send the search text to the active fuzzy search.
+    ///
+    /// Returns `false` if fuzzy search is not active.
+    fn update_fuzzy(&mut self) -> bool {
+        let query = self.history_index.and_then(|index| self.history.get(index));
+        match (&mut self.fuzzy, query) {
+            (Some(fuzzy), Some(query)) => {
+                fuzzy.search(query, self.options.case_sensitivity);
+                true
+            },
+            _ => false,
+        }
+    }
+
+    /// Pass the output of the scoring thread to the active fuzzy search.
+    ///
+    /// Returns `false` if fuzzy search is not active.
+    pub fn update_fuzzy_results(&mut self, results: FuzzyResults) -> bool {
+        match &mut self.fuzzy {
+            Some(fuzzy) => {
+                fuzzy.update(results);
+                true
+            },
+            None => false,
+        }
+    }
+
     /// Search regex text if a search is active.
     fn regex_mut(&mut self) -> Option<&mut String> {
         self.history_index.and_then(move |index| self.history.get_mut(index))
@@ -181,10 +309,73 @@ impl Default for SearchState {
             history: Default::default(),
             origin: Default::default(),
             dfas: Default::default(),
+            options: Default::default(),
+            match_count: Default::default(),
+            fuzzy: Default::default(),
+            filter: Default::default(),
         }
     }
 }
 
+/// Incrementally counted matches of the active search.
+#[derive(Default)]
+pub struct MatchCount {
+    /// Start line and number of matches of every counted chunk of lines.
+    chunks: Vec<(Line, usize)>,
+
+    /// Total number of matches counted so far.
+    total: usize,
+
+    /// First line which has not been counted yet.
+    next_line: Option<Line>,
+
+    /// One-based index of the focused match.
+    focused_index: Option<usize>,
+
+    /// Lines rotated into the scrollback history when matches were last counted.
+    rotated_lines: i64,
+
+    /// Scrollback history size when matches were last counted.
+    history_size: usize,
+
+    /// Grid dimensions and alternate screen state when matches were last counted.
+    layout: (usize, usize, bool),
+}
+
+impl MatchCount {
+    /// Start counting matches from the topmost line.
+    fn new<T>(terminal: &Term<T>) -> Self {
+        Self {
+            next_line: Some(terminal.topmost_line()),
+            rotated_lines: terminal.grid().rotated_lines(),
+            history_size: terminal.history_size(),
+            layout: Self::layout(terminal),
+            ..Default::default()
+        }
+    }
+
+    /// Grid properties which invalidate all counted lines when changed.
+    fn layout<T>(terminal: &Term<T>) -> (usize, usize, bool) {
+        let alt_screen = terminal.mode().contains(TermMode::ALT_SCREEN);
+        (terminal.columns(), terminal.screen_lines(), alt_screen)
+    }
+
+    /// Total number of matches counted so far.
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// Whether all lines have been searched for matches.
+    pub fn is_complete(&self) -> bool {
+        self.next_line.is_none()
+    }
+
+    /// One-based index of the focused match, once its line has been counted.
+    pub fn focused_index(&self) -> Option<usize> {
+        self.focused_index
+    }
+}
+
 /// Vi inline search state.
 pub struct InlineSearchState {
     /// Whether inline search is currently waiting for search character input.
@@ -209,6 +400,7 @@ impl Default for InlineSearchState {
 pub struct ActionContext<'a, N, T> {
     pub notifier: &'a mut N,
     pub terminal: &'a mut Term<T>,
+    pub shared_terminal: &'a Arc<FairMutex<Term<T>>>,
     pub clipboard: &'a mut Clipboard,
     pub mouse: &'a mut Mouse,
     pub touch: &'a mut TouchPurpose,
@@ -219,6 +411,7 @@ pub struct ActionContext<'a, N, T> {
     pub cursor_blink_timed_out: &'a mut bool,
     pub event_loop: &'a EventLoopWindowTarget<Event>,
     pub event_proxy: &'a EventLoopProxy<Event>,
+    pub pane_proxy: &'a EventProxy,
     pub scheduler: &'a mut Scheduler,
     pub search_state: &'a mut SearchState,
     pub inline_search_state: &'a mut InlineSearchState,
@@ -231,7 +424,9 @@ pub struct ActionContext<'a, N, T> {
     pub shell_pid: u32,
 }
 
-impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionContext<'a, N, T> {
+impl<'a, N: Notify + 'a, T: EventListener + Send + 'static> input::ActionContext<T>
+    for ActionContext<'a, N, T>
+{
     #[inline]
     fn write_to_pty<B: Into<Cow<'static, [u8]>>>(&self, val: B) {
         self.notifier.notify(val);
@@ -249,6 +444,13 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
     }
 
     fn scroll(&mut self, scroll: Scroll) {
+        // Scroll through the filtered lines instead of the terminal.
+        if self.display.filter.filtering() {
+            self.display.filter.scroll(scroll, self.terminal.screen_lines());
+            *self.dirty = true;
+            return;
+        }
+
         let old_offset = self.terminal.grid().display_offset() as i32;
 
         let old_vi_cursor = self.terminal.vi_mode_cursor;
@@ -269,8 +471,7 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
         } else if self.mouse.left_button_state == ElementState::Pressed
             || self.mouse.right_button_state == ElementState::Pressed
         {
-            let display_offset = self.terminal.grid().display_offset();
-            let point = self.mouse.point(&self.size_info(), display_offset);
+            let point = self.mouse.point(&self.size_info(), self.terminal.grid());
             self.update_selection(point, self.mouse.cell_side);
         }
 
@@ -398,6 +599,26 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
         self.terminal
     }
 
+    fn save_session(&mut self) {
+        let _ = self.event_proxy.send_event(Event::new(EventType::SaveSession, None));
+    }
+
+    fn export(&mut self, selection: bool) {
+        let format = self.config.export.format.into();
+        let path = export::file_path(&self.config.export, format);
+
+        // Render the content on the export thread, instead of blocking the event loop.
+        let terminal = self.shared_terminal.clone();
+        let colors = self.display.colors;
+        let content = move || export::content(&terminal.lock(), &colors, format, selection);
+
+        let done = |path: &Path, result: io::Result<()>| match result {
+            Ok(()) => info!("Exported terminal content to {path:?}"),
+            Err(err) => error!("Unable to export terminal content to {path:?}: {err}"),
+        };
+        export::spawn_write(path, true, content, done);
+    }
+
     fn spawn_new_instance(&mut self) {
         let mut env_args = env::args();
         let larashell = env_args.next().unwrap();
@@ -447,6 +668,41 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
             .send_event(Event::new(EventType::CreateWindow(WindowOptions::default()), None));
     }
 
+    #[cfg(not(target_os = "macos"))]
+    fn create_new_tab(&mut self) {
+        let mut options = WindowOptions::default();
+        #[cfg(not(windows))]
+        if let Ok(working_directory) = foreground_process_path(self.master_fd, self.shell_pid) {
+            options.terminal_options.working_directory = Some(working_directory);
+        }
+
+        let event = Event::new(EventType::CreateTab(options), self.display.window.id());
+        let _ = self.event_proxy.send_event(event);
+    }
+
+    fn pane_action(&mut self, action: PaneAction) {
+        let event = Event::new(EventType::Pane(action), self.display.window.id());
+        let _ = self.event_proxy.send_event(event);
+    }
+
+    fn tab_action(&mut self, action: TabAction) {
+        let event = Event::new(EventType::Tab(action), self.display.window.id());
+        let _ = self.event_proxy.send_event(event);
+    }
+
+    fn toggle_broadcast_input(&mut self) {
+        let event = Event::new(EventType::ToggleBroadcastInput, self.display.window.id());
+        let _ = self.event_proxy.send_event(event);
+    }
+
+    fn broadcast_input(&self, key: BroadcastKey) {
+        if self.display.broadcast {
+            let input = BroadcastInput::Key(key);
+            let event = Event::new(EventType::BroadcastInput(input), self.display.window.id());
+            let _ = self.event_proxy.send_event(event);
+        }
+    }
+
     fn spawn_daemon<I, S>(&self, program: &str, args: I)
     where
         I: IntoIterator<Item = S> + Debug + Copy,
@@ -525,8 +781,45 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
         self.display.pending_update.dirty = true;
     }
 
+    #[inline]
+    fn start_fuzzy_search(&mut self) {
+        self.start_search(Direction::Left);
+
+        // Hide regex matches of the previous search.
+        self.search_state.dfas = None;
+
+        let proxy = self.pane_proxy.clone();
+        let terminal = self.shared_terminal.clone();
+        self.search_state.fuzzy = Some(FuzzySearch::new(terminal, proxy));
+    }
+
+    #[inline]
+    fn toggle_filter(&mut self) {
+        if self.display.filter.active() {
+            self.display.filter.stop();
+            self.display.damage_tracker.frame().mark_fully_damaged();
+            self.display.pending_update.dirty = true;
+            return;
+        }
+
+        self.start_search(Direction::Left);
+        self.search_state.filter = true;
+        self.search_state.dfas = None;
+        self.display.filter.start();
+    }
+
     #[inline]
     fn confirm_search(&mut self) {
+        if self.search_state.fuzzy.is_some() {
+            self.confirm_fuzzy_search();
+            return;
+        }
+
+        if self.search_state.filter {
+            self.confirm_filter();
+            return;
+        }
+
         // Just cancel search when not in vi mode.
         if !self.terminal.mode().contains(TermMode::VI) {
             self.cancel_search();
@@ -544,6 +837,12 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
 
     #[inline]
     fn cancel_search(&mut self) {
+        if self.search_state.filter {
+            self.display.filter.stop();
+            self.exit_search();
+            return;
+        }
+
         if self.terminal.mode().contains(TermMode::VI) {
             // Recover pre-search state in vi mode.
             self.search_reset_state();
@@ -605,6 +904,13 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
     /// Go to the previous regex in the search history.
     #[inline]
     fn search_history_previous(&mut self) {
+        // Move up through the fuzzy search results instead of the history.
+        if let Some(fuzzy) = &mut self.search_state.fuzzy {
+            fuzzy.select_next();
+            *self.dirty = true;
+            return;
+        }
+
         let index = match &mut self.search_state.history_index {
             None => return,
             Some(index) if *index + 1 >= self.search_state.history.len() => return,
@@ -618,6 +924,13 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
     /// Go to the previous regex in the search history.
     #[inline]
     fn search_history_next(&mut self) {
+        // Move down through the fuzzy search results instead of the history.
+        if let Some(fuzzy) = &mut self.search_state.fuzzy {
+            fuzzy.select_previous();
+            *self.dirty = true;
+            return;
+        }
+
         let index = match &mut self.search_state.history_index {
             Some(0) | None => return,
             Some(index) => index,
@@ -627,8 +940,31 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
         self.update_search();
     }
 
+    #[inline]
+    fn update_search_options(&mut self, update: fn(&mut SearchOptions)) {
+        update(&mut self.search_state.options);
+
+        // Rebuild the search dfas with the new options.
+        let timer_id = TimerId::new(Topic::DelayedSearch, self.display.window.id());
+        self.scheduler.unschedule(timer_id);
+        self.search_reset_state();
+        self.update_search();
+
+        self.display.pending_update.dirty = true;
+    }
+
     #[inline]
     fn advance_search_origin(&mut self, direction: Direction) {
+        if self.search_state.fuzzy.is_some() {
+            self.confirm_fuzzy_search();
+            return;
+        }
+
+        if self.search_state.filter {
+            self.confirm_filter();
+            return;
+        }
+
         // Use focused match as new search origin if available.
         if let Some(focused_match) = &self.search_state.focused_match {
             let new_origin = match direction {
@@ -787,8 +1123,7 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
         };
 
         // Load mouse point, treating message bar and padding as the closest cell.
-        let display_offset = self.terminal().grid().display_offset();
-        let point = self.mouse().point(&self.size_info(), display_offset);
+        let point = self.mouse().point(&self.size_info(), self.terminal().grid());
 
         let cell_side = self.mouse().cell_side;
 
@@ -822,41 +1157,21 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
             for c in text.chars() {
                 self.search_input(c);
             }
-        } else if bracketed && self.terminal().mode().contains(TermMode::BRACKETED_PASTE) {
-            self.on_terminal_input_start();
-
-            self.write_to_pty(&b"\x1b[200~"[..]);
-
-            // Write filtered escape sequences.
-            //
-            // We remove `\x1b` to ensure it's impossible for the pasted text to write the bracketed
-            // paste end escape `\x1b[201~` and `\x03` since some shells incorrectly terminate
-            // bracketed paste when they receive it.
-            let filtered = text.replace(['\x1b', '\x03'], "");
-            self.write_to_pty(filtered.into_bytes());
-
-            self.write_to_pty(&b"\x1b[201~"[..]);
-        } else {
-            self.on_terminal_input_start();
+            return;
+        }
 
-            let payload = if bracketed {
-                // In non-bracketed (ie: normal) mode, terminal applications cannot distinguish
-                // pasted data from keystrokes.
-                //
-                // In theory, we should construct the keystrokes needed to produce the data we are
-                // pasting... since that's neither practical nor sensible (and probably an
-                // impossible task to solve in a general way), we'll just replace line breaks
-                // (windows and unix style) with a single carriage return (\r, which is what the
-                // Enter key produces).
-                text.replace("\r\n", "\r").replace('\n', "\r").into_bytes()
-            } else {
-                // When we explicitly disable bracketed paste don't manipulate with the input,
-                // so we pass user input as is.
-                text.to_owned().into_bytes()
-            };
+        if self.display.broadcast {
+            // Confirm pasting multiple lines once, for all windows of the group.
+            if text.contains('\n') && !self.confirm_broadcast_paste(text) {
+                return;
+            }
 
-            self.write_to_pty(payload);
+            let input = BroadcastInput::Paste(text.into(), bracketed);
+            let event = Event::new(EventType::BroadcastInput(input), self.display.window.id());
+            let _ = self.event_proxy.send_event(event);
         }
+
+        self.write_paste(text, bracketed);
     }
 
     /// Toggle the vi mode status.
@@ -897,6 +1212,25 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
         self.inline_search_state.char_pending = true;
     }
 
+    /// Mark the vi mode cursor's line with a named mark.
+    fn set_vi_mark(&mut self, name: char) {
+        let line = self.terminal.vi_mode_cursor.point.line;
+        self.terminal.grid_mut().set_mark(line, Some(LineMark::Named(name)));
+        self.mark_dirty();
+    }
+
+    /// Move the vi mode cursor to the line with a named mark.
+    fn jump_to_vi_mark(&mut self, name: char) {
+        let line = match self.terminal.grid().find_mark(LineMark::Named(name)) {
+            Some(line) => line,
+            None => return,
+        };
+
+        self.terminal.vi_goto_point(Point::new(line, Column(0)));
+        self.terminal.vi_motion(ViMotion::FirstOccupied);
+        self.mark_dirty();
+    }
+
     /// Jump to the next matching character in the line.
     fn inline_search_next(&mut self) {
         let direction = self.inline_search_state.direction;
@@ -931,8 +1265,14 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
     }
 }
 
-impl<'a, N: Notify + 'a, T: EventListener> ActionContext<'a, N, T> {
+impl<'a, N: Notify + 'a, T: EventListener + Send + 'static> ActionContext<'a, N, T> {
     fn update_search(&mut self) {
+        // Score lines on the fuzzy search thread.
+        if self.search_state.update_fuzzy() {
+            *self.dirty = true;
+            return;
+        }
+
         let regex = match self.search_state.regex() {
             Some(regex) => regex,
             None => return,
@@ -943,18 +1283,31 @@ impl<'a, N: Notify + 'a, T: EventListener> ActionContext<'a, N, T> {
             self.display.window.set_mouse_visible(false);
         }
 
+        if self.search_state.filter {
+            // Show only the lines matching the new regex.
+            let options = self.search_state.options;
+            let regex =
+                RegexSearch::with_options(regex, options).ok().filter(|_| !regex.is_empty());
+            self.display.filter.set_regex(regex);
+            self.update_filter();
+            return;
+        }
+
         if regex.is_empty() {
             // Stop search if there's nothing to search for.
             self.search_reset_state();
             self.search_state.dfas = None;
         } else {
             // Create search dfas for the new regex string.
-            self.search_state.dfas = RegexSearch::new(regex).ok();
+            let options = self.search_state.options;
+            self.search_state.dfas = RegexSearch::with_options(regex, options).ok();
 
             // Update search highlighting.
             self.goto_match(MAX_SEARCH_WHILE_TYPING);
         }
 
+        self.restart_match_count();
+
         *self.dirty = true;
     }
 
@@ -1009,6 +1362,7 @@ impl<'a, N: Notify + 'a, T: EventListener> ActionContext<'a, N, T> {
 
                 // Update the focused match.
                 self.search_state.focused_match = Some(regex_match);
+                self.update_focused_index();
 
                 // Store number of lines the viewport had to be moved.
                 let display_offset = self.terminal.grid().display_offset();
@@ -1030,12 +1384,240 @@ impl<'a, N: Notify + 'a, T: EventListener> ActionContext<'a, N, T> {
 
                 // Clear focused match.
                 self.search_state.focused_match = None;
+                self.search_state.match_count.focused_index = None;
             },
         }
 
         *self.dirty = true;
     }
 
+    /// Jump to the selected fuzzy search result and leave search.
+    fn confirm_fuzzy_search(&mut self) {
+        let fuzzy_match = self.search_state.fuzzy.as_ref().and_then(FuzzySearch::selected_match);
+        if let Some(position) = fuzzy_match.map(|fuzzy_match| fuzzy_match.position) {
+            // Find the line again, since more lines might have been rotated into history.
+            let line = position - self.terminal.grid().rotated_lines();
+            let line = Line(i32::try_from(line).unwrap_or(i32::MIN));
+            let point = Point::new(line, Column(0)).grid_clamp(self.terminal, Boundary::Grid);
+            if self.terminal.mode().contains(TermMode::VI) {
+                self.terminal.vi_goto_point(point);
+                self.terminal.vi_motion(ViMotion::FirstOccupied);
+            } else {
+                self.terminal.scroll_to_point(point);
+            }
+        }
+
+        self.exit_search();
+    }
+
+    /// Leave the filter regex input, keeping the filter view unless the regex is empty.
+    fn confirm_filter(&mut self) {
+        if !self.display.filter.filtering() {
+            self.display.filter.stop();
+        }
+
+        self.exit_search();
+    }
+
+    /// Update the filter view once terminal output has settled.
+    fn schedule_filter_update(&mut self) {
+        let window_id = self.display.window.id();
+        let timer_id = TimerId::new(Topic::FilterUpdate, window_id);
+        if self.display.filter.filtering() && !self.scheduler.scheduled(timer_id) {
+            let event = Event::new(EventType::FilterUpdate, window_id);
+            self.scheduler.schedule(event, FILTER_UPDATE_DELAY, false, timer_id);
+        }
+    }
+
+    /// Update the filter view from the current terminal content.
+    fn update_filter(&mut self) {
+        let window_id = self.display.window.id();
+        let timer_id = TimerId::new(Topic::FilterUpdate, window_id);
+        self.scheduler.unschedule(timer_id);
+
+        // Schedule the next chunk without delay, to process input in between.
+        let context = self.config.scrolling.filter_context;
+        if self.display.filter.update(self.terminal, context) {
+            let event = Event::new(EventType::FilterUpdate, window_id);
+            self.scheduler.schedule(event, Duration::ZERO, false, timer_id);
+        }
+
+        *self.dirty = true;
+    }
+
+    /// Restart counting all matches of the active search.
+    fn restart_match_count(&mut self) {
+        let timer_id = TimerId::new(Topic::SearchCount, self.display.window.id());
+        self.scheduler.unschedule(timer_id);
+
+        if self.search_state.dfas.is_none() || !self.search_active() {
+            self.search_state.match_count = MatchCount::default();
+            return;
+        }
+
+        self.search_state.match_count = MatchCount::new(self.terminal);
+        self.count_matches();
+    }
+
+    /// Update the match count after the terminal content has changed.
+    ///
+    /// Lines in the scrollback history can't change, so only lines which were visible when
+    /// matches were last counted are searched again.
+    fn update_match_count(&mut self) {
+        if self.search_state.dfas.is_none() || !self.search_active() {
+            return;
+        }
+
+        // Restart when lines can't be tracked, like after a resize or clearing the history.
+        let rotated_lines = self.terminal.grid().rotated_lines();
+        let history_size = self.terminal.history_size();
+        let match_count = &self.search_state.match_count;
+        let scrolled = rotated_lines - match_count.rotated_lines;
+        if scrolled < 0
+            || MatchCount::layout(self.terminal) != match_count.layout
+            || history_size as i64 + scrolled < match_count.history_size as i64
+        {
+            self.restart_match_count();
+            return;
+        }
+
+        let match_count = &mut self.search_state.match_count;
+        match_count.rotated_lines = rotated_lines;
+        match_count.history_size = history_size;
+
+        // Move counted lines along with the content rotated into history.
+        let scrolled = min(scrolled, i32::MAX as i64) as i32;
+        for (line, _) in &mut match_count.chunks {
+            *line -= scrolled;
+        }
+        let next_line = match_count.next_line.map(|line| line - scrolled);
+
+        // Remove all chunks overlapping with the previously visible lines.
+        let changed_line = Line(-scrolled);
+        if next_line.map_or(true, |next_line| next_line > changed_line) {
+            let mut next_line = None;
+            while let Some((line, count)) = match_count.chunks.pop() {
+                match_count.total -= count;
+                next_line = Some(line);
+
+                if line < changed_line {
+                    break;
+                }
+            }
+            match_count.next_line = next_line.or(Some(changed_line));
+        } else {
+            match_count.next_line = next_line;
+        }
+
+        // Remove matches in lines which were removed from the top of the history.
+        let topmost_line = self.terminal.topmost_line();
+        while match_count.chunks.get(1).is_some_and(|(line, _)| *line <= topmost_line) {
+            match_count.total -= match_count.chunks.remove(0).1;
+        }
+        if match_count.chunks.first().is_some_and(|(line, _)| *line < topmost_line) {
+            let end_line = match_count.chunks.get(1).map(|(line, _)| *line);
+            let end_line = end_line.or(match_count.next_line).unwrap_or(topmost_line) - 1;
+
+            let count = self.count_lines(topmost_line, end_line);
+            let match_count = &mut self.search_state.match_count;
+            match_count.total = match_count.total - match_count.chunks[0].1 + count;
+            match_count.chunks[0] = (topmost_line, count);
+        }
+        let match_count = &mut self.search_state.match_count;
+        match_count.next_line = match_count.next_line.map(|line| max(line, topmost_line));
+
+        // Continue counting from the first changed line.
+        let timer_id = TimerId::new(Topic::SearchCount, self.display.window.id());
+        self.scheduler.unschedule(timer_id);
+        self.count_matches();
+    }
+
+    /// Count the search matches within a range of lines.
+    fn count_lines(&mut self, start: Line, end: Line) -> usize {
+        let dfas = match &mut self.search_state.dfas {
+            Some(dfas) => dfas,
+            None => return 0,
+        };
+
+        let start = Point::new(start, Column(0));
+        let end = Point::new(end, self.terminal.last_column());
+        RegexIter::new(start, end, Direction::Right, self.terminal, dfas).count()
+    }
+
+    /// Count the search matches in the next chunk of lines.
+    ///
+    /// To avoid blocking on huge scrollback buffers, only a limited number of lines is searched
+    /// before scheduling the next chunk.
+    fn count_matches(&mut self) {
+        let start = match (&self.search_state.dfas, self.search_state.match_count.next_line) {
+            (Some(_), Some(start)) => start,
+            _ => return,
+        };
+
+        // Extend the chunk over wrapped lines, to avoid splitting matches across chunks.
+        let bottommost_line = self.terminal.bottommost_line();
+        let last_column = self.terminal.last_column();
+        let mut end_line = min(start + (MATCH_COUNT_CHUNK_LINES - 1), bottommost_line);
+        while end_line < bottommost_line
+            && self.terminal.grid()[end_line][last_column].flags.contains(Flags::WRAPLINE)
+        {
+            end_line += 1;
+        }
+
+        let count = self.count_lines(start, end_line);
+
+        let match_count = &mut self.search_state.match_count;
+        match_count.chunks.push((start, count));
+        match_count.total += count;
+        match_count.next_line = (end_line < bottommost_line).then(|| end_line + 1);
+
+        // Schedule the next chunk without delay, to process input in between.
+        if !match_count.is_complete() {
+            let timer_id = TimerId::new(Topic::SearchCount, self.display.window.id());
+            let event = Event::new(EventType::SearchCount, self.display.window.id());
+            self.scheduler.schedule(event, Duration::ZERO, false, timer_id);
+        }
+
+        self.update_focused_index();
+
+        *self.dirty = true;
+    }
+
+    /// Update the index of the focused match within all counted matches.
+    fn update_focused_index(&mut self) {
+        let match_count = &mut self.search_state.match_count;
+        match_count.focused_index = None;
+
+        let (dfas, focused_start) =
+            match (&mut self.search_state.dfas, &self.search_state.focused_match) {
+                (Some(dfas), Some(focused_match)) => (dfas, *focused_match.start()),
+                _ => return,
+            };
+
+        // Wait until the chunk containing the focused match has been counted.
+        if match_count.next_line.is_some_and(|next_line| focused_start.line >= next_line) {
+            return;
+        }
+
+        let chunk_index =
+            match_count.chunks.partition_point(|(line, _)| *line <= focused_start.line);
+        let (chunk_line, _) = match chunk_index.checked_sub(1).map(|i| match_count.chunks[i]) {
+            Some(chunk) => chunk,
+            None => return,
+        };
+
+        // Count matches before the focused match within its chunk.
+        let chunk_start = Point::new(chunk_line, Column(0));
+        let preceding =
+            RegexIter::new(chunk_start, focused_start, Direction::Right, self.terminal, dfas)
+                .filter(|regex_match| *regex_match.start() < focused_start)
+                .count();
+
+        let preceding_chunks: usize =
+            match_count.chunks[..chunk_index - 1].iter().map(|(_, count)| count).sum();
+        match_count.focused_index = Some(preceding_chunks + preceding + 1);
+    }
+
     /// Cleanup the search state.
     fn exit_search(&mut self) {
         let vi_mode = self.terminal.mode().contains(TermMode::VI);
@@ -1047,6 +1629,15 @@ impl<'a, N: Notify + 'a, T: EventListener> ActionContext<'a, N, T> {
 
         // Clear focused match.
         self.search_state.focused_match = None;
+
+        // Stop counting matches.
+        let timer_id = TimerId::new(Topic::SearchCount, self.display.window.id());
+        self.scheduler.unschedule(timer_id);
+        self.search_state.match_count = MatchCount::default();
+
+        // Stop the fuzzy search thread.
+        self.search_state.fuzzy = None;
+        self.search_state.filter = false;
     }
 
     /// Update the cursor blinking state.
@@ -1102,6 +1693,91 @@ impl<'a, N: Notify + 'a, T: EventListener> ActionContext<'a, N, T> {
         self.scheduler.schedule(event, blinking_timeout, false, timer_id);
     }
 
+    /// Write pasted text to the PTY.
+    fn write_paste(&mut self, text: &str, bracketed: bool) {
+        if bracketed && self.terminal().mode().contains(TermMode::BRACKETED_PASTE) {
+            self.on_terminal_input_start();
+
+            self.write_to_pty(&b"\x1b[200~"[..]);
+
+            // Write filtered escape sequences.
+            //
+            // We remove `\x1b` to ensure it's impossible for the pasted text to write the bracketed
+            // paste end escape `\x1b[201~` and `\x03` since some shells incorrectly terminate
+            // bracketed paste when they receive it.
+            let filtered = text.replace(['\x1b', '\x03'], "");
+            self.write_to_pty(filtered.into_bytes());
+
+            self.write_to_pty(&b"\x1b[201~"[..]);
+        } else {
+            self.on_terminal_input_start();
+
+            let payload = if bracketed {
+                // In non-bracketed (ie: normal) mode, terminal applications cannot distinguish
+                // pasted data from keystrokes.
+                //
+                // In theory, we should construct the keystrokes needed to produce the data we are
+                // pasting... since that's neither practical nor sensible (and probably an
+                // impossible task to solve in a general way), we'll just replace line breaks
+                // (windows and unix style) with a single carriage return (\r, which is what the
+                // Enter key produces).
+                text.replace("\r\n", "\r").replace('\n', "\r").into_bytes()
+            } else {
+                // When we explicitly disable bracketed paste don't manipulate with the input,
+                // so we pass user input as is.
+                text.to_owned().into_bytes()
+            };
+
+            self.write_to_pty(payload);
+        }
+    }
+
+    /// Ask for confirmation before pasting multiple lines into a broadcast group.
+    ///
+    /// Returns `true` if the paste is repeated while the confirmation is shown.
+    fn confirm_broadcast_paste(&mut self, text: &str) -> bool {
+        let lines = text.lines().count();
+        let text =
+            format!("Paste {lines} lines into all windows of the group? Paste again to confirm");
+        let mut message = Message::new(text, MessageType::Warning);
+        message.set_target(BROADCAST_PASTE_TARGET.into());
+
+        let confirmed = self.message_buffer.is_queued(&message);
+        self.message_buffer.remove_target(BROADCAST_PASTE_TARGET);
+        if !confirmed {
+            self.message_buffer.push(message);
+        }
+        self.display.pending_update.dirty = true;
+
+        confirmed
+    }
+
+    /// Show or hide the window.
+    #[cfg(unix)]
+    fn toggle_visibility(&mut self) {
+        if self.display.window.is_visible() {
+            self.display.window.set_visible(false);
+        } else if self.config.window.dropdown() {
+            self.display.window.show_dropdown(self.config.window.dropdown.height);
+
+            let window_id = self.display.window.id();
+            let event = Event::new(EventType::DropdownSlide, window_id);
+            let timer_id = TimerId::new(Topic::DropdownSlide, window_id);
+            self.scheduler.schedule(event, DROPDOWN_FRAME_INTERVAL, true, timer_id);
+        } else {
+            self.display.window.set_visible(true);
+            self.display.window.focus_window();
+        }
+    }
+
+    /// Advance the slide in animation of a drop-down window.
+    fn slide_dropdown(&mut self) {
+        if !self.display.window.slide_dropdown(self.config.window.dropdown.height) {
+            let timer_id = TimerId::new(Topic::DropdownSlide, self.display.window.id());
+            self.scheduler.unschedule(timer_id);
+        }
+    }
+
     /// Perform vi mode inline search in the specified direction.
     fn inline_search(&mut self, direction: Direction) {
         let c = match self.inline_search_state.character {
@@ -1248,14 +1924,21 @@ impl Mouse {
     /// If the coordinates are outside of the terminal grid, like positions inside the padding, the
     /// coordinates will be clamped to the closest grid coordinates.
     #[inline]
-    pub fn point(&self, size: &SizeInfo, display_offset: usize) -> Point {
+    pub fn point<T>(&self, size: &SizeInfo, grid: &Grid<T>) -> Point {
         let col = self.x.saturating_sub(size.padding_x() as usize) / (size.cell_width() as usize);
         let col = min(Column(col), size.last_column());
 
         let line = self.y.saturating_sub(size.padding_y() as usize) / (size.cell_height() as usize);
         let line = min(line, size.bottommost_line().0 as usize);
 
-        term::viewport_to_point(display_offset, Point::new(line, col))
+        let mut point = term::viewport_to_point(grid.display_offset(), Point::new(line, col));
+
+        // Every cell in double width lines covers two columns.
+        if grid[point.line].line_attribute().is_double_width() {
+            point.column = Column(point.column.0 / 2);
+        }
+
+        point
     }
 }
 
@@ -1283,7 +1966,21 @@ impl input::Processor<EventProxy, ActionContext<'_, Notifier, EventProxy>> {
         match event {
             WinitEvent::UserEvent(Event { payload, .. }) => match payload {
                 EventType::SearchNext => self.ctx.goto_match(None),
+                EventType::SearchCount => self.ctx.count_matches(),
+                EventType::FilterUpdate => self.ctx.update_filter(),
+                EventType::FuzzyResults(results) => {
+                    *self.ctx.dirty |= self.ctx.search_state.update_fuzzy_results(results);
+                },
                 EventType::Scroll(scroll) => self.ctx.scroll(scroll),
+                #[cfg(unix)]
+                EventType::ToggleVisibility => self.ctx.toggle_visibility(),
+                EventType::DropdownSlide => self.ctx.slide_dropdown(),
+                EventType::BroadcastInput(input) => match input {
+                    BroadcastInput::Key(key) => self.broadcast_key_input(&key),
+                    BroadcastInput::Paste(text, bracketed) => {
+                        self.ctx.write_paste(&text, bracketed)
+                    },
+                },
                 EventType::BlinkCursor => {
                     // Only change state when timeout isn't reached, since we could get
                     // BlinkCursor and BlinkCursorTimeout events at the same time.
@@ -1356,13 +2053,35 @@ impl input::Processor<EventProxy, ActionContext<'_, Notifier, EventProxy>> {
                     TerminalEvent::PtyWrite(text) => self.ctx.write_to_pty(text.into_bytes()),
                     TerminalEvent::MouseCursorDirty => self.reset_mouse_cursor(),
                     TerminalEvent::CursorBlinkingChange => self.ctx.update_cursor_blinking(),
-                    TerminalEvent::Exit | TerminalEvent::ChildExit(_) | TerminalEvent::Wakeup => (),
+                    TerminalEvent::Wakeup => {
+                        // Recount search matches in lines which might have changed.
+                        self.ctx.update_match_count();
+
+                        // Update the filter view with the new output.
+                        self.ctx.schedule_filter_update();
+                    },
+                    TerminalEvent::Exit
+                    | TerminalEvent::ChildExit(_)
+                    | TerminalEvent::CommandFinished(_) => (),
                 },
                 #[cfg(unix)]
-                EventType::IpcConfig(_) => (),
+                EventType::SendText(text, bracketed) => self.ctx.paste(&text, bracketed),
+                #[cfg(unix)]
+                EventType::SendKeys(keys) => {
+                    for key in &keys {
+                        self.synthetic_key_input(key);
+                    }
+                },
+                #[cfg(unix)]
+                EventType::Ipc(..) | EventType::IpcSubscribe(..) => (),
+                EventType::SaveSession => (),
                 EventType::Message(_)
                 | EventType::ConfigReload(_)
                 | EventType::CreateWindow(_)
+                | EventType::CreateTab(_)
+                | EventType::Pane(_)
+                | EventType::Tab(_)
+                | EventType::ToggleBroadcastInput
                 | EventType::Frame => (),
             },
             WinitEvent::WindowEvent { event, .. } => {
@@ -1421,6 +2140,15 @@ impl input::Processor<EventProxy, ActionContext<'_, Notifier, EventProxy>> {
                             self.ctx.window().set_urgent(false);
                         }
 
+                        // Hide drop-down windows once they lose focus.
+                        let window_config = &self.ctx.config.window;
+                        if !is_focused
+                            && window_config.dropdown()
+                            && window_config.dropdown.hide_on_focus_loss
+                        {
+                            self.ctx.window().set_visible(false);
+                        }
+
                         self.ctx.update_cursor_blinking();
                         self.on_focus_change(is_focused);
                     },
@@ -1492,6 +2220,46 @@ impl input::Processor<EventProxy, ActionContext<'_, Notifier, EventProxy>> {
             | WinitEvent::AboutToWait => (),
         }
     }
+
+    /// Move keyboard focus away from this pane's terminal.
+    ///
+    /// Returns whether the window had focus.
+    pub fn leave_pane(&mut self) -> bool {
+        // Timers of the window can't tell panes apart, so they're stopped with the pane's search.
+        let window_id = self.ctx.display.window.id();
+        if self.ctx.scheduler.unschedule(TimerId::new(Topic::DelayedSearch, window_id)).is_some() {
+            self.ctx.goto_match(None);
+        }
+        self.ctx.scheduler.unschedule(TimerId::new(Topic::SearchCount, window_id));
+        self.ctx.scheduler.unschedule(TimerId::new(Topic::FilterUpdate, window_id));
+
+        let is_focused = self.ctx.terminal.is_focused;
+        if is_focused {
+            self.ctx.terminal.is_focused = false;
+            self.on_focus_change(false);
+        }
+
+        *self.ctx.dirty = true;
+
+        is_focused
+    }
+
+    /// Move keyboard focus to this pane's terminal.
+    pub fn enter_pane(&mut self, is_focused: bool) {
+        self.ctx.terminal.is_focused = is_focused;
+        if is_focused {
+            self.on_focus_change(true);
+        }
+
+        // Catch up with the output the pane received without keyboard focus.
+        self.ctx.restart_match_count();
+        self.ctx.schedule_filter_update();
+
+        self.ctx.terminal.refresh_title();
+        self.ctx.update_cursor_blinking();
+
+        *self.ctx.dirty = true;
+    }
 }
 
 /// The event processor.
@@ -1503,6 +2271,8 @@ pub struct Processor {
     gl_display: Option<GlutinDisplay>,
     #[cfg(unix)]
     global_ipc_options: ParsedOptions,
+    #[cfg(unix)]
+    ipc_subscribers: IpcSubscribers,
     cli_options: CliOptions,
     config: Rc<UiConfig>,
 }
@@ -1523,6 +2293,8 @@ impl Processor {
             windows: Default::default(),
             #[cfg(unix)]
             global_ipc_options: Default::default(),
+            #[cfg(unix)]
+            ipc_subscribers: Default::default(),
         }
     }
 
@@ -1569,6 +2341,283 @@ impl Processor {
         Ok(())
     }
 
+    /// Process a message received through the IPC socket.
+    #[cfg(unix)]
+    fn handle_ipc(
+        &mut self,
+        event_loop: &EventLoopWindowTarget<Event>,
+        proxy: &EventLoopProxy<Event>,
+        message: SocketMessage,
+    ) -> Result<Value, IpcError> {
+        self.ipc_allowed(IpcScope::from(&message))?;
+
+        match message {
+            #[cfg(not(target_os = "macos"))]
+            SocketMessage::CreateWindow(options) if options.tab => {
+                let window_id = self.ipc_window(None)?.id();
+                let _ = proxy.send_event(Event::new(EventType::CreateTab(options), window_id));
+            },
+            SocketMessage::CreateWindow(options) => {
+                // XXX Ensure that no context is current when creating a new window,
+                // otherwise it may lock the backing buffer of the
+                // surface of current context when asking
+                // e.g. EGL on Wayland to create a new context.
+                for window_context in self.windows.values_mut() {
+                    window_context.display.make_not_current();
+                }
+
+                self.create_window(event_loop, proxy.clone(), options).map_err(|err| {
+                    IpcError::new(IpcErrorCode::Failed, format!("could not open window: {err}"))
+                })?;
+            },
+            SocketMessage::Config(ipc_config) => {
+                let window_id =
+                    ipc_config.window_id.and_then(|id| u64::try_from(id).ok()).map(WindowId::from);
+                if window_id.is_some_and(|window_id| !self.windows.contains_key(&window_id)) {
+                    return Err(IpcError::new(IpcErrorCode::NoWindow, "no matching window"));
+                }
+
+                // Try and parse options as toml.
+                let mut options = ParsedOptions::from_options(&ipc_config.options);
+
+                // Override IPC config for each window with matching ID.
+                for (_, window_context) in self
+                    .windows
+                    .iter_mut()
+                    .filter(|(id, _)| window_id.is_none() || window_id == Some(**id))
+                {
+                    if ipc_config.reset {
+                        window_context.reset_window_config(self.config.clone());
+                    } else {
+                        window_context.add_window_config(self.config.clone(), &options);
+                    }
+                }
+
+                // Persist global options for future windows.
+                if window_id.is_none() {
+                    if ipc_config.reset {
+                        self.global_ipc_options.clear();
+                    } else {
+                        self.global_ipc_options.append(&mut options);
+                    }
+                }
+            },
+            // Exports are answered once the file has been written.
+            SocketMessage::Export(_) => unreachable!("exports are handled by `ipc_export`"),
+            SocketMessage::SendText(ipc_text) => {
+                let window_id = self.ipc_window(ipc_text.window_id)?.id();
+                let event = EventType::SendText(ipc_text.text, ipc_text.bracketed);
+                let _ = proxy.send_event(Event::new(event, window_id));
+            },
+            SocketMessage::SendKeys(ipc_keys) => {
+                let keys = ipc_keys
+                    .keys
+                    .iter()
+                    .map(|key| key.parse())
+                    .collect::<Result<Vec<KeyPress>, _>>()
+                    .map_err(|err| {
+                        IpcError::new(IpcErrorCode::InvalidRequest, format!("invalid key: {err}"))
+                    })?;
+
+                let window_id = self.ipc_window(ipc_keys.window_id)?.id();
+                let _ = proxy.send_event(Event::new(EventType::SendKeys(keys), window_id));
+            },
+            SocketMessage::GetText(ipc_text) => {
+                let window_context = self.ipc_window(ipc_text.window_id)?;
+                return match window_context.text(&ipc_text) {
+                    Some(text) => Ok(serde_json::to_value(text).unwrap_or_default()),
+                    None => Err(IpcError::new(IpcErrorCode::Failed, "no text for this extent")),
+                };
+            },
+            SocketMessage::ListWindows => {
+                let windows: Vec<_> =
+                    self.windows.values().map(WindowContext::ipc_window).collect();
+                return Ok(serde_json::to_value(windows).unwrap_or_default());
+            },
+            SocketMessage::ToggleVisibility(ipc_toggle) => {
+                let window_ids: Vec<_> = match ipc_toggle.window_id {
+                    Some(window_id) => vec![self.ipc_window(Some(window_id))?.id()],
+                    None => self
+                        .windows
+                        .values()
+                        .filter(|window_context| match &ipc_toggle.class {
+                            Some(class) => window_context.display.window.class() == class,
+                            None => window_context.is_dropdown(),
+                        })
+                        .map(WindowContext::id)
+                        .collect(),
+                };
+
+                if window_ids.is_empty() {
+                    return Err(IpcError::new(IpcErrorCode::NoWindow, "no matching window"));
+                }
+
+                for window_id in window_ids {
+                    let _ = proxy.send_event(Event::new(EventType::ToggleVisibility, window_id));
+                }
+            },
+            // Subscriptions are registered by the socket listener.
+            SocketMessage::Subscribe(_) => {
+                let message = "subscriptions require a new connection";
+                return Err(IpcError::new(IpcErrorCode::InvalidRequest, message));
+            },
+        }
+
+        Ok(Value::Null)
+    }
+
+    /// Export terminal content to a file requested through IPC.
+    ///
+    /// The file is written on a separate thread, which answers the request once it's done.
+    #[cfg(unix)]
+    fn ipc_export(&self, ipc_export: IpcExport, replier: IpcReplier) {
+        let result = self.ipc_allowed(IpcScope::Export).and_then(|_| {
+            // Only create files at absolute paths within existing directories.
+            let path = &ipc_export.path;
+            if !path.is_absolute() || !path.parent().is_some_and(Path::is_dir) {
+                let message = format!("export path {path:?} is not inside an existing directory");
+                return Err(IpcError::new(IpcErrorCode::InvalidRequest, message));
+            }
+
+            self.ipc_window(ipc_export.window_id)
+        });
+        let window_context = match result {
+            Ok(window_context) => window_context,
+            Err(err) => return replier.reply(Err(err)),
+        };
+
+        let path = ipc_export.path;
+        let format = ipc_export.format.map_or_else(|| export::path_format(&path), Into::into);
+        window_context.export(format, ipc_export.selection, path, move |path, result| {
+            replier.reply(result.map(|_| Value::Null).map_err(|err| {
+                let message = format!("unable to export terminal content to {path:?}: {err}");
+                IpcError::new(IpcErrorCode::Failed, message)
+            }));
+        });
+    }
+
+    /// Start or stop broadcasting input for all windows in the group of a window.
+    fn toggle_broadcast_input(&mut self, window_id: WindowId) {
+        let (broadcast, group) = match self.windows.get(&window_id) {
+            Some(window_context) => {
+                let group: Vec<_> = self
+                    .windows
+                    .values()
+                    .filter(|other| other.same_group(window_context))
+                    .map(WindowContext::id)
+                    .collect();
+                (!window_context.is_broadcasting(), group)
+            },
+            None => return,
+        };
+
+        for window_id in group {
+            if let Some(window_context) = self.windows.get_mut(&window_id) {
+                window_context.set_broadcasting(broadcast);
+            }
+        }
+    }
+
+    /// Get the window targeted by an IPC message.
+    ///
+    /// Defaults to the focused window, if no window ID is specified.
+    #[cfg(unix)]
+    fn ipc_window(&self, window_id: Option<u64>) -> Result<&WindowContext, IpcError> {
+        let window_context = match window_id {
+            Some(window_id) => self.windows.get(&WindowId::from(window_id)),
+            None => self
+                .windows
+                .values()
+                .find(|window_context| window_context.is_focused())
+                .or_else(|| self.windows.values().next()),
+        };
+
+        window_context.ok_or_else(|| IpcError::new(IpcErrorCode::NoWindow, "no matching window"))
+    }
+
+    /// Check if the config allows an IPC message.
+    #[cfg(unix)]
+    fn ipc_allowed(&self, scope: IpcScope) -> Result<(), IpcError> {
+        // Check the config without IPC overrides, so IPC can't extend its own permissions.
+        if !self.config.ipc.allow.contains(&scope) {
+            let message = "message is not allowed by `ipc.allow` in the config";
+            return Err(IpcError::new(IpcErrorCode::Forbidden, message));
+        }
+
+        Ok(())
+    }
+
+    /// Report an event to all IPC clients subscribed to it.
+    #[cfg(unix)]
+    fn publish_ipc_event(&mut self, event: &WinitEvent<Event>) {
+        let (window_id, data) = match event {
+            WinitEvent::UserEvent(Event {
+                window_id: Some(window_id),
+                pane_id,
+                payload: EventType::Terminal(event),
+            }) => {
+                let window_context = match self.windows.get(window_id) {
+                    Some(window_context) => window_context,
+                    None => return,
+                };
+
+                // Only report the title of the focused pane, and closing the last pane.
+                let is_focused_pane = pane_id.map_or(true, |id| window_context.is_focused_pane(id));
+                match event {
+                    TerminalEvent::Title(_) | TerminalEvent::ResetTitle if !is_focused_pane => {
+                        return
+                    },
+                    TerminalEvent::Exit
+                        if pane_id.is_some_and(|id| !window_context.is_last_pane(id)) =>
+                    {
+                        return
+                    },
+                    _ => (),
+                }
+
+                let data = match event {
+                    TerminalEvent::Title(title) => window_context
+                        .dynamic_title(Some(title))
+                        .map(|title| IpcEventData::Title { title }),
+                    TerminalEvent::ResetTitle => window_context
+                        .dynamic_title(None)
+                        .map(|title| IpcEventData::Title { title }),
+                    TerminalEvent::Bell => Some(IpcEventData::Bell),
+                    TerminalEvent::Exit => Some(IpcEventData::Exit),
+                    TerminalEvent::CommandFinished(exit_code) => {
+                        Some(IpcEventData::CommandFinished { exit_code: *exit_code })
+                    },
+                    _ => return,
+                };
+
+                // Title changes and finished commands usually follow a change of directory.
+                if self.ipc_subscribers.is_subscribed(SubscriptionEvent::Cwd)
+                    && matches!(
+                        event,
+                        TerminalEvent::Title(_)
+                            | TerminalEvent::ResetTitle
+                            | TerminalEvent::CommandFinished(_)
+                    )
+                {
+                    if let Some(cwd) = window_context.cwd() {
+                        self.ipc_subscribers.update_cwd((*window_id).into(), cwd);
+                    }
+                }
+
+                match data {
+                    Some(data) => (*window_id, data),
+                    None => return,
+                }
+            },
+            WinitEvent::WindowEvent { window_id, event: WindowEvent::Focused(focused) } => {
+                (*window_id, IpcEventData::Focus { focused: *focused })
+            },
+            _ => return,
+        };
+
+        self.ipc_subscribers.publish(window_id.into(), data);
+    }
+
     /// Run the event loop.
     ///
     /// The result is exit code generate from the loop.
@@ -1599,6 +2648,9 @@ impl Processor {
                 return;
             }
 
+            #[cfg(unix)]
+            self.publish_ipc_event(&event);
+
             match event {
                 // The event loop just got initialized. Create a window.
                 WinitEvent::Resumed => {
@@ -1610,6 +2662,21 @@ impl Processor {
                         None => return,
                     };
 
+                    // Recreate all windows of a saved session.
+                    let mut window_options = Vec::new();
+                    if let Some(path) = &self.cli_options.restore_session {
+                        match session::load(path.as_deref()) {
+                            Ok(session) => {
+                                window_options =
+                                    session.into_window_options(&initial_window_options)
+                            },
+                            Err(err) => error!("Unable to restore session: {err}"),
+                        }
+                    }
+                    let mut window_options = window_options.into_iter();
+
+                    let initial_window_options =
+                        window_options.next().unwrap_or(initial_window_options);
                     if let Err(err) = self.create_initial_window(
                         event_loop,
                         proxy.clone(),
@@ -1620,6 +2687,12 @@ impl Processor {
                         return;
                     }
 
+                    for options in window_options {
+                        if let Err(err) = self.create_window(event_loop, proxy.clone(), options) {
+                            error!("Could not open window: {:?}", err);
+                        }
+                    }
+
                     info!("Initialisation complete");
                 },
                 WinitEvent::LoopExiting => {
@@ -1643,6 +2716,7 @@ impl Processor {
                 WinitEvent::UserEvent(Event {
                     window_id: Some(window_id),
                     payload: EventType::Terminal(TerminalEvent::Wakeup),
+                    ..
                 }) => {
                     if let Some(window_context) = self.windows.get_mut(&window_id) {
                         window_context.dirty = true;
@@ -1655,6 +2729,7 @@ impl Processor {
                 WinitEvent::UserEvent(Event {
                     window_id: Some(window_id),
                     payload: EventType::Frame,
+                    ..
                 }) => {
                     if let Some(window_context) = self.windows.get_mut(&window_id) {
                         window_context.display.window.has_frame = true;
@@ -1663,10 +2738,31 @@ impl Processor {
                         }
                     }
                 },
+                // Close panes while other panes of their window remain.
+                WinitEvent::UserEvent(Event {
+                    window_id: Some(window_id),
+                    pane_id: Some(pane_id),
+                    payload: EventType::Terminal(TerminalEvent::Exit),
+                }) if self
+                    .windows
+                    .get(&window_id)
+                    .is_some_and(|window| !window.is_last_pane(pane_id)) =>
+                {
+                    if let Some(window_context) = self.windows.get_mut(&window_id) {
+                        window_context.handle_event(
+                            event_loop,
+                            &proxy,
+                            &mut clipboard,
+                            &mut scheduler,
+                            event,
+                        );
+                    }
+                },
                 // Check for shutdown.
                 WinitEvent::UserEvent(Event {
                     window_id: Some(window_id),
                     payload: EventType::Terminal(TerminalEvent::Exit),
+                    ..
                 }) => {
                     // Remove the closed terminal.
                     let window_context = match self.windows.remove(&window_id) {
@@ -1743,36 +2839,44 @@ impl Processor {
                         }
                     }
                 },
-                // Process IPC config update.
+                // Process IPC requests.
                 #[cfg(unix)]
                 WinitEvent::UserEvent(Event {
-                    payload: EventType::IpcConfig(ipc_config),
-                    window_id,
+                    payload: EventType::Ipc(SocketMessage::Export(ipc_export), replier),
+                    ..
                 }) => {
-                    // Try and parse options as toml.
-                    let mut options = ParsedOptions::from_options(&ipc_config.options);
-
-                    // Override IPC config for each window with matching ID.
-                    for (_, window_context) in self
+                    self.ipc_export(ipc_export, replier);
+                },
+                #[cfg(unix)]
+                WinitEvent::UserEvent(Event {
+                    payload: EventType::Ipc(message, replier), ..
+                }) => {
+                    let result = self.handle_ipc(event_loop, &proxy, message);
+                    replier.reply(result);
+                },
+                // Start sending events to an IPC client.
+                #[cfg(unix)]
+                WinitEvent::UserEvent(Event {
+                    payload: EventType::IpcSubscribe(subscriber, replier),
+                    ..
+                }) => {
+                    let result = self.ipc_allowed(IpcScope::Subscribe).map(|_| {
+                        self.ipc_subscribers.add(subscriber);
+                        Value::Null
+                    });
+                    replier.reply(result);
+                },
+                // Save the state of all windows.
+                WinitEvent::UserEvent(Event { payload: EventType::SaveSession, .. }) => {
+                    let windows = self
                         .windows
-                        .iter_mut()
-                        .filter(|(id, _)| window_id.is_none() || window_id == Some(**id))
-                    {
-                        if ipc_config.reset {
-                            window_context.reset_window_config(self.config.clone());
-                        } else {
-                            window_context.add_window_config(self.config.clone(), &options);
-                        }
-                    }
+                        .values()
+                        .map(|window_context| {
+                            (window_context.session_options(), window_context.terminal().clone())
+                        })
+                        .collect();
 
-                    // Persist global options for future windows.
-                    if window_id.is_none() {
-                        if ipc_config.reset {
-                            self.global_ipc_options.clear();
-                        } else {
-                            self.global_ipc_options.append(&mut options);
-                        }
-                    }
+                    session::spawn_save(windows);
                 },
                 // Create a new terminal window.
                 WinitEvent::UserEvent(Event {
@@ -1790,6 +2894,47 @@ impl Processor {
                         error!("Could not open window: {:?}", err);
                     }
                 },
+                // Toggle broadcasting for all windows in the group.
+                WinitEvent::UserEvent(Event {
+                    window_id: Some(window_id),
+                    payload: EventType::ToggleBroadcastInput,
+                    ..
+                }) => self.toggle_broadcast_input(window_id),
+                // Mirror input to the other windows in the group.
+                WinitEvent::UserEvent(Event {
+                    window_id: Some(window_id),
+                    payload: EventType::BroadcastInput(input),
+                    ..
+                }) => {
+                    let source = match self.windows.get(&window_id) {
+                        Some(source) if source.is_broadcasting() => source,
+                        _ => return,
+                    };
+
+                    let targets: Vec<_> = self
+                        .windows
+                        .values()
+                        .filter(|window_context| {
+                            window_context.id() != window_id
+                                && window_context.is_broadcasting()
+                                && window_context.same_group(source)
+                        })
+                        .map(WindowContext::id)
+                        .collect();
+
+                    for target in targets {
+                        let event = Event::new(EventType::BroadcastInput(input.clone()), target);
+                        if let Some(window_context) = self.windows.get_mut(&target) {
+                            window_context.handle_event(
+                                event_loop,
+                                &proxy,
+                                &mut clipboard,
+                                &mut scheduler,
+                                event.into(),
+                            );
+                        }
+                    }
+                },
                 // Process events affecting all windows.
                 WinitEvent::UserEvent(event @ Event { window_id: None, .. }) => {
                     for window_context in self.windows.values_mut() {
@@ -1851,21 +2996,40 @@ impl Processor {
 pub struct EventProxy {
     proxy: EventLoopProxy<Event>,
     window_id: WindowId,
+    pane_id: PaneId,
+
+    /// Terminal mirrors a server session, which answers all requests of the shell itself.
+    mirrored: bool,
 }
 
 impl EventProxy {
-    pub fn new(proxy: EventLoopProxy<Event>, window_id: WindowId) -> Self {
-        Self { proxy, window_id }
+    pub fn new(proxy: EventLoopProxy<Event>, window_id: WindowId, pane_id: PaneId) -> Self {
+        Self { proxy, window_id, pane_id, mirrored: false }
+    }
+
+    /// Drop replies to the shell, since they are already sent by the session server.
+    #[cfg(unix)]
+    pub fn mirrored(mut self) -> Self {
+        self.mirrored = true;
+        self
     }
 
     /// Send an event to the event loop.
     pub fn send_event(&self, event: EventType) {
-        let _ = self.proxy.send_event(Event::new(event, self.window_id));
+        let event =
+            Event { window_id: Some(self.window_id), pane_id: Some(self.pane_id), payload: event };
+        let _ = self.proxy.send_event(event);
     }
 }
 
 impl EventListener for EventProxy {
     fn send_event(&self, event: TerminalEvent) {
-        let _ = self.proxy.send_event(Event::new(event.into(), self.window_id));
+        match event {
+            TerminalEvent::PtyWrite(_)
+            | TerminalEvent::ColorRequest(..)
+            | TerminalEvent::TextAreaSizeRequest(_)
+                if self.mirrored => {},
+            event => EventProxy::send_event(self, event.into()),
+        }
     }
 }
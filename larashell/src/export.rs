@@ -0,0 +1,154 @@
+//! Exporting of terminal content to files.
+
+use std::array;
+use std::fs::{File, OpenOptions};
+use std::io::{Error as IoError, ErrorKind, Result as IoResult, Write};
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use larashell_terminal::grid::Dimensions;
+use larashell_terminal::index::{Column, Point};
+use larashell_terminal::selection::SelectionRange;
+use larashell_terminal::term::color::COUNT;
+use larashell_terminal::term::export::{self, ExportFormat};
+use larashell_terminal::term::Term;
+use larashell_terminal::thread;
+
+use crate::config::export::Export;
+use crate::display::color::List;
+
+/// Maximum number suffixed to the file name, before giving up on finding an unused one.
+const MAX_FILE_SUFFIX: usize = 1_000;
+
+/// Render the terminal's scrollback buffer or its selection.
+pub fn content<T>(
+    terminal: &Term<T>,
+    colors: &List,
+    format: ExportFormat,
+    selection: bool,
+) -> IoResult<String> {
+    let range = if selection {
+        match terminal.selection.as_ref().and_then(|selection| selection.to_range(terminal)) {
+            Some(range) => range,
+            None => return Err(IoError::new(ErrorKind::Other, "no active selection")),
+        }
+    } else {
+        let start = Point::new(terminal.topmost_line(), Column(0));
+        let end = Point::new(terminal.bottommost_line(), terminal.last_column());
+        SelectionRange::new(start, end, false)
+    };
+
+    Ok(render(terminal, colors, format, range))
+}
+
+/// Write exported content to a new file on a separate thread.
+///
+/// The `content` is produced on the same thread, before the file is created. If `unique` is set
+/// and the file already exists, a `-N` suffix is added to its name until an unused one is found.
+/// The `done` callback is called with the final path and the result once the file has been
+/// written.
+pub fn spawn_write<C, F>(mut path: PathBuf, unique: bool, content: C, done: F)
+where
+    C: FnOnce() -> IoResult<String> + Send + 'static,
+    F: FnOnce(&Path, IoResult<()>) + Send + 'static,
+{
+    thread::spawn_named("export", move || {
+        let result = content().and_then(|content| write(&mut path, unique, &content));
+        done(&path, result);
+    });
+}
+
+/// Write content to a new file, which is only accessible to the current user.
+///
+/// Existing files are never overwritten.
+fn write(path: &mut PathBuf, unique: bool, content: &str) -> IoResult<()> {
+    let mut file = create(path, unique)?;
+    file.write_all(content.as_bytes())?;
+    file.flush()
+}
+
+/// Create a new file, updating `path` with the suffixed name if `unique` is set.
+fn create(path: &mut PathBuf, unique: bool) -> IoResult<File> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+    let extension = path.extension().map(|extension| extension.to_string_lossy().into_owned());
+
+    let mut suffix = 0;
+    loop {
+        match options.open(&*path) {
+            Err(err)
+                if unique && err.kind() == ErrorKind::AlreadyExists && suffix < MAX_FILE_SUFFIX =>
+            {
+                suffix += 1;
+                let name = match &extension {
+                    Some(extension) => format!("{stem}-{suffix}.{extension}"),
+                    None => format!("{stem}-{suffix}"),
+                };
+                path.set_file_name(name);
+            },
+            result => return result,
+        }
+    }
+}
+
+/// Render the terminal content within `range`.
+pub fn render<T>(
+    terminal: &Term<T>,
+    colors: &List,
+    format: ExportFormat,
+    range: SelectionRange,
+) -> String {
+    // Apply colors changed by escape sequences to the configured palette.
+    let palette: [_; COUNT] = array::from_fn(|i| terminal.colors()[i].unwrap_or(colors[i].0));
+
+    export::export(terminal, range, format, &palette)
+}
+
+/// Path for a new export file based on the configuration.
+///
+/// Since the file name only has a precision of seconds, it should be written with `unique` set.
+pub fn file_path(config: &Export, format: ExportFormat) -> PathBuf {
+    let directory = config.directory.clone().or_else(home::home_dir).unwrap_or_default();
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    directory.join(format!("larashell-{timestamp}.{}", format.extension()))
+}
+
+/// Guess the export format from the file extension of a path.
+pub fn path_format(path: &Path) -> ExportFormat {
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("html" | "htm") => ExportFormat::Html,
+        Some("ans" | "ansi") => ExportFormat::Ansi,
+        _ => ExportFormat::Plain,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unique_file_names() {
+        let directory =
+            std::env::temp_dir().join(format!("larashell-export-{}", std::process::id()));
+        std::fs::create_dir(&directory).unwrap();
+
+        let path = directory.join("larashell-1.txt");
+        let mut first = path.clone();
+        let mut second = path.clone();
+        create(&mut first, true).unwrap();
+        create(&mut second, true).unwrap();
+        let existing = create(&mut path.clone(), false);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(first, path);
+        assert_eq!(second, directory.join("larashell-1-1.txt"));
+        assert_eq!(existing.unwrap_err().kind(), ErrorKind::AlreadyExists);
+    }
+}
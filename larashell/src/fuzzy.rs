@@ -0,0 +1,476 @@
+//! Fuzzy search through the terminal's scrollback.
+
+use std::cmp::{max, min, Reverse};
+use std::mem;
+use std::sync::mpsc::{self, Sender};
+use std::sync::Arc;
+
+use larashell_terminal::grid::Dimensions;
+use larashell_terminal::index::{Column, Line};
+use larashell_terminal::sync::FairMutex;
+use larashell_terminal::term::cell::Flags;
+use larashell_terminal::term::search::CaseSensitivity;
+use larashell_terminal::term::Term;
+use larashell_terminal::thread;
+
+use crate::event::{EventProxy, EventType};
+
+/// Maximum number of ranked lines reported for a query.
+const MAX_FUZZY_RESULTS: usize = 20;
+
+/// Number of grid lines collected at once, before the terminal is unlocked again.
+const COLLECT_CHUNK_LINES: usize = 1_000;
+
+/// Score for every matched character.
+const SCORE_MATCH: i64 = 16;
+
+/// Penalty for the first character of a gap between matched characters.
+const PENALTY_GAP_START: i64 = 3;
+
+/// Penalty for every additional character of a gap.
+const PENALTY_GAP_EXTENSION: i64 = 1;
+
+/// Bonus for matching the first character of a word.
+const BONUS_BOUNDARY: i64 = 8;
+
+/// Bonus for matching a camelCase hump or the first digit of a number.
+const BONUS_CAMEL: i64 = 7;
+
+/// Minimum bonus for consecutive matched characters.
+const BONUS_CONSECUTIVE: i64 = 4;
+
+/// Multiplier for the bonus of the first query character.
+const BONUS_FIRST_CHAR_MULTIPLIER: i64 = 2;
+
+/// Logical line of terminal text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzyLine {
+    /// Position of the first grid line of the logical line.
+    ///
+    /// This is the line's distance from the top of the screen plus [`Grid::rotated_lines`], which
+    /// doesn't change when new lines are rotated into the scrollback history.
+    ///
+    /// [`Grid::rotated_lines`]: larashell_terminal::grid::Grid::rotated_lines
+    pub position: i64,
+
+    /// Text of all wrapped grid lines.
+    pub text: String,
+}
+
+/// Line matching the fuzzy query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    /// Position of the first grid line of the matched logical line, see [`FuzzyLine::position`].
+    pub position: i64,
+
+    /// Text of the matched line.
+    pub text: String,
+
+    /// Indices of the matched characters in the text.
+    pub positions: Vec<usize>,
+
+    /// Match quality, higher is better.
+    pub score: i64,
+}
+
+/// Ranked results for a fuzzy query.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FuzzyResults {
+    /// Query these results were computed for.
+    pub query: String,
+
+    /// Best matching lines, ordered by descending score.
+    pub matches: Vec<FuzzyMatch>,
+
+    /// Total number of matching lines.
+    pub total: usize,
+}
+
+/// Query sent to the scoring thread.
+struct FuzzyQuery {
+    text: String,
+    case_sensitivity: CaseSensitivity,
+}
+
+/// Fuzzy search state.
+///
+/// The lines are scored on a separate thread, which reports its results through
+/// [`EventType::FuzzyResults`].
+pub struct FuzzySearch {
+    queries: Sender<FuzzyQuery>,
+    results: FuzzyResults,
+    query: String,
+    selected: usize,
+}
+
+impl FuzzySearch {
+    /// Start the scoring thread for all lines of the terminal.
+    ///
+    /// The lines are collected on the scoring thread, to avoid blocking on huge scrollback buffers.
+    pub fn new<T>(terminal: Arc<FairMutex<Term<T>>>, proxy: EventProxy) -> Self
+    where
+        T: Send + 'static,
+    {
+        let (queries, receiver) = mpsc::channel::<FuzzyQuery>();
+
+        thread::spawn_named("fuzzy search", move || {
+            let lines = collect_lines(&terminal);
+            drop(terminal);
+
+            while let Ok(mut query) = receiver.recv() {
+                // Skip queries which have been replaced while scoring.
+                while let Ok(newer_query) = receiver.try_recv() {
+                    query = newer_query;
+                }
+
+                let results = rank(&lines, &query.text, query.case_sensitivity);
+                proxy.send_event(EventType::FuzzyResults(results));
+            }
+        });
+
+        Self {
+            queries,
+            results: Default::default(),
+            query: Default::default(),
+            selected: Default::default(),
+        }
+    }
+
+    /// Score all lines against a new query.
+    pub fn search(&mut self, query: &str, case_sensitivity: CaseSensitivity) {
+        self.query = query.to_owned();
+        let query = FuzzyQuery { text: self.query.clone(), case_sensitivity };
+        let _ = self.queries.send(query);
+    }
+
+    /// Update the results with the output of the scoring thread.
+    pub fn update(&mut self, results: FuzzyResults) {
+        // Ignore results for outdated queries.
+        if results.query != self.query {
+            return;
+        }
+
+        self.results = results;
+        self.selected = 0;
+    }
+
+    /// Ranked results of the latest query.
+    pub fn results(&self) -> &FuzzyResults {
+        &self.results
+    }
+
+    /// Index of the selected result.
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// Selected result.
+    pub fn selected_match(&self) -> Option<&FuzzyMatch> {
+        self.results.matches.get(self.selected)
+    }
+
+    /// Select the next worse result.
+    pub fn select_next(&mut self) {
+        self.selected = (self.selected + 1).min(self.results.matches.len().saturating_sub(1));
+    }
+
+    /// Select the next better result.
+    pub fn select_previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+}
+
+/// Collect the text of all logical lines in the terminal.
+///
+/// The terminal is only locked for [`COLLECT_CHUNK_LINES`] lines at a time, allowing new output to
+/// be processed while collecting the lines.
+pub fn collect_lines<T>(terminal: &FairMutex<Term<T>>) -> Vec<FuzzyLine> {
+    let spacers = Flags::WIDE_CHAR_SPACER | Flags::LEADING_WIDE_CHAR_SPACER;
+
+    let mut lines = Vec::new();
+    let mut text = String::new();
+    let mut start = None;
+    let mut next_position = None;
+    loop {
+        let terminal = terminal.lock();
+        let grid = terminal.grid();
+        let last_column = grid.last_column();
+        let rotated_lines = grid.rotated_lines();
+
+        // Continue below the last collected line, unless it was removed from history meanwhile.
+        let topmost_line = grid.topmost_line();
+        let first_line = next_position.map_or(topmost_line, |position: i64| {
+            let line = i32::try_from(position - rotated_lines).unwrap_or(i32::MIN);
+            max(Line(line), topmost_line)
+        });
+        let end_line = min(first_line + (COLLECT_CHUNK_LINES - 1), grid.bottommost_line());
+
+        for line in (first_line.0..=end_line.0).map(Line) {
+            let position = *start.get_or_insert(rotated_lines + line.0 as i64);
+
+            let row = grid.row(line);
+            for column in 0..grid.columns() {
+                let cell = &row[Column(column)];
+                if !cell.flags.intersects(spacers) {
+                    text.push(cell.c);
+                }
+            }
+
+            if row[last_column].flags.contains(Flags::WRAPLINE) {
+                continue;
+            }
+
+            let trimmed_len = text.trim_end().len();
+            text.truncate(trimmed_len);
+            if !text.is_empty() {
+                lines.push(FuzzyLine { position, text: mem::take(&mut text) });
+            }
+            start = None;
+        }
+
+        if end_line == grid.bottommost_line() {
+            break;
+        }
+        next_position = Some(rotated_lines + end_line.0 as i64 + 1);
+    }
+
+    lines
+}
+
+/// Score all lines against the query, returning the best matches.
+pub fn rank(lines: &[FuzzyLine], query: &str, case_sensitivity: CaseSensitivity) -> FuzzyResults {
+    let mut results = FuzzyResults { query: query.to_owned(), ..Default::default() };
+    if query.is_empty() {
+        return results;
+    }
+
+    let case_sensitive = match case_sensitivity {
+        CaseSensitivity::Smart => query.chars().any(char::is_uppercase),
+        CaseSensitivity::Sensitive => true,
+        CaseSensitivity::Insensitive => false,
+    };
+    let query: Vec<char> = query.chars().map(|c| fold_case(c, case_sensitive)).collect();
+
+    let mut matches: Vec<_> = lines
+        .iter()
+        .filter_map(|line| Some((score(&query, &line.text, case_sensitive)?, line)))
+        .collect();
+    results.total = matches.len();
+
+    // Order by descending score, preferring more recent lines for equal scores.
+    let key =
+        |((score, _), line): &((i64, Vec<usize>), &FuzzyLine)| Reverse((*score, line.position));
+    if matches.len() > MAX_FUZZY_RESULTS {
+        matches.select_nth_unstable_by_key(MAX_FUZZY_RESULTS - 1, key);
+        matches.truncate(MAX_FUZZY_RESULTS);
+    }
+    matches.sort_unstable_by_key(key);
+
+    results.matches = matches
+        .into_iter()
+        .map(|((score, positions), line)| FuzzyMatch {
+            position: line.position,
+            text: line.text.clone(),
+            positions,
+            score,
+        })
+        .collect();
+
+    results
+}
+
+/// Score a text for a case folded query.
+///
+/// Returns the score and the indices of all matched characters, if every query character is
+/// contained in the text in order.
+pub fn score(query: &[char], text: &str, case_sensitive: bool) -> Option<(i64, Vec<usize>)> {
+    let text: Vec<char> = text.chars().map(|c| fold_case(c, case_sensitive)).collect();
+    if query.is_empty() {
+        return None;
+    }
+
+    // Find the end of the first occurrence of the query.
+    let mut query_index = 0;
+    let mut end = None;
+    for (i, c) in text.iter().enumerate() {
+        if *c == query[query_index] {
+            query_index += 1;
+            if query_index == query.len() {
+                end = Some(i);
+                break;
+            }
+        }
+    }
+    let end = end?;
+
+    // Search backwards from the end to find the shortest occurrence.
+    let mut query_index = query.len();
+    let mut start = end;
+    for i in (0..=end).rev() {
+        if text[i] == query[query_index - 1] {
+            query_index -= 1;
+            if query_index == 0 {
+                start = i;
+                break;
+            }
+        }
+    }
+
+    let mut score = 0;
+    let mut positions = Vec::with_capacity(query.len());
+    let mut in_gap = false;
+    let mut consecutive = 0;
+    let mut first_bonus = 0;
+    let mut prev_class =
+        start.checked_sub(1).map_or(CharClass::Whitespace, |i| char_class(text[i]));
+    for (i, c) in text.iter().enumerate().take(end + 1).skip(start) {
+        let class = char_class(*c);
+
+        if positions.len() < query.len() && *c == query[positions.len()] {
+            let mut bonus = bonus(prev_class, class);
+            if consecutive == 0 {
+                first_bonus = bonus;
+            } else {
+                // Keep the bonus of the chunk's first character for all consecutive matches.
+                if bonus >= BONUS_BOUNDARY && bonus > first_bonus {
+                    first_bonus = bonus;
+                }
+                bonus = max(max(bonus, first_bonus), BONUS_CONSECUTIVE);
+            }
+
+            score += SCORE_MATCH;
+            score += if positions.is_empty() { bonus * BONUS_FIRST_CHAR_MULTIPLIER } else { bonus };
+
+            positions.push(i);
+            consecutive += 1;
+            in_gap = false;
+        } else {
+            score -= if in_gap { PENALTY_GAP_EXTENSION } else { PENALTY_GAP_START };
+            consecutive = 0;
+            first_bonus = 0;
+            in_gap = true;
+        }
+
+        prev_class = class;
+    }
+
+    Some((score, positions))
+}
+
+/// Character categories used for scoring bonuses.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum CharClass {
+    Whitespace,
+    Delimiter,
+    NonWord,
+    Lower,
+    Upper,
+    Number,
+}
+
+fn char_class(c: char) -> CharClass {
+    match c {
+        c if c.is_whitespace() => CharClass::Whitespace,
+        '/' | ',' | ':' | ';' | '|' => CharClass::Delimiter,
+        c if c.is_lowercase() => CharClass::Lower,
+        c if c.is_uppercase() => CharClass::Upper,
+        c if c.is_numeric() => CharClass::Number,
+        c if c.is_alphabetic() => CharClass::Lower,
+        _ => CharClass::NonWord,
+    }
+}
+
+/// Bonus for matching a character of `class` following one of `prev_class`.
+fn bonus(prev_class: CharClass, class: CharClass) -> i64 {
+    match (prev_class, class) {
+        (_, CharClass::Whitespace | CharClass::Delimiter | CharClass::NonWord) => 0,
+        (CharClass::Whitespace, _) => BONUS_BOUNDARY + 2,
+        (CharClass::Delimiter, _) => BONUS_BOUNDARY + 1,
+        (CharClass::NonWord, _) => BONUS_BOUNDARY,
+        (CharClass::Lower, CharClass::Upper) => BONUS_CAMEL,
+        (CharClass::Lower | CharClass::Upper, CharClass::Number) => BONUS_CAMEL,
+        _ => 0,
+    }
+}
+
+fn fold_case(c: char, case_sensitive: bool) -> char {
+    if case_sensitive {
+        c
+    } else {
+        c.to_lowercase().next().unwrap_or(c)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use larashell_terminal::term::test::mock_term;
+
+    fn score_str(query: &str, text: &str) -> Option<(i64, Vec<usize>)> {
+        let query: Vec<char> = query.chars().collect();
+        score(&query, text, false)
+    }
+
+    #[test]
+    fn subsequence_required() {
+        assert_eq!(score_str("abc", "xaxbxc").map(|(_, positions)| positions), Some(vec![1, 3, 5]));
+        assert_eq!(score_str("abc", "cba"), None);
+        assert_eq!(score_str("", "abc"), None);
+    }
+
+    #[test]
+    fn shortest_occurrence() {
+        // The match is shrunk to the tightest occurrence before the first complete match.
+        let (_, positions) = score_str("ab", "a xxx ab").unwrap();
+        assert_eq!(positions, vec![6, 7]);
+    }
+
+    #[test]
+    fn boundaries_score_higher() {
+        let (boundary, _) = score_str("fb", "foo bar").unwrap();
+        let (inner, _) = score_str("fb", "xfxxbx").unwrap();
+        assert!(boundary > inner);
+
+        let (consecutive, _) = score_str("foo", "foobar").unwrap();
+        let (spread, _) = score_str("foo", "f_o_o").unwrap();
+        assert!(consecutive > spread);
+    }
+
+    #[test]
+    fn rank_lines() {
+        let lines = vec![
+            FuzzyLine { position: 0, text: "cargo build --release".into() },
+            FuzzyLine { position: 1, text: "git commit".into() },
+            FuzzyLine { position: 2, text: "cargo test".into() },
+        ];
+
+        let results = rank(&lines, "cargo", CaseSensitivity::Smart);
+        assert_eq!(results.total, 2);
+        assert_eq!(results.matches[0].position, 2);
+        assert_eq!(results.matches[1].position, 0);
+
+        let results = rank(&lines, "Cargo", CaseSensitivity::Smart);
+        assert_eq!(results.total, 0);
+
+        let results = rank(&lines, "Cargo", CaseSensitivity::Insensitive);
+        assert_eq!(results.total, 2);
+    }
+
+    #[test]
+    fn collect_wrapped_lines() {
+        #[rustfmt::skip]
+        let term = mock_term("\
+            first\r\n\
+            \r\n\
+            wrapped\n\
+            line\
+        ");
+
+        let lines = collect_lines(&FairMutex::new(term));
+        assert_eq!(lines, vec![
+            FuzzyLine { position: 0, text: "first".into() },
+            FuzzyLine { position: 2, text: "wrappedline".into() },
+        ]);
+    }
+}
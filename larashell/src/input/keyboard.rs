@@ -12,7 +12,7 @@ use larashell_terminal::event::EventListener;
 use larashell_terminal::term::TermMode;
 use winit::platform::modifier_supplement::KeyEventExtModifierSupplement;
 
-use crate::config::{Action, BindingKey, BindingMode};
+use crate::config::{Action, BindingKey, BindingMode, KeyLocation as BindingKeyLocation, KeyPress};
 use crate::event::TYPING_SEARCH_DELAY;
 use crate::input::{ActionContext, Execute, Processor};
 use crate::scheduler::{TimerId, Topic};
@@ -81,21 +81,82 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
         // Mask `Alt` modifier from input when we won't send esc.
         let mods = if self.alt_send_esc(&key, text) { mods } else { mods & !ModifiersState::ALT };
 
-        let build_key_sequence = Self::should_build_sequence(&key, text, mode, mods);
+        let bytes = encode_key(&SequenceKey::from(&key), mods, mode);
 
-        let bytes = if build_key_sequence {
-            build_sequence(key, mods, mode)
-        } else {
-            let mut bytes = Vec::with_capacity(text.len() + 1);
-            if mods.alt_key() {
-                bytes.push(b'\x1b');
-            }
+        // Write only if we have something to write.
+        if !bytes.is_empty() {
+            self.ctx.on_terminal_input_start();
+            self.ctx.broadcast_input(BroadcastKey::new(&key, mods));
+            self.ctx.write_to_pty(bytes);
+        }
+    }
+
+    /// Process a key broadcast by another window of the group.
+    ///
+    /// The key is encoded for this terminal's keyboard modes, since they can differ from the
+    /// modes of the window which received the key.
+    pub fn broadcast_key_input(&mut self, key: &BroadcastKey) {
+        let mode = *self.ctx.terminal().mode();
+        let sequence_key = key.sequence_key();
 
-            bytes.extend_from_slice(text.as_bytes());
-            bytes
+        let bytes = match key.state {
+            ElementState::Pressed => encode_key(&sequence_key, key.mods, mode),
+            ElementState::Released if mode.contains(TermMode::REPORT_EVENT_TYPES) => {
+                encode_key_release(&sequence_key, key.mods, mode).into_owned()
+            },
+            ElementState::Released => return,
+        };
+
+        if !bytes.is_empty() {
+            self.ctx.on_terminal_input_start();
+            self.ctx.write_to_pty(bytes);
+        }
+    }
+
+    /// Process a key press which wasn't received from the window, like keys sent through IPC.
+    ///
+    /// The key is always written to the PTY. Bindings are only applied if they write escape
+    /// sequences, so application mode keys match the key presses received from the window.
+    pub fn synthetic_key_input(&mut self, key: &KeyPress) {
+        let mode = *self.ctx.terminal().mode();
+        let binding_mode = BindingMode::new(&(mode & !TermMode::VI), false);
+        let trigger = BindingKey::Keycode { key: key.key.clone(), location: key.location };
+
+        let bindings = self.ctx.config().key_bindings();
+        let escape = bindings.iter().find_map(|binding| match &binding.action {
+            Action::Esc(escape) if binding.is_triggered_by(binding_mode, key.mods, &trigger) => {
+                Some(escape.clone())
+            },
+            _ => None,
+        });
+
+        let bytes = match escape {
+            Some(escape) => escape.into_bytes(),
+            None => {
+                let text = key.text();
+                let location = match key.location {
+                    BindingKeyLocation::Numpad => KeyLocation::Numpad,
+                    _ => KeyLocation::Standard,
+                };
+                // Like keyboard layouts, apply `Shift` to the logical key.
+                let logical_key = match key.key.as_ref() {
+                    Key::Character(character) if key.mods.shift_key() => {
+                        Key::Character(character.to_uppercase().into())
+                    },
+                    _ => key.key.clone(),
+                };
+                let sequence_key = SequenceKey {
+                    logical_key,
+                    key_without_modifiers: key.key.clone(),
+                    location,
+                    state: ElementState::Pressed,
+                    repeat: false,
+                    text: text.as_deref(),
+                };
+                encode_key(&sequence_key, key.mods, mode)
+            },
         };
 
-        // Write only if we have something to write.
         if !bytes.is_empty() {
             self.ctx.on_terminal_input_start();
             self.ctx.write_to_pty(bytes);
@@ -130,30 +191,6 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
         }
     }
 
-    /// Check whether we should try to build escape sequence for the [`KeyEvent`].
-    fn should_build_sequence(
-        key: &KeyEvent,
-        text: &str,
-        mode: TermMode,
-        mods: ModifiersState,
-    ) -> bool {
-        if mode.contains(TermMode::REPORT_ALL_KEYS_AS_ESC) {
-            return true;
-        }
-
-        let disambiguate = mode.contains(TermMode::DISAMBIGUATE_ESC_CODES)
-            && (key.logical_key == Key::Named(NamedKey::Escape)
-                || (!mods.is_empty() && mods != ModifiersState::SHIFT)
-                || key.location == KeyLocation::Numpad);
-
-        match key.logical_key {
-            _ if disambiguate => true,
-            // Exclude all the named keys unless they have textual representation.
-            Key::Named(named) => named.to_text().is_none(),
-            _ => text.is_empty(),
-        }
-    }
-
     /// Attempt to find a binding and execute its action.
     ///
     /// The provided mode, mods, and key must match what is allowed by a binding
@@ -223,22 +260,9 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
         let text = key.text_with_all_modifiers().unwrap_or_default();
         let mods = if self.alt_send_esc(&key, text) { mods } else { mods & !ModifiersState::ALT };
 
-        let bytes: Cow<'static, [u8]> = match key.logical_key.as_ref() {
-            // NOTE: Echo the key back on release to follow kitty/foot behavior. When
-            // KEYBOARD_REPORT_ALL_KEYS_AS_ESC is used, we build proper escapes for
-            // the keys below.
-            _ if mode.contains(TermMode::REPORT_ALL_KEYS_AS_ESC) => {
-                build_sequence(key, mods, mode).into()
-            },
-            // Winit uses different keys for `Backspace` so we expliictly specify the
-            // values, instead of using what was passed to us from it.
-            Key::Named(NamedKey::Tab) => [b'\t'].as_slice().into(),
-            Key::Named(NamedKey::Enter) => [b'\r'].as_slice().into(),
-            Key::Named(NamedKey::Backspace) => [b'\x7f'].as_slice().into(),
-            Key::Named(NamedKey::Escape) => [b'\x1b'].as_slice().into(),
-            _ => build_sequence(key, mods, mode).into(),
-        };
+        let bytes = encode_key_release(&SequenceKey::from(&key), mods, mode);
 
+        self.ctx.broadcast_input(BroadcastKey::new(&key, mods));
         self.ctx.write_to_pty(bytes);
     }
 
@@ -254,11 +278,140 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
     }
 }
 
+/// Key press details required for encoding it as terminal input.
+pub struct SequenceKey<'a> {
+    pub logical_key: Key,
+    pub key_without_modifiers: Key,
+    pub location: KeyLocation,
+    pub state: ElementState,
+    pub repeat: bool,
+
+    /// Text produced by the key with all modifiers applied.
+    pub text: Option<&'a str>,
+}
+
+impl<'a> From<&'a KeyEvent> for SequenceKey<'a> {
+    fn from(key: &'a KeyEvent) -> Self {
+        Self {
+            logical_key: key.logical_key.clone(),
+            key_without_modifiers: key.key_without_modifiers(),
+            location: key.location,
+            state: key.state,
+            repeat: key.repeat,
+            text: key.text_with_all_modifiers(),
+        }
+    }
+}
+
+/// Key press or release mirrored to the other windows of a broadcast group.
+#[derive(Debug, Clone)]
+pub struct BroadcastKey {
+    logical_key: Key,
+    key_without_modifiers: Key,
+    location: KeyLocation,
+    state: ElementState,
+    repeat: bool,
+    text: Option<String>,
+
+    /// Modifiers applied to the key, without `Alt` if it isn't sent as `ESC`.
+    mods: ModifiersState,
+}
+
+impl BroadcastKey {
+    fn new(key: &KeyEvent, mods: ModifiersState) -> Self {
+        Self {
+            logical_key: key.logical_key.clone(),
+            key_without_modifiers: key.key_without_modifiers(),
+            location: key.location,
+            state: key.state,
+            repeat: key.repeat,
+            text: key.text_with_all_modifiers().map(String::from),
+            mods,
+        }
+    }
+
+    fn sequence_key(&self) -> SequenceKey<'_> {
+        SequenceKey {
+            logical_key: self.logical_key.clone(),
+            key_without_modifiers: self.key_without_modifiers.clone(),
+            location: self.location,
+            state: self.state,
+            repeat: self.repeat,
+            text: self.text.as_deref(),
+        }
+    }
+}
+
+/// Encode a key press as terminal input, without handling any bindings.
+///
+/// The `Alt` modifier in `mods` is sent as `ESC` prefix for textual keys.
+pub fn encode_key(key: &SequenceKey<'_>, mods: ModifiersState, mode: TermMode) -> Vec<u8> {
+    let text = key.text.unwrap_or_default();
+    if should_build_sequence(key, text, mode, mods) {
+        return build_sequence(key, mods, mode);
+    }
+
+    let mut bytes = Vec::with_capacity(text.len() + 1);
+    if mods.alt_key() {
+        bytes.push(b'\x1b');
+    }
+
+    bytes.extend_from_slice(text.as_bytes());
+    bytes
+}
+
+/// Encode a key release as terminal input.
+fn encode_key_release(
+    key: &SequenceKey<'_>,
+    mods: ModifiersState,
+    mode: TermMode,
+) -> Cow<'static, [u8]> {
+    match key.logical_key.as_ref() {
+        // NOTE: Echo the key back on release to follow kitty/foot behavior. When
+        // KEYBOARD_REPORT_ALL_KEYS_AS_ESC is used, we build proper escapes for
+        // the keys below.
+        _ if mode.contains(TermMode::REPORT_ALL_KEYS_AS_ESC) => {
+            build_sequence(key, mods, mode).into()
+        },
+        // Winit uses different keys for `Backspace` so we expliictly specify the
+        // values, instead of using what was passed to us from it.
+        Key::Named(NamedKey::Tab) => [b'\t'].as_slice().into(),
+        Key::Named(NamedKey::Enter) => [b'\r'].as_slice().into(),
+        Key::Named(NamedKey::Backspace) => [b'\x7f'].as_slice().into(),
+        Key::Named(NamedKey::Escape) => [b'\x1b'].as_slice().into(),
+        _ => build_sequence(key, mods, mode).into(),
+    }
+}
+
+/// Check whether we should try to build escape sequence for the [`SequenceKey`].
+fn should_build_sequence(
+    key: &SequenceKey<'_>,
+    text: &str,
+    mode: TermMode,
+    mods: ModifiersState,
+) -> bool {
+    if mode.contains(TermMode::REPORT_ALL_KEYS_AS_ESC) {
+        return true;
+    }
+
+    let disambiguate = mode.contains(TermMode::DISAMBIGUATE_ESC_CODES)
+        && (key.logical_key == Key::Named(NamedKey::Escape)
+            || (!mods.is_empty() && mods != ModifiersState::SHIFT)
+            || key.location == KeyLocation::Numpad);
+
+    match key.logical_key {
+        _ if disambiguate => true,
+        // Exclude all the named keys unless they have textual representation.
+        Key::Named(named) => named.to_text().is_none(),
+        _ => text.is_empty(),
+    }
+}
+
 /// Build a key's keyboard escape sequence based on the given `key`, `mods`, and `mode`.
 ///
 /// The key sequences for `APP_KEYPAD` and alike are handled inside the bindings.
 #[inline(never)]
-fn build_sequence(key: KeyEvent, mods: ModifiersState, mode: TermMode) -> Vec<u8> {
+fn build_sequence(key: &SequenceKey<'_>, mods: ModifiersState, mode: TermMode) -> Vec<u8> {
     let mut modifiers = mods.into();
 
     let kitty_seq = mode.intersects(
@@ -275,7 +428,7 @@ fn build_sequence(key: KeyEvent, mods: ModifiersState, mode: TermMode) -> Vec<u8
     let context =
         SequenceBuilder { mode, modifiers, kitty_seq, kitty_encode_all, kitty_event_type };
 
-    let associated_text = key.text_with_all_modifiers().filter(|text| {
+    let associated_text = key.text.filter(|text| {
         mode.contains(TermMode::REPORT_ASSOCIATED_TEXT)
             && key.state != ElementState::Released
             && !text.is_empty()
@@ -283,11 +436,11 @@ fn build_sequence(key: KeyEvent, mods: ModifiersState, mode: TermMode) -> Vec<u8
     });
 
     let sequence_base = context
-        .try_build_numpad(&key)
-        .or_else(|| context.try_build_named_kitty(&key))
-        .or_else(|| context.try_build_named_normal(&key))
-        .or_else(|| context.try_build_control_char_or_mod(&key, &mut modifiers))
-        .or_else(|| context.try_build_textual(&key, associated_text));
+        .try_build_numpad(key)
+        .or_else(|| context.try_build_named_kitty(key))
+        .or_else(|| context.try_build_named_normal(key))
+        .or_else(|| context.try_build_control_char_or_mod(key, &mut modifiers))
+        .or_else(|| context.try_build_textual(key, associated_text));
 
     let (payload, terminator) = match sequence_base {
         Some(SequenceBase { payload, terminator }) => (payload, terminator),
@@ -327,7 +480,7 @@ fn build_sequence(key: KeyEvent, mods: ModifiersState, mode: TermMode) -> Vec<u8
     payload.into_bytes()
 }
 
-/// Helper to build escape sequence payloads from [`KeyEvent`].
+/// Helper to build escape sequence payloads from [`SequenceKey`].
 pub struct SequenceBuilder {
     mode: TermMode,
     /// The emitted sequence should follow the kitty keyboard protocol.
@@ -343,7 +496,7 @@ impl SequenceBuilder {
     /// Try building sequence from the event's emitting text.
     fn try_build_textual(
         &self,
-        key: &KeyEvent,
+        key: &SequenceKey<'_>,
         associated_text: Option<&str>,
     ) -> Option<SequenceBase> {
         let character = match key.logical_key.as_ref() {
@@ -359,7 +512,7 @@ impl SequenceBuilder {
             let mut unicode_key_code = u32::from(base_character);
 
             // Try to get the base for keys which change based on modifier, like `1` for `!`.
-            match key.key_without_modifiers().as_ref() {
+            match key.key_without_modifiers.as_ref() {
                 Key::Character(unmodded) if alternate_key_code == unicode_key_code => {
                     unicode_key_code = u32::from(unmodded.chars().next().unwrap_or(base_character));
                 },
@@ -389,7 +542,7 @@ impl SequenceBuilder {
     /// Try building from numpad key.
     ///
     /// `None` is returned when the key is neither known nor numpad.
-    fn try_build_numpad(&self, key: &KeyEvent) -> Option<SequenceBase> {
+    fn try_build_numpad(&self, key: &SequenceKey<'_>) -> Option<SequenceBase> {
         if !self.kitty_seq || key.location != KeyLocation::Numpad {
             return None;
         }
@@ -433,7 +586,7 @@ impl SequenceBuilder {
 
     /// Try building from [`NamedKey`] using the kitty keyboard protocol encoding
     /// for functional keys.
-    fn try_build_named_kitty(&self, key: &KeyEvent) -> Option<SequenceBase> {
+    fn try_build_named_kitty(&self, key: &SequenceKey<'_>) -> Option<SequenceBase> {
         let named = match key.logical_key {
             Key::Named(named) if self.kitty_seq => named,
             _ => return None,
@@ -488,7 +641,7 @@ impl SequenceBuilder {
     }
 
     /// Try building from [`NamedKey`].
-    fn try_build_named_normal(&self, key: &KeyEvent) -> Option<SequenceBase> {
+    fn try_build_named_normal(&self, key: &SequenceKey<'_>) -> Option<SequenceBase> {
         let named = match key.logical_key {
             Key::Named(named) => named,
             _ => return None,
@@ -536,7 +689,7 @@ impl SequenceBuilder {
     /// Try building escape from control characters (e.g. Enter) and modifiers.
     fn try_build_control_char_or_mod(
         &self,
-        key: &KeyEvent,
+        key: &SequenceKey<'_>,
         mods: &mut SequenceModifiers,
     ) -> Option<SequenceBase> {
         if !self.kitty_encode_all && !self.kitty_seq {
@@ -12,6 +12,7 @@ use std::ffi::OsStr;
 use std::fmt::Debug;
 use std::marker::PhantomData;
 use std::mem;
+use std::ops::RangeInclusive;
 use std::time::{Duration, Instant};
 
 use log::debug;
@@ -27,10 +28,10 @@ use winit::platform::macos::EventLoopWindowTargetExtMacOS;
 use winit::window::CursorIcon;
 
 use larashell_terminal::event::EventListener;
-use larashell_terminal::grid::{Dimensions, Scroll};
-use larashell_terminal::index::{Boundary, Column, Direction, Point, Side};
+use larashell_terminal::grid::{Dimensions, LineMark, Scroll};
+use larashell_terminal::index::{Boundary, Column, Direction, Line, Point, Side};
 use larashell_terminal::selection::SelectionType;
-use larashell_terminal::term::search::Match;
+use larashell_terminal::term::search::{Match, RegexSearch, SearchOptions};
 use larashell_terminal::term::{ClipboardType, Term, TermMode};
 use larashell_terminal::vi_mode::ViMotion;
 use larashell_terminal::vte::ansi::{ClearMode, Handler};
@@ -43,8 +44,11 @@ use crate::display::{Display, SizeInfo};
 use crate::event::{
     ClickState, Event, EventType, InlineSearchState, Mouse, TouchPurpose, TouchZoom,
 };
+use crate::input::keyboard::BroadcastKey;
 use crate::message_bar::{self, Message};
+use crate::pane::{PaneAction, PaneDirection, SplitDirection};
 use crate::scheduler::{Scheduler, TimerId, Topic};
+use crate::tab::TabAction;
 
 pub mod keyboard;
 
@@ -95,10 +99,18 @@ pub trait ActionContext<T: EventListener> {
     fn terminal(&self) -> &Term<T>;
     fn terminal_mut(&mut self) -> &mut Term<T>;
     fn spawn_new_instance(&mut self) {}
+    fn export(&mut self, _selection: bool) {}
+    fn save_session(&mut self) {}
     #[cfg(target_os = "macos")]
     fn create_new_window(&mut self, _tabbing_id: Option<String>) {}
     #[cfg(not(target_os = "macos"))]
     fn create_new_window(&mut self) {}
+    #[cfg(not(target_os = "macos"))]
+    fn create_new_tab(&mut self) {}
+    fn pane_action(&mut self, _action: PaneAction) {}
+    fn tab_action(&mut self, _action: TabAction) {}
+    fn toggle_broadcast_input(&mut self) {}
+    fn broadcast_input(&self, _key: BroadcastKey) {}
     fn change_font_size(&mut self, _delta: f32) {}
     fn reset_font_size(&mut self) {}
     fn pop_message(&mut self) {}
@@ -110,12 +122,15 @@ pub trait ActionContext<T: EventListener> {
     fn clipboard_mut(&mut self) -> &mut Clipboard;
     fn scheduler_mut(&mut self) -> &mut Scheduler;
     fn start_search(&mut self, _direction: Direction) {}
+    fn start_fuzzy_search(&mut self) {}
+    fn toggle_filter(&mut self) {}
     fn confirm_search(&mut self) {}
     fn cancel_search(&mut self) {}
     fn search_input(&mut self, _c: char) {}
     fn search_pop_word(&mut self) {}
     fn search_history_previous(&mut self) {}
     fn search_history_next(&mut self) {}
+    fn update_search_options(&mut self, _update: fn(&mut SearchOptions)) {}
     fn search_next(&mut self, origin: Point, direction: Direction, side: Side) -> Option<Match>;
     fn advance_search_origin(&mut self, _direction: Direction) {}
     fn search_direction(&self) -> Direction;
@@ -126,6 +141,8 @@ pub trait ActionContext<T: EventListener> {
     fn start_inline_search(&mut self, _direction: Direction, _stop_short: bool) {}
     fn inline_search_next(&mut self) {}
     fn inline_search_previous(&mut self) {}
+    fn set_vi_mark(&mut self, _name: char) {}
+    fn jump_to_vi_mark(&mut self, _name: char) {}
     fn hint_input(&mut self, _character: char) {}
     fn trigger_hint(&mut self, _hint: &HintMatch) {}
     fn expand_selection(&mut self) {}
@@ -140,6 +157,33 @@ pub trait ActionContext<T: EventListener> {
 }
 
 impl Action {
+    /// Search for shell prompts, using the prompt regex if the shell doesn't report them.
+    fn prompt_search<T, A, F, R>(ctx: &A, mut search: F) -> Option<R>
+    where
+        A: ActionContext<T>,
+        T: EventListener,
+        F: FnMut(&Term<T>, Option<&mut RegexSearch>) -> Option<R>,
+    {
+        let term = ctx.terminal();
+        let regex = ctx.config().terminal.prompt_regex.as_ref();
+        match regex.and_then(|regex| regex.with_compiled(|regex| search(term, Some(regex)))) {
+            Some(result) => result,
+            None => search(term, None),
+        }
+    }
+
+    /// Select all lines within a range.
+    fn select_lines<T, A>(ctx: &mut A, lines: RangeInclusive<Line>)
+    where
+        A: ActionContext<T>,
+        T: EventListener,
+    {
+        let start = Point::new(*lines.start(), Column(0));
+        let end = Point::new(*lines.end(), ctx.terminal().last_column());
+        ctx.start_selection(SelectionType::Lines, start, Side::Left);
+        ctx.update_selection(end, Side::Right);
+    }
+
     fn toggle_selection<T, A>(ctx: &mut A, ty: SelectionType)
     where
         A: ActionContext<T>,
@@ -275,6 +319,8 @@ impl<T: EventListener> Execute<T> for Action {
             },
             Action::Vi(ViAction::InlineSearchNext) => ctx.inline_search_next(),
             Action::Vi(ViAction::InlineSearchPrevious) => ctx.inline_search_previous(),
+            Action::Vi(ViAction::SetMark(name)) => ctx.set_vi_mark(*name),
+            Action::Vi(ViAction::JumpToMark(name)) => ctx.jump_to_vi_mark(*name),
             action @ Action::Search(_) if !ctx.search_active() => {
                 debug!("Ignoring {action:?}: Search mode inactive");
             },
@@ -295,10 +341,56 @@ impl<T: EventListener> Execute<T> for Action {
             Action::Search(SearchAction::SearchDeleteWord) => ctx.search_pop_word(),
             Action::Search(SearchAction::SearchHistoryPrevious) => ctx.search_history_previous(),
             Action::Search(SearchAction::SearchHistoryNext) => ctx.search_history_next(),
+            Action::Search(SearchAction::SearchToggleCaseSensitivity) => {
+                ctx.update_search_options(|options| {
+                    options.case_sensitivity = options.case_sensitivity.next();
+                });
+            },
+            Action::Search(SearchAction::SearchToggleWholeWord) => {
+                ctx.update_search_options(|options| options.whole_word = !options.whole_word);
+            },
+            Action::Search(SearchAction::SearchToggleLiteral) => {
+                ctx.update_search_options(|options| options.literal = !options.literal);
+            },
             Action::Mouse(MouseAction::ExpandSelection) => ctx.expand_selection(),
+            Action::Mouse(MouseAction::ToggleMark) => {
+                let point = ctx.mouse().point(&ctx.size_info(), ctx.terminal().grid());
+                let term = ctx.terminal_mut();
+                let mark = match term.grid().mark(point.line) {
+                    Some(_) => None,
+                    None => Some(LineMark::Anonymous),
+                };
+                term.grid_mut().set_mark(point.line, mark);
+                ctx.mark_dirty();
+            },
             Action::SearchForward => ctx.start_search(Direction::Right),
             Action::SearchBackward => ctx.start_search(Direction::Left),
+            Action::FuzzySearch => ctx.start_fuzzy_search(),
+            Action::FilterLines => ctx.toggle_filter(),
             Action::Copy => ctx.copy_selection(ClipboardType::Clipboard),
+            Action::CopyLastCommandOutput => {
+                let output =
+                    Self::prompt_search(ctx, |term, regex| term.last_command_output(regex));
+                if let Some(lines) = output {
+                    Self::select_lines(ctx, lines);
+                    ctx.copy_selection(ClipboardType::Clipboard);
+                }
+            },
+            Action::SelectCommandOutput => {
+                let term = ctx.terminal();
+                let point = if term.mode().contains(TermMode::VI) {
+                    term.vi_mode_cursor.point
+                } else {
+                    ctx.mouse().point(&ctx.size_info(), term.grid())
+                };
+
+                let output =
+                    Self::prompt_search(ctx, |term, regex| term.command_output(point.line, regex));
+                if let Some(lines) = output {
+                    Self::select_lines(ctx, lines);
+                    ctx.copy_selection(ClipboardType::Selection);
+                }
+            },
             #[cfg(not(any(target_os = "macos", windows)))]
             Action::CopySelection => ctx.copy_selection(ClipboardType::Selection),
             Action::ClearSelection => ctx.clear_selection(),
@@ -376,11 +468,71 @@ impl<T: EventListener> Execute<T> for Action {
                 term.vi_motion(ViMotion::FirstOccupied);
                 ctx.mark_dirty();
             },
+            Action::ScrollToPreviousMark
+            | Action::ScrollToNextMark
+            | Action::ScrollToPreviousPrompt
+            | Action::ScrollToNextPrompt => {
+                let direction = match self {
+                    Action::ScrollToPreviousMark | Action::ScrollToPreviousPrompt => {
+                        Direction::Left
+                    },
+                    _ => Direction::Right,
+                };
+
+                // Search from the vi mode cursor, or the top of the viewport.
+                let term = ctx.terminal();
+                let display_offset = term.grid().display_offset() as i32;
+                let origin = if term.mode().contains(TermMode::VI) {
+                    term.vi_mode_cursor.point.line
+                } else {
+                    Line(-display_offset)
+                };
+
+                let line = match self {
+                    Action::ScrollToPreviousMark | Action::ScrollToNextMark => {
+                        term.grid().next_mark(origin, direction)
+                    },
+                    _ => Self::prompt_search(ctx, |term, regex| {
+                        term.prompt_line(origin, direction, regex)
+                    }),
+                };
+                let line = match line {
+                    Some(line) => line,
+                    None => return,
+                };
+
+                // Show the line at the top of the viewport.
+                ctx.scroll(Scroll::Delta(-line.0 - display_offset));
+
+                // Move vi mode cursor.
+                ctx.terminal_mut().vi_mode_cursor.point.line = line;
+                ctx.terminal_mut().vi_motion(ViMotion::FirstOccupied);
+                ctx.mark_dirty();
+            },
             Action::ClearHistory => ctx.terminal_mut().clear_screen(ClearMode::Saved),
+            Action::ExportScrollback => ctx.export(false),
+            Action::ExportSelection if !ctx.selection_is_empty() => ctx.export(true),
+            Action::ExportSelection => (),
+            Action::SaveSession => ctx.save_session(),
             Action::ClearLogNotice => ctx.pop_message(),
             #[cfg(not(target_os = "macos"))]
             Action::CreateNewWindow => ctx.create_new_window(),
             Action::SpawnNewInstance => ctx.spawn_new_instance(),
+            Action::SplitRight => ctx.pane_action(PaneAction::Split(SplitDirection::Right)),
+            Action::SplitDown => ctx.pane_action(PaneAction::Split(SplitDirection::Down)),
+            Action::ClosePane => ctx.terminal_mut().exit(),
+            Action::FocusNextPane => ctx.pane_action(PaneAction::FocusNext),
+            Action::FocusPreviousPane => ctx.pane_action(PaneAction::FocusPrevious),
+            Action::FocusPaneLeft => ctx.pane_action(PaneAction::Focus(PaneDirection::Left)),
+            Action::FocusPaneRight => ctx.pane_action(PaneAction::Focus(PaneDirection::Right)),
+            Action::FocusPaneUp => ctx.pane_action(PaneAction::Focus(PaneDirection::Up)),
+            Action::FocusPaneDown => ctx.pane_action(PaneAction::Focus(PaneDirection::Down)),
+            Action::ResizePaneLeft => ctx.pane_action(PaneAction::Resize(PaneDirection::Left)),
+            Action::ResizePaneRight => ctx.pane_action(PaneAction::Resize(PaneDirection::Right)),
+            Action::ResizePaneUp => ctx.pane_action(PaneAction::Resize(PaneDirection::Up)),
+            Action::ResizePaneDown => ctx.pane_action(PaneAction::Resize(PaneDirection::Down)),
+            Action::TogglePaneZoom => ctx.pane_action(PaneAction::ToggleZoom),
+            Action::ToggleBroadcastInput => ctx.toggle_broadcast_input(),
             #[cfg(target_os = "macos")]
             Action::CreateNewWindow => ctx.create_new_window(None),
             #[cfg(target_os = "macos")]
@@ -412,6 +564,32 @@ impl<T: EventListener> Execute<T> for Action {
             Action::SelectTab9 => ctx.window().select_tab_at_index(8),
             #[cfg(target_os = "macos")]
             Action::SelectLastTab => ctx.window().select_last_tab(),
+            #[cfg(not(target_os = "macos"))]
+            Action::CreateNewTab => ctx.create_new_tab(),
+            #[cfg(not(target_os = "macos"))]
+            Action::SelectNextTab => ctx.tab_action(TabAction::Next),
+            #[cfg(not(target_os = "macos"))]
+            Action::SelectPreviousTab => ctx.tab_action(TabAction::Previous),
+            #[cfg(not(target_os = "macos"))]
+            Action::SelectTab1 => ctx.tab_action(TabAction::Select(0)),
+            #[cfg(not(target_os = "macos"))]
+            Action::SelectTab2 => ctx.tab_action(TabAction::Select(1)),
+            #[cfg(not(target_os = "macos"))]
+            Action::SelectTab3 => ctx.tab_action(TabAction::Select(2)),
+            #[cfg(not(target_os = "macos"))]
+            Action::SelectTab4 => ctx.tab_action(TabAction::Select(3)),
+            #[cfg(not(target_os = "macos"))]
+            Action::SelectTab5 => ctx.tab_action(TabAction::Select(4)),
+            #[cfg(not(target_os = "macos"))]
+            Action::SelectTab6 => ctx.tab_action(TabAction::Select(5)),
+            #[cfg(not(target_os = "macos"))]
+            Action::SelectTab7 => ctx.tab_action(TabAction::Select(6)),
+            #[cfg(not(target_os = "macos"))]
+            Action::SelectTab8 => ctx.tab_action(TabAction::Select(7)),
+            #[cfg(not(target_os = "macos"))]
+            Action::SelectTab9 => ctx.tab_action(TabAction::Select(8)),
+            #[cfg(not(target_os = "macos"))]
+            Action::SelectLastTab => ctx.tab_action(TabAction::SelectLast),
             _ => (),
         }
     }
@@ -434,8 +612,7 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
             self.update_selection_scrolling(y);
         }
 
-        let display_offset = self.ctx.terminal().grid().display_offset();
-        let old_point = self.ctx.mouse().point(&size_info, display_offset);
+        let old_point = self.ctx.mouse().point(&size_info, self.ctx.terminal().grid());
 
         let x = x.clamp(0, size_info.width() as i32 - 1) as usize;
         let y = y.clamp(0, size_info.height() as i32 - 1) as usize;
@@ -445,7 +622,7 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
         let inside_text_area = size_info.contains_point(x, y);
         let cell_side = self.cell_side(x);
 
-        let point = self.ctx.mouse().point(&size_info, display_offset);
+        let point = self.ctx.mouse().point(&size_info, self.ctx.terminal().grid());
         let cell_changed = old_point != point;
 
         // If the mouse hasn't changed cells, do nothing.
@@ -492,9 +669,17 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
     fn cell_side(&self, x: usize) -> Side {
         let size_info = self.ctx.size_info();
 
-        let cell_x =
-            x.saturating_sub(size_info.padding_x() as usize) % size_info.cell_width() as usize;
-        let half_cell_width = (size_info.cell_width() / 2.0) as usize;
+        // Every cell in double width lines covers two columns.
+        let grid = self.ctx.terminal().grid();
+        let line = self.ctx.mouse().point(&size_info, grid).line;
+        let cell_width = if grid[line].line_attribute().is_double_width() {
+            size_info.cell_width() * 2.
+        } else {
+            size_info.cell_width()
+        };
+
+        let cell_x = x.saturating_sub(size_info.padding_x() as usize) % cell_width as usize;
+        let half_cell_width = (cell_width / 2.0) as usize;
 
         let additional_padding =
             (size_info.width() - size_info.padding_x() * 2.) % size_info.cell_width();
@@ -511,8 +696,7 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
     }
 
     fn mouse_report(&mut self, button: u8, state: ElementState) {
-        let display_offset = self.ctx.terminal().grid().display_offset();
-        let point = self.ctx.mouse().point(&self.ctx.size_info(), display_offset);
+        let point = self.ctx.mouse().point(&self.ctx.size_info(), self.ctx.terminal().grid());
 
         // Assure the mouse point is not in the scrollback.
         if point.line < 0 {
@@ -619,8 +803,7 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
             };
 
             // Load mouse point, treating message bar and padding as the closest cell.
-            let display_offset = self.ctx.terminal().grid().display_offset();
-            let point = self.ctx.mouse().point(&self.ctx.size_info(), display_offset);
+            let point = self.ctx.mouse().point(&self.ctx.size_info(), self.ctx.terminal().grid());
 
             if let MouseButton::Left = button {
                 self.on_left_click(point)
@@ -1030,8 +1213,7 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
             + size.cell_height() as usize * (size.screen_lines() + search_height);
 
         let mouse = self.ctx.mouse();
-        let display_offset = self.ctx.terminal().grid().display_offset();
-        let point = self.ctx.mouse().point(&self.ctx.size_info(), display_offset);
+        let point = self.ctx.mouse().point(&self.ctx.size_info(), self.ctx.terminal().grid());
 
         if self.ctx.message().is_none() || (mouse.y <= terminal_end) {
             None
@@ -1046,8 +1228,7 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
 
     /// Icon state of the cursor.
     fn cursor_state(&mut self) -> CursorIcon {
-        let display_offset = self.ctx.terminal().grid().display_offset();
-        let point = self.ctx.mouse().point(&self.ctx.size_info(), display_offset);
+        let point = self.ctx.mouse().point(&self.ctx.size_info(), self.ctx.terminal().grid());
         let hyperlink = self.ctx.terminal().grid()[point].hyperlink();
 
         // Function to check if mouse is on top of a hint.
@@ -1,23 +1,276 @@
 //! LaraShell socket IPC.
+//!
+//! Every request is a single line of JSON, answered with a single line of JSON carrying the same
+//! request ID and either a result or an error object.
+//!
+//! After answering a `subscribe` request, the connection stays open and every subscribed event is
+//! written to it as a single line of JSON.
 
+use std::collections::HashMap;
+use std::error::Error;
 use std::ffi::OsStr;
-use std::io::{BufRead, BufReader, Error as IoError, ErrorKind, Result as IoResult, Write};
+use std::fmt::{self, Display, Formatter};
+use std::fs::{DirBuilder, Permissions};
+use std::io::{BufRead, BufReader, Error as IoError, ErrorKind, Lines, Result as IoResult, Write};
+use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+use std::os::unix::io::AsRawFd;
 use std::os::unix::net::{UnixListener, UnixStream};
-use std::path::PathBuf;
-use std::{env, fs, process};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender, SyncSender};
+use std::time::Duration;
+use std::{env, fs, mem, process};
 
 use log::warn;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use winit::event_loop::EventLoopProxy;
-use winit::window::WindowId;
 
 use larashell_terminal::thread;
 
-use crate::cli::{Options, SocketMessage};
+use crate::cli::{Options, SocketMessage, SubscriptionEvent};
 use crate::event::{Event, EventType};
 
 /// Environment variable name for the IPC socket path.
 const LARASHELL_SOCKET_ENV: &str = "LARASHELL_SOCKET";
 
+/// Maximum time to wait for the event loop to answer a request.
+const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Maximum time to wait for a client to send its request or accept written data.
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Maximum number of events queued for a subscribed client before it is disconnected.
+const MAX_QUEUED_EVENTS: usize = 1024;
+
+/// Request sent to the IPC socket.
+///
+/// The session server uses the same format for its own messages.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct IpcRequest<M = SocketMessage> {
+    /// ID used to match the reply to its request.
+    pub id: u64,
+
+    /// Message which should be processed.
+    pub message: M,
+}
+
+/// Reply to an IPC request.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct IpcResponse {
+    /// ID of the answered request.
+    pub id: u64,
+
+    /// Outcome of the request.
+    #[serde(flatten)]
+    pub reply: IpcReply,
+}
+
+impl IpcResponse {
+    pub fn new(id: u64, result: Result<Value, IpcError>) -> Self {
+        let reply = match result {
+            Ok(value) => IpcReply::Result(value),
+            Err(err) => IpcReply::Error(err),
+        };
+        Self { id, reply }
+    }
+}
+
+/// Outcome of an IPC request.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum IpcReply {
+    Result(Value),
+    Error(IpcError),
+}
+
+/// Error processing an IPC request.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct IpcError {
+    /// Machine readable error category.
+    pub code: IpcErrorCode,
+
+    /// Human readable error description.
+    pub message: String,
+}
+
+impl IpcError {
+    pub fn new<M: Into<String>>(code: IpcErrorCode, message: M) -> Self {
+        Self { code, message: message.into() }
+    }
+}
+
+impl Error for IpcError {}
+
+impl Display for IpcError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// Category of an IPC error.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum IpcErrorCode {
+    /// The request could not be parsed.
+    InvalidRequest,
+
+    /// No window matches the requested window ID.
+    NoWindow,
+
+    /// The message is disabled by the configuration.
+    Forbidden,
+
+    /// The request was valid, but could not be completed.
+    Failed,
+}
+
+/// Channel for answering an IPC request from the event loop.
+///
+/// Dropping the replier without calling [`IpcReplier::reply`] answers the request with an error.
+#[derive(Debug, Clone)]
+pub struct IpcReplier(Sender<Result<Value, IpcError>>);
+
+impl IpcReplier {
+    /// Answer the IPC request.
+    pub fn reply(self, result: Result<Value, IpcError>) {
+        let _ = self.0.send(result);
+    }
+}
+
+/// State of a window reported by the `list-windows` message.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct IpcWindow {
+    /// Window ID, as used by the `--window-id` options.
+    pub id: u64,
+
+    /// Current window title.
+    pub title: String,
+
+    /// Number of terminal columns.
+    pub columns: usize,
+
+    /// Number of terminal lines.
+    pub lines: usize,
+
+    /// Window width in pixels.
+    pub width: u32,
+
+    /// Window height in pixels.
+    pub height: u32,
+
+    /// Working directory of the foreground process.
+    pub cwd: Option<PathBuf>,
+
+    /// Name of the foreground process.
+    pub process: Option<String>,
+
+    /// Whether the window has keyboard focus.
+    pub focused: bool,
+}
+
+/// Terminal content read by the `get-text` message.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct IpcText {
+    /// Text of all lines which have been read.
+    pub text: String,
+
+    /// Number of lines in the scrollback buffer, including the visible lines.
+    pub total_lines: usize,
+
+    /// Whether the extent has more lines above the lines which have been read.
+    pub truncated: bool,
+}
+
+/// Event reported to subscribed IPC clients.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct IpcEvent {
+    /// ID of the window which emitted the event.
+    pub window_id: u64,
+
+    /// Event type and its details.
+    #[serde(flatten)]
+    pub data: IpcEventData,
+}
+
+/// Type and details of an IPC event.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum IpcEventData {
+    Title { title: String },
+    Bell,
+    Exit,
+    Focus { focused: bool },
+    Cwd { cwd: PathBuf },
+    CommandFinished { exit_code: Option<i32> },
+}
+
+impl IpcEventData {
+    fn subscription(&self) -> SubscriptionEvent {
+        match self {
+            Self::Title { .. } => SubscriptionEvent::Title,
+            Self::Bell => SubscriptionEvent::Bell,
+            Self::Exit => SubscriptionEvent::Exit,
+            Self::Focus { .. } => SubscriptionEvent::Focus,
+            Self::Cwd { .. } => SubscriptionEvent::Cwd,
+            Self::CommandFinished { .. } => SubscriptionEvent::CommandFinished,
+        }
+    }
+}
+
+/// IPC client receiving events.
+#[derive(Debug, Clone)]
+pub struct IpcSubscriber {
+    events: Vec<SubscriptionEvent>,
+    sender: SyncSender<IpcEvent>,
+}
+
+/// All IPC clients receiving events.
+#[derive(Default, Debug)]
+pub struct IpcSubscribers {
+    subscribers: Vec<IpcSubscriber>,
+
+    /// Last working directory reported for each window.
+    cwds: HashMap<u64, PathBuf>,
+}
+
+impl IpcSubscribers {
+    /// Start sending events to a client.
+    pub fn add(&mut self, subscriber: IpcSubscriber) {
+        self.subscribers.push(subscriber);
+    }
+
+    /// Check if any client receives an event type.
+    pub fn is_subscribed(&self, event: SubscriptionEvent) -> bool {
+        self.subscribers.iter().any(|subscriber| subscriber.events.contains(&event))
+    }
+
+    /// Send an event to all clients subscribed to it.
+    ///
+    /// Clients which have disconnected or are not reading their events fast enough are removed.
+    pub fn publish(&mut self, window_id: u64, data: IpcEventData) {
+        if data == IpcEventData::Exit {
+            self.cwds.remove(&window_id);
+        }
+
+        let subscription = data.subscription();
+        let event = IpcEvent { window_id, data };
+        self.subscribers.retain(|subscriber| {
+            !subscriber.events.contains(&subscription)
+                || subscriber.sender.try_send(event.clone()).is_ok()
+        });
+    }
+
+    /// Send a `cwd` event if the working directory of a window has changed.
+    pub fn update_cwd(&mut self, window_id: u64, cwd: PathBuf) {
+        if self.cwds.get(&window_id) == Some(&cwd) {
+            return;
+        }
+
+        self.cwds.insert(window_id, cwd.clone());
+        self.publish(window_id, IpcEventData::Cwd { cwd });
+    }
+}
+
 /// Create an IPC socket.
 pub fn spawn_ipc_socket(options: &Options, event_proxy: EventLoopProxy<Event>) -> Option<PathBuf> {
     // Create the IPC socket and export its path as env variable if necessary.
@@ -28,7 +281,7 @@ pub fn spawn_ipc_socket(options: &Options, event_proxy: EventLoopProxy<Event>) -
     });
     env::set_var(LARASHELL_SOCKET_ENV, socket_path.as_os_str());
 
-    let listener = match UnixListener::bind(&socket_path) {
+    let listener = match bind(&socket_path) {
         Ok(listener) => listener,
         Err(err) => {
             warn!("Unable to create socket: {:?}", err);
@@ -38,60 +291,230 @@ pub fn spawn_ipc_socket(options: &Options, event_proxy: EventLoopProxy<Event>) -
 
     // Spawn a thread to listen on the IPC socket.
     thread::spawn_named("socket listener", move || {
-        let mut data = String::new();
         for stream in listener.incoming().filter_map(Result::ok) {
-            data.clear();
-            let mut stream = BufReader::new(stream);
-
-            match stream.read_line(&mut data) {
-                Ok(0) | Err(_) => continue,
-                Ok(_) => (),
-            };
-
-            // Read pending events on socket.
-            let message: SocketMessage = match serde_json::from_str(&data) {
-                Ok(message) => message,
-                Err(err) => {
-                    warn!("Failed to convert data from socket: {}", err);
-                    continue;
-                },
-            };
-
-            // Handle IPC events.
-            match message {
-                SocketMessage::CreateWindow(options) => {
-                    let event = Event::new(EventType::CreateWindow(options), None);
-                    let _ = event_proxy.send_event(event);
-                },
-                SocketMessage::Config(ipc_config) => {
-                    let window_id = ipc_config
-                        .window_id
-                        .and_then(|id| u64::try_from(id).ok())
-                        .map(WindowId::from);
-                    let event = Event::new(EventType::IpcConfig(ipc_config), window_id);
-                    let _ = event_proxy.send_event(event);
-                },
-            }
+            // Answer every client on its own thread, so slow clients can't block the others.
+            let event_proxy = event_proxy.clone();
+            thread::spawn_named("socket connection", move || {
+                let send_event = |event| event_proxy.send_event(Event::new(event, None)).is_ok();
+                if let Err(err) = handle_connection(stream, &send_event) {
+                    warn!("Failed to answer IPC request: {}", err);
+                }
+            });
         }
     });
 
     Some(socket_path)
 }
 
-/// Send a message to the active LaraShell socket.
-pub fn send_message(socket: Option<PathBuf>, message: SocketMessage) -> IoResult<()> {
-    let mut socket = find_socket(socket)?;
+/// Bind a socket which is only accessible to the current user.
+///
+/// The socket is created inside a private directory and only linked to its path once its
+/// permissions have been restricted, so other users can never connect to it.
+pub fn bind(socket_path: &Path) -> IoResult<UnixListener> {
+    let file_name = socket_path
+        .file_name()
+        .ok_or_else(|| IoError::new(ErrorKind::InvalidInput, "socket path has no file name"))?;
+    let parent = socket_path.parent().filter(|parent| !parent.as_os_str().is_empty());
+    let private_dir = parent.unwrap_or_else(|| Path::new(".")).join(format!(
+        ".{}.{}",
+        file_name.to_string_lossy(),
+        process::id()
+    ));
+    DirBuilder::new().mode(0o700).create(&private_dir)?;
+
+    let private_path = private_dir.join(file_name);
+    let result = UnixListener::bind(&private_path).and_then(|listener| {
+        fs::set_permissions(&private_path, Permissions::from_mode(0o600))?;
+
+        // Unlike renaming, linking never replaces the socket of another process.
+        fs::hard_link(&private_path, socket_path)?;
+
+        Ok(listener)
+    });
+
+    let _ = fs::remove_file(&private_path);
+    let _ = fs::remove_dir(&private_dir);
+
+    result
+}
+
+/// Process a request and send back its reply.
+///
+/// The `send_event` function forwards events to the event loop, returning `false` if it is closed.
+fn handle_connection<F>(stream: UnixStream, send_event: &F) -> IoResult<()>
+where
+    F: Fn(EventType) -> bool,
+{
+    // Refuse requests from other users, even if the socket permissions were changed.
+    let trusted = peer_uid(&stream)? == unsafe { libc::geteuid() };
+
+    // Don't wait forever on clients which stop reading or writing.
+    stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+    stream.set_write_timeout(Some(CLIENT_TIMEOUT))?;
 
-    let message = serde_json::to_string(&message)?;
-    socket.write_all(message[..].as_bytes())?;
-    let _ = socket.flush();
+    let mut data = String::new();
+    let mut stream = BufReader::new(stream);
+    if stream.read_line(&mut data)? == 0 {
+        return Ok(());
+    }
+
+    let mut events = None;
+    let response = match serde_json::from_str::<IpcRequest>(&data) {
+        Ok(request) if !trusted => {
+            let message = "socket peer belongs to a different user";
+            IpcResponse::new(request.id, Err(IpcError::new(IpcErrorCode::Forbidden, message)))
+        },
+        Ok(IpcRequest { id, message: SocketMessage::Subscribe(subscribe) }) => {
+            let (sender, receiver) = mpsc::sync_channel(MAX_QUEUED_EVENTS);
+            let subscriber = IpcSubscriber { events: subscribe.events, sender };
+            let result =
+                dispatch(send_event, |replier| EventType::IpcSubscribe(subscriber, replier));
+            if result.is_ok() {
+                events = Some(receiver);
+            }
+            IpcResponse::new(id, result)
+        },
+        Ok(IpcRequest { id, message }) => {
+            IpcResponse::new(id, dispatch(send_event, |replier| EventType::Ipc(message, replier)))
+        },
+        Err(err) => {
+            // Attempt to answer with the request's ID, even if its message is invalid.
+            let id = serde_json::from_str::<Value>(&data)
+                .ok()
+                .and_then(|request| request["id"].as_u64())
+                .unwrap_or_default();
+            let message = format!("invalid request: {err}");
+            IpcResponse::new(id, Err(IpcError::new(IpcErrorCode::InvalidRequest, message)))
+        },
+    };
+
+    let mut stream = stream.into_inner();
+    write_line(&mut stream, &response)?;
+
+    // Stream events on a separate thread, so other requests can still be answered.
+    if let Some(events) = events {
+        thread::spawn_named("socket subscription", move || stream_events(stream, events));
+    }
 
     Ok(())
 }
 
+/// Write events to a subscribed client until it disconnects.
+fn stream_events(mut stream: UnixStream, events: Receiver<IpcEvent>) {
+    for event in events {
+        if write_line(&mut stream, &event).is_err() {
+            break;
+        }
+    }
+}
+
+/// Write a value to the socket as a single line of JSON.
+pub fn write_line<T: Serialize>(stream: &mut UnixStream, value: &T) -> IoResult<()> {
+    let mut line = serde_json::to_string(value)?;
+    line.push('\n');
+    stream.write_all(line.as_bytes())?;
+    stream.flush()
+}
+
+/// Forward a request to the event loop and wait for its reply.
+fn dispatch<F, E>(send_event: &F, event: E) -> Result<Value, IpcError>
+where
+    F: Fn(EventType) -> bool,
+    E: FnOnce(IpcReplier) -> EventType,
+{
+    let (sender, receiver) = mpsc::channel();
+    if !send_event(event(IpcReplier(sender))) {
+        return Err(IpcError::new(IpcErrorCode::Failed, "event loop is closed"));
+    }
+
+    match receiver.recv_timeout(REPLY_TIMEOUT) {
+        Ok(result) => result,
+        Err(_) => Err(IpcError::new(IpcErrorCode::Failed, "request was not answered")),
+    }
+}
+
+/// Get the user ID of the process connected to a socket.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn peer_uid(stream: &UnixStream) -> IoResult<libc::uid_t> {
+    let mut credentials: libc::ucred = unsafe { mem::zeroed() };
+    let mut len = mem::size_of::<libc::ucred>() as libc::socklen_t;
+    let result = unsafe {
+        libc::getsockopt(
+            stream.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            &mut credentials as *mut libc::ucred as *mut libc::c_void,
+            &mut len,
+        )
+    };
+
+    if result == 0 {
+        Ok(credentials.uid)
+    } else {
+        Err(IoError::last_os_error())
+    }
+}
+
+/// Get the user ID of the process connected to a socket.
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+pub fn peer_uid(stream: &UnixStream) -> IoResult<libc::uid_t> {
+    let mut uid = 0;
+    let mut gid = 0;
+    if unsafe { libc::getpeereid(stream.as_raw_fd(), &mut uid, &mut gid) } == 0 {
+        Ok(uid)
+    } else {
+        Err(IoError::last_os_error())
+    }
+}
+
+/// Send a message to the active LaraShell socket and wait for its result.
+pub fn send_message(
+    socket: Option<PathBuf>,
+    message: SocketMessage,
+) -> Result<Value, Box<dyn Error>> {
+    request(socket, message).map(|(value, _)| value)
+}
+
+/// Subscribe to events of the active LaraShell socket.
+///
+/// Returns an iterator over the JSON lines of all subscribed events.
+pub fn subscribe(
+    socket: Option<PathBuf>,
+    message: SocketMessage,
+) -> Result<Lines<BufReader<UnixStream>>, Box<dyn Error>> {
+    request(socket, message).map(|(_, socket)| socket.lines())
+}
+
+/// Send a message to the active LaraShell socket and read its result.
+fn request(
+    socket: Option<PathBuf>,
+    message: SocketMessage,
+) -> Result<(Value, BufReader<UnixStream>), Box<dyn Error>> {
+    request_on(find_socket(socket)?, message)
+}
+
+/// Send a message to a connected socket and read its result.
+pub fn request_on<M: Serialize>(
+    mut socket: UnixStream,
+    message: M,
+) -> Result<(Value, BufReader<UnixStream>), Box<dyn Error>> {
+    let request = IpcRequest { id: process::id().into(), message };
+    write_line(&mut socket, &request)?;
+
+    let mut data = String::new();
+    let mut socket = BufReader::new(socket);
+    socket.read_line(&mut data)?;
+    let response: IpcResponse = serde_json::from_str(&data)?;
+
+    match response.reply {
+        IpcReply::Result(value) => Ok((value, socket)),
+        IpcReply::Error(err) => Err(err.into()),
+    }
+}
+
 /// Directory for the IPC socket file.
 #[cfg(not(target_os = "macos"))]
-fn socket_dir() -> PathBuf {
+pub fn socket_dir() -> PathBuf {
     xdg::BaseDirectories::with_prefix("larashell")
         .ok()
         .and_then(|xdg| xdg.get_runtime_directory().map(ToOwned::to_owned).ok())
@@ -101,7 +524,7 @@ fn socket_dir() -> PathBuf {
 
 /// Directory for the IPC socket file.
 #[cfg(target_os = "macos")]
-fn socket_dir() -> PathBuf {
+pub fn socket_dir() -> PathBuf {
     env::temp_dir()
 }
 
@@ -169,3 +592,167 @@ fn socket_prefix() -> String {
 fn socket_prefix() -> String {
     String::from("LaraShell")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::cell::RefCell;
+
+    use serde_json::json;
+
+    /// Send a request through a socketpair and read its reply.
+    fn roundtrip<F>(request: &str, send_event: F) -> (String, BufReader<UnixStream>)
+    where
+        F: Fn(EventType) -> bool,
+    {
+        let (mut client, server) = UnixStream::pair().unwrap();
+        client.write_all(request.as_bytes()).unwrap();
+        handle_connection(server, &send_event).unwrap();
+
+        let mut client = BufReader::new(client);
+        let mut response = String::new();
+        client.read_line(&mut response).unwrap();
+        (response, client)
+    }
+
+    #[test]
+    fn response_format() {
+        let response = IpcResponse::new(3, Ok(json!([1, 2])));
+        assert_eq!(serde_json::to_string(&response).unwrap(), r#"{"id":3,"result":[1,2]}"#);
+
+        let error = IpcError::new(IpcErrorCode::NoWindow, "no matching window");
+        let response = IpcResponse::new(4, Err(error));
+        let serialized = serde_json::to_string(&response).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"id":4,"error":{"code":"no-window","message":"no matching window"}}"#
+        );
+        assert_eq!(serde_json::from_str::<IpcResponse>(&serialized).unwrap(), response);
+    }
+
+    #[test]
+    fn request_format() {
+        let request: IpcRequest =
+            serde_json::from_str(r#"{"id":1,"message":"ListWindows"}"#).unwrap();
+        assert_eq!(request, IpcRequest { id: 1, message: SocketMessage::ListWindows });
+    }
+
+    #[test]
+    fn event_format() {
+        let data = IpcEventData::CommandFinished { exit_code: Some(2) };
+        let event = IpcEvent { window_id: 7, data };
+        let serialized = serde_json::to_string(&event).unwrap();
+        assert_eq!(serialized, r#"{"window_id":7,"event":"command-finished","exit_code":2}"#);
+        assert_eq!(serde_json::from_str::<IpcEvent>(&serialized).unwrap(), event);
+
+        let event = IpcEvent { window_id: 1, data: IpcEventData::Bell };
+        assert_eq!(serde_json::to_string(&event).unwrap(), r#"{"window_id":1,"event":"bell"}"#);
+    }
+
+    #[test]
+    fn publish_subscribed() {
+        let (sender, receiver) = mpsc::sync_channel(MAX_QUEUED_EVENTS);
+        let mut subscribers = IpcSubscribers::default();
+        subscribers.add(IpcSubscriber { events: vec![SubscriptionEvent::Cwd], sender });
+        assert!(subscribers.is_subscribed(SubscriptionEvent::Cwd));
+        assert!(!subscribers.is_subscribed(SubscriptionEvent::Bell));
+
+        subscribers.publish(1, IpcEventData::Bell);
+        subscribers.update_cwd(1, PathBuf::from("/tmp"));
+        subscribers.update_cwd(1, PathBuf::from("/tmp"));
+        subscribers.update_cwd(2, PathBuf::from("/tmp"));
+
+        let window_ids: Vec<_> = receiver.try_iter().map(|event| event.window_id).collect();
+        assert_eq!(window_ids, vec![1, 2]);
+
+        // Disconnected clients are removed on the next event.
+        drop(receiver);
+        subscribers.update_cwd(1, PathBuf::from("/"));
+        assert!(!subscribers.is_subscribed(SubscriptionEvent::Cwd));
+    }
+
+    #[test]
+    fn drop_slow_subscriber() {
+        let (sender, _receiver) = mpsc::sync_channel(1);
+        let mut subscribers = IpcSubscribers::default();
+        subscribers.add(IpcSubscriber { events: vec![SubscriptionEvent::Bell], sender });
+
+        subscribers.publish(1, IpcEventData::Bell);
+        assert!(subscribers.is_subscribed(SubscriptionEvent::Bell));
+
+        // Clients are removed once their queue is full.
+        subscribers.publish(1, IpcEventData::Bell);
+        assert!(!subscribers.is_subscribed(SubscriptionEvent::Bell));
+    }
+
+    #[test]
+    fn socket_permissions() {
+        let path = env::temp_dir().join(format!("larashell-test-{}.sock", process::id()));
+        let _ = fs::remove_file(&path);
+
+        let listener = bind(&path).unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+
+        // Existing sockets are never replaced.
+        let rebind = bind(&path);
+
+        drop(listener);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(mode & 0o777, 0o600);
+        assert_eq!(rebind.unwrap_err().kind(), ErrorKind::AlreadyExists);
+    }
+
+    #[test]
+    fn peer_credentials() {
+        let (_client, server) = UnixStream::pair().unwrap();
+        assert_eq!(peer_uid(&server).unwrap(), unsafe { libc::geteuid() });
+    }
+
+    #[test]
+    fn answer_request() {
+        let request = "{\"id\":5,\"message\":\"ListWindows\"}\n";
+        let (response, _) = roundtrip(request, |event| match event {
+            EventType::Ipc(SocketMessage::ListWindows, replier) => {
+                replier.reply(Ok(json!([])));
+                true
+            },
+            _ => false,
+        });
+        assert_eq!(response, "{\"id\":5,\"result\":[]}\n");
+
+        let (response, _) = roundtrip(request, |_| false);
+        assert!(response.starts_with(r#"{"id":5,"error":{"code":"failed""#));
+
+        // Dropping the replier answers the request immediately.
+        let (response, _) = roundtrip(request, |_| true);
+        assert!(response.starts_with(r#"{"id":5,"error":{"code":"failed""#));
+
+        let (response, _) = roundtrip("{\"id\":6,\"message\":\"Unknown\"}\n", |_| unreachable!());
+        assert!(response.starts_with(r#"{"id":6,"error":{"code":"invalid-request""#));
+    }
+
+    #[test]
+    fn stream_subscription() {
+        let subscribers = RefCell::new(IpcSubscribers::default());
+
+        let request = "{\"id\":1,\"message\":{\"Subscribe\":{\"events\":[\"Bell\"]}}}\n";
+        let (response, mut client) = roundtrip(request, |event| match event {
+            EventType::IpcSubscribe(subscriber, replier) => {
+                subscribers.borrow_mut().add(subscriber);
+                replier.reply(Ok(Value::Null));
+                true
+            },
+            _ => false,
+        });
+        assert_eq!(response, "{\"id\":1,\"result\":null}\n");
+
+        subscribers.borrow_mut().publish(3, IpcEventData::Title { title: String::new() });
+        subscribers.borrow_mut().publish(3, IpcEventData::Bell);
+
+        let mut event = String::new();
+        client.read_line(&mut event).unwrap();
+        assert_eq!(event, "{\"window_id\":3,\"event\":\"bell\"}\n");
+    }
+}
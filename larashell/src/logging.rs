@@ -54,9 +54,11 @@ const ALLOWED_TARGETS: &[&str] = &[
 ];
 
 /// Initialize the logger to its defaults.
+///
+/// Without an event loop, errors are only written to the log and not shown in the message bar.
 pub fn initialize(
     options: &Options,
-    event_proxy: EventLoopProxy<Event>,
+    event_proxy: Option<EventLoopProxy<Event>>,
 ) -> Result<Option<PathBuf>, log::SetLoggerError> {
     log::set_max_level(options.log_level());
 
@@ -70,12 +72,12 @@ pub fn initialize(
 pub struct Logger {
     logfile: Mutex<OnDemandLogFile>,
     stdout: Mutex<LineWriter<Stdout>>,
-    event_proxy: Mutex<EventLoopProxy<Event>>,
+    event_proxy: Mutex<Option<EventLoopProxy<Event>>>,
     start: Instant,
 }
 
 impl Logger {
-    fn new(event_proxy: EventLoopProxy<Event>) -> Self {
+    fn new(event_proxy: Option<EventLoopProxy<Event>>) -> Self {
         let logfile = Mutex::new(OnDemandLogFile::new());
         let stdout = Mutex::new(LineWriter::new(io::stdout()));
 
@@ -102,6 +104,10 @@ impl Logger {
             Ok(event_proxy) => event_proxy,
             Err(_) => return,
         };
+        let event_proxy = match &*event_proxy {
+            Some(event_proxy) => event_proxy,
+            None => return,
+        };
 
         #[cfg(not(windows))]
         let env_var = format!("${}", LARASHELL_LOG_ENV);
@@ -2,7 +2,7 @@ use std::ffi::{CStr, CString, IntoStringError};
 use std::fmt::{self, Display, Formatter};
 use std::io;
 use std::mem::{self, MaybeUninit};
-use std::os::raw::{c_int, c_void};
+use std::os::raw::{c_char, c_int, c_void};
 use std::path::PathBuf;
 
 /// Error during working directory retrieval.
@@ -68,12 +68,27 @@ pub fn cwd(pid: c_int) -> Result<PathBuf, Error> {
     Ok(CString::from(c_str).into_string().map(PathBuf::from)?)
 }
 
+pub fn name(pid: c_int) -> Result<String, Error> {
+    let mut buffer = [0 as c_char; sys::PROC_PIDPATHINFO_MAXSIZE];
+    let buffer_ptr = buffer.as_mut_ptr() as *mut c_void;
+
+    let c_str = unsafe {
+        match sys::proc_name(pid, buffer_ptr, buffer.len() as u32) {
+            c if c <= 0 => return Err(io::Error::last_os_error().into()),
+            _ => CStr::from_ptr(buffer.as_ptr()),
+        }
+    };
+
+    Ok(CString::from(c_str).into_string()?)
+}
+
 /// Bindings for libproc.
 #[allow(non_camel_case_types)]
 mod sys {
     use std::os::raw::{c_char, c_int, c_longlong, c_void};
 
     pub const PROC_PIDVNODEPATHINFO: c_int = 9;
+    pub const PROC_PIDPATHINFO_MAXSIZE: usize = 4096;
 
     type gid_t = c_int;
     type off_t = c_longlong;
@@ -143,6 +158,8 @@ mod sys {
             buffer: *mut c_void,
             buffersize: c_int,
         ) -> c_int;
+
+        pub fn proc_name(pid: c_int, buffer: *mut c_void, buffersize: u32) -> c_int;
     }
 }
 
@@ -156,4 +173,9 @@ mod tests {
     fn cwd_matches_current_dir() {
         assert_eq!(cwd(process::id() as i32).ok(), env::current_dir().ok());
     }
+
+    #[test]
+    fn name_of_current_process() {
+        assert!(name(process::id() as i32).is_ok_and(|name| !name.is_empty()));
+    }
 }
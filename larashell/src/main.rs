@@ -34,6 +34,8 @@ mod config;
 mod daemon;
 mod display;
 mod event;
+mod export;
+mod fuzzy;
 mod input;
 #[cfg(unix)]
 mod ipc;
@@ -42,11 +44,16 @@ mod logging;
 mod macos;
 mod message_bar;
 mod migrate;
+mod pane;
 #[cfg(windows)]
 mod panic;
 mod renderer;
 mod scheduler;
+#[cfg(unix)]
+mod server;
+mod session;
 mod string;
+mod tab;
 mod window_context;
 
 mod gl {
@@ -55,10 +62,12 @@ mod gl {
 }
 
 #[cfg(unix)]
-use crate::cli::MessageOptions;
+use crate::cli::{MessageOptions, ServerOptions, SocketMessage, MAX_TEXT_LINES};
 use crate::cli::{Options, Subcommands};
 use crate::config::{monitor, UiConfig};
 use crate::event::{Event, Processor};
+#[cfg(unix)]
+use crate::ipc::IpcText;
 #[cfg(target_os = "macos")]
 use crate::macos::locale;
 
@@ -75,12 +84,14 @@ fn main() -> Result<(), Box<dyn Error>> {
     }
 
     // Load command line options.
-    let options = Options::new();
+    let mut options = Options::new();
 
-    match options.subcommands {
+    match options.subcommands.take() {
         #[cfg(unix)]
-        Some(Subcommands::Msg(options)) => msg(options)?,
+        Some(Subcommands::Msg(options)) => msg(*options)?,
         Some(Subcommands::Migrate(options)) => migrate::migrate(options),
+        #[cfg(unix)]
+        Some(Subcommands::Server(server_options)) => server(server_options, options)?,
         None => larashell(options)?,
     }
 
@@ -89,8 +100,78 @@ fn main() -> Result<(), Box<dyn Error>> {
 
 /// `msg` subcommand entrypoint.
 #[cfg(unix)]
-fn msg(options: MessageOptions) -> Result<(), Box<dyn Error>> {
-    ipc::send_message(options.socket, options.message).map_err(|err| err.into())
+fn msg(mut options: MessageOptions) -> Result<(), Box<dyn Error>> {
+    // Resolve paths relative to the client's working directory.
+    if let SocketMessage::Export(ipc_export) = &mut options.message {
+        ipc_export.path = env::current_dir()?.join(&ipc_export.path);
+    }
+
+    // Print events until the LaraShell process closes the connection.
+    if let SocketMessage::Subscribe(_) = &options.message {
+        for event in ipc::subscribe(options.socket, options.message)? {
+            println!("{}", event?);
+        }
+        return Ok(());
+    }
+
+    // Print terminal content without JSON escaping, pointing to the next page of scrollback.
+    if let SocketMessage::GetText(ipc_text) = &options.message {
+        let next_offset = ipc_text.offset + ipc_text.lines.min(MAX_TEXT_LINES);
+        let reply = ipc::send_message(options.socket, options.message)?;
+        let text: IpcText = serde_json::from_value(reply)?;
+        println!("{}", text.text);
+
+        if text.truncated {
+            let total_lines = text.total_lines;
+            eprintln!("Read part of {total_lines} lines, continue with --offset {next_offset}");
+        }
+
+        return Ok(());
+    }
+
+    // Print returned data for queries like `list-windows`.
+    let result = ipc::send_message(options.socket, options.message)?;
+    if !result.is_null() {
+        println!("{}", serde_json::to_string_pretty(&result)?);
+    }
+
+    Ok(())
+}
+
+/// `server` subcommand entrypoint.
+#[cfg(unix)]
+fn server(server_options: ServerOptions, mut options: Options) -> Result<(), Box<dyn Error>> {
+    let socket_path = server_options.socket.unwrap_or_else(server::socket_path);
+
+    // Print the sessions of the running server.
+    if server_options.list {
+        let sessions = server::list_sessions(&socket_path)?;
+        println!("{}", serde_json::to_string_pretty(&sessions)?);
+        return Ok(());
+    }
+
+    // Without an event loop, errors are only written to the log.
+    let _log_file = logging::initialize(&options, None).expect("Unable to initialize logger");
+
+    info!("Starting LaraShell session server");
+    info!("Version {}", env!("VERSION"));
+
+    // Load configuration file.
+    let config = config::load(&mut options);
+    log_config_path(&config);
+
+    // Update the log level from config.
+    log::set_max_level(config.debug.log_level);
+
+    // Set tty environment variables.
+    tty::setup_env();
+
+    // Set env vars from config.
+    for (key, value) in config.env.iter() {
+        env::set_var(key, value);
+    }
+
+    server::run(&socket_path, config)
 }
 
 /// Temporary files stored for LaraShell.
@@ -128,7 +209,7 @@ fn larashell(mut options: Options) -> Result<(), Box<dyn Error>> {
     let window_event_loop = WinitEventLoopBuilder::<Event>::with_user_event().build()?;
 
     // Initialize the logger as soon as possible as to capture output from other subsystems.
-    let log_file = logging::initialize(&options, window_event_loop.create_proxy())
+    let log_file = logging::initialize(&options, Some(window_event_loop.create_proxy()))
         .expect("Unable to initialize logger");
 
     info!("Welcome to LaraShell");
@@ -0,0 +1,954 @@
+//! Split panes inside a terminal window.
+
+use std::error::Error;
+#[cfg(not(windows))]
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use winit::window::WindowId;
+
+use larashell_terminal::event::OnResize;
+use larashell_terminal::event_loop::{EventLoop as PtyEventLoop, Msg, Notifier};
+use larashell_terminal::sync::FairMutex;
+use larashell_terminal::term::{Term, TermSnapshot};
+use larashell_terminal::tty::{self, EventedPty};
+
+#[cfg(unix)]
+use crate::cli::TerminalOptions;
+use crate::cli::{ParsedOptions, WindowOptions};
+use crate::config::UiConfig;
+use crate::display::filter::FilterView;
+use crate::display::hint::HintState;
+use crate::display::SizeInfo;
+use crate::event::{EventProxy, InlineSearchState, SearchState};
+#[cfg(unix)]
+use crate::server::{self, AttachRequest};
+
+/// Minimum number of columns a split leaves to each of its panes.
+const MIN_PANE_COLUMNS: usize = 2;
+
+/// Minimum number of lines a split leaves to each of its panes.
+const MIN_PANE_LINES: usize = 1;
+
+/// Unique identifier of a pane.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct PaneId(usize);
+
+impl PaneId {
+    /// Create an ID which was not used by any other pane.
+    fn next() -> Self {
+        static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+        Self(NEXT_ID.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// Actions changing the panes of a window.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PaneAction {
+    /// Split the focused pane in two.
+    Split(SplitDirection),
+
+    /// Focus the next pane.
+    FocusNext,
+
+    /// Focus the previous pane.
+    FocusPrevious,
+
+    /// Focus the pane next to the focused pane.
+    Focus(PaneDirection),
+
+    /// Move the closest border of the focused pane by one cell.
+    Resize(PaneDirection),
+
+    /// Toggle between showing only the focused pane and showing all panes.
+    ToggleZoom,
+}
+
+/// Placement of the new pane when splitting a pane.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SplitDirection {
+    /// New pane to the right of the old one.
+    Right,
+
+    /// New pane below the old one.
+    Down,
+}
+
+/// Direction for moving between panes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PaneDirection {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl PaneDirection {
+    /// Split whose border moves in this direction.
+    fn split_direction(self) -> SplitDirection {
+        match self {
+            Self::Left | Self::Right => SplitDirection::Right,
+            Self::Up | Self::Down => SplitDirection::Down,
+        }
+    }
+}
+
+/// Part of the window's terminal grid.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct Region {
+    pub line: usize,
+    pub column: usize,
+    pub lines: usize,
+    pub columns: usize,
+}
+
+impl Region {
+    pub fn new(line: usize, column: usize, lines: usize, columns: usize) -> Self {
+        Self { line, column, lines, columns }
+    }
+
+    /// Check if a cell is inside the region.
+    pub fn contains(&self, line: usize, column: usize) -> bool {
+        (self.line..self.line + self.lines).contains(&line)
+            && (self.column..self.column + self.columns).contains(&column)
+    }
+
+    /// Number of lines or columns shared by two regions along a split direction.
+    fn overlap(&self, other: &Region, direction: SplitDirection) -> usize {
+        let (start, end, other_start, other_end) = match direction {
+            SplitDirection::Right => {
+                (self.line, self.line + self.lines, other.line, other.line + other.lines)
+            },
+            SplitDirection::Down => (
+                self.column,
+                self.column + self.columns,
+                other.column,
+                other.column + other.columns,
+            ),
+        };
+
+        end.min(other_end).saturating_sub(start.max(other_start))
+    }
+}
+
+/// Border between the two sides of a split.
+///
+/// Dividers are one cell wide, running vertically for [`SplitDirection::Right`] and horizontally
+/// for [`SplitDirection::Down`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Divider {
+    pub direction: SplitDirection,
+    pub region: Region,
+}
+
+/// Node of the pane layout tree.
+#[derive(Debug)]
+enum Node {
+    Pane(PaneId),
+    Split(Box<Split>),
+}
+
+impl Node {
+    /// Check if a pane is part of this node.
+    fn contains(&self, id: PaneId) -> bool {
+        match self {
+            Self::Pane(pane) => *pane == id,
+            Self::Split(split) => split.first.contains(id) || split.second.contains(id),
+        }
+    }
+
+    /// First pane of this node.
+    fn first_pane(&self) -> PaneId {
+        match self {
+            Self::Pane(pane) => *pane,
+            Self::Split(split) => split.first.first_pane(),
+        }
+    }
+
+    /// Add all panes of this node to `panes`, in layout order.
+    fn panes(&self, panes: &mut Vec<PaneId>) {
+        match self {
+            Self::Pane(pane) => panes.push(*pane),
+            Self::Split(split) => {
+                split.first.panes(panes);
+                split.second.panes(panes);
+            },
+        }
+    }
+
+    /// Compute the regions of all panes and dividers inside `region`.
+    fn layout(
+        &mut self,
+        region: Region,
+        panes: &mut Vec<(PaneId, Region)>,
+        dividers: &mut Vec<Divider>,
+    ) {
+        match self {
+            Self::Pane(pane) => panes.push((*pane, region)),
+            Self::Split(split) => {
+                split.region = region;
+
+                let (first, divider, second) = split.regions();
+                dividers.push(Divider { direction: split.direction, region: divider });
+
+                split.first.layout(first, panes, dividers);
+                split.second.layout(second, panes, dividers);
+            },
+        }
+    }
+
+    /// Replace the pane `id` with a split of it and `new`.
+    fn split(&mut self, id: PaneId, new: PaneId, direction: SplitDirection) -> bool {
+        match self {
+            Self::Pane(pane) if *pane == id => {
+                let split = Split {
+                    direction,
+                    ratio: 0.5,
+                    first: Node::Pane(id),
+                    second: Node::Pane(new),
+                    region: Region::default(),
+                };
+                *self = Self::Split(Box::new(split));
+                true
+            },
+            Self::Pane(_) => false,
+            Self::Split(split) => {
+                split.first.split(id, new, direction) || split.second.split(id, new, direction)
+            },
+        }
+    }
+
+    /// Remove the pane `id`, letting its sibling take over its space.
+    ///
+    /// Returns the first pane of the sibling.
+    fn remove(&mut self, id: PaneId) -> Option<PaneId> {
+        let split = match self {
+            Self::Split(split) => split,
+            Self::Pane(_) => return None,
+        };
+
+        let sibling = if matches!(split.first, Self::Pane(pane) if pane == id) {
+            &mut split.second
+        } else if matches!(split.second, Self::Pane(pane) if pane == id) {
+            &mut split.first
+        } else {
+            return split.first.remove(id).or_else(|| split.second.remove(id));
+        };
+
+        let sibling = std::mem::replace(sibling, Self::Pane(id));
+        let first_pane = sibling.first_pane();
+        *self = sibling;
+
+        Some(first_pane)
+    }
+
+    /// Move a border of the pane `id` by `delta` cells.
+    ///
+    /// The closest split along `direction` containing the pane is changed. Returns `None` if no
+    /// such split exists.
+    fn resize(&mut self, id: PaneId, direction: SplitDirection, delta: isize) -> Option<bool> {
+        let split = match self {
+            Self::Split(split) => split,
+            Self::Pane(_) => return None,
+        };
+
+        let child = if split.first.contains(id) {
+            &mut split.first
+        } else if split.second.contains(id) {
+            &mut split.second
+        } else {
+            return None;
+        };
+
+        if let Some(changed) = child.resize(id, direction, delta) {
+            return Some(changed);
+        }
+
+        if split.direction != direction {
+            return None;
+        }
+
+        let size = split.first_size() as isize + delta;
+        Some(split.set_first_size(size.max(0) as usize))
+    }
+
+    /// Get the split at `index`, counting splits in layout order.
+    fn split_mut(&mut self, index: usize) -> Option<&mut Split> {
+        let split = match self {
+            Self::Split(split) => split,
+            Self::Pane(_) => return None,
+        };
+
+        if index == 0 {
+            return Some(split);
+        }
+
+        let first_count = split.first.split_count();
+        if index <= first_count {
+            split.first.split_mut(index - 1)
+        } else {
+            split.second.split_mut(index - 1 - first_count)
+        }
+    }
+
+    /// Number of splits inside this node.
+    fn split_count(&self) -> usize {
+        match self {
+            Self::Pane(_) => 0,
+            Self::Split(split) => 1 + split.first.split_count() + split.second.split_count(),
+        }
+    }
+}
+
+/// Two panes or groups of panes next to each other.
+#[derive(Debug)]
+struct Split {
+    direction: SplitDirection,
+
+    /// Share of the available space given to the first node.
+    ratio: f32,
+
+    first: Node,
+    second: Node,
+
+    /// Space covered by the split at the last layout.
+    region: Region,
+}
+
+impl Split {
+    /// Lines or columns covered by the split, including the divider.
+    fn extent(&self) -> usize {
+        match self.direction {
+            SplitDirection::Right => self.region.columns,
+            SplitDirection::Down => self.region.lines,
+        }
+    }
+
+    /// Minimum size of either side of the split.
+    fn min_size(&self) -> usize {
+        match self.direction {
+            SplitDirection::Right => MIN_PANE_COLUMNS,
+            SplitDirection::Down => MIN_PANE_LINES,
+        }
+    }
+
+    /// Clamp the size of the first node, leaving space for the second node.
+    fn clamp_first_size(&self, size: usize) -> usize {
+        let available = self.extent().saturating_sub(1);
+        let min = self.min_size().min(available / 2);
+        let max = available.saturating_sub(self.min_size()).max(available - available / 2);
+        size.clamp(min, max)
+    }
+
+    /// Lines or columns covered by the first node.
+    fn first_size(&self) -> usize {
+        let available = self.extent().saturating_sub(1);
+        self.clamp_first_size((available as f32 * self.ratio).round() as usize)
+    }
+
+    /// Move the divider, to give `size` lines or columns to the first node.
+    ///
+    /// Returns `true` if the divider moved.
+    fn set_first_size(&mut self, size: usize) -> bool {
+        let available = self.extent().saturating_sub(1);
+        if available == 0 {
+            return false;
+        }
+
+        let old_size = self.first_size();
+        self.ratio = self.clamp_first_size(size) as f32 / available as f32;
+
+        old_size != self.first_size()
+    }
+
+    /// Regions of the first node, the divider, and the second node.
+    fn regions(&self) -> (Region, Region, Region) {
+        let region = self.region;
+        let size = self.first_size();
+        let remaining = self.extent().saturating_sub(size + 1);
+
+        match self.direction {
+            SplitDirection::Right => (
+                Region { columns: size, ..region },
+                Region { column: region.column + size, columns: 1, ..region },
+                Region { column: region.column + size + 1, columns: remaining, ..region },
+            ),
+            SplitDirection::Down => (
+                Region { lines: size, ..region },
+                Region { line: region.line + size, lines: 1, ..region },
+                Region { line: region.line + size + 1, lines: remaining, ..region },
+            ),
+        }
+    }
+}
+
+/// Arrangement of the panes inside a window.
+#[derive(Debug)]
+pub struct PaneLayout {
+    /// Tree of splits.
+    root: Node,
+
+    /// Pane with keyboard focus.
+    focused: PaneId,
+
+    /// Whether the focused pane covers the whole window.
+    zoomed: bool,
+
+    /// Space covered by all panes at the last layout.
+    region: Region,
+
+    /// Visible panes, in layout order.
+    visible: Vec<(PaneId, Region)>,
+
+    /// Borders between the visible panes, in layout order of their splits.
+    dividers: Vec<Divider>,
+
+    /// Index of the divider which is moved with the mouse.
+    drag: Option<usize>,
+}
+
+impl Default for PaneLayout {
+    fn default() -> Self {
+        let id = PaneId::next();
+        Self {
+            root: Node::Pane(id),
+            focused: id,
+            zoomed: false,
+            region: Region::default(),
+            visible: vec![(id, Region::default())],
+            dividers: Vec::new(),
+            drag: None,
+        }
+    }
+}
+
+impl PaneLayout {
+    /// Pane with keyboard focus.
+    pub fn focused(&self) -> PaneId {
+        self.focused
+    }
+
+    /// Visible panes and their regions.
+    pub fn visible(&self) -> &[(PaneId, Region)] {
+        &self.visible
+    }
+
+    /// Borders between the visible panes.
+    pub fn dividers(&self) -> &[Divider] {
+        &self.dividers
+    }
+
+    /// Region of a visible pane.
+    pub fn region(&self, id: PaneId) -> Option<Region> {
+        self.visible.iter().find(|(pane, _)| *pane == id).map(|(_, region)| *region)
+    }
+
+    /// Check if more than one pane is visible.
+    pub fn is_split(&self) -> bool {
+        self.visible.len() > 1
+    }
+
+    /// Check if a pane is part of the layout.
+    pub fn contains(&self, id: PaneId) -> bool {
+        self.root.contains(id)
+    }
+
+    /// Update the regions of all panes to cover part of the terminal grid.
+    pub fn set_region(&mut self, region: Region) {
+        self.region = region;
+        self.update();
+    }
+
+    /// Split the focused pane, focusing the new pane.
+    pub fn split(&mut self, direction: SplitDirection) -> PaneId {
+        let id = PaneId::next();
+
+        self.root.split(self.focused, id, direction);
+        self.focused = id;
+        self.zoomed = false;
+        self.update();
+
+        id
+    }
+
+    /// Remove a pane from the layout.
+    ///
+    /// If the pane had keyboard focus, the pane taking over its space is focused instead. The
+    /// last pane cannot be removed.
+    pub fn remove(&mut self, id: PaneId) -> bool {
+        let replacement = match self.root.remove(id) {
+            Some(replacement) => replacement,
+            None => return false,
+        };
+
+        if self.focused == id {
+            self.focused = replacement;
+        }
+        self.zoomed = false;
+        self.drag = None;
+        self.update();
+
+        true
+    }
+
+    /// Focus a pane.
+    pub fn focus(&mut self, id: PaneId) {
+        if self.root.contains(id) && self.focused != id {
+            self.focused = id;
+            self.zoomed = false;
+            self.update();
+        }
+    }
+
+    /// All panes of the layout, including panes hidden by zooming, in layout order.
+    pub fn panes(&self) -> Vec<PaneId> {
+        let mut panes = Vec::new();
+        self.root.panes(&mut panes);
+        panes
+    }
+
+    /// Pane following the focused pane in layout order.
+    pub fn next(&self, forward: bool) -> PaneId {
+        let panes = self.panes();
+        let index = panes.iter().position(|pane| *pane == self.focused).unwrap_or_default();
+        let index = if forward { index + 1 } else { index + panes.len() - 1 };
+
+        panes[index % panes.len()]
+    }
+
+    /// Visible pane next to the focused pane.
+    ///
+    /// Picks the pane sharing the most lines or columns with the focused pane.
+    pub fn neighbor(&self, direction: PaneDirection) -> Option<PaneId> {
+        let focused = self.region(self.focused)?;
+        let split_direction = direction.split_direction();
+
+        self.visible
+            .iter()
+            .filter(|(_, region)| match direction {
+                PaneDirection::Left => region.column + region.columns + 1 == focused.column,
+                PaneDirection::Right => focused.column + focused.columns + 1 == region.column,
+                PaneDirection::Up => region.line + region.lines + 1 == focused.line,
+                PaneDirection::Down => focused.line + focused.lines + 1 == region.line,
+            })
+            .map(|(pane, region)| (*pane, region.overlap(&focused, split_direction)))
+            .filter(|(_, overlap)| *overlap > 0)
+            .max_by_key(|(_, overlap)| *overlap)
+            .map(|(pane, _)| pane)
+    }
+
+    /// Move the closest border of the focused pane by one cell.
+    ///
+    /// Returns `true` if the layout changed.
+    pub fn resize_focused(&mut self, direction: PaneDirection) -> bool {
+        if self.zoomed {
+            return false;
+        }
+
+        let delta = match direction {
+            PaneDirection::Left | PaneDirection::Up => -1,
+            PaneDirection::Right | PaneDirection::Down => 1,
+        };
+
+        let changed = self.root.resize(self.focused, direction.split_direction(), delta);
+        if changed == Some(true) {
+            self.update();
+        }
+
+        changed == Some(true)
+    }
+
+    /// Toggle between showing only the focused pane and showing all panes.
+    pub fn toggle_zoom(&mut self) {
+        if matches!(self.root, Node::Split(_)) {
+            self.zoomed = !self.zoomed;
+            self.update();
+        }
+    }
+
+    /// Visible pane containing a cell.
+    pub fn pane_at(&self, line: usize, column: usize) -> Option<PaneId> {
+        self.visible.iter().find(|(_, region)| region.contains(line, column)).map(|(id, _)| *id)
+    }
+
+    /// Start moving the divider containing a cell.
+    ///
+    /// Returns `false` if there is no divider at the cell.
+    pub fn start_drag(&mut self, line: usize, column: usize) -> bool {
+        self.drag = self.dividers.iter().position(|divider| divider.region.contains(line, column));
+        self.drag.is_some()
+    }
+
+    /// Check if a divider is moved with the mouse.
+    pub fn dragging(&self) -> bool {
+        self.drag.is_some()
+    }
+
+    /// Move the dragged divider to a cell.
+    ///
+    /// Returns `true` if the layout changed.
+    pub fn drag(&mut self, line: usize, column: usize) -> bool {
+        let index = match self.drag {
+            Some(index) => index,
+            None => return false,
+        };
+
+        let split = match self.root.split_mut(index) {
+            Some(split) => split,
+            None => return false,
+        };
+
+        let size = match split.direction {
+            SplitDirection::Right => column.saturating_sub(split.region.column),
+            SplitDirection::Down => line.saturating_sub(split.region.line),
+        };
+
+        let changed = split.set_first_size(size);
+        if changed {
+            self.update();
+        }
+
+        changed
+    }
+
+    /// Stop moving a divider with the mouse.
+    pub fn stop_drag(&mut self) {
+        self.drag = None;
+    }
+
+    /// Recompute the regions of all visible panes.
+    fn update(&mut self) {
+        self.visible.clear();
+        self.dividers.clear();
+
+        if self.zoomed {
+            self.visible.push((self.focused, self.region));
+        } else {
+            self.root.layout(self.region, &mut self.visible, &mut self.dividers);
+        }
+    }
+}
+
+/// Search, filter and hint state of a pane.
+///
+/// The window owns the state of its focused pane, it's only stored in the pane while another
+/// pane has keyboard focus.
+pub struct PaneState {
+    pub search_state: SearchState,
+    pub inline_search_state: InlineSearchState,
+    pub filter: FilterView,
+    pub hint_state: HintState,
+}
+
+impl PaneState {
+    fn new(config: &UiConfig) -> Self {
+        Self {
+            hint_state: HintState::new(config.hints.alphabet()),
+            inline_search_state: Default::default(),
+            search_state: Default::default(),
+            filter: Default::default(),
+        }
+    }
+}
+
+/// Terminal running inside a pane.
+pub struct Pane {
+    pub terminal: Arc<FairMutex<Term<EventProxy>>>,
+    pub notifier: Notifier,
+    pub event_proxy: EventProxy,
+
+    /// Size of the pane's terminal.
+    pub size_info: SizeInfo,
+
+    /// Title of the pane's terminal, `None` for the default title.
+    pub title: Option<String>,
+
+    /// Config overrides of the tab the pane was created in.
+    pub config_overrides: ParsedOptions,
+
+    /// Search, filter and hint state while the pane doesn't have keyboard focus.
+    pub state: PaneState,
+
+    #[cfg(not(windows))]
+    pub master_fd: RawFd,
+    #[cfg(not(windows))]
+    pub shell_pid: u32,
+}
+
+impl Pane {
+    /// Create a new terminal and spawn its shell.
+    pub fn new(
+        config: &UiConfig,
+        pty_config: &tty::Options,
+        size_info: SizeInfo,
+        window_id: WindowId,
+        event_proxy: EventProxy,
+        snapshot: Option<TermSnapshot<'_>>,
+    ) -> Result<Self, Box<dyn Error>> {
+        // Create the terminal.
+        //
+        // This object contains all of the state about what's being displayed. It's
+        // wrapped in a clonable mutex since both the I/O loop and display need to
+        // access it.
+        let mut terminal = Term::new(config.term_options(), &size_info, event_proxy.clone());
+
+        // Restore content from a saved session before any shell output arrives.
+        if let Some(snapshot) = snapshot {
+            terminal.restore_scrollback(snapshot);
+        }
+
+        let title = terminal.title().map(ToOwned::to_owned);
+        let terminal = Arc::new(FairMutex::new(terminal));
+
+        // Create the PTY.
+        //
+        // The PTY forks a process to run the shell on the slave side of the
+        // pseudoterminal. A file descriptor for the master side is retained for
+        // reading/writing to the shell.
+        let pty = tty::new(pty_config, size_info.into(), window_id.into())?;
+
+        #[cfg(not(windows))]
+        let master_fd = pty.file().as_raw_fd();
+        #[cfg(not(windows))]
+        let shell_pid = pty.child().id();
+
+        let notifier = Self::spawn(config, &terminal, event_proxy.clone(), pty, pty_config.hold)?;
+
+        Ok(Self {
+            terminal,
+            notifier,
+            event_proxy,
+            size_info,
+            title,
+            config_overrides: Default::default(),
+            state: PaneState::new(config),
+            #[cfg(not(windows))]
+            master_fd,
+            #[cfg(not(windows))]
+            shell_pid,
+        })
+    }
+
+    /// Create the first pane of a new window or tab.
+    pub fn with_options(
+        config: &UiConfig,
+        options: &WindowOptions,
+        size_info: SizeInfo,
+        window_id: WindowId,
+        event_proxy: EventProxy,
+    ) -> Result<Self, Box<dyn Error>> {
+        #[cfg(unix)]
+        if let Some(session) = &options.attach {
+            let session = session.clone();
+            let options = &options.terminal_options;
+            return Self::attach(config, session, options, size_info, window_id, event_proxy);
+        }
+
+        let mut pty_config = config.pty_config();
+        options.terminal_options.override_pty_config(&mut pty_config);
+
+        let snapshot = options.snapshot.as_ref().map(|snapshot| (**snapshot).clone());
+        Self::new(config, &pty_config, size_info, window_id, event_proxy, snapshot)
+    }
+
+    /// Attach to a session of the session server, creating it if necessary.
+    ///
+    /// The shell runs inside the server, so it keeps running after the pane is closed.
+    #[cfg(unix)]
+    pub fn attach(
+        config: &UiConfig,
+        session: String,
+        terminal_options: &TerminalOptions,
+        size_info: SizeInfo,
+        window_id: WindowId,
+        event_proxy: EventProxy,
+    ) -> Result<Self, Box<dyn Error>> {
+        let request = AttachRequest {
+            session,
+            terminal_options: terminal_options.clone(),
+            window_size: size_info.into(),
+            window_id: window_id.into(),
+        };
+        let (pty, shell_pid, snapshot) = server::attach(request)?;
+
+        // The server answers the shell's requests, the pane only mirrors its terminal.
+        let event_proxy = event_proxy.mirrored();
+        let mut terminal = Term::new(config.term_options(), &size_info, event_proxy.clone());
+        terminal.restore(snapshot);
+        let title = terminal.title().map(ToOwned::to_owned);
+        let terminal = Arc::new(FairMutex::new(terminal));
+
+        let hold = terminal_options.hold;
+        let notifier = Self::spawn(config, &terminal, event_proxy.clone(), pty, hold)?;
+
+        Ok(Self {
+            terminal,
+            notifier,
+            event_proxy,
+            size_info,
+            title,
+            config_overrides: Default::default(),
+            state: PaneState::new(config),
+            master_fd: -1,
+            shell_pid,
+        })
+    }
+
+    /// Start the I/O loop of a pane's terminal.
+    fn spawn<T>(
+        config: &UiConfig,
+        terminal: &Arc<FairMutex<Term<EventProxy>>>,
+        event_proxy: EventProxy,
+        pty: T,
+        hold: bool,
+    ) -> Result<Notifier, Box<dyn Error>>
+    where
+        T: EventedPty + OnResize + Send + 'static,
+    {
+        // Create the pseudoterminal I/O loop.
+        //
+        // PTY I/O is ran on another thread as to not occupy cycles used by the
+        // renderer and input processing. Note that access to the terminal state is
+        // synchronized since the I/O loop updates the state, and the display
+        // consumes it periodically.
+        let event_loop =
+            PtyEventLoop::new(Arc::clone(terminal), event_proxy, pty, hold, config.debug.ref_test)?;
+
+        // The event loop channel allows write requests from the event processor
+        // to be sent to the pty loop and ultimately written to the pty.
+        let loop_tx = event_loop.channel();
+
+        // Kick off the I/O thread.
+        let _io_thread = event_loop.spawn();
+
+        Ok(Notifier(loop_tx))
+    }
+}
+
+impl Drop for Pane {
+    fn drop(&mut self) {
+        // Shutdown the terminal's PTY.
+        let _ = self.notifier.0.send(Msg::Shutdown);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout(lines: usize, columns: usize) -> PaneLayout {
+        let mut layout = PaneLayout::default();
+        layout.set_region(Region::new(0, 0, lines, columns));
+        layout
+    }
+
+    #[test]
+    fn split_right() {
+        let mut layout = layout(10, 21);
+        let first = layout.focused();
+        let new = layout.split(SplitDirection::Right);
+
+        assert_eq!(layout.focused(), new);
+        assert_eq!(
+            layout.visible(),
+            &[(first, Region::new(0, 0, 10, 10)), (new, Region::new(0, 11, 10, 10)),]
+        );
+        assert_eq!(
+            layout.dividers(),
+            &[Divider { direction: SplitDirection::Right, region: Region::new(0, 10, 10, 1) }]
+        );
+    }
+
+    #[test]
+    fn nested_splits() {
+        let mut layout = layout(11, 21);
+        let first = layout.focused();
+        let right = layout.split(SplitDirection::Right);
+        let bottom = layout.split(SplitDirection::Down);
+
+        assert_eq!(
+            layout.visible(),
+            &[
+                (first, Region::new(0, 0, 11, 10)),
+                (right, Region::new(0, 11, 5, 10)),
+                (bottom, Region::new(6, 11, 5, 10)),
+            ]
+        );
+
+        assert_eq!(layout.neighbor(PaneDirection::Left), Some(first));
+        assert_eq!(layout.neighbor(PaneDirection::Up), Some(right));
+        assert_eq!(layout.neighbor(PaneDirection::Down), None);
+        assert_eq!(layout.next(true), first);
+        assert_eq!(layout.next(false), right);
+    }
+
+    #[test]
+    fn remove_pane() {
+        let mut layout = layout(11, 21);
+        let first = layout.focused();
+        let right = layout.split(SplitDirection::Right);
+        let bottom = layout.split(SplitDirection::Down);
+
+        assert!(layout.remove(right));
+        assert_eq!(layout.focused(), bottom);
+        assert_eq!(
+            layout.visible(),
+            &[(first, Region::new(0, 0, 11, 10)), (bottom, Region::new(0, 11, 11, 10)),]
+        );
+
+        assert!(layout.remove(first));
+        assert_eq!(layout.visible(), &[(bottom, Region::new(0, 0, 11, 21))]);
+        assert!(!layout.remove(bottom));
+    }
+
+    #[test]
+    fn resize_focused() {
+        let mut layout = layout(10, 21);
+        layout.split(SplitDirection::Right);
+
+        assert!(layout.resize_focused(PaneDirection::Left));
+        assert_eq!(layout.dividers()[0].region, Region::new(0, 9, 10, 1));
+
+        // Horizontal borders are not part of a vertical split.
+        assert!(!layout.resize_focused(PaneDirection::Up));
+
+        // Panes cannot shrink below their minimum size.
+        for _ in 0..20 {
+            layout.resize_focused(PaneDirection::Right);
+        }
+        assert_eq!(layout.dividers()[0].region, Region::new(0, 18, 10, 1));
+    }
+
+    #[test]
+    fn drag_divider() {
+        let mut layout = layout(10, 21);
+        let first = layout.focused();
+        let new = layout.split(SplitDirection::Right);
+
+        assert!(!layout.start_drag(0, 0));
+        assert!(layout.start_drag(5, 10));
+        assert!(layout.drag(5, 4));
+        layout.stop_drag();
+
+        assert_eq!(layout.region(new), Some(Region::new(0, 5, 10, 16)));
+        assert_eq!(layout.pane_at(3, 2), Some(first));
+        assert_eq!(layout.pane_at(3, 4), None);
+    }
+
+    #[test]
+    fn zoom() {
+        let mut layout = layout(10, 21);
+        let first = layout.focused();
+        let new = layout.split(SplitDirection::Right);
+
+        layout.toggle_zoom();
+        assert_eq!(layout.visible(), &[(new, Region::new(0, 0, 10, 21))]);
+        assert!(layout.dividers().is_empty());
+        assert_eq!(layout.panes(), vec![first, new]);
+
+        // Changing focus shows all panes again.
+        layout.focus(first);
+        assert!(layout.is_split());
+    }
+}
@@ -12,6 +12,7 @@ use glutin::display::{GetGlDisplay, GlDisplay};
 use log::{debug, error, info, warn, LevelFilter};
 use unicode_width::UnicodeWidthChar;
 
+use larashell_terminal::grid::LineAttribute;
 use larashell_terminal::index::Point;
 use larashell_terminal::term::cell::Flags;
 
@@ -28,6 +29,8 @@ pub mod rects;
 mod shader;
 mod text;
 
+#[cfg(not(any(target_os = "macos", windows)))]
+pub use text::shaper::Shaper;
 pub use text::{GlyphCache, LoaderApi};
 
 use shader::ShaderVersion;
@@ -228,6 +231,8 @@ impl Renderer {
                 fg,
                 bg,
                 underline: fg,
+                line_attribute: LineAttribute::Normal,
+                shaped: None,
             })
         });
 
@@ -301,6 +306,28 @@ impl Renderer {
         }
     }
 
+    /// Set the viewport and projection for rendering the cells of a pane.
+    ///
+    /// The `size` of the pane uses its offset inside the `window` as padding.
+    pub fn set_pane_viewport(&self, window: &SizeInfo, size: &SizeInfo) {
+        let width = size.width() - 2. * size.padding_x();
+        let height = size.height() - 2. * size.padding_y();
+
+        unsafe {
+            gl::Viewport(
+                size.padding_x() as i32,
+                (window.height() - size.padding_y() - height) as i32,
+                width as i32,
+                height as i32,
+            );
+        }
+
+        match &self.text_renderer {
+            TextRendererProvider::Gles2(renderer) => renderer.resize(size),
+            TextRendererProvider::Glsl3(renderer) => renderer.resize(size),
+        }
+    }
+
     /// Resize the renderer.
     pub fn resize(&self, size_info: &SizeInfo) {
         self.set_viewport(size_info);
@@ -198,17 +198,16 @@ impl RenderLines {
         // The underline color escape does not apply to strikeout.
         let color = if flag.contains(Flags::STRIKEOUT) { cell.fg } else { cell.underline };
 
-        // Include wide char spacer if the current cell is a wide char.
-        let mut end = cell.point;
-        if cell.flags.contains(Flags::WIDE_CHAR) {
-            end.column += 1;
-        }
+        // Include wide char spacer and double width lines.
+        let start = cell.visual_point();
+        let mut end = start;
+        end.column += cell.visual_width() - 1;
 
         // Check if there's an active line.
         if let Some(line) = self.inner.get_mut(&flag).and_then(|lines| lines.last_mut()) {
             if color == line.color
-                && cell.point.column == line.end.column + 1
-                && cell.point.line == line.end.line
+                && start.column == line.end.column + 1
+                && start.line == line.end.line
             {
                 // Update the length of the line.
                 line.end = end;
@@ -217,7 +216,7 @@ impl RenderLines {
         }
 
         // Start new line if there currently is none.
-        let line = RenderLine { start: cell.point, end, color };
+        let line = RenderLine { start, end, color };
         match self.inner.get_mut(&flag) {
             Some(lines) => lines.push(line),
             None => {
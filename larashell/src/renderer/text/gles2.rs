@@ -4,8 +4,6 @@ use std::ptr;
 use crossfont::RasterizedGlyph;
 use log::info;
 
-use larashell_terminal::term::cell::Flags;
-
 use crate::display::content::RenderableCell;
 use crate::display::SizeInfo;
 use crate::gl;
@@ -278,12 +276,13 @@ impl TextRenderBatch for Batch {
         }
 
         // Calculate the cell position.
-        let x = cell.point.column.0 as i16 * size_info.cell_width() as i16;
-        let y = cell.point.line as i16 * size_info.cell_height() as i16;
+        let point = cell.visual_point();
+        let x = point.column.0 as i16 * size_info.cell_width() as i16;
+        let y = point.line as i16 * size_info.cell_height() as i16;
 
         // Calculate the glyph position.
-        let glyph_x = cell.point.column.0 as i16 * size_info.cell_width() as i16 + glyph.left;
-        let glyph_y = (cell.point.line + 1) as i16 * size_info.cell_height() as i16 - glyph.top;
+        let glyph_x = point.column.0 as i16 * size_info.cell_width() as i16 + glyph.left;
+        let glyph_y = (point.line + 1) as i16 * size_info.cell_height() as i16 - glyph.top;
 
         let colored = if glyph.multicolor {
             RenderingGlyphFlags::COLORED
@@ -291,7 +290,7 @@ impl TextRenderBatch for Batch {
             RenderingGlyphFlags::empty()
         };
 
-        let is_wide = if cell.flags.contains(Flags::WIDE_CHAR) { 2 } else { 1 };
+        let is_wide = cell.visual_width() as i16;
 
         let mut vertex = TextVertex {
             x,
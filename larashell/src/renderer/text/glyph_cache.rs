@@ -8,11 +8,16 @@ use crossfont::{
 use log::{error, info};
 use unicode_width::UnicodeWidthChar;
 
+#[cfg(not(any(target_os = "macos", windows)))]
+use larashell_terminal::term::cell::Flags;
+
 use crate::config::font::{Font, FontDescription};
 use crate::config::ui_config::Delta;
 use crate::gl::types::*;
 
 use super::builtin_font;
+#[cfg(not(any(target_os = "macos", windows)))]
+use super::shaper::Shaper;
 
 /// `LoadGlyph` allows for copying a rasterized glyph into graphics memory.
 pub trait LoadGlyph {
@@ -76,6 +81,14 @@ pub struct GlyphCache {
 
     /// Whether to use the built-in font for box drawing characters.
     builtin_box_drawing: bool,
+
+    /// Text shaper for ligatures, if enabled.
+    #[cfg(not(any(target_os = "macos", windows)))]
+    shaper: Option<Shaper>,
+
+    /// Cache of buffered glyphs picked by the shaper, keyed by font style and glyph ID.
+    #[cfg(not(any(target_os = "macos", windows)))]
+    shaped: HashMap<(Flags, u16), Glyph, RandomState>,
 }
 
 impl GlyphCache {
@@ -101,6 +114,10 @@ impl GlyphCache {
             glyph_offset: font.glyph_offset,
             metrics,
             builtin_box_drawing: font.builtin_box_drawing,
+            #[cfg(not(any(target_os = "macos", windows)))]
+            shaper: Shaper::new(font),
+            #[cfg(not(any(target_os = "macos", windows)))]
+            shaped: Default::default(),
         })
     }
 
@@ -239,6 +256,36 @@ impl GlyphCache {
         *self.cache.entry(glyph_key).or_insert(glyph)
     }
 
+    /// Get a glyph picked by the text shaper.
+    ///
+    /// If the glyph has never been loaded before, it will be rasterized and inserted into the
+    /// cache.
+    #[cfg(not(any(target_os = "macos", windows)))]
+    pub fn get_shaped<L>(&mut self, flags: Flags, glyph_id: u16, loader: &mut L) -> Glyph
+    where
+        L: LoadGlyph + ?Sized,
+    {
+        let key = (flags & Flags::BOLD_ITALIC, glyph_id);
+        if let Some(glyph) = self.shaped.get(&key) {
+            return *glyph;
+        }
+
+        let rasterized = self
+            .shaper
+            .as_ref()
+            .and_then(|shaper| shaper.rasterize(flags, glyph_id, self.font_size))
+            .unwrap_or_default();
+        let glyph = self.load_glyph(loader, rasterized);
+
+        *self.shaped.entry(key).or_insert(glyph)
+    }
+
+    /// Text shaper for ligatures, if enabled.
+    #[cfg(not(any(target_os = "macos", windows)))]
+    pub fn shaper_mut(&mut self) -> Option<&mut Shaper> {
+        self.shaper.as_mut()
+    }
+
     /// Load glyph into the atlas.
     ///
     /// This will apply all transforms defined for the glyph cache to the rasterized glyph before
@@ -267,6 +314,10 @@ impl GlyphCache {
     pub fn reset_glyph_cache<L: LoadGlyph>(&mut self, loader: &mut L) {
         loader.clear();
         self.cache = Default::default();
+        #[cfg(not(any(target_os = "macos", windows)))]
+        {
+            self.shaped = Default::default();
+        }
 
         self.load_common_glyphs(loader);
     }
@@ -301,6 +352,11 @@ impl GlyphCache {
         self.metrics = metrics;
         self.builtin_box_drawing = font.builtin_box_drawing;
 
+        #[cfg(not(any(target_os = "macos", windows)))]
+        {
+            self.shaper = Shaper::new(font);
+        }
+
         Ok(())
     }
 
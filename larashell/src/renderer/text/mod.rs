@@ -1,9 +1,10 @@
 use bitflags::bitflags;
 use crossfont::{GlyphKey, RasterizedGlyph};
 
+use larashell_terminal::grid::LineAttribute;
 use larashell_terminal::term::cell::Flags;
 
-use crate::display::content::RenderableCell;
+use crate::display::content::{RenderableCell, ShapedGlyph};
 use crate::display::SizeInfo;
 use crate::gl;
 use crate::gl::types::*;
@@ -13,6 +14,8 @@ mod builtin_font;
 mod gles2;
 mod glsl3;
 pub mod glyph_cache;
+#[cfg(not(any(target_os = "macos", windows)))]
+pub mod shaper;
 
 use atlas::Atlas;
 pub use gles2::Gles2Renderer;
@@ -25,8 +28,9 @@ bitflags! {
     #[repr(C)]
     #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
     struct RenderingGlyphFlags: u8 {
-        const COLORED   = 0b0000_0001;
-        const WIDE_CHAR = 0b0000_0010;
+        const COLORED      = 0b0000_0001;
+        const WIDE_CHAR    = 0b0000_0010;
+        const DOUBLE_WIDTH = 0b0000_0100;
     }
 }
 
@@ -123,7 +127,12 @@ pub trait TextRenderApi<T: TextRenderBatch>: LoadGlyph {
             self.render_batch();
         }
 
-        self.batch().add_item(cell, glyph, size_info);
+        if cell.line_attribute.is_double_width() {
+            let glyph = scale_glyph(glyph, cell.line_attribute, size_info.cell_height() as i16);
+            self.batch().add_item(cell, &glyph, size_info);
+        } else {
+            self.batch().add_item(cell, glyph, size_info);
+        }
 
         // Render batch and clear if it's full.
         if self.batch().full() {
@@ -146,17 +155,24 @@ pub trait TextRenderApi<T: TextRenderBatch>: LoadGlyph {
             _ => glyph_cache.font_key,
         };
 
-        // Ignore hidden cells and render tabs as spaces to prevent font issues.
+        // Ignore hidden cells and cells covered by ligatures, and render tabs as spaces to
+        // prevent font issues.
         let hidden = cell.flags.contains(Flags::HIDDEN);
-        if cell.character == '\t' || hidden {
+        if cell.character == '\t' || hidden || cell.shaped == Some(ShapedGlyph::Covered) {
             cell.character = ' ';
         }
 
         let mut glyph_key =
             GlyphKey { font_key, size: glyph_cache.font_size, character: cell.character };
 
-        // Add cell to batch.
-        let glyph = glyph_cache.get(glyph_key, self, true);
+        // Add cell to batch, using the glyph picked by text shaping if there is one.
+        let glyph = match cell.shaped {
+            #[cfg(not(any(target_os = "macos", windows)))]
+            Some(ShapedGlyph::Glyph(glyph_id)) => {
+                glyph_cache.get_shaped(cell.flags, glyph_id, self)
+            },
+            _ => glyph_cache.get(glyph_key, self, true),
+        };
         self.add_render_item(&cell, &glyph, size_info);
 
         // Render visible zero-width characters.
@@ -172,6 +188,42 @@ pub trait TextRenderApi<T: TextRenderBatch>: LoadGlyph {
     }
 }
 
+/// Scale a glyph for a line with double width or double height characters.
+///
+/// Double height lines only show the top or bottom half of the scaled glyph, so the glyph is
+/// cropped to the cell.
+fn scale_glyph(glyph: &Glyph, line_attribute: LineAttribute, cell_height: i16) -> Glyph {
+    let mut glyph = *glyph;
+    glyph.left *= 2;
+    glyph.width *= 2;
+
+    let shift = match line_attribute {
+        LineAttribute::DoubleHeightTop => 0,
+        LineAttribute::DoubleHeightBottom => cell_height,
+        LineAttribute::Normal | LineAttribute::DoubleWidth => return glyph,
+    };
+
+    // Glyph bounds relative to the top of the cell, before cropping.
+    let top = 2 * (cell_height - glyph.top) - shift;
+    let height = 2 * glyph.height;
+    let bottom = top + height;
+
+    let visible_top = top.clamp(0, cell_height);
+    let visible_bottom = bottom.clamp(visible_top, cell_height);
+
+    if height > 0 {
+        let start = f32::from(visible_top - top) / f32::from(height);
+        let end = f32::from(visible_bottom - top) / f32::from(height);
+        glyph.uv_bot += start * glyph.uv_height;
+        glyph.uv_height *= end - start;
+    }
+
+    glyph.top = cell_height - visible_top;
+    glyph.height = visible_bottom - visible_top;
+
+    glyph
+}
+
 pub trait TextShader {
     fn id(&self) -> GLuint;
 
@@ -0,0 +1,319 @@
+//! Text shaping for programming ligatures.
+
+use std::cell::OnceCell;
+use std::collections::HashMap;
+use std::fs;
+use std::rc::Rc;
+
+use ahash::RandomState;
+use crossfont::ft::fc::{self, Pattern};
+use crossfont::{BitmapBuffer, RasterizedGlyph, Size};
+use freetype::bitmap::PixelMode;
+use freetype::face::LoadFlag;
+use freetype::{Library, RenderMode};
+use log::{debug, warn};
+use rustybuzz::ttf_parser::Tag;
+use rustybuzz::{Face, Feature, UnicodeBuffer};
+
+use larashell_terminal::term::cell::Flags;
+
+use crate::config::font::{Font, FontDescription};
+use crate::display::content::ShapedGlyph;
+
+/// Maximum number of shaped runs kept in the cache.
+const MAX_CACHED_RUNS: usize = 4096;
+
+/// Shaped glyphs for every character of a run, `None` for characters left untouched.
+type ShapedRun = Vec<Option<ShapedGlyph>>;
+
+/// Text shaper for the configured font faces.
+pub struct Shaper {
+    /// Faces for the (Regular, Bold, Italic, Bold Italic) styles.
+    faces: [Option<ShaperFace>; 4],
+
+    /// OpenType features toggled while shaping.
+    features: Vec<Feature>,
+
+    /// Results of previously shaped runs, keyed by style and text.
+    cache: HashMap<(usize, String), ShapedRun, RandomState>,
+}
+
+impl Shaper {
+    /// Load the font faces for shaping.
+    ///
+    /// This will return `None` if ligatures are disabled or the regular font could not be
+    /// loaded.
+    pub fn new(font: &Font) -> Option<Self> {
+        if !font.ligatures.enabled {
+            return None;
+        }
+
+        let library = match Library::init() {
+            Ok(library) => library,
+            Err(err) => {
+                warn!("Unable to initialize FreeType for ligatures: {}", err);
+                return None;
+            },
+        };
+
+        let size = font.size();
+        let regular =
+            ShaperFace::new(&library, font.normal(), fc::Slant::Roman, fc::Weight::Regular, size);
+        if regular.is_none() {
+            warn!("Unable to load \"{}\" for ligatures", font.normal().family);
+            return None;
+        }
+
+        let bold =
+            ShaperFace::new(&library, &font.bold(), fc::Slant::Roman, fc::Weight::Bold, size);
+        let italic =
+            ShaperFace::new(&library, &font.italic(), fc::Slant::Italic, fc::Weight::Regular, size);
+        let bold_italic = ShaperFace::new(
+            &library,
+            &font.bold_italic(),
+            fc::Slant::Italic,
+            fc::Weight::Bold,
+            size,
+        );
+
+        let mut features = Vec::new();
+        for (name, enabled) in &font.ligatures.features {
+            if name.len() != 4 || !name.is_ascii() {
+                warn!("Ignoring invalid OpenType feature \"{}\"", name);
+                continue;
+            }
+
+            let tag = Tag::from_bytes_lossy(name.as_bytes());
+            features.push(Feature::new(tag, u32::from(*enabled), ..));
+        }
+
+        Some(Self {
+            faces: [regular, bold, italic, bold_italic],
+            features,
+            cache: Default::default(),
+        })
+    }
+
+    /// Start shaping a new frame.
+    ///
+    /// The shaping tables of each face are only parsed once per frame, when a run of that style
+    /// is missing from the cache.
+    pub fn frame(&mut self) -> ShaperFrame<'_> {
+        if self.cache.len() > MAX_CACHED_RUNS {
+            self.cache.clear();
+        }
+
+        ShaperFrame {
+            faces: &self.faces,
+            features: &self.features,
+            cache: &mut self.cache,
+            parsed: Default::default(),
+        }
+    }
+
+    /// Rasterize a shaped glyph.
+    pub fn rasterize(&self, flags: Flags, glyph_id: u16, size: Size) -> Option<RasterizedGlyph> {
+        self.faces[style_index(flags)].as_ref()?.rasterize(glyph_id, size)
+    }
+}
+
+/// Shaping state for a single frame.
+pub struct ShaperFrame<'a> {
+    faces: &'a [Option<ShaperFace>; 4],
+    features: &'a [Feature],
+    cache: &'a mut HashMap<(usize, String), ShapedRun, RandomState>,
+    parsed: [OnceCell<Option<Face<'a>>>; 4],
+}
+
+impl<'a> ShaperFrame<'a> {
+    /// Shape a run of single-width characters using the font of the cells' style.
+    ///
+    /// Every character maps to one cell of the returned run, so ligature glyphs are placed on
+    /// the first cell of their cluster while the remaining cells are marked as covered.
+    pub fn shape(&mut self, flags: Flags, text: &str) -> &[Option<ShapedGlyph>] {
+        let style = style_index(flags);
+        let key = (style, text.to_owned());
+
+        if !self.cache.contains_key(&key) {
+            let faces = self.faces;
+            let face = self.parsed[style].get_or_init(|| {
+                let face = faces[style].as_ref()?;
+                Face::from_slice(&face.data, face.index)
+            });
+
+            let shaped = match face {
+                Some(face) => shape_run(face, self.features, text),
+                None => vec![None; text.chars().count()],
+            };
+
+            self.cache.insert(key.clone(), shaped);
+        }
+
+        &self.cache[&key]
+    }
+}
+
+/// Font face used for shaping and rasterizing shaped glyphs.
+struct ShaperFace {
+    /// Raw font file, shared with the FreeType face.
+    data: Rc<Vec<u8>>,
+
+    /// Index of the face within the font file.
+    index: u32,
+
+    /// FreeType face for rasterizing glyphs by ID.
+    ft_face: freetype::Face,
+}
+
+impl ShaperFace {
+    /// Locate the font file through fontconfig and load it.
+    ///
+    /// Styles rendered with synthetic bold or italic are not shaped, since their glyphs would
+    /// not match the rest of the text.
+    fn new(
+        library: &Library,
+        desc: &FontDescription,
+        slant: fc::Slant,
+        weight: fc::Weight,
+        size: Size,
+    ) -> Option<Self> {
+        let config = fc::Config::get_current();
+        let mut pattern = Pattern::new();
+        pattern.add_family(&desc.family);
+        pattern.add_pixelsize(f64::from(size.as_px()));
+
+        match &desc.style {
+            Some(style) => {
+                pattern.add_style(style);
+            },
+            None => {
+                pattern.set_weight(weight);
+                pattern.set_slant(slant);
+            },
+        }
+
+        pattern.config_substitute(config, fc::MatchKind::Pattern);
+        pattern.default_substitute();
+
+        let font = fc::font_match(config, &pattern)?;
+        if font.embolden().next().unwrap_or(false) || font.get_matrix().is_some() {
+            debug!("Not shaping synthetic style of \"{}\"", desc.family);
+            return None;
+        }
+
+        let location = font.ft_face_location(0)?;
+        let data = match fs::read(&location.path) {
+            Ok(data) => Rc::new(data),
+            Err(err) => {
+                warn!("Unable to read font {:?}: {}", location.path, err);
+                return None;
+            },
+        };
+
+        let index = u32::try_from(location.index).ok()?;
+        Face::from_slice(&data, index)?;
+
+        let ft_face = library.new_memory_face(Rc::clone(&data), location.index).ok()?;
+
+        Some(Self { data, index, ft_face })
+    }
+
+    /// Rasterize a glyph by its ID.
+    fn rasterize(&self, glyph_id: u16, size: Size) -> Option<RasterizedGlyph> {
+        let pixelsize = (size.as_px() * 64.) as isize;
+        self.ft_face.set_char_size(pixelsize, 0, 0, 0).ok()?;
+        self.ft_face.load_glyph(u32::from(glyph_id), LoadFlag::TARGET_LIGHT).ok()?;
+
+        let glyph = self.ft_face.glyph();
+        glyph.render_glyph(RenderMode::Normal).ok()?;
+
+        let bitmap = glyph.bitmap();
+        if bitmap.pixel_mode().ok()? != PixelMode::Gray {
+            return None;
+        }
+
+        // Expand the grayscale coverage into an RGB alphamask.
+        let (width, height) = (bitmap.width() as usize, bitmap.rows() as usize);
+        let pitch = bitmap.pitch().unsigned_abs() as usize;
+        let mut buffer = Vec::with_capacity(width * height * 3);
+        for row in 0..height {
+            for &alpha in &bitmap.buffer()[row * pitch..row * pitch + width] {
+                buffer.extend_from_slice(&[alpha; 3]);
+            }
+        }
+
+        let advance = (glyph.advance().x >> 6) as i32;
+
+        Some(RasterizedGlyph {
+            character: ' ',
+            width: width as i32,
+            height: height as i32,
+            top: glyph.bitmap_top(),
+            left: glyph.bitmap_left(),
+            advance: (advance, 0),
+            buffer: BitmapBuffer::Rgb(buffer),
+        })
+    }
+}
+
+/// Shape a run and map the resulting clusters back onto its characters.
+fn shape_run(face: &Face<'_>, features: &[Feature], text: &str) -> ShapedRun {
+    let offsets: Vec<usize> = text.char_indices().map(|(offset, _)| offset).collect();
+    let mut shaped = vec![None; offsets.len()];
+
+    let mut buffer = UnicodeBuffer::new();
+    buffer.push_str(text);
+    let glyphs = rustybuzz::shape(face, features, buffer);
+    let infos = glyphs.glyph_infos();
+
+    let mut start = 0;
+    while start < infos.len() {
+        // Find all glyphs belonging to the cluster.
+        let cluster = infos[start].cluster as usize;
+        let end = infos[start..]
+            .iter()
+            .position(|info| info.cluster as usize != cluster)
+            .map_or(infos.len(), |len| start + len);
+        let next_cluster = infos.get(end).map_or(text.len(), |info| info.cluster as usize);
+
+        // Find all characters belonging to the cluster.
+        let first_char = offsets.partition_point(|&offset| offset < cluster);
+        let last_char = offsets.partition_point(|&offset| offset < next_cluster);
+
+        let cluster_glyphs = &infos[start..end];
+        let chars = text[cluster..next_cluster].chars();
+        let cells = &mut shaped[first_char..last_char];
+
+        if cluster_glyphs.len() == cells.len() {
+            // Replace characters whose glyph was substituted.
+            for ((cell, info), c) in cells.iter_mut().zip(cluster_glyphs).zip(chars) {
+                let nominal = face.glyph_index(c).map(|glyph| u32::from(glyph.0));
+                if info.glyph_id != 0 && Some(info.glyph_id) != nominal {
+                    *cell = Some(ShapedGlyph::Glyph(info.glyph_id as u16));
+                }
+            }
+        } else if cluster_glyphs.len() == 1 && cluster_glyphs[0].glyph_id != 0 {
+            // Draw the ligature on the first cell, covering the rest of the cluster.
+            for cell in cells.iter_mut() {
+                *cell = Some(ShapedGlyph::Covered);
+            }
+            if let Some(cell) = cells.first_mut() {
+                *cell = Some(ShapedGlyph::Glyph(cluster_glyphs[0].glyph_id as u16));
+            }
+        }
+
+        start = end;
+    }
+
+    shaped
+}
+
+/// Index of the face used for a cell's style.
+fn style_index(flags: Flags) -> usize {
+    match flags & Flags::BOLD_ITALIC {
+        Flags::BOLD_ITALIC => 3,
+        Flags::ITALIC => 2,
+        Flags::BOLD => 1,
+        _ => 0,
+    }
+}
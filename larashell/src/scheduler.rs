@@ -26,9 +26,12 @@ impl TimerId {
 pub enum Topic {
     SelectionScrolling,
     DelayedSearch,
+    SearchCount,
+    FilterUpdate,
     BlinkCursor,
     BlinkTimeout,
     Frame,
+    DropdownSlide,
 }
 
 /// Event scheduled to be emitted at a specific time.
@@ -0,0 +1,918 @@
+//! Headless server keeping terminal sessions alive while no window is attached.
+//!
+//! The server listens on its own socket instead of the IPC socket of a LaraShell instance. Each
+//! instance's socket is removed once the instance exits, while sessions outlive all windows
+//! attached to them, so every instance needs a single well-known path to find the server. Requests
+//! use the same JSON lines as the IPC socket, but attached connections switch to binary frames,
+//! since PTY traffic is arbitrary bytes which would have to be escaped in JSON.
+//!
+//! Windows attach to a session with a single IPC request, which is answered with the size of the
+//! session's terminal state. The state follows in snapshot frames of a one byte kind, a big-endian
+//! `u32` length and the payload. Afterwards the server writes the session's PTY output to the
+//! connection, while the window sends its input and size changes as frames.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::io::{self, BufRead, BufReader, ErrorKind, Read, Write};
+use std::net::Shutdown;
+use std::os::unix::io::AsRawFd;
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex, Weak};
+use std::{env, fs, mem};
+
+use log::{info, warn};
+use polling::{Event as PollingEvent, PollMode, Poller};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use larashell_terminal::event::{
+    Event as TerminalEvent, EventListener, Notify, OnResize, WindowSize,
+};
+use larashell_terminal::event_loop::{EventLoop as PtyEventLoop, Msg, Notifier};
+use larashell_terminal::sync::FairMutex;
+use larashell_terminal::term::{Config as TermConfig, Term, TermSize, TermSnapshot};
+use larashell_terminal::thread;
+use larashell_terminal::tty::{self, ChildEvent, EventedPty, EventedReadWrite};
+
+use crate::cli::TerminalOptions;
+use crate::config::UiConfig;
+use crate::display::color::List;
+use crate::ipc::{self, IpcError, IpcErrorCode, IpcRequest, IpcResponse};
+
+/// Environment variable name for the session server socket path.
+const LARASHELL_SERVER_SOCKET_ENV: &str = "LARASHELL_SERVER_SOCKET";
+
+/// Frame carrying input for the session's PTY.
+const FRAME_INPUT: u8 = 0;
+
+/// Frame carrying the JSON encoded window size of the attached window.
+const FRAME_RESIZE: u8 = 1;
+
+/// Frame carrying part of the JSON encoded terminal state of the session.
+const FRAME_SNAPSHOT: u8 = 2;
+
+/// Maximum payload size of a single frame.
+const MAX_FRAME_SIZE: usize = u16::MAX as usize;
+
+/// Maximum number of output chunks queued for an attached window before it is detached.
+const MAX_QUEUED_OUTPUT: usize = 1024;
+
+/// Message sent to the session server.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum ServerMessage {
+    /// Attach a window to a session, creating it if necessary.
+    Attach(AttachRequest),
+
+    /// List all running sessions.
+    ListSessions,
+}
+
+/// Parameters of the `Attach` message.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AttachRequest {
+    /// Name of the session.
+    pub session: String,
+
+    /// Options for starting the session's shell.
+    pub terminal_options: TerminalOptions,
+
+    /// Size of the attaching window.
+    pub window_size: WindowSize,
+
+    /// ID of the attaching window, exported to a new session's shell.
+    pub window_id: u64,
+}
+
+/// Reply to the `Attach` message.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct AttachReply {
+    /// Process ID of the session's shell.
+    pub pid: u32,
+
+    /// Size of the JSON encoded terminal state sent in snapshot frames after the reply.
+    pub snapshot_size: usize,
+}
+
+/// State of a session reported by the `ListSessions` message.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SessionInfo {
+    /// Name of the session.
+    pub name: String,
+
+    /// Process ID of the session's shell.
+    pub pid: u32,
+
+    /// Number of attached windows.
+    pub clients: usize,
+
+    /// Current terminal title.
+    pub title: Option<String>,
+}
+
+/// Path of the session server socket.
+pub fn socket_path() -> PathBuf {
+    env::var_os(LARASHELL_SERVER_SOCKET_ENV)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| ipc::socket_dir().join("larashell-server.sock"))
+}
+
+/// Run the session server until it is killed.
+pub fn run(socket_path: &Path, config: UiConfig) -> Result<(), Box<dyn Error>> {
+    let listener = match ipc::bind(socket_path) {
+        Err(err) if err.kind() == ErrorKind::AlreadyExists => {
+            if UnixStream::connect(socket_path).is_ok() {
+                return Err(format!("server is already running at {:?}", socket_path).into());
+            }
+
+            // Replace the socket left behind by a server which was killed.
+            fs::remove_file(socket_path)?;
+            ipc::bind(socket_path)?
+        },
+        result => result?,
+    };
+
+    // Let windows started inside a session find their server.
+    env::set_var(LARASHELL_SERVER_SOCKET_ENV, socket_path);
+
+    info!("Session server listening on {:?}", socket_path);
+
+    let server = Arc::new(Server {
+        term_config: config.term_options(),
+        pty_config: config.pty_config(),
+        colors: List::from(&config.colors),
+        sessions: Default::default(),
+    });
+
+    for stream in listener.incoming().filter_map(Result::ok) {
+        let server = server.clone();
+        thread::spawn_named("session client", move || {
+            if let Err(err) = server.handle_connection(stream) {
+                warn!("Session client disconnected: {}", err);
+            }
+        });
+    }
+
+    Ok(())
+}
+
+/// Attach to a session of the running server, creating the session if necessary.
+///
+/// Returns the connection to the session, which can be used in place of a local PTY, with the
+/// process ID of the session's shell and its terminal state.
+pub fn attach(
+    request: AttachRequest,
+) -> Result<(RemotePty, u32, TermSnapshot<'static>), Box<dyn Error>> {
+    attach_on(connect(&socket_path())?, request)
+}
+
+/// Attach to a session through a connected socket.
+fn attach_on(
+    socket: UnixStream,
+    request: AttachRequest,
+) -> Result<(RemotePty, u32, TermSnapshot<'static>), Box<dyn Error>> {
+    let (reply, mut socket) = ipc::request_on(socket, ServerMessage::Attach(request))?;
+    let reply: AttachReply = serde_json::from_value(reply)?;
+
+    // Reassemble the terminal state from its frames.
+    let mut snapshot = Vec::with_capacity(reply.snapshot_size);
+    while snapshot.len() < reply.snapshot_size {
+        match read_frame(&mut socket)? {
+            Some((FRAME_SNAPSHOT, payload)) => snapshot.extend_from_slice(&payload),
+            Some((kind, _)) => return Err(format!("unexpected session frame {kind}").into()),
+            None => return Err("session closed while attaching".into()),
+        }
+    }
+    let snapshot = serde_json::from_slice(&snapshot)?;
+
+    // Confirm that the state was read, so the server starts sending output.
+    let mut socket = socket.into_inner();
+    write_frame(&mut socket, FRAME_INPUT, &[])?;
+
+    Ok((RemotePty::new(socket)?, reply.pid, snapshot))
+}
+
+/// List all sessions of the running server.
+pub fn list_sessions(socket_path: &Path) -> Result<Value, Box<dyn Error>> {
+    let (sessions, _) = ipc::request_on(connect(socket_path)?, ServerMessage::ListSessions)?;
+    Ok(sessions)
+}
+
+/// Connect to the session server socket.
+fn connect(socket_path: &Path) -> Result<UnixStream, Box<dyn Error>> {
+    UnixStream::connect(socket_path).map_err(|err| {
+        format!("could not connect to session server at {:?}: {}", socket_path, err).into()
+    })
+}
+
+/// Session server state.
+struct Server {
+    term_config: TermConfig,
+    pty_config: tty::Options,
+    colors: List,
+    sessions: Mutex<HashMap<String, Arc<Session>>>,
+}
+
+impl Server {
+    /// Answer a request, forwarding input for attached windows until they disconnect.
+    fn handle_connection(self: &Arc<Self>, stream: UnixStream) -> Result<(), Box<dyn Error>> {
+        // Refuse requests from other users, even if the socket permissions were changed.
+        if ipc::peer_uid(&stream)? != unsafe { libc::geteuid() } {
+            return Err("socket peer belongs to a different user".into());
+        }
+
+        let mut data = String::new();
+        let mut stream = BufReader::new(stream);
+        if stream.read_line(&mut data)? == 0 {
+            return Ok(());
+        }
+        let mut stream = stream.into_inner();
+
+        let request = match serde_json::from_str::<IpcRequest<ServerMessage>>(&data) {
+            Ok(request) => request,
+            Err(err) => {
+                let error = IpcError::new(IpcErrorCode::InvalidRequest, format!("{err}"));
+                ipc::write_line(&mut stream, &IpcResponse::new(0, Err(error)))?;
+                return Ok(());
+            },
+        };
+
+        match request.message {
+            ServerMessage::Attach(attach) => self.attach(request.id, stream, attach),
+            ServerMessage::ListSessions => {
+                let sessions = serde_json::to_value(self.list_sessions())?;
+                ipc::write_line(&mut stream, &IpcResponse::new(request.id, Ok(sessions)))?;
+                Ok(())
+            },
+        }
+    }
+
+    /// Attach a window to a session.
+    fn attach(
+        self: &Arc<Self>,
+        id: u64,
+        mut stream: UnixStream,
+        request: AttachRequest,
+    ) -> Result<(), Box<dyn Error>> {
+        static NEXT_CLIENT: AtomicU64 = AtomicU64::new(0);
+
+        let (sender, receiver) = mpsc::sync_channel(MAX_QUEUED_OUTPUT);
+        let client = NEXT_CLIENT.fetch_add(1, Ordering::Relaxed);
+        let result = self.session(&request).and_then(|session| {
+            session.resize(request.window_size);
+
+            // Start recording output while the snapshot is taken, so no bytes are lost.
+            let terminal = session.terminal.lock();
+            let mut clients = session.clients.lock().unwrap();
+            if clients.closed {
+                return Err("session has exited".into());
+            }
+            clients.senders.insert(client, sender);
+
+            let snapshot = serde_json::to_vec(&terminal.snapshot())?;
+            Ok((session.clone(), snapshot))
+        });
+
+        let (session, snapshot) = match result {
+            Ok(result) => result,
+            Err(err) => {
+                let error = IpcError::new(IpcErrorCode::Failed, format!("{err}"));
+                ipc::write_line(&mut stream, &IpcResponse::new(id, Err(error)))?;
+                return Ok(());
+            },
+        };
+
+        info!("Window attached to session {:?}", request.session);
+
+        let result = Self::write_snapshot(&mut stream, id, session.pid, &snapshot)
+            .map_err(Into::into)
+            .and_then(|_| {
+                drop(snapshot);
+                session.forward_input(stream.try_clone()?, receiver)
+            });
+
+        session.clients.lock().unwrap().senders.remove(&client);
+        let _ = stream.shutdown(Shutdown::Both);
+
+        info!("Window detached from session {:?}", request.session);
+
+        result
+    }
+
+    /// Send the reply to an attach request, followed by the session's terminal state.
+    fn write_snapshot(
+        stream: &mut UnixStream,
+        id: u64,
+        pid: u32,
+        snapshot: &[u8],
+    ) -> io::Result<()> {
+        let reply = AttachReply { pid, snapshot_size: snapshot.len() };
+        let reply = serde_json::to_value(reply)?;
+        ipc::write_line(stream, &IpcResponse::new(id, Ok(reply)))?;
+
+        for chunk in snapshot.chunks(MAX_FRAME_SIZE) {
+            write_frame(stream, FRAME_SNAPSHOT, chunk)?;
+        }
+
+        Ok(())
+    }
+
+    /// Get a session by its name, creating it if it does not exist.
+    fn session(self: &Arc<Self>, request: &AttachRequest) -> Result<Arc<Session>, Box<dyn Error>> {
+        let mut sessions = self.sessions.lock().unwrap();
+        if let Some(session) = sessions.get(&request.session) {
+            return Ok(session.clone());
+        }
+
+        let session = Arc::new(Session::new(self, request)?);
+        sessions.insert(request.session.clone(), session.clone());
+
+        info!("Created session {:?}", request.session);
+
+        Ok(session)
+    }
+
+    /// State of all sessions.
+    fn list_sessions(&self) -> Vec<SessionInfo> {
+        // Clone the sessions, since terminals must not be locked while holding the sessions lock.
+        let sessions: Vec<_> = self
+            .sessions
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(name, session)| (name.clone(), session.clone()))
+            .collect();
+
+        let mut sessions: Vec<_> = sessions
+            .into_iter()
+            .map(|(name, session)| SessionInfo {
+                name,
+                pid: session.pid,
+                clients: session.clients.lock().unwrap().senders.len(),
+                title: session.terminal.lock().title().map(ToOwned::to_owned),
+            })
+            .collect();
+        sessions.sort_by(|a, b| a.name.cmp(&b.name));
+        sessions
+    }
+}
+
+/// Terminal session kept alive by the server.
+///
+/// Locks must be taken in the order terminal, clients and sessions.
+struct Session {
+    terminal: Arc<FairMutex<Term<SessionListener>>>,
+    notifier: Mutex<Notifier>,
+    clients: Arc<Mutex<Clients>>,
+
+    /// Size of the most recently attached or resized window.
+    size: Arc<Mutex<WindowSize>>,
+
+    pid: u32,
+}
+
+impl Session {
+    /// Create a new terminal and spawn its shell.
+    fn new(server: &Arc<Server>, request: &AttachRequest) -> Result<Self, Box<dyn Error>> {
+        let mut pty_config = server.pty_config.clone();
+        request.terminal_options.override_pty_config(&mut pty_config);
+
+        let window_size = request.window_size;
+        let size = TermSize::from(window_size);
+        let listener = SessionListener {
+            notifier: Default::default(),
+            size: Arc::new(Mutex::new(window_size)),
+            colors: server.colors,
+        };
+        let terminal = Term::new(server.term_config.clone(), &size, listener.clone());
+        let terminal = Arc::new(FairMutex::new(terminal));
+
+        let pty = tty::new(&pty_config, window_size, request.window_id)?;
+        let pid = pty.child().id();
+
+        let clients = Arc::new(Mutex::new(Clients::default()));
+        let recorder = Recorder {
+            name: request.session.clone(),
+            clients: clients.clone(),
+            server: Arc::downgrade(server),
+        };
+
+        let mut event_loop =
+            PtyEventLoop::new(terminal.clone(), listener.clone(), pty, pty_config.hold, false)?;
+        event_loop.set_recorder(Box::new(recorder));
+
+        *listener.notifier.lock().unwrap() = Some(Notifier(event_loop.channel()));
+        let notifier = Mutex::new(Notifier(event_loop.channel()));
+
+        event_loop.spawn();
+
+        Ok(Self { terminal, notifier, clients, size: listener.size, pid })
+    }
+
+    /// Resize the session's terminal and PTY.
+    fn resize(&self, window_size: WindowSize) {
+        self.terminal.lock().resize(TermSize::from(window_size));
+        *self.size.lock().unwrap() = window_size;
+        let _ = self.notifier.lock().unwrap().0.send(Msg::Resize(window_size));
+    }
+
+    /// Stream output to an attached window and forward its input, until it detaches.
+    fn forward_input(
+        &self,
+        stream: UnixStream,
+        output: mpsc::Receiver<Vec<u8>>,
+    ) -> Result<(), Box<dyn Error>> {
+        serve_window(stream, output, |kind, payload| {
+            match kind {
+                FRAME_INPUT => self.notifier.lock().unwrap().notify(payload),
+                FRAME_RESIZE => self.resize(serde_json::from_slice(&payload)?),
+                _ => warn!("Ignoring session frame of unknown kind {kind}"),
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Stream output to an attached window and pass its frames to `handle_frame`, until it detaches.
+fn serve_window<F>(
+    mut stream: UnixStream,
+    output: mpsc::Receiver<Vec<u8>>,
+    mut handle_frame: F,
+) -> Result<(), Box<dyn Error>>
+where
+    F: FnMut(u8, Vec<u8>) -> Result<(), Box<dyn Error>>,
+{
+    // Wait until the window has read the reply, before writing any output.
+    if read_frame(&mut stream)?.is_none() {
+        return Ok(());
+    }
+
+    let mut output_stream = stream.try_clone()?;
+    thread::spawn_named("session output", move || {
+        for bytes in output {
+            if output_stream.write_all(&bytes).is_err() {
+                break;
+            }
+        }
+
+        // Disconnect the window once the session has exited.
+        let _ = output_stream.shutdown(Shutdown::Both);
+    });
+
+    while let Some((kind, payload)) = read_frame(&mut stream)? {
+        handle_frame(kind, payload)?;
+    }
+
+    Ok(())
+}
+
+/// Windows attached to a session.
+#[derive(Default)]
+struct Clients {
+    /// Channels receiving a copy of the session's PTY output.
+    senders: HashMap<u64, SyncSender<Vec<u8>>>,
+
+    /// Whether the session's PTY was closed.
+    closed: bool,
+}
+
+/// Recorder forwarding a session's PTY output to all attached windows.
+struct Recorder {
+    name: String,
+    clients: Arc<Mutex<Clients>>,
+    server: Weak<Server>,
+}
+
+impl Write for Recorder {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // Detach windows which fall behind, instead of blocking the session's PTY.
+        let mut clients = self.clients.lock().unwrap();
+        clients.senders.retain(|_, sender| match sender.try_send(buf.to_vec()) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) => {
+                warn!("Detaching window which is too slow to read session {:?}", self.name);
+                false
+            },
+            Err(TrySendError::Disconnected(_)) => false,
+        });
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        // The recorder is dropped with the PTY event loop, which stops once the shell exits.
+        let mut clients = self.clients.lock().unwrap();
+        clients.senders.clear();
+        clients.closed = true;
+        drop(clients);
+
+        if let Some(server) = self.server.upgrade() {
+            server.sessions.lock().unwrap().remove(&self.name);
+        }
+
+        info!("Session {:?} has exited", self.name);
+    }
+}
+
+/// Event listener of a session's terminal, answering requests of the shell.
+///
+/// Since events are emitted while the terminal is locked, the terminal state cannot be used here.
+#[derive(Clone)]
+struct SessionListener {
+    notifier: Arc<Mutex<Option<Notifier>>>,
+    size: Arc<Mutex<WindowSize>>,
+    colors: List,
+}
+
+impl EventListener for SessionListener {
+    fn send_event(&self, event: TerminalEvent) {
+        let text = match event {
+            TerminalEvent::PtyWrite(text) => text,
+            TerminalEvent::ColorRequest(index, format) => format(self.colors[index].0),
+            TerminalEvent::TextAreaSizeRequest(format) => format(*self.size.lock().unwrap()),
+            _ => return,
+        };
+
+        if let Some(notifier) = &*self.notifier.lock().unwrap() {
+            notifier.notify(text.into_bytes());
+        }
+    }
+}
+
+/// Connection to a server session, used by an attached window in place of its PTY.
+pub struct RemotePty {
+    reader: SessionReader,
+    writer: SessionWriter,
+    signals: UnixStream,
+}
+
+impl RemotePty {
+    fn new(socket: UnixStream) -> io::Result<Self> {
+        // The session's end is reported like the exit of a local shell.
+        let (signals, exit) = UnixStream::pair()?;
+        signals.set_nonblocking(true)?;
+        exit.set_nonblocking(true)?;
+
+        let writer = SessionWriter {
+            socket: socket.try_clone()?,
+            exit: exit.try_clone()?,
+            pending: Vec::new(),
+            pending_input: 0,
+        };
+        let reader = SessionReader { socket, exit };
+
+        Ok(Self { reader, writer, signals })
+    }
+}
+
+impl EventedReadWrite for RemotePty {
+    type Reader = SessionReader;
+    type Writer = SessionWriter;
+
+    #[inline]
+    unsafe fn register(
+        &mut self,
+        poll: &Arc<Poller>,
+        mut interest: PollingEvent,
+        poll_opts: PollMode,
+    ) -> io::Result<()> {
+        interest.key = tty::PTY_READ_WRITE_TOKEN;
+        unsafe {
+            poll.add_with_mode(&self.reader.socket, interest, poll_opts)?;
+        }
+
+        unsafe {
+            poll.add_with_mode(
+                &self.signals,
+                PollingEvent::readable(tty::PTY_CHILD_EVENT_TOKEN),
+                PollMode::Level,
+            )
+        }
+    }
+
+    #[inline]
+    fn reregister(
+        &mut self,
+        poll: &Arc<Poller>,
+        mut interest: PollingEvent,
+        poll_opts: PollMode,
+    ) -> io::Result<()> {
+        interest.key = tty::PTY_READ_WRITE_TOKEN;
+        poll.modify_with_mode(&self.reader.socket, interest, poll_opts)?;
+
+        poll.modify_with_mode(
+            &self.signals,
+            PollingEvent::readable(tty::PTY_CHILD_EVENT_TOKEN),
+            PollMode::Level,
+        )
+    }
+
+    #[inline]
+    fn deregister(&mut self, poll: &Arc<Poller>) -> io::Result<()> {
+        poll.delete(&self.reader.socket)?;
+        poll.delete(&self.signals)
+    }
+
+    #[inline]
+    fn reader(&mut self) -> &mut SessionReader {
+        &mut self.reader
+    }
+
+    #[inline]
+    fn writer(&mut self) -> &mut SessionWriter {
+        &mut self.writer
+    }
+}
+
+impl EventedPty for RemotePty {
+    fn next_child_event(&mut self) -> Option<ChildEvent> {
+        match self.signals.read(&mut [0]) {
+            Ok(1) => Some(ChildEvent::Exited(None)),
+            _ => None,
+        }
+    }
+}
+
+impl OnResize for RemotePty {
+    fn on_resize(&mut self, window_size: WindowSize) {
+        // Frames which can't be sent right away are sent before the next input.
+        let size = serde_json::to_vec(&window_size).unwrap_or_default();
+        encode_frame(&mut self.writer.pending, FRAME_RESIZE, &size);
+        let _ = self.writer.send_pending();
+    }
+}
+
+/// Output of a server session.
+pub struct SessionReader {
+    socket: UnixStream,
+    exit: UnixStream,
+}
+
+impl Read for SessionReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // Only reads are non-blocking, since input frames must always be written completely.
+        let fd = self.socket.as_raw_fd();
+        let len = unsafe { libc::recv(fd, buf.as_mut_ptr().cast(), buf.len(), libc::MSG_DONTWAIT) };
+
+        match len {
+            0 => {
+                let _ = self.exit.write(&[0]);
+                Err(ErrorKind::WouldBlock.into())
+            },
+            len if len < 0 => Err(io::Error::last_os_error()),
+            len => Ok(len as usize),
+        }
+    }
+}
+
+/// Input of a server session.
+///
+/// Frames are sent without blocking the PTY I/O loop. Input is only accepted once its frame has
+/// been sent completely, so the I/O loop retries it while the server isn't reading.
+pub struct SessionWriter {
+    socket: UnixStream,
+    exit: UnixStream,
+
+    /// Encoded frames which have not been sent yet.
+    pending: Vec<u8>,
+
+    /// Number of input bytes in the pending frames.
+    pending_input: usize,
+}
+
+impl SessionWriter {
+    /// Send as much of the pending frames as possible without blocking.
+    fn send_pending(&mut self) -> io::Result<()> {
+        let fd = self.socket.as_raw_fd();
+        while !self.pending.is_empty() {
+            let pending = &self.pending;
+            let len = unsafe {
+                libc::send(fd, pending.as_ptr().cast(), pending.len(), libc::MSG_DONTWAIT)
+            };
+
+            if len < 0 {
+                let err = io::Error::last_os_error();
+                if err.kind() != ErrorKind::Interrupted {
+                    return Err(err);
+                }
+            } else {
+                self.pending.drain(..len as usize);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Write for SessionWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        // The I/O loop retries the same input until it was accepted, so it's only queued once.
+        if self.pending_input == 0 {
+            let len = buf.len().min(MAX_FRAME_SIZE);
+            encode_frame(&mut self.pending, FRAME_INPUT, &buf[..len]);
+            self.pending_input = len;
+        }
+
+        match self.send_pending() {
+            Ok(()) => Ok(mem::take(&mut self.pending_input)),
+            Err(err) if err.kind() == ErrorKind::WouldBlock => Err(err),
+            Err(_) => {
+                let _ = self.exit.write(&[0]);
+                Err(ErrorKind::WouldBlock.into())
+            },
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Append a frame to a buffer.
+fn encode_frame(buf: &mut Vec<u8>, kind: u8, payload: &[u8]) {
+    buf.reserve(payload.len() + 5);
+    buf.push(kind);
+    buf.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    buf.extend_from_slice(payload);
+}
+
+/// Write a frame to a session connection.
+fn write_frame<W: Write>(writer: &mut W, kind: u8, payload: &[u8]) -> io::Result<()> {
+    let mut frame = Vec::new();
+    encode_frame(&mut frame, kind, payload);
+    writer.write_all(&frame)
+}
+
+/// Read a frame from a session connection.
+///
+/// Returns [`None`] once the connection was closed.
+fn read_frame<R: Read>(reader: &mut R) -> io::Result<Option<(u8, Vec<u8>)>> {
+    let mut header = [0; 5];
+    match reader.read_exact(&mut header) {
+        Ok(()) => (),
+        Err(err) if err.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(err) => return Err(err),
+    }
+
+    let len = u32::from_be_bytes([header[1], header[2], header[3], header[4]]) as usize;
+    if len > MAX_FRAME_SIZE {
+        return Err(io::Error::new(ErrorKind::InvalidData, "session frame is too large"));
+    }
+
+    let mut payload = vec![0; len];
+    reader.read_exact(&mut payload)?;
+    Ok(Some((header[0], payload)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use larashell_terminal::event::VoidListener;
+
+    #[test]
+    fn frame_roundtrip() {
+        let mut buf = Vec::new();
+        write_frame(&mut buf, FRAME_INPUT, b"ls\r").unwrap();
+        write_frame(&mut buf, FRAME_RESIZE, b"").unwrap();
+        assert_eq!(&buf[..8], &[FRAME_INPUT, 0, 0, 0, 3, b'l', b's', b'\r']);
+
+        let mut reader = buf.as_slice();
+        assert_eq!(read_frame(&mut reader).unwrap(), Some((FRAME_INPUT, b"ls\r".to_vec())));
+        assert_eq!(read_frame(&mut reader).unwrap(), Some((FRAME_RESIZE, Vec::new())));
+        assert_eq!(read_frame(&mut reader).unwrap(), None);
+    }
+
+    #[test]
+    fn oversized_frame() {
+        let header = [FRAME_INPUT, 0xff, 0xff, 0xff, 0xff];
+        let err = read_frame(&mut header.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn nonblocking_input() {
+        let (socket, mut peer) = UnixStream::pair().unwrap();
+        let (exit, _signals) = UnixStream::pair().unwrap();
+        let mut writer = SessionWriter { socket, exit, pending: Vec::new(), pending_input: 0 };
+
+        // Fill the socket buffer without blocking.
+        let input = vec![b'a'; MAX_FRAME_SIZE];
+        let mut written = 0;
+        let err = loop {
+            match writer.write(&input) {
+                Ok(len) => written += len,
+                Err(err) => break err,
+            }
+        };
+        assert_eq!(err.kind(), ErrorKind::WouldBlock);
+
+        let reader = std::thread::spawn(move || {
+            let mut read = 0;
+            while let Some((kind, payload)) = read_frame(&mut peer).unwrap() {
+                assert_eq!(kind, FRAME_INPUT);
+                read += payload.len();
+            }
+            read
+        });
+
+        // Retried input is only sent once.
+        loop {
+            match writer.write(&input) {
+                Ok(len) => break written += len,
+                Err(err) => assert_eq!(err.kind(), ErrorKind::WouldBlock),
+            }
+        }
+        drop(writer);
+
+        assert_eq!(reader.join().unwrap(), written);
+    }
+
+    #[test]
+    fn attach_and_detach() {
+        let (client, server) = UnixStream::pair().unwrap();
+        let window_size =
+            WindowSize { num_lines: 100, num_cols: 200, cell_width: 8, cell_height: 16 };
+
+        // Use a terminal large enough to split its state into multiple frames.
+        let size = TermSize::from(window_size);
+        let terminal = Term::new(TermConfig::default(), &size, VoidListener);
+        let snapshot = serde_json::to_vec(&terminal.snapshot()).unwrap();
+        assert!(snapshot.len() > MAX_FRAME_SIZE);
+
+        let (sender, receiver) = mpsc::sync_channel(MAX_QUEUED_OUTPUT);
+        let server = std::thread::spawn(move || {
+            let mut stream = BufReader::new(server);
+            let mut data = String::new();
+            stream.read_line(&mut data).unwrap();
+            let request: IpcRequest<ServerMessage> = serde_json::from_str(&data).unwrap();
+            let mut stream = stream.into_inner();
+
+            Server::write_snapshot(&mut stream, request.id, 42, &snapshot).unwrap();
+
+            let mut frames = Vec::new();
+            serve_window(stream, receiver, |kind, payload| {
+                frames.push((kind, payload));
+                Ok(())
+            })
+            .unwrap();
+            frames
+        });
+
+        let request = AttachRequest {
+            session: "test".into(),
+            terminal_options: Default::default(),
+            window_size,
+            window_id: 0,
+        };
+        let (mut pty, pid, attached) = attach_on(client, request).unwrap();
+        assert_eq!(pid, 42);
+        assert_eq!(attached, terminal.snapshot());
+
+        // Output is only sent once the snapshot has been read.
+        sender.send(b"output".to_vec()).unwrap();
+        let mut output = [0; 6];
+        let mut read = 0;
+        while read < output.len() {
+            match pty.reader().read(&mut output[read..]) {
+                Ok(len) => read += len,
+                Err(err) => assert_eq!(err.kind(), ErrorKind::WouldBlock),
+            }
+        }
+        assert_eq!(&output, b"output");
+
+        pty.writer().write_all(b"input").unwrap();
+        pty.on_resize(window_size);
+
+        // Detach by closing the connection.
+        drop(pty);
+        let frames = server.join().unwrap();
+        assert_eq!(
+            frames,
+            vec![
+                (FRAME_INPUT, b"input".to_vec()),
+                (FRAME_RESIZE, serde_json::to_vec(&window_size).unwrap()),
+            ]
+        );
+    }
+
+    #[test]
+    fn message_format() {
+        let request = IpcRequest { id: 1, message: ServerMessage::ListSessions };
+        assert_eq!(
+            serde_json::to_string(&request).unwrap(),
+            r#"{"id":1,"message":"ListSessions"}"#
+        );
+    }
+}
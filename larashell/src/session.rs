@@ -0,0 +1,207 @@
+//! Saving and restoring of terminal sessions.
+
+use std::error::Error;
+use std::fs::{self, OpenOptions};
+use std::io::{BufWriter, Write};
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use larashell_terminal::sync::FairMutex;
+use larashell_terminal::term::{Term, TermSnapshot};
+use larashell_terminal::thread;
+
+use crate::cli::{TerminalOptions, WindowOptions};
+
+/// Version of the session file format.
+///
+/// This must be incremented whenever the serialized data changes in an incompatible way, which
+/// includes the serialization of the terminal grid. Files using the previous version should then
+/// be converted in [`migrate`].
+const VERSION: u64 = 1;
+
+/// Saved state of all windows.
+///
+/// The version is checked before deserialization, see [`parse`].
+#[derive(Deserialize, Debug)]
+pub struct Session<'a> {
+    windows: Vec<WindowSnapshot<'a>>,
+}
+
+impl Session<'static> {
+    /// Options for recreating all windows of the session.
+    pub fn into_window_options(self, options: &WindowOptions) -> Vec<WindowOptions> {
+        self.windows
+            .into_iter()
+            .map(|window| {
+                let mut options = options.clone();
+                options.terminal_options = window.terminal_options;
+                options.snapshot = Some(Box::new(window.terminal));
+                options
+            })
+            .collect()
+    }
+}
+
+/// Saved state of a single window.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct WindowSnapshot<'a> {
+    /// Options for spawning the shell, using the working directory at the time of saving.
+    pub terminal_options: TerminalOptions,
+
+    /// Terminal content and state.
+    pub terminal: TermSnapshot<'a>,
+}
+
+/// Terminals of all windows which are part of a session.
+pub type SessionWindows<T> = Vec<(TerminalOptions, Arc<FairMutex<Term<T>>>)>;
+
+/// Write the session of all windows as JSON.
+///
+/// Every terminal is only locked while its own snapshot is serialized into memory, so saving a
+/// session never blocks a window while writing to disk, or all windows at once.
+fn write_session<W: Write, T>(
+    writer: &mut W,
+    windows: &SessionWindows<T>,
+) -> Result<(), Box<dyn Error>> {
+    write!(writer, "{{\"version\":{VERSION},\"windows\":[")?;
+
+    for (i, (terminal_options, terminal)) in windows.iter().enumerate() {
+        let snapshot = {
+            let terminal = terminal.lock();
+            let terminal_options = terminal_options.clone();
+            serde_json::to_vec(&WindowSnapshot { terminal_options, terminal: terminal.snapshot() })?
+        };
+
+        if i > 0 {
+            writer.write_all(b",")?;
+        }
+        writer.write_all(&snapshot)?;
+    }
+
+    writer.write_all(b"]}")?;
+
+    Ok(())
+}
+
+/// Save a session on a separate thread.
+pub fn spawn_save<T: Send + 'static>(windows: SessionWindows<T>) {
+    thread::spawn_named("session writer", move || match save(&windows) {
+        Ok(path) => info!("Saved session to {path:?}"),
+        Err(err) => error!("Unable to save session: {err}"),
+    });
+}
+
+/// Write a session to a new file in the sessions directory.
+fn save<T>(windows: &SessionWindows<T>) -> Result<PathBuf, Box<dyn Error>> {
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let path = sessions_dir()?.join(format!("session-{timestamp}.json"));
+
+    // Write to a temporary file first, to never leave a truncated session behind.
+    let tmp_path = path.with_extension("json.tmp");
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut file = BufWriter::new(options.open(&tmp_path)?);
+    write_session(&mut file, windows)?;
+    file.flush()?;
+    fs::rename(&tmp_path, &path)?;
+
+    Ok(path)
+}
+
+/// Load a session, defaulting to the most recently saved one.
+pub fn load(path: Option<&Path>) -> Result<Session<'static>, Box<dyn Error>> {
+    let path = match path {
+        Some(path) => path.to_owned(),
+        None => latest_session()?,
+    };
+
+    parse(&fs::read(path)?)
+}
+
+/// Deserialize a session, converting older versions to the current format.
+fn parse(data: &[u8]) -> Result<Session<'static>, Box<dyn Error>> {
+    let value: Value = serde_json::from_slice(data)?;
+
+    let version = value.get("version").and_then(Value::as_u64).ok_or("missing session version")?;
+    if version > VERSION {
+        return Err(
+            format!("session version {version} requires a newer version of LaraShell").into()
+        );
+    }
+
+    Ok(serde_json::from_value(migrate(value, version)?)?)
+}
+
+/// Convert a session from an older format version to the current one.
+fn migrate(value: Value, version: u64) -> Result<Value, Box<dyn Error>> {
+    match version {
+        VERSION => Ok(value),
+        _ => Err(format!("unsupported session version {version}").into()),
+    }
+}
+
+/// Path of the most recently modified session file.
+fn latest_session() -> Result<PathBuf, Box<dyn Error>> {
+    fs::read_dir(sessions_dir()?)?
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().extension().is_some_and(|extension| extension == "json"))
+        .filter_map(|entry| Some((entry.metadata().ok()?.modified().ok()?, entry.path())))
+        .max()
+        .map(|(_, path)| path)
+        .ok_or_else(|| "no saved session found".into())
+}
+
+/// Directory for saved sessions.
+#[cfg(not(windows))]
+fn sessions_dir() -> Result<PathBuf, Box<dyn Error>> {
+    Ok(xdg::BaseDirectories::with_prefix("larashell")?.create_state_directory("sessions")?)
+}
+
+/// Directory for saved sessions.
+#[cfg(windows)]
+fn sessions_dir() -> Result<PathBuf, Box<dyn Error>> {
+    let path = dirs::data_local_dir().ok_or("no local data directory")?.join("larashell\\sessions");
+    fs::create_dir_all(&path)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use larashell_terminal::event::VoidListener;
+    use larashell_terminal::term::test::TermSize;
+    use larashell_terminal::term::Config;
+
+    #[test]
+    fn session_roundtrip() {
+        let size = TermSize::new(10, 5);
+        let term = Arc::new(FairMutex::new(Term::new(Config::default(), &size, VoidListener)));
+
+        let mut terminal_options = TerminalOptions::default();
+        terminal_options.working_directory = Some("/tmp".into());
+        let windows = vec![(terminal_options, term.clone())];
+        let mut data = Vec::new();
+        write_session(&mut data, &windows).unwrap();
+
+        let session = parse(&data).unwrap();
+        assert_eq!(session.windows.len(), 1);
+        assert_eq!(session.windows[0].terminal, term.lock().snapshot());
+        assert_eq!(session.windows[0].terminal_options.working_directory, Some("/tmp".into()));
+    }
+
+    #[test]
+    fn newer_version_is_rejected() {
+        let data = format!("{{\"version\":{},\"windows\":[]}}", VERSION + 1);
+        assert!(parse(data.as_bytes()).is_err());
+    }
+}
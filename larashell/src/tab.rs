@@ -0,0 +1,363 @@
+//! Tabs inside a terminal window.
+
+use std::cmp;
+
+use unicode_width::UnicodeWidthChar;
+
+use crate::pane::{PaneId, PaneLayout, Region};
+
+/// Maximum number of columns used by a single tab in the tab bar.
+const MAX_TAB_COLUMNS: usize = 30;
+
+/// Actions selecting a tab of a window.
+///
+/// On macOS, tabs are native windows selected through the window instead.
+#[cfg_attr(target_os = "macos", allow(dead_code))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TabAction {
+    /// Select the tab after the selected tab.
+    Next,
+
+    /// Select the tab before the selected tab.
+    Previous,
+
+    /// Select the tab at an index.
+    Select(usize),
+
+    /// Select the last tab.
+    SelectLast,
+}
+
+/// Tabs of a window, each with its own panes.
+#[derive(Debug)]
+pub struct Tabs {
+    /// Pane layouts of all tabs, in tab bar order.
+    layouts: Vec<PaneLayout>,
+
+    /// Index of the selected tab.
+    active: usize,
+
+    /// Whether the selected tab is moved with the mouse.
+    dragging: bool,
+
+    /// Lines of the window's terminal grid.
+    lines: usize,
+
+    /// Columns of the window's terminal grid.
+    columns: usize,
+}
+
+impl Tabs {
+    pub fn new(layout: PaneLayout) -> Self {
+        Self { layouts: vec![layout], active: 0, dragging: false, lines: 0, columns: 0 }
+    }
+
+    /// Number of tabs.
+    pub fn len(&self) -> usize {
+        self.layouts.len()
+    }
+
+    /// Index of the selected tab.
+    pub fn active_index(&self) -> usize {
+        self.active
+    }
+
+    /// Pane layout of the selected tab.
+    pub fn active(&self) -> &PaneLayout {
+        &self.layouts[self.active]
+    }
+
+    /// Mutable pane layout of the selected tab.
+    pub fn active_mut(&mut self) -> &mut PaneLayout {
+        &mut self.layouts[self.active]
+    }
+
+    /// Pane layouts of all tabs, in tab bar order.
+    pub fn iter(&self) -> impl Iterator<Item = &PaneLayout> {
+        self.layouts.iter()
+    }
+
+    /// Index of the tab containing a pane.
+    pub fn tab_of(&self, id: PaneId) -> Option<usize> {
+        self.layouts.iter().position(|layout| layout.contains(id))
+    }
+
+    /// Number of lines at the top of the window used by the tab bar.
+    pub fn bar_lines(&self) -> usize {
+        usize::from(self.layouts.len() > 1)
+    }
+
+    /// Update the pane regions of all tabs for a terminal grid of `lines` x `columns`.
+    pub fn resize(&mut self, lines: usize, columns: usize) {
+        self.lines = lines;
+        self.columns = columns;
+
+        let bar_lines = self.bar_lines();
+        let region =
+            Region::new(bar_lines, 0, cmp::max(lines.saturating_sub(bar_lines), 1), columns);
+        for layout in &mut self.layouts {
+            layout.set_region(region);
+        }
+    }
+
+    /// Add a tab after the selected tab and select it.
+    pub fn push(&mut self, layout: PaneLayout) {
+        self.active += 1;
+        self.layouts.insert(self.active, layout);
+        self.dragging = false;
+        self.resize(self.lines, self.columns);
+    }
+
+    /// Remove a tab.
+    ///
+    /// If the tab was selected, the tab taking over its position is selected instead. The last
+    /// tab cannot be removed.
+    pub fn remove(&mut self, index: usize) -> bool {
+        if self.layouts.len() == 1 || index >= self.layouts.len() {
+            return false;
+        }
+
+        self.layouts.remove(index);
+        if index < self.active || self.active == self.layouts.len() {
+            self.active -= 1;
+        }
+        self.dragging = false;
+        self.resize(self.lines, self.columns);
+
+        true
+    }
+
+    /// Remove a pane from its tab, removing the tab with its last pane.
+    ///
+    /// Returns `false` if the pane was the last pane of the window.
+    pub fn remove_pane(&mut self, id: PaneId) -> bool {
+        match self.tab_of(id) {
+            Some(index) => self.layouts[index].remove(id) || self.remove(index),
+            None => false,
+        }
+    }
+
+    /// Select a tab.
+    ///
+    /// Returns `true` if the selected tab changed.
+    pub fn select(&mut self, index: usize) -> bool {
+        let changed = index < self.layouts.len() && index != self.active;
+        if changed {
+            self.active = index;
+        }
+        changed
+    }
+
+    /// Index of the tab targeted by an action.
+    pub fn action_index(&self, action: TabAction) -> usize {
+        let len = self.layouts.len();
+        match action {
+            TabAction::Next => (self.active + 1) % len,
+            TabAction::Previous => (self.active + len - 1) % len,
+            TabAction::Select(index) => index,
+            TabAction::SelectLast => len - 1,
+        }
+    }
+
+    /// Number of columns used by each tab in the tab bar.
+    pub fn tab_width(&self) -> usize {
+        (self.columns / self.layouts.len()).clamp(1, MAX_TAB_COLUMNS)
+    }
+
+    /// Tab shown at a column of the tab bar.
+    pub fn tab_at(&self, column: usize) -> Option<usize> {
+        let index = column / self.tab_width();
+        (self.bar_lines() > 0 && index < self.layouts.len()).then_some(index)
+    }
+
+    /// Start moving the selected tab with the mouse.
+    pub fn start_drag(&mut self) {
+        self.dragging = self.bar_lines() > 0;
+    }
+
+    /// Check if the selected tab is moved with the mouse.
+    pub fn dragging(&self) -> bool {
+        self.dragging
+    }
+
+    /// Move the dragged tab to the position below a column of the tab bar.
+    ///
+    /// Returns `true` if the tab order changed.
+    pub fn drag(&mut self, column: usize) -> bool {
+        let index = cmp::min(column / self.tab_width(), self.layouts.len() - 1);
+        if !self.dragging || index == self.active {
+            return false;
+        }
+
+        let layout = self.layouts.remove(self.active);
+        self.layouts.insert(index, layout);
+        self.active = index;
+
+        true
+    }
+
+    /// Stop moving a tab with the mouse.
+    pub fn stop_drag(&mut self) {
+        self.dragging = false;
+    }
+}
+
+/// Content of the tab bar.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TabBar {
+    /// Titles of all tabs, in tab bar order.
+    pub titles: Vec<String>,
+
+    /// Index of the selected tab.
+    pub active: usize,
+
+    /// Number of columns used by each tab.
+    pub tab_width: usize,
+}
+
+impl TabBar {
+    /// Text of a tab, filling exactly `tab_width` columns.
+    ///
+    /// Wide characters are followed by a spacer, to keep the column offsets intact.
+    pub fn label(&self, index: usize) -> String {
+        let title = self.titles.get(index).map_or("", String::as_str);
+
+        // Leave room for a space on either side of the title.
+        let available = self.tab_width.saturating_sub(2);
+        let title_width: usize = title.chars().filter_map(|c| c.width()).sum();
+
+        let mut label = String::from(" ");
+        let mut width = 1;
+        for c in title.chars() {
+            let char_width = match c.width() {
+                Some(0) | None => continue,
+                Some(char_width) => char_width,
+            };
+
+            // Truncate long titles with an ellipsis.
+            if title_width > available && width + char_width > available {
+                label.push('…');
+                width += 1;
+                break;
+            }
+
+            label.push(c);
+            if char_width == 2 {
+                label.push(' ');
+            }
+            width += char_width;
+        }
+
+        label.push_str(&" ".repeat(self.tab_width.saturating_sub(width)));
+        label
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::pane::SplitDirection;
+
+    fn tabs(count: usize) -> Tabs {
+        let mut tabs = Tabs::new(PaneLayout::default());
+        tabs.resize(10, 40);
+        for _ in 1..count {
+            tabs.push(PaneLayout::default());
+        }
+        tabs
+    }
+
+    #[test]
+    fn tab_bar_region() {
+        let tabs = tabs(1);
+        assert_eq!(tabs.bar_lines(), 0);
+        assert_eq!(tabs.active().visible()[0].1, Region::new(0, 0, 10, 40));
+
+        let tabs = self::tabs(2);
+        assert_eq!(tabs.bar_lines(), 1);
+        for layout in tabs.iter() {
+            assert_eq!(layout.visible()[0].1, Region::new(1, 0, 9, 40));
+        }
+    }
+
+    #[test]
+    fn push_and_remove() {
+        let mut tabs = tabs(3);
+        assert_eq!(tabs.active_index(), 2);
+
+        let first = tabs.iter().next().unwrap().focused();
+        tabs.select(0);
+        tabs.push(PaneLayout::default());
+        assert_eq!(tabs.active_index(), 1);
+        assert_eq!(tabs.tab_of(first), Some(0));
+
+        // Removing the selected tab selects its successor.
+        assert!(tabs.remove(1));
+        assert_eq!(tabs.active_index(), 1);
+
+        // Removing the last tab selects its predecessor.
+        tabs.select(2);
+        assert!(tabs.remove(2));
+        assert_eq!(tabs.active_index(), 1);
+
+        assert!(tabs.remove(0));
+        assert!(!tabs.remove(0));
+        assert_eq!(tabs.bar_lines(), 0);
+    }
+
+    #[test]
+    fn remove_pane() {
+        let mut tabs = tabs(2);
+        let first = tabs.iter().next().unwrap().focused();
+        let split = tabs.active_mut().split(SplitDirection::Right);
+
+        // Closing a split pane keeps its tab.
+        assert!(tabs.remove_pane(split));
+        assert_eq!(tabs.len(), 2);
+
+        // Closing the last pane of a tab closes the tab.
+        let last = tabs.active().focused();
+        assert!(tabs.remove_pane(last));
+        assert_eq!(tabs.len(), 1);
+
+        assert!(!tabs.remove_pane(first));
+    }
+
+    #[test]
+    fn actions() {
+        let tabs = tabs(3);
+        assert_eq!(tabs.action_index(TabAction::Next), 0);
+        assert_eq!(tabs.action_index(TabAction::Previous), 1);
+        assert_eq!(tabs.action_index(TabAction::SelectLast), 2);
+    }
+
+    #[test]
+    fn drag_tab() {
+        let mut tabs = tabs(3);
+        let dragged = tabs.active().focused();
+        assert_eq!(tabs.tab_width(), 13);
+        assert_eq!(tabs.tab_at(14), Some(1));
+        assert_eq!(tabs.tab_at(39), None);
+
+        // Tabs only move while dragging.
+        assert!(!tabs.drag(0));
+
+        tabs.start_drag();
+        assert!(tabs.drag(0));
+        tabs.stop_drag();
+
+        assert_eq!(tabs.active_index(), 0);
+        assert_eq!(tabs.tab_of(dragged), Some(0));
+    }
+
+    #[test]
+    fn labels() {
+        let titles = vec!["zsh".into(), "vim main.rs".into(), "日本".into()];
+        let bar = TabBar { titles, active: 0, tab_width: 8 };
+
+        assert_eq!(bar.label(0), " zsh    ");
+        assert_eq!(bar.label(1), " vim m… ");
+        assert_eq!(bar.label(2), " 日 本    ");
+    }
+}
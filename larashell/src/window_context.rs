@@ -1,69 +1,90 @@
 //! Terminal window context.
 
+use std::collections::HashMap;
 use std::error::Error;
 use std::fs::File;
+#[cfg(unix)]
+use std::io;
 use std::io::Write;
 use std::mem;
-#[cfg(not(windows))]
-use std::os::unix::io::{AsRawFd, RawFd};
+#[cfg(unix)]
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 use std::sync::Arc;
+use std::time::Duration;
 
 use glutin::config::GetGlConfig;
 use glutin::display::GetGlDisplay;
 #[cfg(all(feature = "x11", not(any(target_os = "macos", windows))))]
 use glutin::platform::x11::X11GlConfigExt;
-use log::info;
+use log::{error, info};
 use raw_window_handle::HasRawDisplayHandle;
 use serde_json as json;
-use winit::event::{Event as WinitEvent, Modifiers, WindowEvent};
+use winit::event::{ElementState, Event as WinitEvent, Modifiers, MouseButton, WindowEvent};
 use winit::event_loop::{EventLoopProxy, EventLoopWindowTarget};
 use winit::window::WindowId;
 
-use larashell_terminal::event::Event as TerminalEvent;
-use larashell_terminal::event_loop::{EventLoop as PtyEventLoop, Msg, Notifier};
+use larashell_terminal::event::{Event as TerminalEvent, Notify, OnResize};
+use larashell_terminal::event_loop::Notifier;
 use larashell_terminal::grid::{Dimensions, Scroll};
 use larashell_terminal::index::Direction;
+#[cfg(unix)]
+use larashell_terminal::index::{Column, Line, Point};
+#[cfg(unix)]
+use larashell_terminal::selection::SelectionRange;
 use larashell_terminal::sync::FairMutex;
-use larashell_terminal::term::test::TermSize;
-use larashell_terminal::term::{Term, TermMode};
-use larashell_terminal::tty;
+#[cfg(unix)]
+use larashell_terminal::term::export::ExportFormat;
+use larashell_terminal::term::{Term, TermMode, TermSize};
 
-use crate::cli::{ParsedOptions, WindowOptions};
+#[cfg(unix)]
+use crate::cli::{IpcGetText, TextExtent, MAX_TEXT_LINES};
+use crate::cli::{ParsedOptions, TerminalOptions, WindowOptions};
 use crate::clipboard::Clipboard;
 use crate::config::UiConfig;
+#[cfg(not(windows))]
+use crate::daemon::{foreground_process_name, foreground_process_path};
+use crate::display::color::Rgb;
 use crate::display::window::Window;
 use crate::display::Display;
 use crate::event::{
-    ActionContext, Event, EventProxy, InlineSearchState, Mouse, SearchState, TouchPurpose,
+    ActionContext, Event, EventProxy, EventType, InlineSearchState, Mouse, SearchState,
+    TouchPurpose,
 };
 #[cfg(unix)]
+use crate::export;
+#[cfg(unix)]
+use crate::ipc::{IpcText, IpcWindow};
+#[cfg(unix)]
 use crate::logging::LOG_TARGET_IPC_CONFIG;
-use crate::message_bar::MessageBuffer;
-use crate::scheduler::Scheduler;
+use crate::message_bar::{Message, MessageBuffer, MessageType};
+use crate::pane::{Pane, PaneAction, PaneId, PaneLayout, SplitDirection};
+use crate::scheduler::{Scheduler, TimerId, Topic};
+use crate::tab::{TabBar, Tabs};
 use crate::{input, renderer};
 
+/// Message bar target of the confirmation for closing a window with multiple panes.
+const CLOSE_WINDOW_TARGET: &str = "close_window";
+
 /// Event context for one individual LaraShell window.
 pub struct WindowContext {
     pub message_buffer: MessageBuffer,
     pub display: Display,
     pub dirty: bool,
     event_queue: Vec<WinitEvent<Event>>,
-    terminal: Arc<FairMutex<Term<EventProxy>>>,
+    tabs: Tabs,
+    panes: HashMap<PaneId, Pane>,
     cursor_blink_timed_out: bool,
     modifiers: Modifiers,
     inline_search_state: InlineSearchState,
     search_state: SearchState,
-    notifier: Notifier,
     mouse: Mouse,
     touch: TouchPurpose,
     occluded: bool,
     preserve_title: bool,
-    #[cfg(not(windows))]
-    master_fd: RawFd,
-    #[cfg(not(windows))]
-    shell_pid: u32,
+    group: Option<String>,
     window_config: ParsedOptions,
+    terminal_options: TerminalOptions,
     config: Rc<UiConfig>,
 }
 
@@ -173,14 +194,11 @@ impl WindowContext {
 
     /// Create a new terminal window context.
     fn new(
-        display: Display,
+        mut display: Display,
         config: Rc<UiConfig>,
         options: WindowOptions,
         proxy: EventLoopProxy<Event>,
     ) -> Result<Self, Box<dyn Error>> {
-        let mut pty_config = config.pty_config();
-        options.terminal_options.override_pty_config(&mut pty_config);
-
         let preserve_title = options.window_identity.title.is_some();
 
         info!(
@@ -189,48 +207,33 @@ impl WindowContext {
             display.size_info.columns()
         );
 
-        let event_proxy = EventProxy::new(proxy, display.window.id());
-
-        // Create the terminal.
-        //
-        // This object contains all of the state about what's being displayed. It's
-        // wrapped in a clonable mutex since both the I/O loop and display need to
-        // access it.
-        let terminal = Term::new(config.term_options(), &display.size_info, event_proxy.clone());
-        let terminal = Arc::new(FairMutex::new(terminal));
-
-        // Create the PTY.
-        //
-        // The PTY forks a process to run the shell on the slave side of the
-        // pseudoterminal. A file descriptor for the master side is retained for
-        // reading/writing to the shell.
-        let pty = tty::new(&pty_config, display.size_info.into(), display.window.id().into())?;
-
-        #[cfg(not(windows))]
-        let master_fd = pty.file().as_raw_fd();
-        #[cfg(not(windows))]
-        let shell_pid = pty.child().id();
+        let window_id = display.window.id();
 
-        // Create the pseudoterminal I/O loop.
-        //
-        // PTY I/O is ran on another thread as to not occupy cycles used by the
-        // renderer and input processing. Note that access to the terminal state is
-        // synchronized since the I/O loop updates the state, and the display
-        // consumes it periodically.
-        let event_loop = PtyEventLoop::new(
-            Arc::clone(&terminal),
+        let layout = PaneLayout::default();
+        let pane_id = layout.focused();
+        let event_proxy = EventProxy::new(proxy, window_id, pane_id);
+        let mut pane = Pane::with_options(
+            &config,
+            &options,
+            display.size_info,
+            window_id,
             event_proxy.clone(),
-            pty,
-            pty_config.hold,
-            config.debug.ref_test,
         )?;
 
-        // The event loop channel allows write requests from the event processor
-        // to be sent to the pty loop and ultimately written to the pty.
-        let loop_tx = event_loop.channel();
+        // Restore the title of a saved session or an attached server session.
+        let title = match options.snapshot.as_ref() {
+            Some(snapshot) => snapshot.title.clone(),
+            None => pane.terminal.lock().title().map(ToOwned::to_owned),
+        };
+        if let Some(title) = &title {
+            if !preserve_title && config.window.dynamic_title {
+                display.window.set_title(title.clone());
+            }
+        }
+        pane.title = title;
 
-        // Kick off the I/O thread.
-        let _io_thread = event_loop.spawn();
+        let mut tabs = Tabs::new(layout);
+        tabs.resize(display.size_info.screen_lines(), display.size_info.columns());
 
         // Start cursor blinking, in case `Focused` isn't sent on startup.
         if config.cursor.style().blinking {
@@ -239,15 +242,13 @@ impl WindowContext {
 
         // Create context for the LaraShell window.
         Ok(WindowContext {
+            terminal_options: options.terminal_options,
+            group: options.group,
             preserve_title,
-            terminal,
+            panes: HashMap::from([(pane_id, pane)]),
+            tabs,
             display,
-            #[cfg(not(windows))]
-            master_fd,
-            #[cfg(not(windows))]
-            shell_pid,
             config,
-            notifier: Notifier(loop_tx),
             cursor_blink_timed_out: Default::default(),
             inline_search_state: Default::default(),
             message_buffer: Default::default(),
@@ -270,7 +271,10 @@ impl WindowContext {
         self.config = self.window_config.override_config_rc(self.config.clone());
 
         self.display.update_config(&self.config);
-        self.terminal.lock().set_options(self.config.term_options());
+        for pane in self.panes.values_mut() {
+            let config = pane.config_overrides.override_config_rc(self.config.clone());
+            pane.terminal.lock().set_options(config.term_options());
+        }
 
         // Reload cursor if its thickness has changed.
         if (old_config.cursor.thickness() - self.config.cursor.thickness()).abs() > f32::EPSILON {
@@ -386,9 +390,13 @@ impl WindowContext {
         }
 
         // Redraw the window.
-        let terminal = self.terminal.lock();
+        let tab_bar = self.tab_bar();
+        let terminal = self.panes[&self.tabs.active().focused()].terminal.lock();
         self.display.draw(
             terminal,
+            self.tabs.active(),
+            &self.panes,
+            tab_bar.as_ref(),
             scheduler,
             &self.message_buffer,
             &self.config,
@@ -421,52 +429,239 @@ impl WindowContext {
             },
         }
 
-        let mut terminal = self.terminal.lock();
+        let old_focused = self.tabs.active().focused();
+        let mut old_is_searching = self.search_state.history_index.is_some();
+
+        // Events are passed to the focused pane in batches, flushing them whenever the focused
+        // pane could change.
+        let mut batch = Vec::new();
+        for event in mem::take(&mut self.event_queue) {
+            // Keep track of the titles shown in the tab bar.
+            if let WinitEvent::UserEvent(event) = &event {
+                match (event.pane_id(), event.payload()) {
+                    (Some(id), EventType::Terminal(TerminalEvent::Title(title))) => {
+                        self.set_pane_title(id, Some(title.clone()))
+                    },
+                    (Some(id), EventType::Terminal(TerminalEvent::ResetTitle)) => {
+                        self.set_pane_title(id, None)
+                    },
+                    _ => (),
+                }
+            }
 
-        let old_is_searching = self.search_state.history_index.is_some();
+            match event {
+                // Events from terminals without keyboard focus.
+                WinitEvent::UserEvent(event)
+                    if event.pane_id().is_some_and(|id| !self.is_focused_pane(id)) =>
+                {
+                    match (event.pane_id(), event.payload()) {
+                        (Some(id), EventType::Terminal(TerminalEvent::Exit)) => {
+                            self.close_pane(event_loop, event_proxy, clipboard, scheduler, id)
+                        },
+                        (Some(id), EventType::Terminal(event)) => self.handle_pane_event(id, event),
+                        (Some(id), EventType::FuzzyResults(results)) => {
+                            if let Some(pane) = self.panes.get_mut(&id) {
+                                pane.state.search_state.update_fuzzy_results(results.clone());
+                            }
+                        },
+                        _ => (),
+                    }
+                },
+                WinitEvent::UserEvent(event)
+                    if matches!(
+                        event.payload(),
+                        EventType::Pane(_)
+                            | EventType::Tab(_)
+                            | EventType::CreateTab(_)
+                            | EventType::Terminal(TerminalEvent::Exit)
+                    ) =>
+                {
+                    self.with_processor(
+                        event_loop,
+                        event_proxy,
+                        clipboard,
+                        scheduler,
+                        |processor| {
+                            batch.drain(..).for_each(|event| processor.handle_event(event));
+                        },
+                    );
+
+                    match event.payload() {
+                        EventType::Pane(action) => {
+                            self.pane_action(event_loop, event_proxy, clipboard, scheduler, *action)
+                        },
+                        EventType::Tab(action) => {
+                            let index = self.tabs.action_index(*action);
+                            self.select_tab(event_loop, event_proxy, clipboard, scheduler, index);
+                        },
+                        EventType::CreateTab(options) => {
+                            if let Err(err) = self.create_tab(
+                                event_loop,
+                                event_proxy,
+                                clipboard,
+                                scheduler,
+                                options,
+                            ) {
+                                error!("Unable to create tab: {err}");
+                            }
+                        },
+                        _ => {
+                            let id =
+                                event.pane_id().unwrap_or_else(|| self.tabs.active().focused());
+                            self.close_pane(event_loop, event_proxy, clipboard, scheduler, id);
+                        },
+                    }
+                },
+                WinitEvent::WindowEvent { event: WindowEvent::CloseRequested, .. } => {
+                    if !self.confirm_close() {
+                        continue;
+                    }
+
+                    // Close all other panes, so the window closes with the focused pane.
+                    self.close_inactive_panes();
+
+                    batch.push(event);
+                },
+                WinitEvent::WindowEvent {
+                    event:
+                        WindowEvent::MouseInput {
+                            state: ElementState::Pressed,
+                            button: MouseButton::Left,
+                            ..
+                        },
+                    ..
+                } => {
+                    self.with_processor(
+                        event_loop,
+                        event_proxy,
+                        clipboard,
+                        scheduler,
+                        |processor| {
+                            batch.drain(..).for_each(|event| processor.handle_event(event));
+                        },
+                    );
+
+                    let cell = self.display.window_size.cell_at(self.mouse.x, self.mouse.y);
+                    if let Some((line, column)) = cell {
+                        // Select the clicked tab, allowing it to be moved with the mouse.
+                        if line < self.tabs.bar_lines() {
+                            if let Some(index) = self.tabs.tab_at(column) {
+                                self.select_tab(
+                                    event_loop,
+                                    event_proxy,
+                                    clipboard,
+                                    scheduler,
+                                    index,
+                                );
+                                self.tabs.start_drag();
+                            }
+                            continue;
+                        }
+
+                        // Start moving a divider instead of the pane below it.
+                        if self.tabs.active_mut().start_drag(line, column) {
+                            continue;
+                        }
+
+                        // Focus the clicked pane.
+                        match self.tabs.active().pane_at(line, column) {
+                            Some(id) if !self.is_focused_pane(id) => {
+                                self.switch_focus(
+                                    event_loop,
+                                    event_proxy,
+                                    clipboard,
+                                    scheduler,
+                                    id,
+                                );
+
+                                let size_info = self.display.size_info;
+                                self.mouse.inside_text_area =
+                                    size_info.contains_point(self.mouse.x, self.mouse.y);
+                            },
+                            _ => (),
+                        }
+                    }
+
+                    batch.push(event);
+                },
+                WinitEvent::WindowEvent {
+                    event: WindowEvent::CursorMoved { position, .. },
+                    ..
+                } if self.tabs.dragging() => {
+                    let (x, y) = (position.x.max(0.) as usize, position.y.max(0.) as usize);
+                    if let Some((_, column)) = self.display.window_size.cell_at(x, y) {
+                        self.dirty |= self.tabs.drag(column);
+                    }
+                },
+                WinitEvent::WindowEvent {
+                    event: WindowEvent::CursorMoved { position, .. },
+                    ..
+                } if self.tabs.active().dragging() => {
+                    let (x, y) = (position.x.max(0.) as usize, position.y.max(0.) as usize);
+                    if let Some((line, column)) = self.display.window_size.cell_at(x, y) {
+                        self.display.pending_update.dirty |=
+                            self.tabs.active_mut().drag(line, column);
+                    }
+                },
+                WinitEvent::WindowEvent {
+                    event:
+                        WindowEvent::MouseInput {
+                            state: ElementState::Released,
+                            button: MouseButton::Left,
+                            ..
+                        },
+                    ..
+                } if self.tabs.dragging() || self.tabs.active().dragging() => {
+                    self.tabs.stop_drag();
+                    self.tabs.active_mut().stop_drag();
+                },
+                event => batch.push(event),
+            }
+        }
 
-        let context = ActionContext {
-            cursor_blink_timed_out: &mut self.cursor_blink_timed_out,
-            message_buffer: &mut self.message_buffer,
-            inline_search_state: &mut self.inline_search_state,
-            search_state: &mut self.search_state,
-            modifiers: &mut self.modifiers,
-            notifier: &mut self.notifier,
-            display: &mut self.display,
-            mouse: &mut self.mouse,
-            touch: &mut self.touch,
-            dirty: &mut self.dirty,
-            occluded: &mut self.occluded,
-            terminal: &mut terminal,
-            #[cfg(not(windows))]
-            master_fd: self.master_fd,
-            #[cfg(not(windows))]
-            shell_pid: self.shell_pid,
-            preserve_title: self.preserve_title,
-            config: &self.config,
-            event_proxy,
-            event_loop,
-            clipboard,
-            scheduler,
-        };
-        let mut processor = input::Processor::new(context);
+        self.with_processor(event_loop, event_proxy, clipboard, scheduler, |processor| {
+            batch.drain(..).for_each(|event| processor.handle_event(event));
+        });
 
-        for event in self.event_queue.drain(..) {
-            processor.handle_event(event);
+        // Don't treat the search of a newly focused pane as a new search.
+        if self.tabs.active().focused() != old_focused {
+            old_is_searching = self.search_state.history_index.is_some();
         }
 
+        let pane = match self.panes.get_mut(&self.tabs.active().focused()) {
+            Some(pane) => pane,
+            None => return,
+        };
+        let mut terminal = pane.terminal.lock();
+
         // Process DisplayUpdate events.
         if self.display.pending_update.dirty {
+            let old_size = (terminal.columns(), terminal.screen_lines());
             Self::submit_display_update(
                 &mut terminal,
                 &mut self.display,
-                &mut self.notifier,
+                &mut pane.notifier,
+                &mut self.tabs,
                 &self.message_buffer,
                 &mut self.search_state,
                 old_is_searching,
                 &self.config,
             );
             self.dirty = true;
+
+            // Filter the resized grid again.
+            if self.display.filter.filtering()
+                && old_size != (terminal.columns(), terminal.screen_lines())
+            {
+                let timer_id = TimerId::new(Topic::FilterUpdate, self.display.window.id());
+                let event = Event::new(EventType::FilterUpdate, self.display.window.id());
+                scheduler.unschedule(timer_id);
+                scheduler.schedule(event, Duration::ZERO, false, timer_id);
+            }
+
+            drop(terminal);
+            self.resize_inactive_panes();
+            terminal = self.panes[&self.tabs.active().focused()].terminal.lock();
         }
 
         if self.dirty || self.mouse.hint_highlight_dirty {
@@ -490,6 +685,180 @@ impl WindowContext {
         }
     }
 
+    /// Check if the terminal has keyboard focus.
+    #[cfg(unix)]
+    pub fn is_focused(&self) -> bool {
+        self.focused_pane().terminal.lock().is_focused
+    }
+
+    /// Check if this is a drop-down window.
+    #[cfg(unix)]
+    pub fn is_dropdown(&self) -> bool {
+        self.config.window.dropdown()
+    }
+
+    /// Check if input of this window is broadcast to its group.
+    pub fn is_broadcasting(&self) -> bool {
+        self.display.broadcast
+    }
+
+    /// Start or stop broadcasting input to the window's group.
+    pub fn set_broadcasting(&mut self, broadcast: bool) {
+        self.display.broadcast = broadcast;
+        self.dirty = true;
+        if self.display.window.has_frame {
+            self.display.window.request_redraw();
+        }
+    }
+
+    /// Check if another window belongs to the same broadcast group.
+    ///
+    /// Windows without an explicit group are grouped by their class.
+    pub fn same_group(&self, other: &WindowContext) -> bool {
+        match (&self.group, &other.group) {
+            (Some(group), Some(other_group)) => group == other_group,
+            (None, None) => self.display.window.class() == other.display.window.class(),
+            _ => false,
+        }
+    }
+
+    /// Check if a pane has keyboard focus inside this window.
+    pub fn is_focused_pane(&self, id: PaneId) -> bool {
+        self.tabs.active().focused() == id
+    }
+
+    /// Check if a pane is the only pane left in this window.
+    pub fn is_last_pane(&self, id: PaneId) -> bool {
+        self.panes.len() == 1 && self.panes.contains_key(&id)
+    }
+
+    /// Write the terminal's scrollback buffer or selection to a new file.
+    ///
+    /// The content is rendered and written on a separate thread, calling `done` once finished.
+    #[cfg(unix)]
+    pub fn export<F>(&self, format: ExportFormat, selection: bool, path: PathBuf, done: F)
+    where
+        F: FnOnce(&Path, io::Result<()>) + Send + 'static,
+    {
+        let terminal = self.terminal().clone();
+        let colors = self.display.colors;
+        let content = move || export::content(&terminal.lock(), &colors, format, selection);
+        export::spawn_write(path, false, content, done);
+    }
+
+    /// Window state reported through IPC.
+    #[cfg(unix)]
+    pub fn ipc_window(&self) -> IpcWindow {
+        let size = self.display.window.inner_size();
+        let pane = self.focused_pane();
+        let terminal = pane.terminal.lock();
+        IpcWindow {
+            id: self.id().into(),
+            title: self.display.window.title().to_owned(),
+            columns: terminal.columns(),
+            lines: terminal.screen_lines(),
+            width: size.width,
+            height: size.height,
+            cwd: self.cwd(),
+            process: foreground_process_name(pane.master_fd, pane.shell_pid).ok(),
+            focused: terminal.is_focused,
+        }
+    }
+
+    /// Working directory of the foreground process.
+    #[cfg(unix)]
+    pub fn cwd(&self) -> Option<PathBuf> {
+        let pane = self.focused_pane();
+        foreground_process_path(pane.master_fd, pane.shell_pid).ok()
+    }
+
+    /// Title the window shows after a terminal title change.
+    ///
+    /// Passing `None` resets the title. Returns `None` if the window keeps its title.
+    #[cfg(unix)]
+    pub fn dynamic_title(&self, title: Option<&str>) -> Option<String> {
+        let window_config = &self.config.window;
+        match title {
+            Some(title) if !self.preserve_title && window_config.dynamic_title => {
+                Some(title.to_owned())
+            },
+            None if window_config.dynamic_title => Some(window_config.identity.title.clone()),
+            _ => None,
+        }
+    }
+
+    /// Read part of the terminal content.
+    ///
+    /// Scrollback is read in pages of at most [`MAX_TEXT_LINES`] lines, skipping `offset` lines
+    /// at the bottom. Returns `None` if the terminal has no content for the extent.
+    #[cfg(unix)]
+    pub fn text(&self, ipc_text: &IpcGetText) -> Option<IpcText> {
+        let IpcGetText { extent, ansi, lines, offset, .. } = *ipc_text;
+        let terminal = self.focused_pane().terminal.lock();
+        let last_column = terminal.last_column();
+        let total_lines = terminal.total_lines();
+        let mut truncated = false;
+        let range = match extent {
+            TextExtent::Screen => {
+                let top = -(terminal.grid().display_offset() as i32);
+                let start = Point::new(Line(top), Column(0));
+                let end = Point::new(Line(top + terminal.screen_lines() as i32 - 1), last_column);
+                SelectionRange::new(start, end, false)
+            },
+            TextExtent::Scrollback => {
+                let lines = lines.min(MAX_TEXT_LINES).min(total_lines.saturating_sub(offset));
+                if lines == 0 {
+                    return None;
+                }
+                truncated = offset + lines < total_lines;
+
+                let bottom = terminal.bottommost_line() - offset;
+                let start = Point::new(bottom - (lines - 1), Column(0));
+                let end = Point::new(bottom, last_column);
+                SelectionRange::new(start, end, false)
+            },
+            TextExtent::Selection => terminal.selection.as_ref()?.to_range(&terminal)?,
+            TextExtent::LastOutput => {
+                let regex = self.config.terminal.prompt_regex.as_ref();
+                let lines = regex
+                    .and_then(|regex| {
+                        regex.with_compiled(|regex| terminal.last_command_output(Some(regex)))
+                    })
+                    .unwrap_or_else(|| terminal.last_command_output(None))?;
+                let start = Point::new(*lines.start(), Column(0));
+                let end = Point::new(*lines.end(), last_column);
+                SelectionRange::new(start, end, false)
+            },
+        };
+
+        let text = if ansi {
+            export::render(&terminal, &self.display.colors, ExportFormat::Ansi, range)
+        } else if extent == TextExtent::Selection {
+            terminal.selection_to_string()?
+        } else {
+            terminal.bounds_to_string(range.start, range.end)
+        };
+
+        Some(IpcText { text, total_lines, truncated })
+    }
+
+    /// Terminal options for recreating this window in a new session.
+    pub fn session_options(&self) -> TerminalOptions {
+        let mut options = self.terminal_options.clone();
+
+        #[cfg(not(windows))]
+        if let Some(working_directory) = self.pane_cwd() {
+            options.working_directory = Some(working_directory);
+        }
+
+        options
+    }
+
+    /// Access the terminal state.
+    pub fn terminal(&self) -> &Arc<FairMutex<Term<EventProxy>>> {
+        &self.focused_pane().terminal
+    }
+
     /// ID of this terminal context.
     pub fn id(&self) -> WindowId {
         self.display.window.id()
@@ -498,7 +867,7 @@ impl WindowContext {
     /// Write the ref test results to the disk.
     pub fn write_ref_test_results(&self) {
         // Dump grid state.
-        let mut grid = self.terminal.lock().grid().clone();
+        let mut grid = self.focused_pane().terminal.lock().grid().clone();
         grid.initialize_all();
         grid.truncate();
 
@@ -523,11 +892,447 @@ impl WindowContext {
             .expect("write config.json");
     }
 
+    /// Pane with keyboard focus.
+    fn focused_pane(&self) -> &Pane {
+        &self.panes[&self.tabs.active().focused()]
+    }
+
+    /// Working directory of the focused pane's foreground process.
+    #[cfg(not(windows))]
+    fn pane_cwd(&self) -> Option<PathBuf> {
+        let pane = self.focused_pane();
+        foreground_process_path(pane.master_fd, pane.shell_pid).ok()
+    }
+
+    /// Run the input processor for the focused pane.
+    fn with_processor<F, R>(
+        &mut self,
+        event_loop: &EventLoopWindowTarget<Event>,
+        event_proxy: &EventLoopProxy<Event>,
+        clipboard: &mut Clipboard,
+        scheduler: &mut Scheduler,
+        f: F,
+    ) -> R
+    where
+        F: FnOnce(&mut input::Processor<EventProxy, ActionContext<'_, Notifier, EventProxy>>) -> R,
+    {
+        let pane = self.panes.get_mut(&self.tabs.active().focused()).expect("focused pane");
+        let mut terminal = pane.terminal.lock();
+
+        let context = ActionContext {
+            cursor_blink_timed_out: &mut self.cursor_blink_timed_out,
+            message_buffer: &mut self.message_buffer,
+            inline_search_state: &mut self.inline_search_state,
+            search_state: &mut self.search_state,
+            modifiers: &mut self.modifiers,
+            notifier: &mut pane.notifier,
+            display: &mut self.display,
+            mouse: &mut self.mouse,
+            touch: &mut self.touch,
+            dirty: &mut self.dirty,
+            occluded: &mut self.occluded,
+            terminal: &mut terminal,
+            shared_terminal: &pane.terminal,
+            #[cfg(not(windows))]
+            master_fd: pane.master_fd,
+            #[cfg(not(windows))]
+            shell_pid: pane.shell_pid,
+            preserve_title: self.preserve_title,
+            config: &self.config,
+            pane_proxy: &pane.event_proxy,
+            event_proxy,
+            event_loop,
+            clipboard,
+            scheduler,
+        };
+        let mut processor = input::Processor::new(context);
+
+        f(&mut processor)
+    }
+
+    /// Handle terminal events of a pane without keyboard focus.
+    fn handle_pane_event(&mut self, id: PaneId, event: &TerminalEvent) {
+        let pane = match self.panes.get(&id) {
+            Some(pane) => pane,
+            None => return,
+        };
+
+        match event {
+            TerminalEvent::Bell => {
+                self.display.visual_bell.ring();
+                self.dirty = true;
+            },
+            TerminalEvent::ColorRequest(index, format) => {
+                let color = pane.terminal.lock().colors()[*index]
+                    .map(Rgb)
+                    .unwrap_or(self.display.colors[*index]);
+                pane.notifier.notify(format(color.0).into_bytes());
+            },
+            TerminalEvent::TextAreaSizeRequest(format) => {
+                let text = format(pane.size_info.into());
+                pane.notifier.notify(text.into_bytes());
+            },
+            TerminalEvent::PtyWrite(text) => pane.notifier.notify(text.clone().into_bytes()),
+            _ => (),
+        }
+    }
+
+    /// Change the panes of the window.
+    fn pane_action(
+        &mut self,
+        event_loop: &EventLoopWindowTarget<Event>,
+        event_proxy: &EventLoopProxy<Event>,
+        clipboard: &mut Clipboard,
+        scheduler: &mut Scheduler,
+        action: PaneAction,
+    ) {
+        match action {
+            PaneAction::Split(direction) => {
+                if let Err(err) =
+                    self.split(event_loop, event_proxy, clipboard, scheduler, direction)
+                {
+                    error!("Unable to split pane: {err}");
+                }
+            },
+            PaneAction::FocusNext | PaneAction::FocusPrevious => {
+                let id = self.tabs.active().next(action == PaneAction::FocusNext);
+                self.switch_focus(event_loop, event_proxy, clipboard, scheduler, id);
+            },
+            PaneAction::Focus(direction) => {
+                if let Some(id) = self.tabs.active().neighbor(direction) {
+                    self.switch_focus(event_loop, event_proxy, clipboard, scheduler, id);
+                }
+            },
+            PaneAction::Resize(direction) => {
+                self.display.pending_update.dirty |=
+                    self.tabs.active_mut().resize_focused(direction);
+            },
+            PaneAction::ToggleZoom => {
+                self.tabs.active_mut().toggle_zoom();
+                self.display.pending_update.dirty = true;
+            },
+        }
+    }
+
+    /// Split the focused pane, starting a new shell in the new pane.
+    fn split(
+        &mut self,
+        event_loop: &EventLoopWindowTarget<Event>,
+        event_proxy: &EventLoopProxy<Event>,
+        clipboard: &mut Clipboard,
+        scheduler: &mut Scheduler,
+        direction: SplitDirection,
+    ) -> Result<(), Box<dyn Error>> {
+        // Start the new shell with the config and working directory of the focused pane.
+        let mut config_overrides = self.focused_pane().config_overrides.clone();
+        let config = config_overrides.override_config_rc(self.config.clone());
+        let mut pty_config = config.pty_config();
+        self.terminal_options.override_pty_config(&mut pty_config);
+        #[cfg(not(windows))]
+        if let Some(working_directory) = self.pane_cwd() {
+            pty_config.working_directory = Some(working_directory);
+        }
+
+        let is_focused = self.leave_focused_pane(event_loop, event_proxy, clipboard, scheduler);
+
+        let layout = self.tabs.active_mut();
+        let id = layout.split(direction);
+        let window_size = self.display.window_size;
+        let size_info = layout.region(id).map_or(window_size, |region| window_size.pane(region));
+
+        let window_id = self.id();
+        let pane_proxy = EventProxy::new(event_proxy.clone(), window_id, id);
+        match Pane::new(&config, &pty_config, size_info, window_id, pane_proxy, None) {
+            Ok(mut pane) => {
+                pane.config_overrides = config_overrides;
+                self.panes.insert(id, pane);
+                self.enter_focused_pane(event_loop, event_proxy, clipboard, scheduler, is_focused);
+                Ok(())
+            },
+            Err(err) => {
+                self.tabs.active_mut().remove(id);
+                self.enter_focused_pane(event_loop, event_proxy, clipboard, scheduler, is_focused);
+                Err(err)
+            },
+        }
+    }
+
+    /// Close a pane after its shell has exited.
+    ///
+    /// Closing the last pane closes the window.
+    fn close_pane(
+        &mut self,
+        event_loop: &EventLoopWindowTarget<Event>,
+        event_proxy: &EventLoopProxy<Event>,
+        clipboard: &mut Clipboard,
+        scheduler: &mut Scheduler,
+        id: PaneId,
+    ) {
+        if !self.panes.contains_key(&id) {
+            return;
+        }
+
+        if self.is_last_pane(id) {
+            let event = Event::new(TerminalEvent::Exit.into(), self.id());
+            let _ = event_proxy.send_event(event);
+            return;
+        }
+
+        if !self.is_focused_pane(id) {
+            self.tabs.remove_pane(id);
+            self.panes.remove(&id);
+            self.display.pending_update.dirty = true;
+            return;
+        }
+
+        let is_focused = self.leave_focused_pane(event_loop, event_proxy, clipboard, scheduler);
+        self.tabs.remove_pane(id);
+        self.panes.remove(&id);
+        self.enter_focused_pane(event_loop, event_proxy, clipboard, scheduler, is_focused);
+    }
+
+    /// Move keyboard focus to another pane.
+    fn switch_focus(
+        &mut self,
+        event_loop: &EventLoopWindowTarget<Event>,
+        event_proxy: &EventLoopProxy<Event>,
+        clipboard: &mut Clipboard,
+        scheduler: &mut Scheduler,
+        id: PaneId,
+    ) {
+        if self.is_focused_pane(id) || !self.panes.contains_key(&id) {
+            return;
+        }
+
+        let is_focused = self.leave_focused_pane(event_loop, event_proxy, clipboard, scheduler);
+        self.tabs.active_mut().focus(id);
+        self.enter_focused_pane(event_loop, event_proxy, clipboard, scheduler, is_focused);
+    }
+
+    /// Select a tab, moving keyboard focus to its focused pane.
+    fn select_tab(
+        &mut self,
+        event_loop: &EventLoopWindowTarget<Event>,
+        event_proxy: &EventLoopProxy<Event>,
+        clipboard: &mut Clipboard,
+        scheduler: &mut Scheduler,
+        index: usize,
+    ) {
+        if index == self.tabs.active_index() || index >= self.tabs.len() {
+            return;
+        }
+
+        let is_focused = self.leave_focused_pane(event_loop, event_proxy, clipboard, scheduler);
+        self.tabs.select(index);
+        self.enter_focused_pane(event_loop, event_proxy, clipboard, scheduler, is_focused);
+    }
+
+    /// Open a new tab after the selected tab, starting a new shell in it.
+    fn create_tab(
+        &mut self,
+        event_loop: &EventLoopWindowTarget<Event>,
+        event_proxy: &EventLoopProxy<Event>,
+        clipboard: &mut Clipboard,
+        scheduler: &mut Scheduler,
+        options: &WindowOptions,
+    ) -> Result<(), Box<dyn Error>> {
+        let is_focused = self.leave_focused_pane(event_loop, event_proxy, clipboard, scheduler);
+
+        let layout = PaneLayout::default();
+        let id = layout.focused();
+        self.tabs.push(layout);
+
+        // Adding the first tab shows the tab bar, shrinking the existing panes.
+        self.resize_inactive_panes();
+
+        let window_size = self.display.window_size;
+        let size_info =
+            self.tabs.active().region(id).map_or(window_size, |region| window_size.pane(region));
+
+        // Apply the config overrides of the tab to its terminals.
+        let mut config_overrides = options.config_overrides();
+        let config = config_overrides.override_config_rc(self.config.clone());
+
+        let window_id = self.id();
+        let pane_proxy = EventProxy::new(event_proxy.clone(), window_id, id);
+        match Pane::with_options(&config, options, size_info, window_id, pane_proxy) {
+            Ok(mut pane) => {
+                pane.config_overrides = config_overrides;
+                self.panes.insert(id, pane);
+                self.enter_focused_pane(event_loop, event_proxy, clipboard, scheduler, is_focused);
+                Ok(())
+            },
+            Err(err) => {
+                self.tabs.remove(self.tabs.active_index());
+                self.resize_inactive_panes();
+                self.enter_focused_pane(event_loop, event_proxy, clipboard, scheduler, is_focused);
+                Err(err)
+            },
+        }
+    }
+
+    /// Content of the tab bar, if the window has more than one tab.
+    fn tab_bar(&self) -> Option<TabBar> {
+        if self.tabs.len() < 2 {
+            return None;
+        }
+
+        let titles = self
+            .tabs
+            .iter()
+            .map(|layout| self.panes.get(&layout.focused())?.title.clone())
+            .map(|title| title.unwrap_or_else(|| self.config.window.identity.title.clone()))
+            .collect();
+
+        Some(TabBar { titles, active: self.tabs.active_index(), tab_width: self.tabs.tab_width() })
+    }
+
+    /// Update the title of a pane shown in the tab bar.
+    fn set_pane_title(&mut self, id: PaneId, title: Option<String>) {
+        if let Some(pane) = self.panes.get_mut(&id) {
+            pane.title = title;
+            self.dirty |= self.tabs.len() > 1;
+        }
+    }
+
+    /// Remove keyboard focus from the focused pane.
+    ///
+    /// Returns whether the window had focus.
+    fn leave_focused_pane(
+        &mut self,
+        event_loop: &EventLoopWindowTarget<Event>,
+        event_proxy: &EventLoopProxy<Event>,
+        clipboard: &mut Clipboard,
+        scheduler: &mut Scheduler,
+    ) -> bool {
+        let is_focused =
+            self.with_processor(event_loop, event_proxy, clipboard, scheduler, |processor| {
+                processor.leave_pane()
+            });
+
+        let size_info = self.display.size_info;
+        if let Some(pane) = self.panes.get_mut(&self.tabs.active().focused()) {
+            pane.size_info = size_info;
+        }
+
+        self.swap_pane_state();
+
+        is_focused
+    }
+
+    /// Give keyboard focus to the pane focused by the layout.
+    fn enter_focused_pane(
+        &mut self,
+        event_loop: &EventLoopWindowTarget<Event>,
+        event_proxy: &EventLoopProxy<Event>,
+        clipboard: &mut Clipboard,
+        scheduler: &mut Scheduler,
+        is_focused: bool,
+    ) {
+        self.display.size_info = self.focused_pane().size_info;
+        self.display.pending_update.dirty = true;
+
+        self.swap_pane_state();
+        self.display.hint_state.update_alphabet(self.config.hints.alphabet());
+
+        self.with_processor(event_loop, event_proxy, clipboard, scheduler, |processor| {
+            processor.enter_pane(is_focused)
+        });
+    }
+
+    /// Exchange the search, filter and hint state of the window with the focused pane's.
+    ///
+    /// This moves the state into the pane when it loses keyboard focus, and back into the
+    /// window once it's focused again.
+    fn swap_pane_state(&mut self) {
+        let pane = match self.panes.get_mut(&self.tabs.active().focused()) {
+            Some(pane) => pane,
+            None => return,
+        };
+
+        let state = &mut pane.state;
+        mem::swap(&mut self.search_state, &mut state.search_state);
+        mem::swap(&mut self.inline_search_state, &mut state.inline_search_state);
+        mem::swap(&mut self.display.filter, &mut state.filter);
+        mem::swap(&mut self.display.hint_state, &mut state.hint_state);
+    }
+
+    /// Ask for confirmation before closing a window with more than one pane.
+    ///
+    /// Returns `true` if the window can be closed.
+    fn confirm_close(&mut self) -> bool {
+        if self.panes.len() < 2 {
+            return true;
+        }
+
+        let tabs = self.tabs.len();
+        let text = if tabs > 1 {
+            format!("Close the window with its {tabs} tabs? Close again to confirm")
+        } else {
+            format!("Close the window with its {} panes? Close again to confirm", self.panes.len())
+        };
+        let mut message = Message::new(text, MessageType::Warning);
+        message.set_target(CLOSE_WINDOW_TARGET.into());
+
+        let confirmed = self.message_buffer.is_queued(&message);
+        self.message_buffer.remove_target(CLOSE_WINDOW_TARGET);
+        if !confirmed {
+            self.message_buffer.push(message);
+        }
+        self.display.pending_update.dirty = true;
+
+        confirmed
+    }
+
+    /// Close every pane except the focused one, one tab at a time.
+    fn close_inactive_panes(&mut self) {
+        // Close all other tabs with their panes.
+        while self.tabs.len() > 1 {
+            let index = if self.tabs.active_index() == 0 { 1 } else { 0 };
+            let layout = self.tabs.iter().nth(index).expect("inactive tab");
+            for id in layout.panes() {
+                self.panes.remove(&id);
+            }
+            self.tabs.remove(index);
+        }
+
+        // Close the other splits of the remaining tab.
+        let focused = self.tabs.active().focused();
+        for id in self.tabs.active().panes() {
+            if id != focused {
+                self.tabs.remove_pane(id);
+                self.panes.remove(&id);
+            }
+        }
+    }
+
+    /// Resize the terminals of all visible panes without keyboard focus, in every tab.
+    fn resize_inactive_panes(&mut self) {
+        let focused = self.tabs.active().focused();
+        for (id, region) in self.tabs.iter().flat_map(|layout| layout.visible()) {
+            let pane = match self.panes.get_mut(id) {
+                Some(pane) if *id != focused => pane,
+                _ => continue,
+            };
+
+            let size_info = self.display.window_size.pane(*region);
+            if size_info.screen_lines() != pane.size_info.screen_lines()
+                || size_info.columns() != pane.size_info.columns()
+            {
+                pane.notifier.on_resize(size_info.into());
+                pane.terminal.lock().resize(size_info);
+            }
+            pane.size_info = size_info;
+        }
+    }
+
     /// Submit the pending changes to the `Display`.
+    #[allow(clippy::too_many_arguments)]
     fn submit_display_update(
         terminal: &mut Term<EventProxy>,
         display: &mut Display,
         notifier: &mut Notifier,
+        tabs: &mut Tabs,
         message_buffer: &MessageBuffer,
         search_state: &mut SearchState,
         old_is_searching: bool,
@@ -542,7 +1347,7 @@ impl WindowContext {
             search_state.direction == Direction::Left
         };
 
-        display.handle_update(terminal, notifier, message_buffer, search_state, config);
+        display.handle_update(terminal, notifier, tabs, message_buffer, search_state, config);
 
         let new_is_searching = search_state.history_index.is_some();
         if !old_is_searching && new_is_searching {
@@ -556,10 +1361,3 @@ impl WindowContext {
         }
     }
 }
-
-impl Drop for WindowContext {
-    fn drop(&mut self) {
-        // Shutdown the terminal's PTY.
-        let _ = self.notifier.0.send(Msg::Shutdown);
-    }
-}
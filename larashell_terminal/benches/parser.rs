@@ -0,0 +1,87 @@
+//! Throughput comparison between the `vte` escape sequence processor and LaraShell's extended one.
+//!
+//! Run with `cargo bench -p larashell_terminal --bench parser`.
+
+use std::time::{Duration, Instant};
+
+use larashell_terminal::event::VoidListener;
+use larashell_terminal::parser::Processor;
+use larashell_terminal::term::test::TermSize;
+use larashell_terminal::term::{Config, Term};
+use larashell_terminal::vte::ansi;
+
+/// Number of lines written to the terminal.
+const LINES: usize = 200_000;
+
+/// Number of runs, keeping the fastest one.
+const RUNS: usize = 5;
+
+fn main() {
+    let inputs = [("plain", plain_output()), ("escapes", escape_output())];
+    for (name, input) in &inputs {
+        let vte = fastest(|term| {
+            let mut parser: ansi::Processor = ansi::Processor::new();
+            for byte in input {
+                parser.advance(term, *byte);
+            }
+        });
+
+        let extended = fastest(|term| {
+            let mut parser: Processor = Processor::new();
+            for byte in input {
+                parser.advance(term, *byte);
+            }
+        });
+
+        println!(
+            "{name:>8}: vte {:>8.1} MiB/s, extended {:>8.1} MiB/s",
+            mib_per_second(input.len(), vte),
+            mib_per_second(input.len(), extended),
+        );
+    }
+}
+
+/// Fastest of multiple runs writing into a new terminal.
+fn fastest<F: FnMut(&mut Term<VoidListener>)>(mut write: F) -> Duration {
+    (0..RUNS)
+        .map(|_| {
+            let size = TermSize::new(120, 50);
+            let mut term = Term::new(Config::default(), &size, VoidListener);
+
+            let start = Instant::now();
+            write(&mut term);
+            start.elapsed()
+        })
+        .min()
+        .unwrap_or_default()
+}
+
+/// Generate text without escape sequences.
+fn plain_output() -> Vec<u8> {
+    let mut input = Vec::new();
+    for i in 0..LINES {
+        let line = format!("2024-01-01T00:00:{:02}Z worker {i}: processed request\r\n", i % 60);
+        input.extend_from_slice(line.as_bytes());
+    }
+    input
+}
+
+/// Generate output dense with SGR, cursor movement and OSC sequences, like full screen programs.
+fn escape_output() -> Vec<u8> {
+    let mut input = Vec::new();
+    for i in 0..LINES {
+        let line = format!(
+            "\x1b[{};1H\x1b[38;2;{};80;160m\x1b[1m{i:>6}\x1b[0m \x1b[48;5;{}m status \x1b[49m\x1b[K\
+             \x1b]8;;https://example.com/{i}\x1b\\link\x1b]8;;\x1b\\\r\n",
+            i % 50 + 1,
+            i % 256,
+            i % 256,
+        );
+        input.extend_from_slice(line.as_bytes());
+    }
+    input
+}
+
+fn mib_per_second(bytes: usize, duration: Duration) -> f64 {
+    bytes as f64 / 1024. / 1024. / duration.as_secs_f64().max(f64::EPSILON)
+}
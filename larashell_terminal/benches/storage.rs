@@ -0,0 +1,75 @@
+//! Throughput comparison between uncompressed, compressed and spilled scrollback history.
+//!
+//! Run with `cargo bench -p larashell_terminal --bench storage`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use larashell_terminal::event::VoidListener;
+use larashell_terminal::grid::Dimensions;
+use larashell_terminal::index::{Column, Line};
+use larashell_terminal::parser::Processor;
+use larashell_terminal::term::test::TermSize;
+use larashell_terminal::term::{Config, Term};
+
+/// Number of lines written to the terminal.
+const LINES: usize = 200_000;
+
+fn main() {
+    let input = log_output();
+
+    let uncompressed = Config { scrolling_history: 100_000, ..Config::default() };
+    let compressed =
+        Config { scrolling_history: 1_000, scrolling_history_memory: 1 << 30, ..Config::default() };
+
+    let spilled = Config {
+        scrolling_history: 1_000,
+        scrolling_history_memory: 1 << 20,
+        scrolling_history_spill: true,
+        ..Config::default()
+    };
+
+    let configs =
+        [("uncompressed", uncompressed), ("compressed", compressed), ("spilled", spilled)];
+    for (name, config) in configs {
+        let size = TermSize::new(120, 50);
+        let mut term = Term::new(config, &size, VoidListener);
+        let mut parser: Processor = Processor::new();
+
+        let start = Instant::now();
+        for byte in &input {
+            parser.advance(&mut term, *byte);
+        }
+        let write = start.elapsed();
+
+        let grid = term.grid();
+        let start = Instant::now();
+        for line in (grid.topmost_line().0..=grid.bottommost_line().0).map(Line) {
+            black_box(grid[line][Column(0)].c);
+        }
+        let read = start.elapsed();
+
+        println!(
+            "{name:>12}: write {:>8.1} MiB/s, read {:>10.0} lines/s, {} lines retained",
+            input.len() as f64 / 1024. / 1024. / seconds(write),
+            grid.total_lines() as f64 / seconds(read),
+            grid.total_lines(),
+        );
+    }
+}
+
+/// Generate colored log output.
+fn log_output() -> Vec<u8> {
+    let mut input = Vec::new();
+    for i in 0..LINES {
+        let level = ["\x1b[32mINFO\x1b[0m", "\x1b[33mWARN\x1b[0m", "\x1b[31mERROR\x1b[0m"][i % 3];
+        let line =
+            format!("2024-01-01T00:00:{:02}Z {level} worker {i}: processed request\r\n", i % 60);
+        input.extend_from_slice(line.as_bytes());
+    }
+    input
+}
+
+fn seconds(duration: Duration) -> f64 {
+    duration.as_secs_f64().max(f64::EPSILON)
+}
@@ -2,6 +2,9 @@ use std::borrow::Cow;
 use std::fmt::{self, Debug, Formatter};
 use std::sync::Arc;
 
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
 use crate::term::ClipboardType;
 use crate::vte::ansi::Rgb;
 
@@ -55,6 +58,9 @@ pub enum Event {
 
     /// Child process exited with an error code.
     ChildExit(i32),
+
+    /// Shell command finished, with its exit code if the shell reported it.
+    CommandFinished(Option<i32>),
 }
 
 impl Debug for Event {
@@ -73,6 +79,7 @@ impl Debug for Event {
             Event::Bell => write!(f, "Bell"),
             Event::Exit => write!(f, "Exit"),
             Event::ChildExit(code) => write!(f, "ChildExit({code})"),
+            Event::CommandFinished(code) => write!(f, "CommandFinished({code:?})"),
         }
     }
 }
@@ -85,6 +92,7 @@ pub trait Notify {
     fn notify<B: Into<Cow<'static, [u8]>>>(&self, _: B);
 }
 
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Copy, Clone, Debug)]
 pub struct WindowSize {
     pub num_lines: u16,
@@ -15,9 +15,9 @@ use log::error;
 use polling::{Event as PollingEvent, Events, PollMode};
 
 use crate::event::{self, Event, EventListener, WindowSize};
+use crate::parser::Processor;
 use crate::sync::FairMutex;
 use crate::term::Term;
-use crate::vte::ansi;
 use crate::{thread, tty};
 
 /// Max bytes to read from the PTY before forced terminal synchronization.
@@ -52,6 +52,7 @@ pub struct EventLoop<T: tty::EventedPty, U: EventListener> {
     event_proxy: U,
     hold: bool,
     ref_test: bool,
+    recorder: Option<Box<dyn Write + Send>>,
 }
 
 impl<T, U> EventLoop<T, U>
@@ -78,9 +79,18 @@ where
             event_proxy,
             hold,
             ref_test,
+            recorder: None,
         })
     }
 
+    /// Copy all bytes read from the PTY to a writer.
+    ///
+    /// The bytes are written right before they are parsed, while the terminal is locked. This
+    /// replaces the recording of ref tests.
+    pub fn set_recorder(&mut self, recorder: Box<dyn Write + Send>) {
+        self.recorder = Some(recorder);
+    }
+
     pub fn channel(&self) -> EventLoopSender {
         EventLoopSender { sender: self.tx.clone(), poller: self.poll.clone() }
     }
@@ -220,11 +230,12 @@ where
 
             let mut events = Events::with_capacity(NonZeroUsize::new(1024).unwrap());
 
-            let mut pipe = if self.ref_test {
-                Some(File::create("./larashell.recording").expect("create larashell recording"))
-            } else {
-                None
-            };
+            let mut pipe = self.recorder.take().or_else(|| {
+                self.ref_test.then(|| {
+                    let file = File::create("./larashell.recording");
+                    Box::new(file.expect("create larashell recording")) as Box<dyn Write + Send>
+                })
+            });
 
             'event_loop: loop {
                 // Wakeup the event loop when a synchronized update timeout was reached.
@@ -405,7 +416,7 @@ impl EventLoopSender {
 pub struct State {
     write_list: VecDeque<Cow<'static, [u8]>>,
     writing: Option<Writing>,
-    parser: ansi::Processor,
+    parser: Processor,
 }
 
 impl State {
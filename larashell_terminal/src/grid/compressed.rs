@@ -0,0 +1,920 @@
+//! Compact storage for rows which are rarely accessed.
+
+use std::borrow::Cow;
+use std::collections::VecDeque;
+#[cfg(feature = "serde")]
+use std::fmt;
+#[cfg(feature = "serde")]
+use std::marker::PhantomData;
+use std::mem;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+use log::error;
+#[cfg(feature = "serde")]
+use serde::de::{SeqAccess, Visitor};
+#[cfg(feature = "serde")]
+use serde::ser::SerializeSeq;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::grid::row::{LineAttribute, LineMark, PromptMark, Row};
+use crate::grid::spill::{self, SpillCell, SpillFile};
+use crate::grid::GridCell;
+use crate::index::Column;
+use crate::term::cell::Flags;
+
+/// Run-length encoded row with interned cell attributes.
+///
+/// The characters of every cell are stored as UTF-8 text, while all remaining cell attributes
+/// are deduplicated within the row and referenced by runs of consecutive cells. Trailing default
+/// cells are not stored at all.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CompressedRow<T> {
+    /// Characters of all stored cells.
+    text: Box<str>,
+
+    /// Number of consecutive cells sharing the same attributes, with the attributes' index.
+    runs: Box<[(u32, u32)]>,
+
+    /// Distinct cell attributes of this row.
+    attributes: Box<[T]>,
+
+    /// Total number of cells in the row.
+    columns: usize,
+
+    /// DECDWL/DECDHL attribute of the line.
+    line_attribute: LineAttribute,
+
+    /// Bookmark of the line.
+    mark: Option<LineMark>,
+
+    /// Shell integration mark of the line.
+    prompt_mark: Option<PromptMark>,
+}
+
+impl<T: GridCell + Clone + Default + PartialEq> CompressedRow<T> {
+    /// Compress a row.
+    pub fn new(row: &Row<T>) -> Self {
+        let default = T::default();
+        let len = row.len() - row[..].iter().rev().take_while(|cell| **cell == default).count();
+
+        let mut text = String::with_capacity(len);
+        let mut runs: Vec<(u32, u32)> = Vec::new();
+        let mut attributes: Vec<T> = Vec::new();
+
+        for cell in &row[..Column(len)] {
+            let (c, cell_attributes) = cell.split();
+            text.push(c);
+
+            let index = match attributes.iter().position(|attrs| *attrs == cell_attributes) {
+                Some(index) => index as u32,
+                None => {
+                    attributes.push(cell_attributes);
+                    attributes.len() as u32 - 1
+                },
+            };
+
+            match runs.last_mut() {
+                Some((count, last_index)) if *last_index == index => *count += 1,
+                _ => runs.push((1, index)),
+            }
+        }
+
+        Self {
+            text: text.into_boxed_str(),
+            runs: runs.into_boxed_slice(),
+            attributes: attributes.into_boxed_slice(),
+            columns: row.len(),
+            line_attribute: row.line_attribute(),
+            mark: row.mark(),
+            prompt_mark: row.prompt_mark(),
+        }
+    }
+
+    /// Restore the original row.
+    pub fn inflate(&self) -> Row<T> {
+        let mut cells = Vec::with_capacity(self.columns);
+
+        let mut chars = self.text.chars();
+        for &(count, index) in self.runs.iter() {
+            let attributes = &self.attributes[index as usize];
+            for c in chars.by_ref().take(count as usize) {
+                cells.push(T::join(c, attributes));
+            }
+        }
+
+        cells.resize_with(self.columns, T::default);
+
+        let mut row = Row::from_vec(cells, self.columns);
+        row.set_line_attribute(self.line_attribute);
+        row.set_mark(self.mark);
+        row.set_prompt_mark(self.prompt_mark);
+        row
+    }
+}
+
+impl<T: SpillCell> CompressedRow<T> {
+    /// Append the binary representation of the row to `buf`.
+    pub fn encode(&self, buf: &mut Vec<u8>) {
+        spill::write_u32(buf, self.columns as u32);
+        buf.push(self.line_attribute as u8);
+
+        // Marks are stored with an offset, to leave room for `None` and anonymous marks.
+        let mark = match self.mark {
+            None => 0,
+            Some(LineMark::Anonymous) => 1,
+            Some(LineMark::Named(c)) => c as u32 + 2,
+        };
+        spill::write_u32(buf, mark);
+
+        buf.push(match self.prompt_mark {
+            None => 0,
+            Some(PromptMark::Prompt) => 1,
+            Some(PromptMark::Output) => 2,
+        });
+
+        spill::write_u32(buf, self.text.len() as u32);
+        buf.extend_from_slice(self.text.as_bytes());
+
+        spill::write_u32(buf, self.runs.len() as u32);
+        for &(count, index) in self.runs.iter() {
+            spill::write_u32(buf, count);
+            spill::write_u32(buf, index);
+        }
+
+        spill::write_u32(buf, self.attributes.len() as u32);
+        for attributes in self.attributes.iter() {
+            attributes.encode(buf);
+        }
+    }
+
+    /// Read a row previously written by [`CompressedRow::encode`].
+    pub fn decode(mut bytes: &[u8]) -> Option<Self> {
+        let bytes = &mut bytes;
+
+        let columns = spill::read_u32(bytes)? as usize;
+        let line_attribute = match spill::read_u8(bytes)? {
+            0 => LineAttribute::Normal,
+            1 => LineAttribute::DoubleWidth,
+            2 => LineAttribute::DoubleHeightTop,
+            3 => LineAttribute::DoubleHeightBottom,
+            _ => return None,
+        };
+        let mark = decode_mark(spill::read_u32(bytes)?)?;
+        let prompt_mark = decode_prompt_mark(spill::read_u8(bytes)?)?;
+
+        let text_len = spill::read_u32(bytes)? as usize;
+        let text = std::str::from_utf8(spill::read_bytes(bytes, text_len)?).ok()?;
+
+        let runs_len = spill::read_u32(bytes)? as usize;
+        let mut runs = Vec::with_capacity(runs_len.min(columns));
+        for _ in 0..runs_len {
+            runs.push((spill::read_u32(bytes)?, spill::read_u32(bytes)?));
+        }
+
+        let attributes_len = spill::read_u32(bytes)? as usize;
+        let mut attributes = Vec::with_capacity(attributes_len.min(columns));
+        for _ in 0..attributes_len {
+            attributes.push(T::decode(bytes)?);
+        }
+
+        // Reject rows which would index out of bounds during inflation.
+        if runs.iter().any(|&(_, index)| index as usize >= attributes.len()) {
+            return None;
+        }
+
+        Some(Self {
+            text: text.into(),
+            runs: runs.into_boxed_slice(),
+            attributes: attributes.into_boxed_slice(),
+            columns,
+            line_attribute,
+            mark,
+            prompt_mark,
+        })
+    }
+}
+
+/// Read the mark of a row previously written by [`CompressedRow::encode`], without decoding it.
+pub fn encoded_mark(mut bytes: &[u8]) -> Option<LineMark> {
+    // Skip the number of columns and the line attribute.
+    let bytes = &mut bytes;
+    spill::read_bytes(bytes, 5)?;
+    decode_mark(spill::read_u32(bytes)?)?
+}
+
+/// Read the shell integration mark of a row previously written by [`CompressedRow::encode`],
+/// without decoding it.
+pub fn encoded_prompt_mark(mut bytes: &[u8]) -> Option<PromptMark> {
+    // Skip the number of columns, the line attribute and the mark.
+    let bytes = &mut bytes;
+    spill::read_bytes(bytes, 9)?;
+    decode_prompt_mark(spill::read_u8(bytes)?)?
+}
+
+/// Convert an encoded mark back to the mark, returning `None` if it is invalid.
+fn decode_mark(mark: u32) -> Option<Option<LineMark>> {
+    match mark {
+        0 => Some(None),
+        1 => Some(Some(LineMark::Anonymous)),
+        c => Some(Some(LineMark::Named(char::from_u32(c - 2)?))),
+    }
+}
+
+/// Convert an encoded shell integration mark back to the mark, returning `None` if it is
+/// invalid.
+fn decode_prompt_mark(prompt_mark: u8) -> Option<Option<PromptMark>> {
+    match prompt_mark {
+        0 => Some(None),
+        1 => Some(Some(PromptMark::Prompt)),
+        2 => Some(Some(PromptMark::Output)),
+        _ => None,
+    }
+}
+
+impl<T> CompressedRow<T> {
+    /// Approximate number of bytes allocated by this row.
+    pub fn heap_size(&self) -> usize {
+        self.text.len()
+            + self.runs.len() * mem::size_of::<(u32, u32)>()
+            + self.attributes.len() * mem::size_of::<T>()
+    }
+}
+
+/// Maximum number of rows which are kept inflated before they're compressed again.
+const MAX_INFLATED_ROWS: usize = 1_000;
+
+/// Compressed row inside the [`CompressedHistory`].
+#[derive(Clone, Debug)]
+struct CompressedEntry<T> {
+    /// Compressed row, `None` if the row has been modified since it was compressed.
+    compressed: Option<CompressedRow<T>>,
+
+    /// Inflated row, lazily populated on access.
+    row: OnceLock<Row<T>>,
+
+    /// Function for inflating the compressed row.
+    ///
+    /// This allows accessing the row without placing any bounds on `T`.
+    inflate: fn(&CompressedRow<T>) -> Row<T>,
+
+    /// Approximate number of bytes used by the inflated row.
+    row_size: usize,
+}
+
+impl<T> CompressedEntry<T> {
+    /// Approximate number of bytes used by this entry, ignoring the inflated row.
+    fn size(&self) -> usize {
+        mem::size_of::<Self>() + self.compressed.as_ref().map_or(0, CompressedRow::heap_size)
+    }
+}
+
+/// Scrollback history stored in compressed form.
+///
+/// Rows are inflated on access and compressed again once too many rows have been inflated. The
+/// oldest rows are removed once the total size of compressed and inflated rows exceeds the
+/// configured memory limit, or moved to a [`SpillFile`] if one is present.
+#[derive(Debug)]
+pub struct CompressedHistory<T> {
+    /// Compressed rows, starting with the most recent one.
+    rows: VecDeque<CompressedEntry<T>>,
+
+    /// Approximate number of bytes used by all compressed rows.
+    size: usize,
+
+    /// Maximum number of bytes used by compressed rows.
+    max_size: usize,
+
+    /// Ids of all rows inflated since inflated rows were last released.
+    ///
+    /// Ids are assigned in order, starting with the oldest row, so they remain valid while rows
+    /// are added or removed.
+    inflated: Mutex<Vec<usize>>,
+
+    /// Approximate number of bytes used by all inflated rows.
+    inflated_size: AtomicUsize,
+
+    /// Id of the next row added to the history.
+    next_id: usize,
+
+    /// File storing rows which exceed the memory limit.
+    spill: Option<SpillFile<T>>,
+}
+
+impl<T> CompressedHistory<T> {
+    /// Number of rows in the compressed history, including rows stored on disk.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.rows.len() + self.spilled_len()
+    }
+
+    /// Number of rows stored on disk.
+    #[inline]
+    pub fn spilled_len(&self) -> usize {
+        self.spill.as_ref().map_or(0, SpillFile::len)
+    }
+
+    /// Whether rows exceeding the memory limit are stored on disk.
+    #[inline]
+    pub fn has_spill(&self) -> bool {
+        self.spill.is_some()
+    }
+
+    /// Set the file for storing rows exceeding the memory limit.
+    ///
+    /// All rows stored in the previous file are removed.
+    pub fn set_spill(&mut self, spill: Option<SpillFile<T>>) {
+        self.spill = spill;
+    }
+
+    /// Maximum number of bytes used by compressed rows.
+    #[inline]
+    pub fn max_size(&self) -> usize {
+        self.max_size
+    }
+
+    /// Update the memory limit, removing the oldest rows if necessary.
+    pub fn set_max_size(&mut self, max_size: usize) {
+        self.max_size = max_size;
+        self.evict();
+    }
+
+    /// Access a row, inflating it if necessary.
+    #[inline]
+    pub fn get(&self, index: usize) -> Option<&Row<T>> {
+        let entry = match self.rows.get(index) {
+            Some(entry) => entry,
+            None => return self.spill.as_ref()?.get(index - self.rows.len()),
+        };
+
+        let row = match entry.row.get() {
+            Some(row) => row,
+            None => {
+                let mut inflated = false;
+                let row = entry.row.get_or_init(|| {
+                    inflated = true;
+                    (entry.inflate)(entry.compressed.as_ref().unwrap())
+                });
+
+                if inflated {
+                    self.inflated_size.fetch_add(entry.row_size, Ordering::Relaxed);
+                    self.inflated.lock().unwrap().push(self.next_id - 1 - index);
+                }
+
+                row
+            },
+        };
+
+        Some(row)
+    }
+
+    /// Index of the row with the specified id, if it is still part of the history.
+    fn index(&self, id: usize) -> Option<usize> {
+        let index = self.next_id.checked_sub(id + 1)?;
+        (index < self.rows.len()).then_some(index)
+    }
+
+    /// Read a row without keeping it inflated.
+    ///
+    /// This avoids inflating the entire history when scanning through it.
+    pub fn row(&self, index: usize) -> Option<Cow<'_, Row<T>>>
+    where
+        T: Clone,
+    {
+        let entry = match self.rows.get(index) {
+            Some(entry) => entry,
+            None => return self.spill.as_ref()?.row(index - self.rows.len()),
+        };
+
+        match entry.row.get() {
+            Some(row) => Some(Cow::Borrowed(row)),
+            None => Some(Cow::Owned((entry.inflate)(entry.compressed.as_ref().unwrap()))),
+        }
+    }
+
+    /// Bookmark of a row, without inflating it.
+    pub fn mark(&self, index: usize) -> Option<LineMark> {
+        let entry = match self.rows.get(index) {
+            Some(entry) => entry,
+            None => return self.spill.as_ref()?.mark(index - self.rows.len()),
+        };
+
+        match (entry.row.get(), &entry.compressed) {
+            (Some(row), _) => row.mark(),
+            (None, Some(compressed)) => compressed.mark,
+            (None, None) => unreachable!(),
+        }
+    }
+
+    /// Shell integration mark of a row, without inflating it.
+    pub fn prompt_mark(&self, index: usize) -> Option<PromptMark> {
+        let entry = match self.rows.get(index) {
+            Some(entry) => entry,
+            None => return self.spill.as_ref()?.prompt_mark(index - self.rows.len()),
+        };
+
+        match (entry.row.get(), &entry.compressed) {
+            (Some(row), _) => row.prompt_mark(),
+            (None, Some(compressed)) => compressed.prompt_mark,
+            (None, None) => unreachable!(),
+        }
+    }
+
+    /// Set the bookmark of a row, without inflating it.
+    pub fn set_mark(&mut self, index: usize, mark: Option<LineMark>) {
+        let entry = match self.rows.get_mut(index) {
+            Some(entry) => entry,
+            None => {
+                let index = index - self.rows.len();
+                if let Some(row) = self.spill.as_mut().and_then(|spill| spill.get_mut(index)) {
+                    row.set_mark(mark);
+                }
+                return;
+            },
+        };
+
+        if let Some(row) = entry.row.get_mut() {
+            row.set_mark(mark);
+        }
+        if let Some(compressed) = &mut entry.compressed {
+            compressed.mark = mark;
+        }
+    }
+
+    /// Mutably access a row, inflating it if necessary.
+    ///
+    /// The row is compressed again once inflated rows are released.
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut Row<T>> {
+        if index >= self.rows.len() {
+            return self.spill.as_mut()?.get_mut(index - self.rows.len());
+        }
+
+        self.get(index);
+
+        let entry = &mut self.rows[index];
+        if let Some(compressed) = entry.compressed.take() {
+            self.size -= compressed.heap_size();
+        }
+
+        entry.row.get_mut()
+    }
+
+    /// Remove the most recent row.
+    pub fn pop(&mut self) -> Option<Row<T>> {
+        let mut entry = match self.rows.pop_front() {
+            Some(entry) => entry,
+            None => return self.spill.as_mut()?.pop(),
+        };
+        self.remove(&entry);
+        self.next_id -= 1;
+
+        match entry.row.take() {
+            Some(row) => Some(row),
+            None => Some((entry.inflate)(entry.compressed.as_ref().unwrap())),
+        }
+    }
+
+    /// Remove all rows.
+    pub fn clear(&mut self) {
+        self.rows.clear();
+        self.size = 0;
+        self.inflated.get_mut().unwrap().clear();
+        *self.inflated_size.get_mut() = 0;
+        self.next_id = 0;
+
+        if let Some(spill) = &mut self.spill {
+            spill.clear();
+        }
+    }
+
+    /// Update the memory usage for a removed row.
+    fn remove(&mut self, entry: &CompressedEntry<T>) {
+        self.size -= entry.size();
+        if entry.row.get().is_some() {
+            *self.inflated_size.get_mut() -= entry.row_size;
+        }
+    }
+
+    /// Remove the oldest rows until the memory limit is satisfied.
+    fn evict(&mut self) {
+        while self.size + *self.inflated_size.get_mut() > self.max_size {
+            let mut entry = self.rows.pop_back().unwrap();
+            self.remove(&entry);
+
+            if let Some(spill) = &mut self.spill {
+                match (&entry.compressed, entry.row.take()) {
+                    (Some(compressed), _) => spill.push_compressed(compressed),
+                    (None, Some(row)) => spill.push(&row),
+                    (None, None) => unreachable!(),
+                }
+            }
+        }
+    }
+}
+
+impl<T: GridCell + Clone + Default + PartialEq> CompressedHistory<T> {
+    /// Add a new most recent row.
+    pub fn push(&mut self, row: &Row<T>) {
+        if self.max_size == 0 && self.spill.is_none() {
+            return;
+        }
+
+        let entry = CompressedEntry {
+            compressed: Some(CompressedRow::new(row)),
+            row: OnceLock::new(),
+            inflate: CompressedRow::inflate,
+            row_size: mem::size_of::<Row<T>>() + row.len() * mem::size_of::<T>(),
+        };
+        self.size += entry.size();
+        self.rows.push_front(entry);
+        self.next_id += 1;
+
+        self.evict();
+    }
+
+    /// Compress all inflated rows again, if too many rows have been inflated.
+    ///
+    /// Since inflated rows count towards the memory limit, the oldest rows are removed if the
+    /// limit has been exceeded by inflating rows.
+    pub fn release_inflated(&mut self) {
+        if let Some(spill) = &mut self.spill {
+            spill.release_inflated();
+        }
+
+        if self.inflated.get_mut().unwrap().len() < MAX_INFLATED_ROWS {
+            self.evict();
+            return;
+        }
+
+        for id in mem::take(self.inflated.get_mut().unwrap()) {
+            let entry = match self.index(id) {
+                Some(index) => &mut self.rows[index],
+                None => continue,
+            };
+
+            let row = match entry.row.take() {
+                Some(row) => row,
+                None => continue,
+            };
+            *self.inflated_size.get_mut() -= entry.row_size;
+
+            if entry.compressed.is_none() {
+                let compressed = CompressedRow::new(&row);
+                self.size += compressed.heap_size();
+                entry.compressed = Some(compressed);
+            }
+        }
+
+        self.evict();
+    }
+
+    /// Resize all rows to `columns`, reflowing wrapped lines if `reflow` is enabled.
+    ///
+    /// The history is rebuilt one logical line at a time, so it is never inflated all at once.
+    pub fn resize(&mut self, reflow: bool, columns: usize) {
+        let spill = self.spill.take();
+        let new_spill = spill.as_ref().and_then(|spill| match spill.empty_copy(columns) {
+            Ok(spill) => Some(spill),
+            Err(err) => {
+                error!("Unable to create scrollback file: {err}");
+                None
+            },
+        });
+
+        let mut old = mem::replace(self, Self { max_size: self.max_size, ..Self::default() });
+        self.spill = new_spill;
+
+        let mut reflower = Reflow::new(reflow, columns);
+        let mut output = |row: Row<T>| self.push(&row);
+
+        // Start with the oldest rows, since every row is added as the most recent one.
+        if let Some(spill) = &spill {
+            for index in (0..spill.len()).rev() {
+                if let Some(row) = spill.row(index) {
+                    reflower.push(row.into_owned(), &mut output);
+                }
+            }
+        }
+        drop(spill);
+
+        while let Some(mut entry) = old.rows.pop_back() {
+            let row = match entry.row.take() {
+                Some(row) => row,
+                None => (entry.inflate)(entry.compressed.as_ref().unwrap()),
+            };
+            reflower.push(row, &mut output);
+        }
+
+        reflower.finish(&mut output);
+    }
+}
+
+/// Reflow of rows to a new number of columns, one logical line at a time.
+struct Reflow<T> {
+    /// Whether wrapped lines should be reflowed, instead of truncating or extending each row.
+    reflow: bool,
+
+    /// Target number of columns.
+    columns: usize,
+
+    /// Cells of the logical line which is currently being reflowed.
+    cells: Vec<T>,
+
+    /// Whether the current logical line has been continued by a wrapped row.
+    pending: bool,
+
+    /// Bookmark of the current logical line.
+    mark: Option<LineMark>,
+
+    /// Shell integration mark of the current logical line.
+    prompt_mark: Option<PromptMark>,
+}
+
+impl<T: GridCell + Clone + Default> Reflow<T> {
+    fn new(reflow: bool, columns: usize) -> Self {
+        Self { reflow, columns, cells: Vec::new(), pending: false, mark: None, prompt_mark: None }
+    }
+
+    /// Add the next row, passing all reflowed rows to `output`.
+    fn push(&mut self, mut row: Row<T>, output: &mut impl FnMut(Row<T>)) {
+        // Double width lines are truncated instead of reflowed, like in the rest of the grid.
+        if !self.reflow || row.line_attribute().is_double_width() {
+            if self.pending {
+                self.flush(true, output);
+            }
+
+            if row.len() < self.columns {
+                row.grow(self.columns);
+            } else {
+                row.shrink(self.columns);
+            }
+            output(row);
+
+            return;
+        }
+
+        // Keep the marks of all rows merged into the logical line.
+        if !self.pending {
+            self.mark = None;
+            self.prompt_mark = None;
+        }
+        self.mark = self.mark.or(row.mark());
+        self.prompt_mark = self.prompt_mark.or(row.prompt_mark());
+
+        let mut cells = row.front_split_off(row.len());
+        let wrapped = cells.last().is_some_and(|cell| cell.flags().contains(Flags::WRAPLINE));
+
+        if wrapped {
+            if let Some(cell) = cells.last_mut() {
+                cell.flags_mut().remove(Flags::WRAPLINE);
+            }
+
+            // Remove leading spacers, since wide chars are wrapped again if necessary.
+            if cells
+                .last()
+                .is_some_and(|cell| cell.flags().contains(Flags::LEADING_WIDE_CHAR_SPACER))
+            {
+                cells.pop();
+            }
+
+            self.cells.append(&mut cells);
+            self.pending = true;
+        } else {
+            let len = cells.iter().rposition(|cell| !cell.is_empty()).map_or(0, |index| index + 1);
+            cells.truncate(len);
+
+            self.cells.append(&mut cells);
+            self.flush(false, output);
+        }
+    }
+
+    /// Flush the logical line which is still pending.
+    ///
+    /// Since the line continues in the rows following the history, its last row stays wrapped.
+    fn finish(mut self, output: &mut impl FnMut(Row<T>)) {
+        if self.pending {
+            self.flush(true, output);
+        }
+    }
+
+    /// Split the current logical line into rows.
+    fn flush(&mut self, wrapped: bool, output: &mut impl FnMut(Row<T>)) {
+        let columns = self.columns;
+        let mut row_cells = Vec::with_capacity(columns);
+
+        for cell in mem::take(&mut self.cells) {
+            // Move wide chars which don't fit into the row to the next one.
+            let wide = cell.flags().contains(Flags::WIDE_CHAR);
+            if row_cells.len() == columns || (wide && columns > 1 && row_cells.len() == columns - 1)
+            {
+                if row_cells.len() < columns {
+                    let mut spacer = T::default();
+                    spacer.flags_mut().insert(Flags::LEADING_WIDE_CHAR_SPACER);
+                    row_cells.push(spacer);
+                }
+
+                let cells = mem::replace(&mut row_cells, Vec::with_capacity(columns));
+                self.emit(cells, true, output);
+            }
+
+            row_cells.push(cell);
+        }
+
+        self.emit(row_cells, wrapped, output);
+        self.pending = false;
+    }
+
+    /// Output a single row of the current logical line.
+    fn emit(&mut self, mut cells: Vec<T>, wrapped: bool, output: &mut impl FnMut(Row<T>)) {
+        let occ = cells.len();
+        cells.resize_with(self.columns, T::default);
+
+        if wrapped {
+            cells[self.columns - 1].flags_mut().insert(Flags::WRAPLINE);
+        }
+
+        // Marks are only kept on the first row of the logical line.
+        let mut row = Row::from_vec(cells, occ);
+        row.set_mark(self.mark.take());
+        row.set_prompt_mark(self.prompt_mark.take());
+
+        output(row);
+    }
+}
+
+impl<T> Default for CompressedHistory<T> {
+    fn default() -> Self {
+        Self {
+            rows: VecDeque::new(),
+            size: 0,
+            max_size: 0,
+            inflated: Default::default(),
+            inflated_size: AtomicUsize::new(0),
+            next_id: 0,
+            spill: None,
+        }
+    }
+}
+
+impl<T: Clone> Clone for CompressedHistory<T> {
+    fn clone(&self) -> Self {
+        Self {
+            rows: self.rows.clone(),
+            size: self.size,
+            max_size: self.max_size,
+            inflated: Mutex::new(self.inflated.lock().unwrap().clone()),
+            inflated_size: AtomicUsize::new(self.inflated_size.load(Ordering::Relaxed)),
+            next_id: self.next_id,
+            spill: self.spill.as_ref().and_then(|spill| match spill.try_clone() {
+                Ok(spill) => Some(spill),
+                Err(err) => {
+                    error!("Unable to copy scrollback file: {err}");
+                    None
+                },
+            }),
+        }
+    }
+}
+
+impl<T: PartialEq> PartialEq for CompressedHistory<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && (0..self.len()).all(|i| self.get(i) == other.get(i))
+    }
+}
+
+/// Rows are serialized oldest first, inflating only a single row at a time.
+#[cfg(feature = "serde")]
+impl<T: Serialize + Clone> Serialize for CompressedHistory<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.len()))?;
+        for row in (0..self.len()).rev().filter_map(|index| self.row(index)) {
+            seq.serialize_element(&*row)?;
+        }
+        seq.end()
+    }
+}
+
+/// Deserialized rows are compressed without a memory limit, until the limit is configured.
+#[cfg(feature = "serde")]
+impl<'de, T> Deserialize<'de> for CompressedHistory<T>
+where
+    T: Deserialize<'de> + GridCell + Clone + Default + PartialEq,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct HistoryVisitor<T>(PhantomData<T>);
+
+        impl<'de, T> Visitor<'de> for HistoryVisitor<T>
+        where
+            T: Deserialize<'de> + GridCell + Clone + Default + PartialEq,
+        {
+            type Value = CompressedHistory<T>;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a sequence of rows")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut history = CompressedHistory { max_size: usize::MAX, ..Default::default() };
+                while let Some(row) = seq.next_element::<Row<T>>()? {
+                    history.push(&row);
+                }
+                Ok(history)
+            }
+        }
+
+        deserializer.deserialize_seq(HistoryVisitor(PhantomData))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::mem;
+
+    use crate::grid::row::{LineAttribute, LineMark, PromptMark, Row};
+    use crate::index::Column;
+    use crate::term::cell::{Cell, Flags};
+    use crate::vte::ansi::{Color, NamedColor};
+
+    use super::{CompressedHistory, CompressedRow};
+
+    #[test]
+    fn roundtrip() {
+        let mut row = Row::<Cell>::new(80);
+        for (i, c) in "hello 世 world".chars().enumerate() {
+            row[Column(i)].c = c;
+        }
+        row[Column(6)].flags.insert(Flags::WIDE_CHAR);
+        row[Column(7)].flags.insert(Flags::WIDE_CHAR_SPACER);
+        row[Column(2)].fg = Color::Named(NamedColor::Red);
+        row[Column(3)].push_zerowidth('\u{301}');
+        row[Column(79)].bg = Color::Named(NamedColor::Blue);
+        row.set_line_attribute(LineAttribute::DoubleWidth);
+        row.set_mark(Some(LineMark::Named('a')));
+        row.set_prompt_mark(Some(PromptMark::Output));
+
+        let compressed = CompressedRow::new(&row);
+        assert_eq!(compressed.inflate(), row);
+
+        let mut buf = Vec::new();
+        compressed.encode(&mut buf);
+        assert_eq!(CompressedRow::decode(&buf).unwrap(), compressed);
+    }
+
+    #[test]
+    fn trailing_cells_are_omitted() {
+        let mut row = Row::<Cell>::new(200);
+        for (i, c) in "prompt $ ls".chars().enumerate() {
+            row[Column(i)].c = c;
+        }
+
+        let compressed = CompressedRow::new(&row);
+        assert_eq!(compressed.text.len(), 11);
+        assert_eq!(compressed.runs.len(), 1);
+        assert!(compressed.heap_size() < 200 * mem::size_of::<Cell>() / 10);
+        assert_eq!(compressed.inflate(), row);
+    }
+
+    #[test]
+    fn reading_rows_keeps_them_compressed() {
+        let mut history = CompressedHistory::<Cell>::default();
+        history.set_max_size(usize::MAX);
+
+        let mut row = Row::<Cell>::new(10);
+        row[Column(0)].c = 'x';
+        for _ in 0..10 {
+            history.push(&row);
+        }
+
+        for i in 0..10 {
+            assert_eq!(*history.row(i).unwrap(), row);
+        }
+        assert!(history.rows.iter().all(|entry| entry.row.get().is_none()));
+    }
+
+    #[test]
+    fn inflated_rows_count_towards_limit() {
+        let mut history = CompressedHistory::<Cell>::default();
+        history.set_max_size(usize::MAX);
+
+        let mut row = Row::<Cell>::new(10);
+        row[Column(0)].c = 'x';
+        for _ in 0..10 {
+            history.push(&row);
+        }
+
+        // Inflating rows within the limit keeps all of them.
+        history.set_max_size(history.size + 2 * history.rows[0].row_size);
+        history.get(0);
+        history.get(9);
+        history.release_inflated();
+        assert_eq!(history.len(), 10);
+
+        // Exceeding the limit removes the oldest rows, including inflated ones.
+        history.get(5);
+        history.release_inflated();
+        assert!(history.len() < 10);
+        assert_eq!(history.index(0), None);
+        assert_eq!(*history.get(0).unwrap(), row);
+    }
+}
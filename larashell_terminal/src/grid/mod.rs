@@ -1,22 +1,30 @@
 //! A specialized 2D grid implementation optimized for use in a terminal.
 
+use std::borrow::Cow;
 use std::cmp::{max, min};
+use std::collections::BTreeMap;
 use std::ops::{Bound, Deref, Index, IndexMut, Range, RangeBounds};
 
+use log::error;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-use crate::index::{Column, Line, Point};
+use crate::index::{Column, Direction, Line, Point};
 use crate::term::cell::{Flags, ResetDiscriminant};
 use crate::vte::ansi::{CharsetIndex, StandardCharset};
 
+mod compressed;
+mod rectangle;
 pub mod resize;
 mod row;
+pub mod spill;
 mod storage;
 #[cfg(test)]
 mod tests;
 
-pub use self::row::Row;
+pub use self::rectangle::{Rectangle, RectangleExtent};
+pub use self::row::{LineAttribute, LineMark, PromptMark, Row};
+use self::spill::{SpillCell, SpillFile};
 use self::storage::Storage;
 
 pub trait GridCell: Sized {
@@ -28,6 +36,26 @@ pub trait GridCell: Sized {
 
     fn flags(&self) -> &Flags;
     fn flags_mut(&mut self) -> &mut Flags;
+
+    /// Split the cell into its character and the remaining attributes.
+    ///
+    /// This is used to deduplicate attributes of compressed scrollback rows.
+    #[inline]
+    fn split(&self) -> (char, Self)
+    where
+        Self: Clone,
+    {
+        (' ', self.clone())
+    }
+
+    /// Combine a character with attributes previously returned by [`GridCell::split`].
+    #[inline]
+    fn join(_c: char, attributes: &Self) -> Self
+    where
+        Self: Clone,
+    {
+        attributes.clone()
+    }
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
@@ -118,6 +146,13 @@ pub struct Grid<T> {
 
     /// Lines in the grid. Each row holds a list of cells corresponding to the
     /// columns in that row.
+    #[cfg_attr(
+        feature = "serde",
+        serde(bound(
+            serialize = "T: Serialize + Clone",
+            deserialize = "T: Deserialize<'de> + GridCell + Clone + Default + PartialEq",
+        ))
+    )]
     raw: Storage<T>,
 
     /// Number of columns.
@@ -135,6 +170,21 @@ pub struct Grid<T> {
 
     /// Maximum number of lines in history.
     max_scroll_limit: usize,
+
+    /// Index of all marked lines.
+    ///
+    /// Lines are identified by their position relative to `rotated_lines`, which keeps the index
+    /// valid while lines are rotated into the scrollback history.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    marks: BTreeMap<i64, LineMark>,
+
+    /// Index of all lines with a shell integration mark, identified like `marks`.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    prompt_marks: BTreeMap<i64, PromptMark>,
+
+    /// Total number of lines rotated into the scrollback history.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    rotated_lines: i64,
 }
 
 impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
@@ -147,20 +197,35 @@ impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
             cursor: Cursor::default(),
             lines,
             columns,
+            marks: BTreeMap::new(),
+            prompt_marks: BTreeMap::new(),
+            rotated_lines: 0,
         }
     }
 
     /// Update the size of the scrollback history.
     pub fn update_history(&mut self, history_size: usize) {
-        let current_history_size = self.history_size();
+        let current_history_size = self.uncompressed_history_size();
         if current_history_size > history_size {
+            self.raw.compress(current_history_size - history_size);
             self.raw.shrink_lines(current_history_size - history_size);
         }
-        self.display_offset = min(self.display_offset, history_size);
         self.max_scroll_limit = history_size;
+        self.display_offset = min(self.display_offset, self.history_size());
+    }
+
+    /// Update the maximum memory used by compressed scrollback history in bytes.
+    ///
+    /// Lines exceeding the scrollback history are compressed and kept until this limit is
+    /// reached, instead of being discarded.
+    pub fn update_compressed_history(&mut self, max_size: usize) {
+        self.raw.set_max_compressed_size(max_size);
+        self.display_offset = min(self.display_offset, self.history_size());
     }
 
     pub fn scroll_display(&mut self, scroll: Scroll) {
+        self.raw.release_inflated();
+
         self.display_offset = match scroll {
             Scroll::Delta(count) => {
                 min(max((self.display_offset as i32) + count, 0) as usize, self.history_size())
@@ -173,16 +238,17 @@ impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
     }
 
     fn increase_scroll_limit(&mut self, count: usize) {
-        let count = min(count, self.max_scroll_limit - self.history_size());
+        let count =
+            min(count, self.max_scroll_limit.saturating_sub(self.uncompressed_history_size()));
         if count != 0 {
             self.raw.initialize(count, self.columns);
         }
     }
 
     fn decrease_scroll_limit(&mut self, count: usize) {
-        let count = min(count, self.history_size());
+        let count = min(count, self.uncompressed_history_size());
         if count != 0 {
-            self.raw.shrink_lines(min(count, self.history_size()));
+            self.raw.shrink_lines(count);
             self.display_offset = min(self.display_offset, self.history_size());
         }
     }
@@ -202,6 +268,8 @@ impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
             return;
         }
 
+        let screen_marks = self.take_screen_marks();
+
         // Which implementation we can use depends on the existence of a scrollback history.
         //
         // Since a scrollback history prevents us from rotating the entire buffer downwards, we
@@ -244,15 +312,36 @@ impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
                 self.raw[line].reset(&self.cursor.template);
             }
         }
+
+        if screen_marks {
+            self.index_marks(Line(0)..Line(self.lines as i32));
+        }
     }
 
     /// Move lines at the bottom toward the top.
     ///
     /// This is the performance-sensitive part of scrolling.
+    #[inline]
     pub fn scroll_up<D>(&mut self, region: &Range<Line>, positions: usize)
     where
         T: ResetDiscriminant<D>,
         D: PartialEq,
+    {
+        self.scroll_up_with_history(region, positions, true);
+    }
+
+    /// Move lines at the bottom toward the top.
+    ///
+    /// If `compress_history` is `false`, lines rotated out of the scrollback history are
+    /// discarded instead of being moved to the compressed history.
+    fn scroll_up_with_history<D>(
+        &mut self,
+        region: &Range<Line>,
+        positions: usize,
+        compress_history: bool,
+    ) where
+        T: ResetDiscriminant<D>,
+        D: PartialEq,
     {
         // When rotating the entire region with fixed lines at the top, just reset everything.
         if region.end - region.start <= positions && region.start != 0 {
@@ -263,16 +352,28 @@ impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
             return;
         }
 
+        self.raw.release_inflated();
+
+        let screen_marks = self.take_screen_marks();
+        let mut rotated = 0;
+
         // Update display offset when not pinned to active area.
         if self.display_offset != 0 {
-            self.display_offset = min(self.display_offset + positions, self.max_scroll_limit);
+            self.display_offset += positions;
         }
 
         // Only rotate the entire history if the active region starts at the top.
         if region.start == 0 {
             // Create scrollback for the new lines.
+            let history_size = self.uncompressed_history_size();
             self.increase_scroll_limit(positions);
 
+            // Compress lines which are rotated out of the scrollback history.
+            if compress_history {
+                let added = self.uncompressed_history_size() - history_size;
+                self.raw.compress(positions - added);
+            }
+
             // Swap the lines fixed at the top to their target positions after rotation.
             //
             // Since we've made sure that the rotation will never rotate away the entire region, we
@@ -287,6 +388,8 @@ impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
 
             // Rotate the entire line buffer upward.
             self.raw.rotate(-(positions as isize));
+            self.rotated_lines += positions as i64;
+            rotated = positions;
 
             // Swap the fixed lines at the bottom back into position.
             let screen_lines = self.screen_lines() as i32;
@@ -304,6 +407,21 @@ impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
         for i in (region.end.0 - positions as i32..region.end.0).map(Line::from) {
             self.raw[i].reset(&self.cursor.template);
         }
+
+        self.display_offset = min(self.display_offset, self.history_size());
+
+        // Update marks of lines which were moved within the screen or rotated into history.
+        if screen_marks {
+            let start = max(Line(-(rotated as i32)), self.topmost_line());
+            self.index_marks(start..Line(self.lines as i32));
+        }
+
+        // Forget marks of lines which were removed from the history.
+        if !self.marks.is_empty() || !self.prompt_marks.is_empty() {
+            let topmost = self.mark_key(self.topmost_line());
+            self.marks = self.marks.split_off(&topmost);
+            self.prompt_marks = self.prompt_marks.split_off(&topmost);
+        }
     }
 
     pub fn clear_viewport<D>(&mut self)
@@ -339,6 +457,8 @@ impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
         D: PartialEq,
     {
         self.clear_history();
+        self.marks.clear();
+        self.prompt_marks.clear();
 
         self.saved_cursor = Cursor::default();
         self.cursor = Cursor::default();
@@ -352,6 +472,26 @@ impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
     }
 }
 
+impl<T: SpillCell + GridCell + Default + PartialEq + Clone> Grid<T> {
+    /// Enable or disable storing history exceeding the compressed history's memory limit on disk.
+    pub fn update_history_spill(&mut self, enabled: bool) {
+        if enabled == self.raw.has_spill() {
+            return;
+        }
+
+        let spill = if enabled {
+            SpillFile::new(self.columns)
+                .map_err(|err| error!("Unable to create scrollback file: {err}"))
+                .ok()
+        } else {
+            None
+        };
+
+        self.raw.set_spill(spill);
+        self.display_offset = min(self.display_offset, self.history_size());
+    }
+}
+
 impl<T> Grid<T> {
     /// Reset a visible region within the grid.
     pub fn reset_region<D, R: RangeBounds<Line>>(&mut self, bounds: R)
@@ -382,7 +522,10 @@ impl<T> Grid<T> {
     #[inline]
     pub fn clear_history(&mut self) {
         // Explicitly purge all lines from history.
-        self.raw.shrink_lines(self.history_size());
+        self.raw.shrink_lines(self.uncompressed_history_size());
+        self.raw.clear_compressed();
+        self.marks = self.marks.split_off(&self.rotated_lines);
+        self.prompt_marks = self.prompt_marks.split_off(&self.rotated_lines);
 
         // Reset display offset.
         self.display_offset = 0;
@@ -398,7 +541,7 @@ impl<T> Grid<T> {
         self.truncate();
 
         // Initialize everything with empty new lines.
-        self.raw.initialize(self.max_scroll_limit - self.history_size(), self.columns);
+        self.raw.initialize(self.max_scroll_limit - self.uncompressed_history_size(), self.columns);
     }
 
     /// This is used only for truncating before saving ref-tests.
@@ -414,6 +557,17 @@ impl<T> Grid<T> {
         GridIterator { grid: self, point, end }
     }
 
+    /// Read a line without keeping compressed history inflated.
+    ///
+    /// This should be preferred over indexing when scanning through large parts of the history.
+    #[inline]
+    pub fn row(&self, line: Line) -> Cow<'_, Row<T>>
+    where
+        T: Clone,
+    {
+        self.raw.row(line)
+    }
+
     /// Iterate over all visible cells.
     ///
     /// This is slightly more optimized than calling `Grid::iter_from` in combination with
@@ -433,11 +587,169 @@ impl<T> Grid<T> {
         self.display_offset
     }
 
+    /// Total number of lines rotated into the scrollback history.
+    ///
+    /// This allows tracking lines across scrolling, as long as the grid is not resized.
+    #[inline]
+    pub fn rotated_lines(&self) -> i64 {
+        self.rotated_lines
+    }
+
+    /// Number of scrollback lines which are not compressed.
+    #[inline]
+    fn uncompressed_history_size(&self) -> usize {
+        self.raw.len() - self.lines
+    }
+
     #[inline]
     pub fn cursor_cell(&mut self) -> &mut T {
         let point = self.cursor.point;
         &mut self[point.line][point.column]
     }
+
+    /// Bookmark of a line.
+    ///
+    /// Unlike indexing, this never inflates lines of the compressed history.
+    #[inline]
+    pub fn mark(&self, line: Line) -> Option<LineMark> {
+        self.raw.mark(line)
+    }
+
+    /// Set or remove the bookmark of a line.
+    ///
+    /// Named marks are moved, removing them from any other line.
+    pub fn set_mark(&mut self, line: Line, mark: Option<LineMark>) {
+        if let Some(mark @ LineMark::Named(_)) = mark {
+            if let Some(old_line) = self.find_mark(mark) {
+                self.raw.set_mark(old_line, None);
+                self.marks.remove(&self.mark_key(old_line));
+            }
+        }
+
+        self.raw.set_mark(line, mark);
+
+        let key = self.mark_key(line);
+        match mark {
+            Some(mark) => self.marks.insert(key, mark),
+            None => self.marks.remove(&key),
+        };
+    }
+
+    /// Find the bottommost line holding a mark.
+    pub fn find_mark(&self, mark: LineMark) -> Option<Line> {
+        self.marks
+            .iter()
+            .rev()
+            .filter(|(_, indexed)| **indexed == mark)
+            .map(|(key, _)| self.mark_line(*key))
+            .find(|line| self.has_mark(*line, mark))
+    }
+
+    /// Find the closest marked line above or below a line.
+    pub fn next_mark(&self, line: Line, direction: Direction) -> Option<Line> {
+        let key = self.mark_key(line);
+        let marked = |(key, mark): (&i64, &LineMark)| {
+            let line = self.mark_line(*key);
+            self.has_mark(line, *mark).then_some(line)
+        };
+
+        match direction {
+            Direction::Left => self.marks.range(..key).rev().find_map(marked),
+            Direction::Right => self.marks.range(key + 1..).find_map(marked),
+        }
+    }
+
+    /// Shell integration mark of a line.
+    ///
+    /// Unlike indexing, this never inflates lines of the compressed history.
+    #[inline]
+    pub fn prompt_mark(&self, line: Line) -> Option<PromptMark> {
+        self.raw.prompt_mark(line)
+    }
+
+    /// Set or remove the shell integration mark of a line.
+    pub fn set_prompt_mark(&mut self, line: Line, prompt_mark: Option<PromptMark>) {
+        self[line].set_prompt_mark(prompt_mark);
+
+        let key = self.mark_key(line);
+        match prompt_mark {
+            Some(prompt_mark) => self.prompt_marks.insert(key, prompt_mark),
+            None => self.prompt_marks.remove(&key),
+        };
+    }
+
+    /// Find the closest line above or below a line with a shell integration mark.
+    pub fn next_prompt_mark(&self, line: Line, direction: Direction) -> Option<(Line, PromptMark)> {
+        let key = self.mark_key(line);
+        let marked = |(key, prompt_mark): (&i64, &PromptMark)| {
+            let line = self.mark_line(*key);
+            let valid = line >= self.topmost_line()
+                && line <= self.bottommost_line()
+                && self.raw.prompt_mark(line) == Some(*prompt_mark);
+            valid.then_some((line, *prompt_mark))
+        };
+
+        match direction {
+            Direction::Left => self.prompt_marks.range(..key).rev().find_map(marked),
+            Direction::Right => self.prompt_marks.range(key + 1..).find_map(marked),
+        }
+    }
+
+    /// Rebuild the index of marked lines.
+    ///
+    /// This must be called after marked lines were replaced without going through
+    /// [`Grid::set_mark`] or [`Grid::set_prompt_mark`].
+    pub fn reindex_marks(&mut self) {
+        self.marks.clear();
+        self.prompt_marks.clear();
+        self.index_marks(self.topmost_line()..Line(self.lines as i32));
+    }
+
+    /// Add the marks of all lines within `lines` to the index.
+    fn index_marks(&mut self, lines: Range<Line>) {
+        for line in (lines.start.0..lines.end.0).map(Line::from) {
+            if let Some(mark) = self.raw.mark(line) {
+                self.marks.insert(self.mark_key(line), mark);
+            }
+            if let Some(prompt_mark) = self.raw.prompt_mark(line) {
+                self.prompt_marks.insert(self.mark_key(line), prompt_mark);
+            }
+        }
+    }
+
+    /// Remove the marks of all visible lines from the index.
+    ///
+    /// Returns `true` if any visible line was marked.
+    fn take_screen_marks(&mut self) -> bool {
+        if self.marks.is_empty() && self.prompt_marks.is_empty() {
+            return false;
+        }
+
+        let marks = self.marks.split_off(&self.rotated_lines);
+        let prompt_marks = self.prompt_marks.split_off(&self.rotated_lines);
+        !marks.is_empty() || !prompt_marks.is_empty()
+    }
+
+    /// Check if the index entry of a line is still up to date.
+    ///
+    /// Lines can lose their mark without updating the index when they're cleared.
+    fn has_mark(&self, line: Line, mark: LineMark) -> bool {
+        line >= self.topmost_line()
+            && line <= self.bottommost_line()
+            && self.raw.mark(line) == Some(mark)
+    }
+
+    /// Key of a line in the index of marked lines.
+    #[inline]
+    fn mark_key(&self, line: Line) -> i64 {
+        self.rotated_lines + line.0 as i64
+    }
+
+    /// Line of a key in the index of marked lines.
+    #[inline]
+    fn mark_line(&self, key: i64) -> Line {
+        Line((key - self.rotated_lines) as i32)
+    }
 }
 
 impl<T: PartialEq> PartialEq for Grid<T> {
@@ -450,6 +762,8 @@ impl<T: PartialEq> PartialEq for Grid<T> {
     }
 }
 
+impl<T: Eq> Eq for Grid<T> {}
+
 impl<T> Index<Line> for Grid<T> {
     type Output = Row<T>;
 
@@ -521,7 +835,7 @@ pub trait Dimensions {
 impl<G> Dimensions for Grid<G> {
     #[inline]
     fn total_lines(&self) -> usize {
-        self.raw.len()
+        self.raw.len() + self.raw.compressed_len()
     }
 
     #[inline]
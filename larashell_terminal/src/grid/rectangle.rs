@@ -0,0 +1,177 @@
+//! Operations on rectangular areas of the grid.
+
+use std::cmp::min;
+
+use crate::grid::{Dimensions, Grid};
+use crate::index::{Column, Line, Point};
+use crate::term::cell::{Cell, Flags};
+
+/// Rectangular area inside the visible region of the grid.
+///
+/// All edges are inclusive.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Rectangle {
+    pub top: Line,
+    pub left: Column,
+    pub bottom: Line,
+    pub right: Column,
+}
+
+impl Rectangle {
+    #[inline]
+    pub fn new(top: Line, left: Column, bottom: Line, right: Column) -> Self {
+        Self { top, left, bottom, right }
+    }
+
+    /// Number of lines covered by the rectangle.
+    #[inline]
+    pub fn height(&self) -> usize {
+        (self.bottom.0 - self.top.0 + 1) as usize
+    }
+
+    /// Number of columns covered by the rectangle.
+    #[inline]
+    pub fn width(&self) -> usize {
+        self.right.0 - self.left.0 + 1
+    }
+
+    /// Check if a point is inside the rectangle.
+    #[inline]
+    pub fn contains(&self, point: Point) -> bool {
+        (self.top..=self.bottom).contains(&point.line)
+            && (self.left..=self.right).contains(&point.column)
+    }
+}
+
+/// Area affected by attribute changes inside a rectangle.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum RectangleExtent {
+    /// Treat the area as a stream of characters, wrapping from the right edge of one line to the
+    /// left edge of the next.
+    #[default]
+    Stream,
+
+    /// Only change cells inside the exact rectangle.
+    Rectangle,
+}
+
+impl Grid<Cell> {
+    /// Copy the content of `source` so its top-left corner is at `destination`.
+    ///
+    /// Content which would be moved outside of the visible region is discarded.
+    pub fn copy_rectangle(&mut self, source: Rectangle, destination: Point) {
+        let screen_lines = self.screen_lines() as i32;
+        let height = min(source.height() as i32, screen_lines - destination.line.0) as usize;
+        let width = min(source.width(), self.columns() - destination.column.0);
+        if height == 0 || width == 0 {
+            return;
+        }
+
+        // Buffer source cells, since source and destination are allowed to overlap.
+        let cells: Vec<Vec<Cell>> = (0..height)
+            .map(|offset| {
+                let row = &self[source.top + offset];
+                row[source.left..source.left + width].to_vec()
+            })
+            .collect();
+
+        for (offset, cells) in cells.into_iter().enumerate() {
+            let line = destination.line + offset;
+            let start = destination.column;
+            let row = &mut self[line][start..start + width];
+            for (cell, new_cell) in row.iter_mut().zip(cells) {
+                // Keep line wrapping state of the destination.
+                let wrapline = cell.flags & Flags::WRAPLINE;
+                *cell = new_cell;
+                cell.flags.remove(Flags::WRAPLINE);
+                cell.flags.insert(wrapline);
+            }
+        }
+
+        let right = destination.column + (width - 1);
+        let bottom = destination.line + (height - 1);
+        self.repair_wide_chars(Rectangle::new(destination.line, destination.column, bottom, right));
+    }
+
+    /// Replace every cell inside the rectangle with `template`.
+    pub fn fill_rectangle(&mut self, rect: Rectangle, template: &Cell) {
+        for line in (rect.top.0..=rect.bottom.0).map(Line::from) {
+            for cell in &mut self[line][rect.left..rect.right + 1] {
+                let wrapline = cell.flags & Flags::WRAPLINE;
+                *cell = template.clone();
+                cell.flags.insert(wrapline);
+            }
+        }
+
+        self.repair_wide_chars(rect);
+    }
+
+    /// Erase all characters inside the rectangle which are not protected.
+    ///
+    /// Unlike [`Grid::fill_rectangle`], this keeps the visual attributes of all cells.
+    pub fn selective_erase_rectangle(&mut self, rect: Rectangle) {
+        for line in (rect.top.0..=rect.bottom.0).map(Line::from) {
+            for cell in &mut self[line][rect.left..rect.right + 1] {
+                if cell.flags.contains(Flags::PROTECTED) {
+                    continue;
+                }
+
+                cell.clear_wide();
+                cell.flags.remove(Flags::WIDE_CHAR_SPACER | Flags::LEADING_WIDE_CHAR_SPACER);
+            }
+        }
+
+        self.repair_wide_chars(rect);
+    }
+
+    /// Apply `f` to the flags of all cells inside the rectangle.
+    pub fn change_rectangle_flags<F>(&mut self, rect: Rectangle, extent: RectangleExtent, mut f: F)
+    where
+        F: FnMut(&mut Flags),
+    {
+        let last_column = self.last_column();
+        for line in (rect.top.0..=rect.bottom.0).map(Line::from) {
+            let (left, right) = match extent {
+                RectangleExtent::Rectangle => (rect.left, rect.right),
+                RectangleExtent::Stream if rect.top == rect.bottom => (rect.left, rect.right),
+                RectangleExtent::Stream if line == rect.top => (rect.left, last_column),
+                RectangleExtent::Stream if line == rect.bottom => (Column(0), rect.right),
+                RectangleExtent::Stream => (Column(0), last_column),
+            };
+
+            for cell in &mut self[line][left..right + 1] {
+                f(&mut cell.flags);
+            }
+        }
+    }
+
+    /// Remove fullwidth characters which were split at the edges of a modified rectangle.
+    fn repair_wide_chars(&mut self, rect: Rectangle) {
+        let columns = self.columns();
+        for line in (rect.top.0..=rect.bottom.0).map(Line::from) {
+            let row = &mut self[line];
+
+            for boundary in [rect.left.0, rect.right.0 + 1] {
+                // Drop spacers without their fullwidth character.
+                if boundary < columns
+                    && row[Column(boundary)].flags.contains(Flags::WIDE_CHAR_SPACER)
+                {
+                    let has_wide =
+                        boundary > 0 && row[Column(boundary - 1)].flags.contains(Flags::WIDE_CHAR);
+                    if !has_wide {
+                        row[Column(boundary)].flags.remove(Flags::WIDE_CHAR_SPACER);
+                    }
+                }
+
+                // Drop fullwidth characters without their spacer.
+                if boundary > 0 && row[Column(boundary - 1)].flags.contains(Flags::WIDE_CHAR) {
+                    let has_spacer = boundary < columns
+                        && row[Column(boundary)].flags.contains(Flags::WIDE_CHAR_SPACER);
+                    if !has_spacer {
+                        row[Column(boundary - 1)].clear_wide();
+                    }
+                }
+            }
+        }
+    }
+}
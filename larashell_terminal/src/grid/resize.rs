@@ -3,7 +3,7 @@
 use std::cmp::{max, min, Ordering};
 use std::mem;
 
-use crate::index::{Boundary, Column, Line};
+use crate::index::{Boundary, Column, Line, Point};
 use crate::term::cell::{Flags, ResetDiscriminant};
 
 use crate::grid::row::Row;
@@ -19,6 +19,8 @@ impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
         // Use empty template cell for resetting cells due to resize.
         let template = mem::take(&mut self.cursor.template);
 
+        let columns_changed = self.columns != columns;
+
         match self.lines.cmp(&lines) {
             Ordering::Less => self.grow_lines(lines),
             Ordering::Greater => self.shrink_lines(lines),
@@ -31,6 +33,20 @@ impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
             Ordering::Equal => (),
         }
 
+        // Reflow the compressed history separately, to avoid inflating all of it at once.
+        if columns_changed {
+            self.raw.resize_compressed(reflow, columns);
+            self.display_offset = min(self.display_offset, self.history_size());
+        }
+
+        // Compress history which no longer fits into the scrollback buffer.
+        self.update_history(self.max_scroll_limit);
+
+        // Reflow moves marked lines, so their position has to be looked up again.
+        if !self.marks.is_empty() || !self.prompt_marks.is_empty() {
+            self.reindex_marks();
+        }
+
         // Restore template cell.
         self.cursor.template = template;
     }
@@ -47,6 +63,9 @@ impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
     {
         let lines_added = target - self.lines;
 
+        // Pull lines which will become visible out of the compressed history.
+        self.raw.decompress(lines_added.saturating_sub(self.uncompressed_history_size()));
+
         // Need to resize before updating buffer.
         self.raw.grow_visible_lines(target);
         self.lines = target;
@@ -57,7 +76,7 @@ impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
         // Move existing lines up for every line that couldn't be pulled from history.
         if from_history != lines_added {
             let delta = lines_added - from_history;
-            self.scroll_up(&(Line(0)..Line(target as i32)), delta);
+            self.scroll_up_with_history(&(Line(0)..Line(target as i32)), delta, false);
         }
 
         // Move cursor down for every line pulled from history.
@@ -102,7 +121,11 @@ impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
         // Check if a row needs to be wrapped.
         let should_reflow = |row: &Row<T>| -> bool {
             let len = Column(row.len());
-            reflow && len.0 > 0 && len < columns && row[len - 1].flags().contains(Flags::WRAPLINE)
+            reflow
+                && len.0 > 0
+                && len < columns
+                && !row.line_attribute().is_double_width()
+                && row[len - 1].flags().contains(Flags::WRAPLINE)
         };
 
         self.columns = columns;
@@ -121,7 +144,11 @@ impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
         for (i, mut row) in rows.drain(..).enumerate().rev() {
             // Check if reflowing should be performed.
             let last_row = match reversed.last_mut() {
-                Some(last_row) if should_reflow(last_row) => last_row,
+                Some(last_row)
+                    if should_reflow(last_row) && !row.line_attribute().is_double_width() =>
+                {
+                    last_row
+                },
                 _ => {
                     reversed.push(row);
                     continue;
@@ -164,16 +191,35 @@ impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
             // Add removed cells to previous row and reflow content.
             last_row.append(&mut cells);
 
+            // Keep the mark of lines which are merged into the previous row.
+            if row.is_clear() && last_row.mark().is_none() {
+                last_row.set_mark(row.mark());
+            }
+            if row.is_clear() && last_row.prompt_mark().is_none() {
+                last_row.set_prompt_mark(row.prompt_mark());
+            }
+
             let cursor_buffer_line = self.lines - self.cursor.point.line.0 as usize - 1;
 
             if i == cursor_buffer_line && reflow {
                 // Resize cursor's line and reflow the cursor if necessary.
-                let mut target = self.cursor.point.sub(self, Boundary::Cursor, num_wrapped);
+                //
+                // The target line is not clamped, since the cursor's line is merged into the
+                // history when the cursor is at the top of the screen.
+                let move_left = |mut point: Point, count: usize| {
+                    if point.column.0 < count {
+                        point.line -= 1;
+                        point.column += columns;
+                    }
+                    point.column -= count;
+                    point
+                };
+                let mut target = move_left(self.cursor.point, num_wrapped);
 
                 // Clamp to the last column, if no content was reflown with the cursor.
                 if target.column.0 == 0 && row.is_clear() {
                     self.cursor.input_needs_wrap = true;
-                    target = target.sub(self, Boundary::Cursor, 1);
+                    target = move_left(target, 1);
                 }
                 self.cursor.point.column = target.column;
 
@@ -268,13 +314,19 @@ impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
                 row.append_front(buffered);
             }
 
+            // Double width lines are truncated instead of reflowed.
+            let reflow_row = reflow && !row.line_attribute().is_double_width();
+
             loop {
                 // Remove all cells which require reflowing.
                 let mut wrapped = match row.shrink(columns) {
-                    Some(wrapped) if reflow => wrapped,
+                    Some(wrapped) if reflow_row => wrapped,
                     _ => {
                         let cursor_buffer_line = self.lines - self.cursor.point.line.0 as usize - 1;
-                        if reflow && i == cursor_buffer_line && self.cursor.point.column > columns {
+                        if reflow_row
+                            && i == cursor_buffer_line
+                            && self.cursor.point.column > columns
+                        {
                             // If there are empty cells before the cursor, we assume it is explicit
                             // whitespace and need to wrap it like normal content.
                             Vec::new()
@@ -295,6 +347,12 @@ impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
 
                     let wide_char = mem::replace(&mut row[Column(columns - 1)], spacer);
                     wrapped.insert(0, wide_char);
+
+                    // Move the cursor with the cells shifted by the spacer.
+                    let cursor_buffer_line = self.lines - self.cursor.point.line.0 as usize - 1;
+                    if i == cursor_buffer_line && self.cursor.point.column >= columns - 1 {
+                        self.cursor.point.column += 1;
+                    }
                 }
 
                 // Remove wide char spacer before shrinking.
@@ -365,7 +423,9 @@ impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
 
         // Reverse iterator and use it as the new grid storage.
         let mut reversed: Vec<Row<T>> = new_raw.drain(..).rev().collect();
-        reversed.truncate(self.max_scroll_limit + self.lines);
+        if self.raw.max_compressed_size() == 0 && !self.raw.has_spill() {
+            reversed.truncate(self.max_scroll_limit + self.lines);
+        }
         self.raw.replace_inner(reversed);
 
         // Clamp display offset in case some lines went off.
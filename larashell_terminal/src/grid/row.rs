@@ -11,6 +11,53 @@ use crate::grid::GridCell;
 use crate::index::Column;
 use crate::term::cell::ResetDiscriminant;
 
+/// Rendering attribute of a whole line.
+///
+/// These are set by DECDWL/DECDHL and cause every cell in the line to be drawn with twice its
+/// usual width. Lines which are not [`LineAttribute::Normal`] can only hold half as many
+/// characters as the grid has columns.
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum LineAttribute {
+    /// Single width, single height line.
+    #[default]
+    Normal,
+    /// Double width, single height line.
+    DoubleWidth,
+    /// Top half of a double width, double height line.
+    DoubleHeightTop,
+    /// Bottom half of a double width, double height line.
+    DoubleHeightBottom,
+}
+
+impl LineAttribute {
+    /// Check if every cell in the line is drawn with twice its usual width.
+    #[inline]
+    pub fn is_double_width(self) -> bool {
+        self != LineAttribute::Normal
+    }
+}
+
+/// Bookmark attached to a line.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum LineMark {
+    /// Unnamed mark, any number of lines can hold one.
+    Anonymous,
+    /// Named mark, only a single line can hold each name.
+    Named(char),
+}
+
+/// Shell integration mark of a line, set through OSC 133.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum PromptMark {
+    /// The line contains the start of a shell prompt.
+    Prompt,
+    /// The line contains the start of a command's output.
+    Output,
+}
+
 /// A row in the grid.
 #[derive(Default, Clone, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -22,11 +69,26 @@ pub struct Row<T> {
     /// This is the upper bound on the number of elements in the row, which have been modified
     /// since the last reset. All cells after this point are guaranteed to be equal.
     pub(crate) occ: usize,
+
+    /// DECDWL/DECDHL attribute of the line.
+    #[cfg_attr(feature = "serde", serde(default))]
+    line_attribute: LineAttribute,
+
+    /// Bookmark of the line.
+    #[cfg_attr(feature = "serde", serde(default))]
+    mark: Option<LineMark>,
+
+    /// Shell integration mark of the line.
+    #[cfg_attr(feature = "serde", serde(default))]
+    prompt_mark: Option<PromptMark>,
 }
 
 impl<T: PartialEq> PartialEq for Row<T> {
     fn eq(&self, other: &Self) -> bool {
         self.inner == other.inner
+            && self.line_attribute == other.line_attribute
+            && self.mark == other.mark
+            && self.prompt_mark == other.prompt_mark
     }
 }
 
@@ -52,7 +114,7 @@ impl<T: Clone + Default> Row<T> {
             inner.set_len(columns);
         }
 
-        Row { inner, occ: 0 }
+        Row::from_vec(inner, 0)
     }
 
     /// Increase the number of columns in the row.
@@ -111,6 +173,9 @@ impl<T: Clone + Default> Row<T> {
         }
 
         self.occ = 0;
+        self.line_attribute = LineAttribute::Normal;
+        self.mark = None;
+        self.prompt_mark = None;
     }
 }
 
@@ -118,7 +183,56 @@ impl<T: Clone + Default> Row<T> {
 impl<T> Row<T> {
     #[inline]
     pub fn from_vec(vec: Vec<T>, occ: usize) -> Row<T> {
-        Row { inner: vec, occ }
+        Row {
+            inner: vec,
+            occ,
+            line_attribute: LineAttribute::Normal,
+            mark: None,
+            prompt_mark: None,
+        }
+    }
+
+    /// DECDWL/DECDHL attribute of the line.
+    #[inline]
+    pub fn line_attribute(&self) -> LineAttribute {
+        self.line_attribute
+    }
+
+    #[inline]
+    pub fn set_line_attribute(&mut self, line_attribute: LineAttribute) {
+        self.line_attribute = line_attribute;
+    }
+
+    /// Bookmark of the line.
+    #[inline]
+    pub fn mark(&self) -> Option<LineMark> {
+        self.mark
+    }
+
+    #[inline]
+    pub fn set_mark(&mut self, mark: Option<LineMark>) {
+        self.mark = mark;
+    }
+
+    /// Shell integration mark of the line.
+    #[inline]
+    pub fn prompt_mark(&self) -> Option<PromptMark> {
+        self.prompt_mark
+    }
+
+    #[inline]
+    pub fn set_prompt_mark(&mut self, prompt_mark: Option<PromptMark>) {
+        self.prompt_mark = prompt_mark;
+    }
+
+    /// Number of cells which can hold characters, taking the line attribute into account.
+    #[inline]
+    pub fn usable_len(&self) -> usize {
+        if self.line_attribute.is_double_width() {
+            max(self.inner.len() / 2, 1)
+        } else {
+            self.inner.len()
+        }
     }
 
     #[inline]
@@ -0,0 +1,761 @@
+//! Scrollback history stored in a temporary file.
+
+use std::borrow::Cow;
+use std::collections::{HashSet, VecDeque};
+use std::fs::File;
+use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+use log::error;
+
+use crate::grid::compressed::{self, CompressedRow};
+use crate::grid::row::{LineMark, PromptMark, Row};
+use crate::grid::GridCell;
+
+/// Maximum number of rows which are kept inflated before they're released again.
+const MAX_INFLATED_ROWS: usize = 1_000;
+
+/// Default maximum size of the file in bytes.
+const MAX_FILE_SIZE: usize = 1 << 30;
+
+/// Minimum size of the memory map in bytes.
+const MIN_MAP_SIZE: usize = 1 << 20;
+
+/// Number of bytes buffered before they're written to the file.
+const WRITE_BUFFER_SIZE: usize = 1 << 16;
+
+/// Counter for unique spill file names within this process.
+static SPILL_FILE_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Cells which can be written to a [`SpillFile`].
+pub trait SpillCell: Sized {
+    /// Append the binary representation of the cell to `buf`.
+    fn encode(&self, buf: &mut Vec<u8>);
+
+    /// Read a cell from the start of `bytes`, advancing it past the cell.
+    fn decode(bytes: &mut &[u8]) -> Option<Self>;
+}
+
+/// Append a `u32` to `buf`.
+#[inline]
+pub fn write_u32(buf: &mut Vec<u8>, value: u32) {
+    buf.extend_from_slice(&value.to_le_bytes());
+}
+
+/// Read a `u32` from the start of `bytes`.
+#[inline]
+pub fn read_u32(bytes: &mut &[u8]) -> Option<u32> {
+    let value = read_bytes(bytes, 4)?;
+    Some(u32::from_le_bytes([value[0], value[1], value[2], value[3]]))
+}
+
+/// Read a single byte from the start of `bytes`.
+#[inline]
+pub fn read_u8(bytes: &mut &[u8]) -> Option<u8> {
+    Some(read_bytes(bytes, 1)?[0])
+}
+
+/// Read `len` bytes from the start of `bytes`.
+#[inline]
+pub fn read_bytes<'a>(bytes: &mut &'a [u8], len: usize) -> Option<&'a [u8]> {
+    if bytes.len() < len {
+        return None;
+    }
+
+    let (value, remaining) = bytes.split_at(len);
+    *bytes = remaining;
+    Some(value)
+}
+
+/// Location of a row inside the [`SpillFile`].
+#[derive(Clone, Debug)]
+struct SpillEntry<T> {
+    /// Byte offset of the row in the file.
+    offset: usize,
+
+    /// Length of the encoded row in bytes.
+    len: usize,
+
+    /// Inflated row, lazily populated on access.
+    row: OnceLock<Box<Row<T>>>,
+}
+
+/// Temporary file storing the oldest rows of the scrollback history.
+///
+/// The file is created with permissions restricted to the current user and unlinked immediately,
+/// so it is removed as soon as the terminal is dropped. Rows are appended with regular writes and
+/// read through a memory map. Once the file reaches its maximum size, the oldest rows are removed.
+#[derive(Debug)]
+pub struct SpillFile<T> {
+    file: File,
+
+    /// Read-only map of the file.
+    map: Mmap,
+
+    /// Number of bytes written to the file, excluding pending bytes.
+    size: usize,
+
+    /// Number of bytes in the file which no longer belong to any row.
+    garbage: usize,
+
+    /// Maximum size of the file in bytes.
+    ///
+    /// Since unused bytes are only removed once they make up half of the file, the rows stored in
+    /// the file are limited to half of this size.
+    max_size: usize,
+
+    /// Bytes which have not been written to the file yet.
+    pending: Vec<u8>,
+
+    /// Rows stored in the file, starting with the oldest one.
+    rows: VecDeque<SpillEntry<T>>,
+
+    /// Number of oldest rows removed since the file was created.
+    ///
+    /// Rows are identified by their index plus this offset, so ids remain valid while the oldest
+    /// rows are removed.
+    removed: usize,
+
+    /// Number of columns rows are resized to when inflated.
+    columns: usize,
+
+    /// Ids of all inflated rows.
+    inflated: Mutex<Vec<usize>>,
+
+    /// Ids of all rows modified since they were written.
+    dirty: HashSet<usize>,
+
+    /// Function for encoding a row.
+    encode: fn(&Row<T>, &mut Vec<u8>),
+
+    /// Function for encoding a compressed row.
+    encode_compressed: fn(&CompressedRow<T>, &mut Vec<u8>),
+
+    /// Function for decoding a row with the specified number of columns.
+    decode: fn(&[u8], usize) -> Row<T>,
+}
+
+impl<T> SpillFile<T> {
+    /// Create a new spill file in the temporary directory.
+    pub fn new(columns: usize) -> io::Result<Self>
+    where
+        T: SpillCell + GridCell + Clone + Default + PartialEq,
+    {
+        Self::with_codec(columns, encode_row::<T>, CompressedRow::encode, decode_row::<T>)
+    }
+
+    fn with_codec(
+        columns: usize,
+        encode: fn(&Row<T>, &mut Vec<u8>),
+        encode_compressed: fn(&CompressedRow<T>, &mut Vec<u8>),
+        decode: fn(&[u8], usize) -> Row<T>,
+    ) -> io::Result<Self> {
+        let id = SPILL_FILE_ID.fetch_add(1, Ordering::Relaxed);
+        let name = format!("larashell-scrollback-{}-{id}", std::process::id());
+        let path = std::env::temp_dir().join(name);
+
+        let file = create_private_file(&path)?;
+        std::fs::remove_file(&path)?;
+
+        Ok(Self {
+            file,
+            map: Mmap::default(),
+            size: 0,
+            garbage: 0,
+            max_size: MAX_FILE_SIZE,
+            pending: Vec::new(),
+            rows: VecDeque::new(),
+            removed: 0,
+            columns,
+            inflated: Default::default(),
+            dirty: HashSet::new(),
+            encode,
+            encode_compressed,
+            decode,
+        })
+    }
+
+    /// Number of rows in the file.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Check if the file contains no rows.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Access a row, with `0` being the most recent row.
+    pub fn get(&self, index: usize) -> Option<&Row<T>> {
+        let index = self.entry_index(index)?;
+        let entry = &self.rows[index];
+        let row = entry.row.get_or_init(|| {
+            self.inflated.lock().unwrap().push(self.removed + index);
+            Box::new(self.read(entry))
+        });
+
+        Some(row)
+    }
+
+    /// Read a row without keeping it inflated, with `0` being the most recent row.
+    pub fn row(&self, index: usize) -> Option<Cow<'_, Row<T>>>
+    where
+        T: Clone,
+    {
+        let entry = &self.rows[self.entry_index(index)?];
+        match entry.row.get() {
+            Some(row) => Some(Cow::Borrowed(row)),
+            None => Some(Cow::Owned(self.read(entry))),
+        }
+    }
+
+    /// Bookmark of a row, without decoding it.
+    pub fn mark(&self, index: usize) -> Option<LineMark> {
+        let entry = &self.rows[self.entry_index(index)?];
+        match entry.row.get() {
+            Some(row) => row.mark(),
+            None => compressed::encoded_mark(self.bytes(entry)),
+        }
+    }
+
+    /// Shell integration mark of a row, without decoding it.
+    pub fn prompt_mark(&self, index: usize) -> Option<PromptMark> {
+        let entry = &self.rows[self.entry_index(index)?];
+        match entry.row.get() {
+            Some(row) => row.prompt_mark(),
+            None => compressed::encoded_prompt_mark(self.bytes(entry)),
+        }
+    }
+
+    /// Mutably access a row, with `0` being the most recent row.
+    ///
+    /// The row is written to the file again once inflated rows are released.
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut Row<T>> {
+        self.get(index)?;
+
+        let index = self.entry_index(index)?;
+        self.dirty.insert(self.removed + index);
+
+        self.rows[index].row.get_mut().map(|row| &mut **row)
+    }
+
+    /// Index of a row in `rows`, with `0` being the most recent row.
+    #[inline]
+    fn entry_index(&self, index: usize) -> Option<usize> {
+        self.rows.len().checked_sub(index + 1)
+    }
+
+    /// Index of a row in `rows` from its id, if it is still stored in the file.
+    #[inline]
+    fn id_index(&self, id: usize) -> Option<usize> {
+        id.checked_sub(self.removed).filter(|index| *index < self.rows.len())
+    }
+
+    /// Append a new most recent row.
+    pub fn push(&mut self, row: &Row<T>) {
+        let mut buf = Vec::new();
+        (self.encode)(row, &mut buf);
+        self.push_encoded(&buf);
+    }
+
+    /// Append a new most recent row, which has already been compressed.
+    pub fn push_compressed(&mut self, row: &CompressedRow<T>) {
+        let mut buf = Vec::new();
+        (self.encode_compressed)(row, &mut buf);
+        self.push_encoded(&buf);
+    }
+
+    fn push_encoded(&mut self, buf: &[u8]) {
+        match self.write(buf) {
+            Ok(offset) => {
+                self.rows.push_back(SpillEntry { offset, len: buf.len(), row: OnceLock::new() })
+            },
+            Err(err) => error!("Unable to write scrollback to disk: {err}"),
+        }
+
+        self.truncate();
+    }
+
+    /// Remove the oldest rows until they fit within the maximum file size.
+    fn truncate(&mut self) {
+        let mut used = self.size + self.pending.len() - self.garbage;
+        if used <= self.max_size / 2 {
+            return;
+        }
+
+        while used > self.max_size / 2 {
+            let entry = match self.rows.pop_front() {
+                Some(entry) => entry,
+                None => break,
+            };
+
+            self.dirty.remove(&self.removed);
+            self.removed += 1;
+
+            self.garbage += entry.len;
+            used -= entry.len;
+        }
+
+        self.compact();
+    }
+
+    /// Remove the most recent row.
+    pub fn pop(&mut self) -> Option<Row<T>> {
+        let id = self.removed + self.rows.len().checked_sub(1)?;
+        self.dirty.remove(&id);
+        self.inflated.get_mut().unwrap().retain(|inflated| *inflated != id);
+
+        let mut entry = self.rows.pop_back()?;
+        let row = match entry.row.take() {
+            Some(row) => *row,
+            None => self.read(&entry),
+        };
+
+        self.garbage += entry.len;
+        self.compact();
+
+        Some(row)
+    }
+
+    /// Remove all rows.
+    pub fn clear(&mut self) {
+        self.rows.clear();
+        self.removed = 0;
+        self.dirty.clear();
+        self.inflated.get_mut().unwrap().clear();
+        self.pending.clear();
+        self.size = 0;
+        self.garbage = 0;
+
+        if let Err(err) = self.file.set_len(0) {
+            error!("Unable to truncate scrollback file: {err}");
+        }
+    }
+
+    /// Update the number of columns of inflated rows.
+    pub fn set_columns(&mut self, columns: usize) {
+        if self.columns != columns {
+            self.release();
+            self.columns = columns;
+        }
+    }
+
+    /// Release all inflated rows, if too many rows have been inflated.
+    pub fn release_inflated(&mut self) {
+        if self.inflated.get_mut().unwrap().len() >= MAX_INFLATED_ROWS {
+            self.release();
+        }
+    }
+
+    /// Write modified rows back to the file and release all inflated rows.
+    fn release(&mut self) {
+        let mut buf = Vec::new();
+        for id in std::mem::take(&mut self.dirty) {
+            let index = match self.id_index(id) {
+                Some(index) => index,
+                None => continue,
+            };
+            let row = match self.rows[index].row.get() {
+                Some(row) => row,
+                None => continue,
+            };
+
+            buf.clear();
+            (self.encode)(row, &mut buf);
+
+            match self.write(&buf) {
+                Ok(offset) => {
+                    self.garbage += self.rows[index].len;
+                    self.rows[index].offset = offset;
+                    self.rows[index].len = buf.len();
+                },
+                Err(err) => error!("Unable to write scrollback to disk: {err}"),
+            }
+        }
+
+        for id in std::mem::take(self.inflated.get_mut().unwrap()) {
+            if let Some(index) = self.id_index(id) {
+                self.rows[index].row.take();
+            }
+        }
+
+        self.compact();
+    }
+
+    /// Rewrite the file without unused bytes, once they make up most of it.
+    ///
+    /// Unused bytes are left behind whenever rows are removed or written again after they were
+    /// modified.
+    fn compact(&mut self) {
+        let written = self.size + self.pending.len();
+        if self.garbage < MIN_MAP_SIZE || self.garbage * 2 < written {
+            return;
+        }
+
+        let mut compacted = match self.empty_copy(self.columns) {
+            Ok(compacted) => compacted,
+            Err(err) => {
+                error!("Unable to compact scrollback file: {err}");
+                return;
+            },
+        };
+
+        // Copy the encoded rows, without inflating any of them.
+        let mut offsets = Vec::with_capacity(self.rows.len());
+        for entry in &self.rows {
+            match compacted.write(self.bytes(entry)) {
+                Ok(offset) => offsets.push(offset),
+                Err(err) => {
+                    error!("Unable to compact scrollback file: {err}");
+                    return;
+                },
+            }
+        }
+
+        for (entry, offset) in self.rows.iter_mut().zip(offsets) {
+            entry.offset = offset;
+        }
+
+        self.file = compacted.file;
+        self.map = compacted.map;
+        self.size = compacted.size;
+        self.pending = compacted.pending;
+        self.garbage = 0;
+    }
+
+    /// Decode a row from the file.
+    fn read(&self, entry: &SpillEntry<T>) -> Row<T> {
+        (self.decode)(self.bytes(entry), self.columns)
+    }
+
+    /// Encoded bytes of a row.
+    fn bytes(&self, entry: &SpillEntry<T>) -> &[u8] {
+        match entry.offset.checked_sub(self.size) {
+            Some(offset) => &self.pending[offset..offset + entry.len],
+            None => &self.map.as_slice()[entry.offset..entry.offset + entry.len],
+        }
+    }
+
+    /// Append bytes to the file, returning their offset.
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        if self.pending.len() + bytes.len() > WRITE_BUFFER_SIZE {
+            self.flush()?;
+        }
+
+        let offset = self.size + self.pending.len();
+        self.pending.extend_from_slice(bytes);
+
+        Ok(offset)
+    }
+
+    /// Write all buffered bytes to the file.
+    fn flush(&mut self) -> io::Result<()> {
+        write_at(&self.file, &self.pending, self.size)?;
+        self.size += self.pending.len();
+        self.pending.clear();
+
+        // Grow the map to cover all written bytes.
+        if self.size > self.map.len() {
+            let len = self.size.max(self.map.len() * 2).max(MIN_MAP_SIZE);
+            self.map = Mmap::new(&self.file, len)?;
+        }
+
+        Ok(())
+    }
+
+    /// Create a new empty file, storing rows with `columns` columns.
+    pub fn empty_copy(&self, columns: usize) -> io::Result<Self> {
+        let mut copy = Self::with_codec(columns, self.encode, self.encode_compressed, self.decode)?;
+        copy.max_size = self.max_size;
+        Ok(copy)
+    }
+
+    /// Create a copy of the file with all its rows.
+    ///
+    /// Rows are copied in their encoded form, only modified rows are encoded again.
+    pub fn try_clone(&self) -> io::Result<Self> {
+        let mut clone = self.empty_copy(self.columns)?;
+
+        let mut buf = Vec::new();
+        for (index, entry) in self.rows.iter().enumerate() {
+            let bytes = match entry.row.get() {
+                Some(row) if self.dirty.contains(&(self.removed + index)) => {
+                    buf.clear();
+                    (self.encode)(row, &mut buf);
+                    &buf[..]
+                },
+                _ => self.bytes(entry),
+            };
+
+            let offset = clone.write(bytes)?;
+            clone.rows.push_back(SpillEntry { offset, len: bytes.len(), row: OnceLock::new() });
+        }
+
+        Ok(clone)
+    }
+}
+
+/// Encode a row in its compressed form.
+fn encode_row<T>(row: &Row<T>, buf: &mut Vec<u8>)
+where
+    T: SpillCell + GridCell + Clone + Default + PartialEq,
+{
+    CompressedRow::new(row).encode(buf);
+}
+
+/// Decode a row from its compressed form, resizing it to `columns`.
+fn decode_row<T>(bytes: &[u8], columns: usize) -> Row<T>
+where
+    T: SpillCell + GridCell + Clone + Default + PartialEq,
+{
+    let mut row = match CompressedRow::decode(bytes) {
+        Some(compressed) => compressed.inflate(),
+        None => {
+            error!("Invalid scrollback row in spill file");
+            return Row::new(columns);
+        },
+    };
+
+    if row.len() < columns {
+        row.grow(columns);
+    } else {
+        row.shrink(columns);
+    }
+
+    row
+}
+
+#[cfg(unix)]
+fn create_private_file(path: &std::path::Path) -> io::Result<File> {
+    use std::fs::OpenOptions;
+    use std::os::unix::fs::OpenOptionsExt;
+
+    OpenOptions::new().read(true).write(true).create_new(true).mode(0o600).open(path)
+}
+
+#[cfg(not(unix))]
+fn create_private_file(_path: &std::path::Path) -> io::Result<File> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "scrollback spill files require unix"))
+}
+
+#[cfg(unix)]
+fn write_at(file: &File, bytes: &[u8], offset: usize) -> io::Result<()> {
+    use std::os::unix::fs::FileExt;
+
+    file.write_all_at(bytes, offset as u64)
+}
+
+#[cfg(not(unix))]
+fn write_at(_file: &File, _bytes: &[u8], _offset: usize) -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "scrollback spill files require unix"))
+}
+
+/// Read-only shared memory map of a file.
+#[derive(Debug)]
+struct Mmap {
+    ptr: *mut libc::c_void,
+    len: usize,
+}
+
+// SAFETY: The map is read-only and only replaced through mutable references.
+unsafe impl Send for Mmap {}
+unsafe impl Sync for Mmap {}
+
+impl Default for Mmap {
+    fn default() -> Self {
+        Self { ptr: std::ptr::null_mut(), len: 0 }
+    }
+}
+
+impl Mmap {
+    #[cfg(unix)]
+    fn new(file: &File, len: usize) -> io::Result<Self> {
+        use std::os::unix::io::AsRawFd;
+
+        // SAFETY: Mapping a file we own, access is limited to bytes which have been written.
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ,
+                libc::MAP_SHARED,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(Self { ptr, len })
+    }
+
+    #[cfg(not(unix))]
+    fn new(_file: &File, _len: usize) -> io::Result<Self> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "scrollback spill files require unix"))
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        if self.ptr.is_null() {
+            return &[];
+        }
+
+        // SAFETY: The pointer is valid for `len` bytes while the map is alive.
+        unsafe { std::slice::from_raw_parts(self.ptr as *const u8, self.len) }
+    }
+}
+
+impl Drop for Mmap {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if !self.ptr.is_null() {
+            // SAFETY: The map was created by `mmap` with the same length.
+            unsafe {
+                libc::munmap(self.ptr, self.len);
+            }
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::os::unix::fs::PermissionsExt;
+
+    use crate::grid::row::Row;
+    use crate::index::Column;
+    use crate::term::cell::{Cell, Flags, Hyperlink};
+    use crate::vte::ansi::{Color, NamedColor, Rgb};
+
+    use super::{create_private_file, SpillFile};
+
+    fn row(text: &str) -> Row<Cell> {
+        let mut row = Row::<Cell>::new(10);
+        for (i, c) in text.chars().enumerate() {
+            row[Column(i)].c = c;
+        }
+        row
+    }
+
+    #[test]
+    fn push_and_read() {
+        let mut spill = SpillFile::<Cell>::new(10).unwrap();
+
+        let mut styled = row("styled");
+        styled[Column(0)].fg = Color::Spec(Rgb { r: 1, g: 2, b: 3 });
+        styled[Column(1)].bg = Color::Named(NamedColor::DimCyan);
+        styled[Column(2)].flags.insert(Flags::BOLD | Flags::PROTECTED);
+        styled[Column(3)].push_zerowidth('\u{301}');
+        styled[Column(4)].set_underline_color(Some(Color::Indexed(42)));
+        styled[Column(5)].set_hyperlink(Some(Hyperlink::new(Some("id"), "https://x".into())));
+
+        spill.push(&row("first"));
+        spill.push(&styled);
+
+        assert_eq!(spill.len(), 2);
+        assert_eq!(spill.get(0), Some(&styled));
+        assert_eq!(spill.get(1), Some(&row("first")));
+        assert_eq!(spill.get(2), None);
+    }
+
+    #[test]
+    fn modified_rows_are_written_back() {
+        let mut spill = SpillFile::<Cell>::new(10).unwrap();
+        spill.push(&row("old"));
+
+        spill.get_mut(0).unwrap()[Column(0)].c = 'n';
+        spill.set_columns(5);
+
+        let mut expected = row("nld");
+        expected.shrink(5);
+        assert_eq!(spill.get(0), Some(&expected));
+        assert_eq!(spill.pop(), Some(expected));
+        assert_eq!(spill.len(), 0);
+    }
+
+    #[test]
+    fn clone_modified_rows() {
+        let mut spill = SpillFile::<Cell>::new(10).unwrap();
+        spill.push(&row("old"));
+        spill.push(&row("other"));
+
+        spill.get_mut(1).unwrap()[Column(0)].c = 'n';
+
+        let clone = spill.try_clone().unwrap();
+        assert_eq!(clone.get(0), Some(&row("other")));
+        assert_eq!(clone.get(1), Some(&row("nld")));
+    }
+
+    #[test]
+    fn compact_removed_rows() {
+        let mut spill = SpillFile::<Cell>::new(10).unwrap();
+        let mut long = row("long");
+        for i in 0..10 {
+            long[Column(i)].push_zerowidth('\u{301}');
+        }
+
+        // Removed rows leave unused bytes behind, until most of the file is unused.
+        let mut written = 0;
+        while spill.garbage == 0 || written < 2 * super::MIN_MAP_SIZE {
+            spill.push(&long);
+            written += spill.rows.back().unwrap().len;
+            if spill.len() > 1 {
+                spill.pop();
+            }
+        }
+
+        assert_eq!(spill.len(), 1);
+        assert!(spill.garbage < super::MIN_MAP_SIZE);
+        assert!(spill.size + spill.pending.len() < written);
+        assert_eq!(spill.get(0), Some(&long));
+    }
+
+    #[test]
+    fn oldest_rows_exceeding_max_size() {
+        let mut spill = SpillFile::<Cell>::new(10).unwrap();
+        spill.max_size = 4 * super::MIN_MAP_SIZE;
+
+        spill.push(&row("old"));
+        spill.get_mut(0).unwrap()[Column(0)].c = 'n';
+        let len = spill.rows[0].len;
+
+        // Inflated and modified rows stay valid while the oldest rows are removed.
+        let mut count = 1;
+        while spill.removed == 0 {
+            spill.push(&row("new"));
+            count += 1;
+        }
+        spill.get_mut(0).unwrap()[Column(0)].c = 'm';
+        for _ in 0..10 {
+            spill.push(&row("new"));
+            count += 1;
+        }
+        spill.set_columns(5);
+
+        let mut expected = row("mew");
+        expected.shrink(5);
+        assert_eq!(spill.len() + spill.removed, count);
+        assert!(spill.len() * len <= spill.max_size / 2);
+        assert!(spill.size + spill.pending.len() <= spill.max_size);
+        assert_eq!(spill.get(10), Some(&expected));
+        assert!(spill.dirty.is_empty());
+    }
+
+    #[test]
+    fn private_permissions() {
+        let path =
+            std::env::temp_dir().join(format!("larashell-spill-test-{}", std::process::id()));
+        let file = create_private_file(&path).unwrap();
+        let mode = file.metadata().unwrap().permissions().mode();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(mode & 0o777, 0o600);
+    }
+}
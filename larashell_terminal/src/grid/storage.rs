@@ -1,3 +1,4 @@
+use std::borrow::Cow;
 use std::cmp::max;
 use std::mem;
 use std::mem::MaybeUninit;
@@ -6,7 +7,9 @@ use std::ops::{Index, IndexMut};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-use super::Row;
+use super::compressed::CompressedHistory;
+use super::spill::SpillFile;
+use super::{GridCell, LineMark, PromptMark, Row};
 use crate::index::Line;
 
 /// Maximum number of buffered lines outside of the grid for performance optimization.
@@ -25,6 +28,9 @@ const MAX_CACHE_SIZE: usize = 1_000;
 /// implementation is provided. Anything from [`Vec`] that should be exposed must be done so
 /// manually.
 ///
+/// Lines which are rotated out of the ring buffer can be kept in a [`CompressedHistory`] above
+/// the topmost line of the ring buffer, to allow for bigger scrollback buffers.
+///
 /// [`slice::rotate_left`]: https://doc.rust-lang.org/std/primitive.slice.html#method.rotate_left
 /// [`Deref`]: std::ops::Deref
 /// [`zero`]: #structfield.zero
@@ -50,6 +56,19 @@ pub struct Storage<T> {
     /// As long as `len` is bigger than `inner`, it is also possible to grow the scrollback buffer
     /// without any additional insertions.
     len: usize,
+
+    /// Compressed lines above the topmost line of the ring buffer.
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default,
+            bound(
+                serialize = "T: Serialize + Clone",
+                deserialize = "T: Deserialize<'de> + GridCell + Clone + Default + PartialEq",
+            )
+        )
+    )]
+    compressed: CompressedHistory<T>,
 }
 
 impl<T: PartialEq> PartialEq for Storage<T> {
@@ -58,7 +77,7 @@ impl<T: PartialEq> PartialEq for Storage<T> {
         assert_eq!(self.zero, 0);
         assert_eq!(other.zero, 0);
 
-        self.inner == other.inner && self.len == other.len
+        self.inner == other.inner && self.len == other.len && self.compressed == other.compressed
     }
 }
 
@@ -72,7 +91,13 @@ impl<T> Storage<T> {
         let mut inner = Vec::with_capacity(visible_lines);
         inner.resize_with(visible_lines, || Row::new(columns));
 
-        Storage { inner, zero: 0, visible_lines, len: visible_lines }
+        Storage {
+            inner,
+            zero: 0,
+            visible_lines,
+            len: visible_lines,
+            compressed: Default::default(),
+        }
     }
 
     /// Increase the number of lines in the buffer.
@@ -142,16 +167,163 @@ impl<T> Storage<T> {
         self.len
     }
 
+    /// Number of lines in the compressed history, including lines stored on disk.
+    #[inline]
+    pub fn compressed_len(&self) -> usize {
+        self.compressed.len()
+    }
+
+    /// Number of compressed lines stored on disk.
+    #[cfg(test)]
+    #[inline]
+    pub fn spilled_len(&self) -> usize {
+        self.compressed.spilled_len()
+    }
+
+    /// Whether compressed lines exceeding the memory limit are stored on disk.
+    #[inline]
+    pub fn has_spill(&self) -> bool {
+        self.compressed.has_spill()
+    }
+
+    /// Set the file for storing compressed lines exceeding the memory limit.
+    #[inline]
+    pub fn set_spill(&mut self, spill: Option<SpillFile<T>>) {
+        self.compressed.set_spill(spill);
+    }
+
+    /// Resize all lines of the compressed history, reflowing them if `reflow` is enabled.
+    #[inline]
+    pub fn resize_compressed(&mut self, reflow: bool, columns: usize)
+    where
+        T: GridCell + Clone + Default + PartialEq,
+    {
+        self.compressed.resize(reflow, columns);
+    }
+
+    /// Maximum memory used by the compressed history in bytes.
+    #[inline]
+    pub fn max_compressed_size(&self) -> usize {
+        self.compressed.max_size()
+    }
+
+    /// Update the maximum memory used by the compressed history in bytes.
+    ///
+    /// Setting this to zero disables the compressed history, unless lines are stored on disk.
+    #[inline]
+    pub fn set_max_compressed_size(&mut self, max_size: usize) {
+        self.compressed.set_max_size(max_size);
+    }
+
+    /// Remove all lines from the compressed history.
+    #[inline]
+    pub fn clear_compressed(&mut self) {
+        self.compressed.clear();
+    }
+
+    /// Copy the topmost lines of the ring buffer into the compressed history.
+    ///
+    /// This should be called before these lines are removed from the ring buffer, either by
+    /// rotating them to the bottom or shrinking the buffer.
+    pub fn compress(&mut self, count: usize)
+    where
+        T: GridCell + Clone + Default + PartialEq,
+    {
+        if self.compressed.max_size() == 0 && !self.compressed.has_spill() {
+            return;
+        }
+
+        // Start with the oldest line, since every line is added as the most recent one.
+        for distance in (self.len - count.min(self.len)..self.len).rev() {
+            let index = self.raw_index(distance);
+            self.compressed.push(&self.inner[index]);
+        }
+    }
+
+    /// Move lines from the compressed history back into the ring buffer.
+    pub fn decompress(&mut self, count: usize) {
+        for _ in 0..count {
+            let row = match self.compressed.pop() {
+                Some(row) => row,
+                None => break,
+            };
+
+            if self.len == self.inner.len() {
+                self.rezero();
+                self.inner.push(row);
+            } else {
+                let index = self.raw_index(self.len);
+                self.inner[index] = row;
+            }
+
+            self.len += 1;
+        }
+    }
+
+    /// Compress inflated lines of the compressed history again, if necessary.
+    #[inline]
+    pub fn release_inflated(&mut self)
+    where
+        T: GridCell + Clone + Default + PartialEq,
+    {
+        self.compressed.release_inflated();
+    }
+
+    /// Read a line without keeping compressed lines inflated.
+    #[inline]
+    pub fn row(&self, line: Line) -> Cow<'_, Row<T>>
+    where
+        T: Clone,
+    {
+        let distance = self.distance(line);
+        if distance < self.len {
+            Cow::Borrowed(&self.inner[self.raw_index(distance)])
+        } else {
+            self.compressed.row(distance - self.len).expect("line out of bounds")
+        }
+    }
+
+    /// Bookmark of a line, without inflating compressed lines.
+    #[inline]
+    pub fn mark(&self, line: Line) -> Option<LineMark> {
+        let distance = self.distance(line);
+        if distance < self.len {
+            self.inner[self.raw_index(distance)].mark()
+        } else {
+            self.compressed.mark(distance - self.len)
+        }
+    }
+
+    /// Shell integration mark of a line, without inflating compressed lines.
+    #[inline]
+    pub fn prompt_mark(&self, line: Line) -> Option<PromptMark> {
+        let distance = self.distance(line);
+        if distance < self.len {
+            self.inner[self.raw_index(distance)].prompt_mark()
+        } else {
+            self.compressed.prompt_mark(distance - self.len)
+        }
+    }
+
+    /// Set the bookmark of a line, without inflating compressed lines.
+    #[inline]
+    pub fn set_mark(&mut self, line: Line, mark: Option<LineMark>) {
+        let distance = self.distance(line);
+        if distance < self.len {
+            let index = self.raw_index(distance);
+            self.inner[index].set_mark(mark);
+        } else {
+            self.compressed.set_mark(distance - self.len, mark);
+        }
+    }
+
     /// Swap implementation for Row<T>.
     ///
     /// Exploits the known size of Row<T> to produce a slightly more efficient
-    /// swap than going through slice::swap.
-    ///
-    /// The default implementation from swap generates 8 movups and 4 movaps
-    /// instructions. This implementation achieves the swap in only 8 movups
-    /// instructions.
+    /// swap than going through slice::swap, by copying it one qword at a time.
     pub fn swap(&mut self, a: Line, b: Line) {
-        debug_assert_eq!(mem::size_of::<Row<T>>(), mem::size_of::<usize>() * 4);
+        const QWORDS: usize = 5;
+        debug_assert_eq!(mem::size_of::<Row<T>>(), mem::size_of::<usize>() * QWORDS);
 
         let a = self.compute_index(a);
         let b = self.compute_index(b);
@@ -167,7 +339,7 @@ impl<T> Storage<T> {
             //
             // The optimizer unrolls this loop and vectorizes it.
             let mut tmp: MaybeUninit<usize>;
-            for i in 0..4 {
+            for i in 0..QWORDS as isize {
                 tmp = *a_ptr.offset(i);
                 *a_ptr.offset(i) = *b_ptr.offset(i);
                 *b_ptr.offset(i) = tmp;
@@ -218,18 +390,30 @@ impl<T> Storage<T> {
     /// Compute actual index in underlying storage given the requested index.
     #[inline]
     fn compute_index(&self, requested: Line) -> usize {
-        debug_assert!(requested.0 < self.visible_lines as i32);
+        let distance = self.distance(requested);
+
+        debug_assert!(distance < self.len);
+
+        self.raw_index(distance)
+    }
 
-        let positive = -(requested - self.visible_lines).0 as usize - 1;
+    /// Compute the distance of a line from the bottommost line.
+    #[inline]
+    fn distance(&self, requested: Line) -> usize {
+        debug_assert!(requested.0 < self.visible_lines as i32);
 
-        debug_assert!(positive < self.len);
+        -(requested - self.visible_lines).0 as usize - 1
+    }
 
-        let zeroed = self.zero + positive;
+    /// Compute actual index in underlying storage given the distance from the bottommost line.
+    #[inline]
+    fn raw_index(&self, distance: usize) -> usize {
+        let zeroed = self.zero + distance;
 
         // Use if/else instead of remainder here to improve performance.
         //
         // Requires `zeroed` to be smaller than `self.inner.len() * 2`,
-        // but both `self.zero` and `requested` are always smaller than `self.inner.len()`.
+        // but both `self.zero` and `distance` are always smaller than `self.inner.len()`.
         if zeroed >= self.inner.len() {
             zeroed - self.inner.len()
         } else {
@@ -254,16 +438,25 @@ impl<T> Index<Line> for Storage<T> {
 
     #[inline]
     fn index(&self, index: Line) -> &Self::Output {
-        let index = self.compute_index(index);
-        &self.inner[index]
+        let distance = self.distance(index);
+        if distance < self.len {
+            &self.inner[self.raw_index(distance)]
+        } else {
+            self.compressed.get(distance - self.len).expect("line out of bounds")
+        }
     }
 }
 
 impl<T> IndexMut<Line> for Storage<T> {
     #[inline]
     fn index_mut(&mut self, index: Line) -> &mut Self::Output {
-        let index = self.compute_index(index);
-        &mut self.inner[index]
+        let distance = self.distance(index);
+        if distance < self.len {
+            let index = self.raw_index(distance);
+            &mut self.inner[index]
+        } else {
+            self.compressed.get_mut(distance - self.len).expect("line out of bounds")
+        }
     }
 }
 
@@ -358,6 +551,7 @@ mod tests {
             zero: 0,
             visible_lines: 3,
             len: 3,
+            compressed: Default::default(),
         };
 
         // Grow buffer.
@@ -369,6 +563,7 @@ mod tests {
             zero: 0,
             visible_lines: 4,
             len: 4,
+            compressed: Default::default(),
         };
         expected.inner.append(&mut vec![filled_row('\0'); MAX_CACHE_SIZE]);
 
@@ -399,6 +594,7 @@ mod tests {
             zero: 1,
             visible_lines: 3,
             len: 3,
+            compressed: Default::default(),
         };
 
         // Grow buffer.
@@ -410,6 +606,7 @@ mod tests {
             zero: 0,
             visible_lines: 4,
             len: 4,
+            compressed: Default::default(),
         };
         expected.inner.append(&mut vec![filled_row('\0'); MAX_CACHE_SIZE]);
 
@@ -437,6 +634,7 @@ mod tests {
             zero: 1,
             visible_lines: 3,
             len: 3,
+            compressed: Default::default(),
         };
 
         // Shrink buffer.
@@ -448,6 +646,7 @@ mod tests {
             zero: 1,
             visible_lines: 2,
             len: 2,
+            compressed: Default::default(),
         };
         assert_eq!(storage.visible_lines, expected.visible_lines);
         assert_eq!(storage.inner, expected.inner);
@@ -473,6 +672,7 @@ mod tests {
             zero: 0,
             visible_lines: 3,
             len: 3,
+            compressed: Default::default(),
         };
 
         // Shrink buffer.
@@ -484,6 +684,7 @@ mod tests {
             zero: 0,
             visible_lines: 2,
             len: 2,
+            compressed: Default::default(),
         };
         assert_eq!(storage.visible_lines, expected.visible_lines);
         assert_eq!(storage.inner, expected.inner);
@@ -522,6 +723,7 @@ mod tests {
             zero: 2,
             visible_lines: 6,
             len: 6,
+            compressed: Default::default(),
         };
 
         // Shrink buffer.
@@ -540,6 +742,7 @@ mod tests {
             zero: 2,
             visible_lines: 2,
             len: 2,
+            compressed: Default::default(),
         };
         assert_eq!(storage.visible_lines, expected.visible_lines);
         assert_eq!(storage.inner, expected.inner);
@@ -574,6 +777,7 @@ mod tests {
             zero: 2,
             visible_lines: 1,
             len: 2,
+            compressed: Default::default(),
         };
 
         // Truncate buffer.
@@ -585,6 +789,7 @@ mod tests {
             zero: 0,
             visible_lines: 1,
             len: 2,
+            compressed: Default::default(),
         };
         assert_eq!(storage.visible_lines, expected.visible_lines);
         assert_eq!(storage.inner, expected.inner);
@@ -609,6 +814,7 @@ mod tests {
             zero: 2,
             visible_lines: 1,
             len: 2,
+            compressed: Default::default(),
         };
 
         // Truncate buffer.
@@ -620,6 +826,7 @@ mod tests {
             zero: 0,
             visible_lines: 1,
             len: 2,
+            compressed: Default::default(),
         };
         assert_eq!(storage.visible_lines, expected.visible_lines);
         assert_eq!(storage.inner, expected.inner);
@@ -666,6 +873,7 @@ mod tests {
             zero: 2,
             visible_lines: 0,
             len: 6,
+            compressed: Default::default(),
         };
 
         // Shrink buffer.
@@ -684,6 +892,7 @@ mod tests {
             zero: 2,
             visible_lines: 0,
             len: 3,
+            compressed: Default::default(),
         };
         assert_eq!(storage.inner, shrinking_expected.inner);
         assert_eq!(storage.zero, shrinking_expected.zero);
@@ -705,6 +914,7 @@ mod tests {
             zero: 2,
             visible_lines: 0,
             len: 4,
+            compressed: Default::default(),
         };
 
         assert_eq!(storage.inner, growing_expected.inner);
@@ -727,6 +937,7 @@ mod tests {
             zero: 2,
             visible_lines: 0,
             len: 6,
+            compressed: Default::default(),
         };
 
         // Initialize additional lines.
@@ -744,7 +955,13 @@ mod tests {
         ];
         let expected_init_size = std::cmp::max(init_size, MAX_CACHE_SIZE);
         expected_inner.append(&mut vec![filled_row('\0'); expected_init_size]);
-        let expected_storage = Storage { inner: expected_inner, zero: 0, visible_lines: 0, len: 9 };
+        let expected_storage = Storage {
+            inner: expected_inner,
+            zero: 0,
+            visible_lines: 0,
+            len: 9,
+            compressed: Default::default(),
+        };
 
         assert_eq!(storage.len, expected_storage.len);
         assert_eq!(storage.zero, expected_storage.zero);
@@ -758,6 +975,7 @@ mod tests {
             zero: 2,
             visible_lines: 0,
             len: 3,
+            compressed: Default::default(),
         };
 
         storage.rotate(2);
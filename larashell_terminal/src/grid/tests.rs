@@ -2,7 +2,11 @@
 
 use super::*;
 
+use crate::event::VoidListener;
 use crate::term::cell::Cell;
+use crate::term::test::TermSize;
+use crate::term::{Config, Term};
+use crate::vte::ansi::{Color, Handler, Hyperlink as VteHyperlink};
 
 impl GridCell for usize {
     fn is_empty(&self) -> bool {
@@ -348,6 +352,401 @@ fn shrink_reflow_disabled() {
     assert_eq!(grid[Line(0)][Column(1)], cell('2'));
 }
 
+#[test]
+fn copy_overlapping_rectangle() {
+    let mut grid = Grid::<Cell>::new(3, 4, 0);
+    for (i, c) in "abcd".chars().enumerate() {
+        grid[Line(0)][Column(i)] = cell(c);
+    }
+
+    grid.copy_rectangle(
+        Rectangle::new(Line(0), Column(0), Line(0), Column(2)),
+        Point::new(Line(0), Column(1)),
+    );
+
+    let text: String = grid[Line(0)][..].iter().map(|cell| cell.c).collect();
+    assert_eq!(text, "aabc");
+}
+
+#[test]
+fn fill_rectangle_splits_wide_chars() {
+    let mut grid = Grid::<Cell>::new(1, 4, 0);
+    grid[Line(0)][Column(0)] = cell('汉');
+    grid[Line(0)][Column(0)].flags.insert(Flags::WIDE_CHAR);
+    grid[Line(0)][Column(1)].flags.insert(Flags::WIDE_CHAR_SPACER);
+    grid[Line(0)][Column(2)] = cell('字');
+    grid[Line(0)][Column(2)].flags.insert(Flags::WIDE_CHAR);
+    grid[Line(0)][Column(3)].flags.insert(Flags::WIDE_CHAR_SPACER);
+
+    grid.fill_rectangle(Rectangle::new(Line(0), Column(1), Line(0), Column(2)), &cell('x'));
+
+    assert_eq!(grid[Line(0)][Column(0)], Cell::default());
+    assert_eq!(grid[Line(0)][Column(1)], cell('x'));
+    assert_eq!(grid[Line(0)][Column(2)], cell('x'));
+    assert_eq!(grid[Line(0)][Column(3)], Cell::default());
+}
+
+#[test]
+fn compressed_history_scroll_up() {
+    let mut grid = Grid::<Cell>::new(2, 4, 2);
+    grid.update_compressed_history(1 << 20);
+
+    for i in 0..10 {
+        grid[Line(1)][Column(0)] = cell(char::from_digit(i, 10).unwrap());
+        grid.scroll_up::<Color>(&(Line(0)..Line(2)), 1);
+    }
+
+    assert_eq!(grid.total_lines(), 12);
+    assert_eq!(grid.raw.compressed_len(), 8);
+    for i in 0..10 {
+        assert_eq!(grid[Line(i - 9)][Column(0)], cell(char::from_digit(i as u32, 10).unwrap()));
+    }
+    assert_eq!(grid[Line(-10)][Column(0)], Cell::default());
+
+    grid.update_compressed_history(0);
+
+    assert_eq!(grid.total_lines(), 4);
+}
+
+#[test]
+fn compressed_history_reflow() {
+    let mut grid = Grid::<Cell>::new(1, 4, 0);
+    grid.update_compressed_history(1 << 20);
+    for (i, c) in "abcd".chars().enumerate() {
+        grid[Line(0)][Column(i)] = cell(c);
+    }
+    grid.scroll_up::<Color>(&(Line(0)..Line(1)), 1);
+
+    grid.resize(true, 1, 2);
+
+    assert_eq!(grid.total_lines(), 3);
+    assert_eq!(grid.raw.compressed_len(), 2);
+    assert_eq!(grid[Line(-2)][Column(0)], cell('a'));
+    assert_eq!(grid[Line(-2)][Column(1)], wrap_cell('b'));
+    assert_eq!(grid[Line(-1)][Column(0)], cell('c'));
+    assert_eq!(grid[Line(-1)][Column(1)], cell('d'));
+}
+
+#[test]
+fn compressed_history_reflow_wrapped() {
+    let mut grid = Grid::<Cell>::new(1, 2, 0);
+    grid.update_compressed_history(1 << 20);
+    grid[Line(0)][Column(0)] = cell('a');
+    grid[Line(0)][Column(1)] = wrap_cell('b');
+    grid.scroll_up::<Color>(&(Line(0)..Line(1)), 1);
+    grid[Line(0)][Column(0)] = cell('c');
+    grid.scroll_up::<Color>(&(Line(0)..Line(1)), 1);
+
+    assert_eq!(grid.raw.compressed_len(), 2);
+
+    grid.resize(true, 1, 4);
+
+    assert_eq!(grid.total_lines(), 2);
+    assert_eq!(grid.raw.compressed_len(), 1);
+    assert_eq!(grid[Line(-1)][Column(0)], cell('a'));
+    assert_eq!(grid[Line(-1)][Column(1)], cell('b'));
+    assert_eq!(grid[Line(-1)][Column(2)], cell('c'));
+    assert_eq!(grid[Line(-1)][Column(3)], Cell::default());
+}
+
+#[test]
+fn compressed_history_grow_lines() {
+    let mut grid = Grid::<Cell>::new(2, 1, 0);
+    grid.update_compressed_history(1 << 20);
+    grid[Line(0)][Column(0)] = cell('1');
+    grid[Line(1)][Column(0)] = cell('2');
+    grid.scroll_up::<Color>(&(Line(0)..Line(2)), 2);
+
+    assert_eq!(grid.raw.compressed_len(), 2);
+
+    grid.resize(true, 4, 1);
+
+    assert_eq!(grid.total_lines(), 4);
+    assert_eq!(grid.raw.compressed_len(), 0);
+    assert_eq!(grid[Line(0)][Column(0)], cell('1'));
+    assert_eq!(grid[Line(1)][Column(0)], cell('2'));
+    assert_eq!(grid[Line(2)][Column(0)], Cell::default());
+}
+
+#[cfg(unix)]
+#[test]
+fn spilled_history_reflow() {
+    let mut grid = Grid::<Cell>::new(1, 4, 0);
+    grid.update_compressed_history(1);
+    grid.update_history_spill(true);
+    for (i, c) in "abcd".chars().enumerate() {
+        grid[Line(0)][Column(i)] = cell(c);
+    }
+    grid.scroll_up::<Color>(&(Line(0)..Line(1)), 1);
+
+    assert_eq!(grid.raw.spilled_len(), 1);
+
+    grid.resize(true, 1, 2);
+
+    assert_eq!(grid.total_lines(), 3);
+    assert_eq!(grid[Line(-2)][Column(0)], cell('a'));
+    assert_eq!(grid[Line(-2)][Column(1)], wrap_cell('b'));
+    assert_eq!(grid[Line(-1)][Column(0)], cell('c'));
+    assert_eq!(grid[Line(-1)][Column(1)], cell('d'));
+}
+
+#[test]
+fn marks_follow_scrolling() {
+    let mut grid = Grid::<Cell>::new(3, 1, 1);
+    grid.set_mark(Line(1), Some(LineMark::Named('a')));
+    grid.set_mark(Line(2), Some(LineMark::Anonymous));
+
+    grid.scroll_up::<Color>(&(Line(0)..Line(3)), 1);
+
+    assert_eq!(grid.find_mark(LineMark::Named('a')), Some(Line(0)));
+    assert_eq!(grid.next_mark(Line(2), Direction::Left), Some(Line(1)));
+    assert_eq!(grid.next_mark(Line(1), Direction::Left), Some(Line(0)));
+    assert_eq!(grid.next_mark(Line(0), Direction::Right), Some(Line(1)));
+    assert_eq!(grid.next_mark(Line(1), Direction::Right), None);
+
+    // Marks are removed together with lines rotated out of the history.
+    grid.scroll_up::<Color>(&(Line(0)..Line(3)), 2);
+
+    assert_eq!(grid.find_mark(LineMark::Named('a')), None);
+    assert_eq!(grid.next_mark(Line(2), Direction::Left), Some(Line(-1)));
+    assert_eq!(grid[Line(2)].mark(), None);
+}
+
+#[test]
+fn named_marks_are_unique() {
+    let mut grid = Grid::<Cell>::new(3, 1, 0);
+    grid.set_mark(Line(0), Some(LineMark::Named('a')));
+    grid.set_mark(Line(2), Some(LineMark::Named('a')));
+
+    assert_eq!(grid[Line(0)].mark(), None);
+    assert_eq!(grid.find_mark(LineMark::Named('a')), Some(Line(2)));
+}
+
+#[test]
+fn marks_survive_reflow() {
+    let mut grid = Grid::<Cell>::new(2, 2, 1);
+    grid[Line(0)][Column(0)] = cell('a');
+    grid[Line(0)][Column(1)] = wrap_cell('b');
+    grid[Line(1)][Column(0)] = cell('c');
+    grid.set_mark(Line(1), Some(LineMark::Named('a')));
+
+    grid.resize(true, 2, 4);
+
+    assert_eq!(grid[Line(0)][Column(2)], cell('c'));
+    assert_eq!(grid.find_mark(LineMark::Named('a')), Some(Line(0)));
+
+    grid.resize(true, 2, 2);
+
+    assert_eq!(grid[Line(-1)][Column(0)], cell('a'));
+    assert_eq!(grid.find_mark(LineMark::Named('a')), Some(Line(-1)));
+}
+
+#[test]
+fn compressed_history_keeps_marks() {
+    let mut grid = Grid::<Cell>::new(1, 1, 0);
+    grid.update_compressed_history(1 << 20);
+    grid[Line(0)][Column(0)] = cell('a');
+    grid.set_mark(Line(0), Some(LineMark::Named('a')));
+
+    grid.scroll_up::<Color>(&(Line(0)..Line(1)), 1);
+
+    assert_eq!(grid.raw.compressed_len(), 1);
+    assert_eq!(grid.find_mark(LineMark::Named('a')), Some(Line(-1)));
+}
+
+#[test]
+fn marks_follow_scroll_regions() {
+    let mut grid = Grid::<Cell>::new(4, 1, 1);
+    grid.set_mark(Line(1), Some(LineMark::Named('a')));
+    grid.set_mark(Line(3), Some(LineMark::Named('b')));
+
+    // Lines below the region stay in place while the region is rotated into history.
+    grid.scroll_up::<Color>(&(Line(0)..Line(3)), 1);
+
+    assert_eq!(grid.find_mark(LineMark::Named('a')), Some(Line(0)));
+    assert_eq!(grid.find_mark(LineMark::Named('b')), Some(Line(3)));
+
+    grid.scroll_down::<Color>(&(Line(0)..Line(3)), 2);
+
+    assert_eq!(grid.find_mark(LineMark::Named('a')), Some(Line(2)));
+    assert_eq!(grid.find_mark(LineMark::Named('b')), Some(Line(3)));
+    assert_eq!(grid.next_mark(Line(3), Direction::Left), Some(Line(2)));
+}
+
+#[test]
+fn cleared_lines_lose_marks() {
+    let mut grid = Grid::<Cell>::new(2, 1, 0);
+    grid.set_mark(Line(1), Some(LineMark::Anonymous));
+
+    grid.reset_region::<Color, _>(..);
+
+    assert_eq!(grid.next_mark(Line(0), Direction::Right), None);
+}
+
+#[test]
+fn compressed_marks_stay_compressed() {
+    let mut grid = Grid::<Cell>::new(1, 1, 0);
+    grid.update_compressed_history(1 << 20);
+    grid.scroll_up::<Color>(&(Line(0)..Line(1)), 1);
+    grid.scroll_up::<Color>(&(Line(0)..Line(1)), 1);
+
+    grid.set_mark(Line(-2), Some(LineMark::Named('a')));
+
+    assert_eq!(grid.find_mark(LineMark::Named('a')), Some(Line(-2)));
+    assert_eq!(grid.next_mark(Line(0), Direction::Left), Some(Line(-2)));
+    assert_eq!(grid.raw.compressed_len(), 2);
+    assert_eq!(*grid.raw.row(Line(-2)), {
+        let mut row = Row::new(1);
+        row.set_mark(Some(LineMark::Named('a')));
+        row
+    });
+    assert!(matches!(grid.raw.row(Line(-2)), Cow::Owned(_)));
+}
+
+#[test]
+fn reflow_roundtrip() {
+    for seed in 1..=500 {
+        let mut rng = XorShift(seed);
+
+        let columns = rng.below(20) + 2;
+        let config = Config { scrolling_history: 10_000, ..Config::default() };
+        let mut term = Term::new(config, &TermSize::new(columns, 4), VoidListener);
+        let mut lines = write_random_lines(&mut term, &mut rng);
+
+        for _ in 0..rng.below(6) + 1 {
+            let size = TermSize::new(rng.below(20) + 2, rng.below(6) + 1);
+            term.resize(size);
+
+            assert_eq!(logical_lines(&term), lines, "seed {seed}");
+            assert_valid_wide_chars(&term, seed);
+        }
+
+        // New input must continue the last line.
+        term.input('z');
+        lines.last_mut().unwrap().push(('z', None));
+        assert_eq!(logical_lines(&term), lines, "seed {seed}");
+    }
+}
+
+/// Deterministic random number generator for property tests.
+struct XorShift(u64);
+
+impl XorShift {
+    fn below(&mut self, max: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % max as u64) as usize
+    }
+}
+
+/// Character of a logical line, with the URI of its hyperlink.
+type LineChar = (char, Option<String>);
+
+/// Write random lines of narrow and wide characters with hyperlinks.
+fn write_random_lines(term: &mut Term<VoidListener>, rng: &mut XorShift) -> Vec<Vec<LineChar>> {
+    const CHARS: [char; 6] = ['a', 'b', 'c', '世', '界', '😀'];
+
+    let mut lines = Vec::new();
+    for i in 0..rng.below(8) + 1 {
+        if i > 0 {
+            term.carriage_return();
+            term.linefeed();
+        }
+
+        let mut line = Vec::new();
+        let mut uri = None;
+        for _ in 0..rng.below(40) + 1 {
+            // Toggle hyperlinks at random positions.
+            if rng.below(8) == 0 {
+                uri = match uri {
+                    Some(_) => None,
+                    None => Some(format!("https://example.org/{}", rng.below(100))),
+                };
+                term.set_hyperlink(uri.clone().map(|uri| VteHyperlink { id: None, uri }));
+            }
+
+            let c = CHARS[rng.below(CHARS.len())];
+            term.input(c);
+            line.push((c, uri.clone()));
+        }
+        term.set_hyperlink(None);
+
+        lines.push(line);
+    }
+
+    lines
+}
+
+/// Read all logical lines, without trailing empty cells and lines.
+fn logical_lines(term: &Term<VoidListener>) -> Vec<Vec<LineChar>> {
+    let grid = term.grid();
+    let spacers = Flags::WIDE_CHAR_SPACER | Flags::LEADING_WIDE_CHAR_SPACER;
+
+    let mut lines = vec![Vec::new()];
+    for line in (grid.topmost_line().0..=grid.bottommost_line().0).map(Line) {
+        let row = &grid[line];
+        for cell in row[..].iter().filter(|cell| !cell.flags.intersects(spacers)) {
+            let uri = cell.hyperlink().map(|hyperlink| hyperlink.uri().to_owned());
+            lines.last_mut().unwrap().push((cell.c, uri));
+        }
+
+        if !row[grid.last_column()].flags.contains(Flags::WRAPLINE) {
+            lines.push(Vec::new());
+        }
+    }
+
+    for line in &mut lines {
+        while line.last().is_some_and(|(c, uri)| *c == ' ' && uri.is_none()) {
+            line.pop();
+        }
+    }
+    while lines.last().is_some_and(Vec::is_empty) {
+        lines.pop();
+    }
+
+    lines
+}
+
+/// Check that wide chars are paired with their spacers and the cursor isn't inside one.
+fn assert_valid_wide_chars(term: &Term<VoidListener>, seed: u64) {
+    let grid = term.grid();
+    let columns = grid.columns();
+    for line in (grid.topmost_line().0..=grid.bottommost_line().0).map(Line) {
+        let row = &grid[line];
+        for column in 0..columns {
+            let flags = row[Column(column)].flags;
+            if flags.contains(Flags::WIDE_CHAR) {
+                let spacer = row[..].get(column + 1).map(|cell| cell.flags);
+                assert!(
+                    spacer.is_some_and(|flags| flags.contains(Flags::WIDE_CHAR_SPACER)),
+                    "seed {seed}: wide char without spacer at {line}:{column}"
+                );
+            }
+            if flags.contains(Flags::WIDE_CHAR_SPACER) {
+                let wide = column.checked_sub(1).map(|column| row[Column(column)].flags);
+                assert!(
+                    wide.is_some_and(|flags| flags.contains(Flags::WIDE_CHAR)),
+                    "seed {seed}: spacer without wide char at {line}:{column}"
+                );
+            }
+            if flags.contains(Flags::LEADING_WIDE_CHAR_SPACER) {
+                assert!(
+                    column + 1 == columns && flags.contains(Flags::WRAPLINE),
+                    "seed {seed}: leading spacer at {line}:{column}"
+                );
+            }
+        }
+    }
+
+    // The cursor only rests on a spacer after writing a wide char into the last column.
+    let cursor = grid.cursor.point;
+    assert!(
+        !grid[cursor].flags.contains(Flags::WIDE_CHAR_SPACER) || grid.cursor.input_needs_wrap,
+        "seed {seed}: cursor inside wide char at {cursor:?}"
+    );
+}
+
 // https://github.com/rust-lang/rust-clippy/pull/6375
 #[allow(clippy::all)]
 fn cell(c: char) -> Cell {
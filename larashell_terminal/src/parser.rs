@@ -0,0 +1,1378 @@
+//! Escape sequence parser extending the one provided by `vte`.
+//!
+//! The [`vte::ansi::Processor`] only dispatches the escape sequences supported by `vte`. The
+//! [`Processor`] in this module dispatches them to the same [`Handler`] methods, while also
+//! dispatching all additional sequences supported by LaraShell through the [`ExtendedHandler`]
+//! trait.
+//!
+//! [`vte::ansi::Processor`]: crate::vte::ansi::Processor
+
+use std::fmt::Write;
+use std::str::FromStr;
+use std::time::Duration;
+use std::{iter, mem, str};
+
+use log::debug;
+
+use crate::grid::{LineAttribute, RectangleExtent};
+use crate::vte::ansi::cursor_icon::CursorIcon;
+use crate::vte::ansi::{
+    Attr, CharsetIndex, ClearMode, Color, CursorShape, CursorStyle, Handler, Hyperlink,
+    KeyboardModes, KeyboardModesApplyBehavior, LineClearMode, Mode, ModifyOtherKeys, NamedColor,
+    NamedMode, NamedPrivateMode, PrivateMode, Rgb, StandardCharset, StdSyncHandler,
+    TabulationClearMode, Timeout, C0,
+};
+use crate::vte::{Params, ParamsIter, Parser, Perform};
+
+/// Rectangular area parameters of DEC rectangular area operations.
+///
+/// All coordinates are 1-based and inclusive. Edges which were omitted or specified as `0` use the
+/// default value, which is the edge of the screen.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct RectangularArea {
+    pub top: Option<usize>,
+    pub left: Option<usize>,
+    pub bottom: Option<usize>,
+    pub right: Option<usize>,
+}
+
+impl RectangularArea {
+    /// Parse the next four parameters as rectangle edges.
+    fn from_params(params: &mut ParamsIter<'_>) -> Self {
+        let mut next_param = || match params.next() {
+            Some(&[param, ..]) if param != 0 => Some(param as usize),
+            _ => None,
+        };
+
+        Self { top: next_param(), left: next_param(), bottom: next_param(), right: next_param() }
+    }
+}
+
+/// Maximum number of bytes accepted in a device control string.
+const MAX_DCS_LENGTH: usize = 1024;
+
+/// Setting requested by DECRQSS.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum StatusString {
+    /// SGR - Character attributes.
+    GraphicRendition,
+    /// DECSCUSR - Cursor style.
+    CursorStyle,
+    /// DECSTBM - Top and bottom margins.
+    ScrollingRegion,
+}
+
+impl StatusString {
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        match bytes {
+            b"m" => Some(Self::GraphicRendition),
+            b" q" => Some(Self::CursorStyle),
+            b"r" => Some(Self::ScrollingRegion),
+            _ => None,
+        }
+    }
+}
+
+/// Shell integration mark reported through OSC 133.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ShellMark {
+    /// Start of the shell prompt.
+    PromptStart,
+    /// Start of the command typed by the user.
+    CommandStart,
+    /// Start of the command's output.
+    OutputStart,
+    /// End of the command, with its exit code if the shell reported it.
+    CommandEnd(Option<i32>),
+}
+
+impl ShellMark {
+    fn from_bytes(bytes: &[u8], params: &[&[u8]]) -> Option<Self> {
+        match bytes {
+            b"A" => Some(Self::PromptStart),
+            b"B" => Some(Self::CommandStart),
+            b"C" => Some(Self::OutputStart),
+            b"D" => {
+                let exit_code = params
+                    .first()
+                    .and_then(|code| std::str::from_utf8(code).ok())
+                    .and_then(|code| code.parse().ok());
+                Some(Self::CommandEnd(exit_code))
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Type that handles escape sequences which are not supported by [`Handler`].
+///
+/// Like with [`Handler`], all methods are no-ops by default.
+pub trait ExtendedHandler {
+    /// DECCRA - Copy a rectangular area so its top-left corner is at `top`/`left`.
+    fn copy_rectangular_area(&mut self, _source: RectangularArea, _top: usize, _left: usize) {}
+
+    /// DECFRA - Fill a rectangular area with a character.
+    fn fill_rectangular_area(&mut self, _c: char, _area: RectangularArea) {}
+
+    /// DECERA - Erase a rectangular area.
+    fn erase_rectangular_area(&mut self, _area: RectangularArea) {}
+
+    /// DECSERA - Erase all unprotected characters in a rectangular area.
+    fn selective_erase_rectangular_area(&mut self, _area: RectangularArea) {}
+
+    /// DECCARA - Change the attributes of all cells in a rectangular area.
+    fn change_rectangular_area_attributes(&mut self, _area: RectangularArea, _attrs: Vec<Attr>) {}
+
+    /// DECSACE - Select the extent of the area changed by DECCARA.
+    fn set_attribute_change_extent(&mut self, _extent: RectangleExtent) {}
+
+    /// DECSCA - Set whether new characters are protected from selective erasure.
+    fn set_character_protection(&mut self, _protected: bool) {}
+
+    /// DECSWL/DECDWL/DECDHL - Set the width and height of the cursor line.
+    fn set_line_attribute(&mut self, _line_attribute: LineAttribute) {}
+
+    /// DECRQSS - Report the value of a setting.
+    ///
+    /// The setting is `None` if it is not supported.
+    fn request_status_string(&mut self, _setting: Option<StatusString>) {}
+
+    /// XTGETTCAP - Report the values of terminfo capabilities.
+    fn request_terminfo_capabilities(&mut self, _names: Vec<String>) {}
+
+    /// XTVERSION - Report the name and version of the terminal.
+    fn report_version(&mut self) {}
+
+    /// OSC 133 - Mark the position of shell prompts, commands and their output.
+    fn shell_mark(&mut self, _mark: ShellMark) {}
+}
+
+/// Synchronized update timeout.
+const SYNC_UPDATE_TIMEOUT: Duration = Duration::from_millis(150);
+
+/// Maximum number of bytes read in one synchronized update (2MiB).
+const SYNC_BUFFER_SIZE: usize = 0x20_0000;
+
+/// BSU CSI sequence for beginning or extending synchronized updates.
+const BSU_CSI: &[u8] = b"\x1b[?2026h";
+
+/// ESU CSI sequence for terminating synchronized updates.
+const ESU_CSI: &[u8] = b"\x1b[?2026l";
+
+/// Escape sequence processor.
+///
+/// All bytes are parsed once, dispatching extended sequences to the [`ExtendedHandler`] and all
+/// other escape sequences to the [`Handler`] as soon as they are complete. This keeps both
+/// families of sequences in the order they were received, including during synchronized updates.
+#[derive(Default)]
+pub struct Processor<T: Timeout = StdSyncHandler> {
+    state: ProcessorState<T>,
+    parser: Parser,
+}
+
+/// Internal state of the [`Processor`].
+#[derive(Default)]
+struct ProcessorState<T: Timeout> {
+    /// Last printed character for repetition.
+    preceding_char: Option<char>,
+
+    /// Handler for synchronized updates.
+    sync_timeout: T,
+
+    /// Bytes read during a synchronized update.
+    sync_buffer: Vec<u8>,
+
+    /// Device control string which is currently being received.
+    dcs: Option<Dcs>,
+}
+
+/// Partially received device control string.
+#[derive(Debug)]
+struct Dcs {
+    kind: DcsKind,
+    data: Vec<u8>,
+}
+
+/// Supported device control strings.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum DcsKind {
+    /// DECRQSS.
+    StatusString,
+    /// XTGETTCAP.
+    TerminfoCapabilities,
+}
+
+impl<T: Timeout> Processor<T> {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Synchronized update timeout.
+    #[inline]
+    pub fn sync_timeout(&self) -> &T {
+        &self.state.sync_timeout
+    }
+
+    /// Number of bytes in the synchronization buffer.
+    #[inline]
+    pub fn sync_bytes_count(&self) -> usize {
+        self.state.sync_buffer.len()
+    }
+
+    /// Process a new byte from the PTY.
+    #[inline]
+    pub fn advance<H>(&mut self, handler: &mut H, byte: u8)
+    where
+        H: Handler + ExtendedHandler,
+    {
+        if self.state.sync_timeout.pending_timeout() {
+            self.advance_sync(handler, byte);
+        } else {
+            let mut performer = Performer::new(&mut self.state, handler);
+            self.parser.advance(&mut performer, byte);
+        }
+    }
+
+    /// End a synchronized update.
+    pub fn stop_sync<H>(&mut self, handler: &mut H)
+    where
+        H: Handler + ExtendedHandler,
+    {
+        // Process all synchronized bytes.
+        let buffer = mem::take(&mut self.state.sync_buffer);
+        let mut performer = Performer::new(&mut self.state, handler);
+        for &byte in &buffer {
+            self.parser.advance(&mut performer, byte);
+        }
+
+        // Report that update ended, since we could end due to timeout.
+        handler.unset_private_mode(NamedPrivateMode::SyncUpdate.into());
+
+        // Resetting state after processing makes sure we don't interpret buffered sync escapes.
+        self.state.sync_buffer = buffer;
+        self.state.sync_buffer.clear();
+        self.state.sync_timeout.clear_timeout();
+    }
+
+    /// Process a new byte during a synchronized update.
+    #[cold]
+    fn advance_sync<H>(&mut self, handler: &mut H, byte: u8)
+    where
+        H: Handler + ExtendedHandler,
+    {
+        self.state.sync_buffer.push(byte);
+
+        // NOTE: Like `vte`, only EXACTLY `\e[?2026h`/`\e[?2026l` extend or terminate the update.
+        let buffer = &self.state.sync_buffer;
+        if buffer.ends_with(BSU_CSI) {
+            self.state.sync_timeout.set_timeout(SYNC_UPDATE_TIMEOUT);
+        } else if buffer.ends_with(ESU_CSI) || buffer.len() >= SYNC_BUFFER_SIZE - 1 {
+            self.stop_sync(handler);
+        }
+    }
+}
+
+/// Helper type that implements [`Perform`] for all sequences.
+struct Performer<'a, H: Handler + ExtendedHandler, T: Timeout> {
+    state: &'a mut ProcessorState<T>,
+    handler: &'a mut H,
+}
+
+impl<'a, H: Handler + ExtendedHandler, T: Timeout> Performer<'a, H, T> {
+    #[inline]
+    fn new(state: &'a mut ProcessorState<T>, handler: &'a mut H) -> Self {
+        Self { state, handler }
+    }
+
+    /// Dispatch a CSI escape sequence supported by [`Handler`].
+    #[allow(clippy::cognitive_complexity)]
+    fn standard_csi(&mut self, params: &Params, intermediates: &[u8], action: char) {
+        macro_rules! unhandled {
+            () => {{
+                debug!(
+                    "[Unhandled CSI] action={:?}, params={:?}, intermediates={:?}",
+                    action, params, intermediates
+                );
+            }};
+        }
+
+        let mut params_iter = params.iter();
+        let handler = &mut self.handler;
+
+        let mut next_param_or = |default: u16| match params_iter.next() {
+            Some(&[param, ..]) if param != 0 => param,
+            _ => default,
+        };
+
+        match (action, intermediates) {
+            ('@', []) => handler.insert_blank(next_param_or(1) as usize),
+            ('A', []) => handler.move_up(next_param_or(1) as usize),
+            ('B', []) | ('e', []) => handler.move_down(next_param_or(1) as usize),
+            ('b', []) => {
+                if let Some(c) = self.state.preceding_char {
+                    for _ in 0..next_param_or(1) {
+                        handler.input(c);
+                    }
+                } else {
+                    debug!("tried to repeat with no preceding char");
+                }
+            },
+            ('C', []) | ('a', []) => handler.move_forward(next_param_or(1) as usize),
+            ('c', intermediates) if next_param_or(0) == 0 => {
+                handler.identify_terminal(intermediates.first().map(|&i| i as char))
+            },
+            ('D', []) => handler.move_backward(next_param_or(1) as usize),
+            ('d', []) => handler.goto_line(next_param_or(1) as i32 - 1),
+            ('E', []) => handler.move_down_and_cr(next_param_or(1) as usize),
+            ('F', []) => handler.move_up_and_cr(next_param_or(1) as usize),
+            ('G', []) | ('`', []) => handler.goto_col(next_param_or(1) as usize - 1),
+            ('g', []) => {
+                let mode = match next_param_or(0) {
+                    0 => TabulationClearMode::Current,
+                    3 => TabulationClearMode::All,
+                    _ => return unhandled!(),
+                };
+
+                handler.clear_tabs(mode);
+            },
+            ('H', []) | ('f', []) => {
+                let y = next_param_or(1) as i32;
+                let x = next_param_or(1) as usize;
+                handler.goto(y - 1, x - 1);
+            },
+            ('h', []) => {
+                for param in params_iter.map(|param| param[0]) {
+                    handler.set_mode(mode(param))
+                }
+            },
+            ('h', [b'?']) => {
+                for param in params_iter.map(|param| param[0]) {
+                    // Handle sync updates opaquely.
+                    if param == NamedPrivateMode::SyncUpdate as u16 {
+                        self.state.sync_timeout.set_timeout(SYNC_UPDATE_TIMEOUT);
+                    }
+
+                    handler.set_private_mode(private_mode(param))
+                }
+            },
+            ('I', []) => handler.move_forward_tabs(next_param_or(1)),
+            ('J', []) => {
+                let mode = match next_param_or(0) {
+                    0 => ClearMode::Below,
+                    1 => ClearMode::Above,
+                    2 => ClearMode::All,
+                    3 => ClearMode::Saved,
+                    _ => return unhandled!(),
+                };
+
+                handler.clear_screen(mode);
+            },
+            ('K', []) => {
+                let mode = match next_param_or(0) {
+                    0 => LineClearMode::Right,
+                    1 => LineClearMode::Left,
+                    2 => LineClearMode::All,
+                    _ => return unhandled!(),
+                };
+
+                handler.clear_line(mode);
+            },
+            ('L', []) => handler.insert_blank_lines(next_param_or(1) as usize),
+            ('l', []) => {
+                for param in params_iter.map(|param| param[0]) {
+                    handler.unset_mode(mode(param))
+                }
+            },
+            ('l', [b'?']) => {
+                for param in params_iter.map(|param| param[0]) {
+                    handler.unset_private_mode(private_mode(param))
+                }
+            },
+            ('M', []) => handler.delete_lines(next_param_or(1) as usize),
+            ('m', []) => {
+                if params.is_empty() {
+                    handler.terminal_attribute(Attr::Reset);
+                } else {
+                    attrs_from_sgr_parameters(&mut **handler, &mut params_iter);
+                }
+            },
+            ('m', [b'>']) => {
+                let mode = match (next_param_or(1) == 4).then(|| next_param_or(0)) {
+                    Some(0) => ModifyOtherKeys::Reset,
+                    Some(1) => ModifyOtherKeys::EnableExceptWellDefined,
+                    Some(2) => ModifyOtherKeys::EnableAll,
+                    _ => return unhandled!(),
+                };
+                handler.set_modify_other_keys(mode);
+            },
+            ('m', [b'?']) => {
+                if params_iter.next() == Some(&[4]) {
+                    handler.report_modify_other_keys();
+                } else {
+                    unhandled!()
+                }
+            },
+            ('n', []) => handler.device_status(next_param_or(0) as usize),
+            ('P', []) => handler.delete_chars(next_param_or(1) as usize),
+            ('p', [b'$']) => handler.report_mode(mode(next_param_or(0))),
+            ('p', [b'?', b'$']) => handler.report_private_mode(private_mode(next_param_or(0))),
+            ('q', [b' ']) => {
+                // DECSCUSR (CSI Ps SP q) -- Set Cursor Style.
+                let cursor_style_id = next_param_or(0);
+                let shape = match cursor_style_id {
+                    0 => None,
+                    1 | 2 => Some(CursorShape::Block),
+                    3 | 4 => Some(CursorShape::Underline),
+                    5 | 6 => Some(CursorShape::Beam),
+                    _ => return unhandled!(),
+                };
+                let cursor_style =
+                    shape.map(|shape| CursorStyle { shape, blinking: cursor_style_id % 2 == 1 });
+
+                handler.set_cursor_style(cursor_style);
+            },
+            ('r', []) => {
+                let top = next_param_or(1) as usize;
+                let bottom =
+                    params_iter.next().map(|param| param[0] as usize).filter(|&param| param != 0);
+
+                handler.set_scrolling_region(top, bottom);
+            },
+            ('S', []) => handler.scroll_up(next_param_or(1) as usize),
+            ('s', []) => handler.save_cursor_position(),
+            ('T', []) => handler.scroll_down(next_param_or(1) as usize),
+            ('t', []) => match next_param_or(1) as usize {
+                14 => handler.text_area_size_pixels(),
+                18 => handler.text_area_size_chars(),
+                22 => handler.push_title(),
+                23 => handler.pop_title(),
+                _ => unhandled!(),
+            },
+            ('u', [b'?']) => handler.report_keyboard_mode(),
+            ('u', [b'=']) => {
+                let mode = KeyboardModes::from_bits_truncate(next_param_or(0) as u8);
+                let behavior = match next_param_or(1) {
+                    3 => KeyboardModesApplyBehavior::Difference,
+                    2 => KeyboardModesApplyBehavior::Union,
+                    // Default is replace.
+                    _ => KeyboardModesApplyBehavior::Replace,
+                };
+                handler.set_keyboard_mode(mode, behavior);
+            },
+            ('u', [b'>']) => {
+                let mode = KeyboardModes::from_bits_truncate(next_param_or(0) as u8);
+                handler.push_keyboard_mode(mode);
+            },
+            ('u', [b'<']) => {
+                // The default is 1.
+                handler.pop_keyboard_modes(next_param_or(1));
+            },
+            ('u', []) => handler.restore_cursor_position(),
+            ('X', []) => handler.erase_chars(next_param_or(1) as usize),
+            ('Z', []) => handler.move_backward_tabs(next_param_or(1)),
+            _ => unhandled!(),
+        }
+    }
+
+    /// Dispatch an OSC escape sequence supported by [`Handler`].
+    fn standard_osc(&mut self, params: &[&[u8]], bell_terminated: bool) {
+        let terminator = if bell_terminated { "\x07" } else { "\x1b\\" };
+
+        fn unhandled(params: &[&[u8]]) {
+            let mut buf = String::new();
+            for items in params {
+                buf.push('[');
+                for item in *items {
+                    let _ = write!(buf, "{:?}", *item as char);
+                }
+                buf.push_str("],");
+            }
+            debug!("[unhandled osc_dispatch]: [{}] at line {}", &buf, line!());
+        }
+
+        if params.is_empty() || params[0].is_empty() {
+            return;
+        }
+
+        match params[0] {
+            // Set window title.
+            b"0" | b"2" => {
+                if params.len() >= 2 {
+                    let title = params[1..]
+                        .iter()
+                        .flat_map(|x| str::from_utf8(x))
+                        .collect::<Vec<&str>>()
+                        .join(";")
+                        .trim()
+                        .to_owned();
+                    self.handler.set_title(Some(title));
+                    return;
+                }
+                unhandled(params);
+            },
+
+            // Set color index.
+            b"4" => {
+                if params.len() <= 1 || params.len() % 2 == 0 {
+                    unhandled(params);
+                    return;
+                }
+
+                for chunk in params[1..].chunks(2) {
+                    let index = match parse_number(chunk[0]) {
+                        Some(index) => index,
+                        None => {
+                            unhandled(params);
+                            continue;
+                        },
+                    };
+
+                    if let Some(c) = xparse_color(chunk[1]) {
+                        self.handler.set_color(index as usize, c);
+                    } else if chunk[1] == b"?" {
+                        let prefix = format!("4;{index}");
+                        self.handler.dynamic_color_sequence(prefix, index as usize, terminator);
+                    } else {
+                        unhandled(params);
+                    }
+                }
+            },
+
+            // Hyperlink.
+            b"8" if params.len() > 2 => {
+                let link_params = params[1];
+
+                // NOTE: The escape sequence is of form 'OSC 8 ; params ; URI ST', where
+                // URI is URL-encoded. However `;` is a special character and might be
+                // passed as is, thus we need to rebuild the URI.
+                let mut uri = str::from_utf8(params[2]).unwrap_or_default().to_string();
+                for param in params[3..].iter() {
+                    uri.push(';');
+                    uri.push_str(str::from_utf8(param).unwrap_or_default());
+                }
+
+                // The OSC 8 escape sequence must be stopped when getting an empty `uri`.
+                if uri.is_empty() {
+                    self.handler.set_hyperlink(None);
+                    return;
+                }
+
+                // Link parameters are in format of `key1=value1:key2=value2`. Currently only key
+                // `id` is defined.
+                let id = link_params
+                    .split(|&b| b == b':')
+                    .find_map(|kv| kv.strip_prefix(b"id="))
+                    .and_then(|kv| str::from_utf8(kv).ok().map(|e| e.to_owned()));
+
+                self.handler.set_hyperlink(Some(Hyperlink { id, uri }));
+            },
+
+            // Get/set Foreground, Background, Cursor colors.
+            b"10" | b"11" | b"12" => {
+                if params.len() >= 2 {
+                    if let Some(mut dynamic_code) = parse_number(params[0]) {
+                        for param in &params[1..] {
+                            // 10 is the first dynamic color, also the foreground.
+                            let offset = dynamic_code as usize - 10;
+                            let index = NamedColor::Foreground as usize + offset;
+
+                            // End of setting dynamic colors.
+                            if index > NamedColor::Cursor as usize {
+                                unhandled(params);
+                                break;
+                            }
+
+                            if let Some(color) = xparse_color(param) {
+                                self.handler.set_color(index, color);
+                            } else if param == b"?" {
+                                self.handler.dynamic_color_sequence(
+                                    dynamic_code.to_string(),
+                                    index,
+                                    terminator,
+                                );
+                            } else {
+                                unhandled(params);
+                            }
+                            dynamic_code += 1;
+                        }
+                        return;
+                    }
+                }
+                unhandled(params);
+            },
+
+            // Set mouse cursor shape.
+            b"22" if params.len() == 2 => {
+                let shape = String::from_utf8_lossy(params[1]);
+                match CursorIcon::from_str(&shape) {
+                    Ok(cursor_icon) => self.handler.set_mouse_cursor_icon(cursor_icon),
+                    Err(_) => debug!("[osc 22] unrecognized cursor icon shape: {shape:?}"),
+                }
+            },
+
+            // Set cursor style.
+            b"50" => {
+                if params.len() >= 2
+                    && params[1].len() >= 13
+                    && params[1][0..12] == *b"CursorShape="
+                {
+                    let shape = match params[1][12] as char {
+                        '0' => CursorShape::Block,
+                        '1' => CursorShape::Beam,
+                        '2' => CursorShape::Underline,
+                        _ => return unhandled(params),
+                    };
+                    self.handler.set_cursor_shape(shape);
+                    return;
+                }
+                unhandled(params);
+            },
+
+            // Set clipboard.
+            b"52" => {
+                if params.len() < 3 {
+                    return unhandled(params);
+                }
+
+                let clipboard = params[1].first().unwrap_or(&b'c');
+                match params[2] {
+                    b"?" => self.handler.clipboard_load(*clipboard, terminator),
+                    base64 => self.handler.clipboard_store(*clipboard, base64),
+                }
+            },
+
+            // Reset color index.
+            b"104" => {
+                // Reset all color indexes when no parameters are given.
+                if params.len() == 1 || params[1].is_empty() {
+                    for i in 0..256 {
+                        self.handler.reset_color(i);
+                    }
+                    return;
+                }
+
+                // Reset color indexes given as parameters.
+                for param in &params[1..] {
+                    match parse_number(param) {
+                        Some(index) => self.handler.reset_color(index as usize),
+                        None => unhandled(params),
+                    }
+                }
+            },
+
+            // Reset foreground color.
+            b"110" => self.handler.reset_color(NamedColor::Foreground as usize),
+
+            // Reset background color.
+            b"111" => self.handler.reset_color(NamedColor::Background as usize),
+
+            // Reset text cursor color.
+            b"112" => self.handler.reset_color(NamedColor::Cursor as usize),
+
+            _ => unhandled(params),
+        }
+    }
+
+    /// Dispatch an ESC escape sequence supported by [`Handler`].
+    fn standard_esc(&mut self, intermediates: &[u8], byte: u8) {
+        macro_rules! unhandled {
+            () => {{
+                debug!(
+                    "[unhandled] esc_dispatch ints={:?}, byte={:?} ({:02x})",
+                    intermediates, byte as char, byte
+                );
+            }};
+        }
+
+        macro_rules! configure_charset {
+            ($charset:path, $intermediates:expr) => {{
+                let index: CharsetIndex = match $intermediates {
+                    [b'('] => CharsetIndex::G0,
+                    [b')'] => CharsetIndex::G1,
+                    [b'*'] => CharsetIndex::G2,
+                    [b'+'] => CharsetIndex::G3,
+                    _ => return unhandled!(),
+                };
+                self.handler.configure_charset(index, $charset)
+            }};
+        }
+
+        match (byte, intermediates) {
+            (b'B', intermediates) => configure_charset!(StandardCharset::Ascii, intermediates),
+            (b'D', []) => self.handler.linefeed(),
+            (b'E', []) => {
+                self.handler.linefeed();
+                self.handler.carriage_return();
+            },
+            (b'H', []) => self.handler.set_horizontal_tabstop(),
+            (b'M', []) => self.handler.reverse_index(),
+            (b'Z', []) => self.handler.identify_terminal(None),
+            (b'c', []) => self.handler.reset_state(),
+            (b'0', intermediates) => {
+                configure_charset!(StandardCharset::SpecialCharacterAndLineDrawing, intermediates)
+            },
+            (b'7', []) => self.handler.save_cursor_position(),
+            (b'8', [b'#']) => self.handler.decaln(),
+            (b'8', []) => self.handler.restore_cursor_position(),
+            (b'=', []) => self.handler.set_keypad_application_mode(),
+            (b'>', []) => self.handler.unset_keypad_application_mode(),
+            // String terminator, do nothing (parser handles as string terminator).
+            (b'\\', []) => (),
+            _ => unhandled!(),
+        }
+    }
+}
+
+impl<'a, H: Handler + ExtendedHandler, T: Timeout> Perform for Performer<'a, H, T> {
+    #[inline]
+    fn print(&mut self, c: char) {
+        self.handler.input(c);
+        self.state.preceding_char = Some(c);
+    }
+
+    #[inline]
+    fn execute(&mut self, byte: u8) {
+        match byte {
+            C0::HT => self.handler.put_tab(1),
+            C0::BS => self.handler.backspace(),
+            C0::CR => self.handler.carriage_return(),
+            C0::LF | C0::VT | C0::FF => self.handler.linefeed(),
+            C0::BEL => self.handler.bell(),
+            C0::SUB => self.handler.substitute(),
+            C0::SI => self.handler.set_active_charset(CharsetIndex::G0),
+            C0::SO => self.handler.set_active_charset(CharsetIndex::G1),
+            _ => debug!("[unhandled] execute byte={:02x}", byte),
+        }
+    }
+
+    fn csi_dispatch(
+        &mut self,
+        params: &Params,
+        intermediates: &[u8],
+        has_ignored_intermediates: bool,
+        action: char,
+    ) {
+        if has_ignored_intermediates || intermediates.len() > 2 {
+            unhandled(params, intermediates, action);
+            return;
+        }
+
+        let mut params_iter = params.iter();
+        let handler = &mut self.handler;
+
+        match (action, intermediates) {
+            ('q', [b'>']) => match params_iter.next().map(|param| param[0]) {
+                None | Some(0) => handler.report_version(),
+                Some(_) => unhandled(params, intermediates, action),
+            },
+            ('q', [b'"']) => match params_iter.next().map(|param| param[0]) {
+                None | Some(0) | Some(2) => handler.set_character_protection(false),
+                Some(1) => handler.set_character_protection(true),
+                Some(_) => unhandled(params, intermediates, action),
+            },
+            ('r', [b'$']) => {
+                let area = RectangularArea::from_params(&mut params_iter);
+                let mut attrs: Vec<Attr> =
+                    params_iter.filter_map(|param| rect_attr(param[0])).collect();
+                if attrs.is_empty() {
+                    attrs.push(Attr::Reset);
+                }
+                handler.change_rectangular_area_attributes(area, attrs);
+            },
+            ('v', [b'$']) => {
+                let source = RectangularArea::from_params(&mut params_iter);
+
+                // Skip source page.
+                params_iter.next();
+
+                let mut next_param_or = |default: usize| match params_iter.next() {
+                    Some(&[param, ..]) if param != 0 => param as usize,
+                    _ => default,
+                };
+                let top = next_param_or(1);
+                let left = next_param_or(1);
+
+                handler.copy_rectangular_area(source, top, left);
+            },
+            ('x', [b'$']) => {
+                let c = params_iter
+                    .next()
+                    .and_then(|param| char::from_u32(param[0] as u32))
+                    .filter(|&c| matches!(c, ' '..='~' | '\u{a0}'..='\u{ff}'));
+                let area = RectangularArea::from_params(&mut params_iter);
+
+                match c {
+                    Some(c) => handler.fill_rectangular_area(c, area),
+                    None => unhandled(params, intermediates, action),
+                }
+            },
+            ('x', [b'*']) => match params_iter.next().map(|param| param[0]) {
+                None | Some(0) | Some(1) => {
+                    handler.set_attribute_change_extent(RectangleExtent::Stream)
+                },
+                Some(2) => handler.set_attribute_change_extent(RectangleExtent::Rectangle),
+                Some(_) => unhandled(params, intermediates, action),
+            },
+            ('z', [b'$']) => {
+                let area = RectangularArea::from_params(&mut params_iter);
+                handler.erase_rectangular_area(area);
+            },
+            ('{', [b'$']) => {
+                let area = RectangularArea::from_params(&mut params_iter);
+                handler.selective_erase_rectangular_area(area);
+            },
+            _ => self.standard_csi(params, intermediates, action),
+        }
+    }
+
+    fn hook(&mut self, _params: &Params, intermediates: &[u8], ignore: bool, action: char) {
+        let kind = match (action, intermediates) {
+            _ if ignore => None,
+            ('q', [b'$']) => Some(DcsKind::StatusString),
+            ('q', [b'+']) => Some(DcsKind::TerminfoCapabilities),
+            _ => None,
+        };
+
+        self.state.dcs = kind.map(|kind| Dcs { kind, data: Vec::new() });
+    }
+
+    fn put(&mut self, byte: u8) {
+        if let Some(dcs) = &mut self.state.dcs {
+            if dcs.data.len() < MAX_DCS_LENGTH {
+                dcs.data.push(byte);
+            } else {
+                debug!("[unhandled] DCS exceeding {MAX_DCS_LENGTH} bytes");
+                self.state.dcs = None;
+            }
+        }
+    }
+
+    fn unhook(&mut self) {
+        let dcs = match self.state.dcs.take() {
+            Some(dcs) => dcs,
+            None => return,
+        };
+
+        match dcs.kind {
+            DcsKind::StatusString => {
+                let setting = StatusString::from_bytes(&dcs.data);
+                self.handler.request_status_string(setting);
+            },
+            DcsKind::TerminfoCapabilities => {
+                let names = dcs
+                    .data
+                    .split(|&byte| byte == b';')
+                    .filter_map(|name| {
+                        let name = decode_hex(name);
+                        if name.is_none() {
+                            debug!("[unhandled] Invalid XTGETTCAP capability name");
+                        }
+                        name
+                    })
+                    .collect();
+                self.handler.request_terminfo_capabilities(names);
+            },
+        }
+    }
+
+    fn osc_dispatch(&mut self, params: &[&[u8]], bell_terminated: bool) {
+        match params {
+            [b"133", kind, params @ ..] => match ShellMark::from_bytes(kind, params) {
+                Some(mark) => self.handler.shell_mark(mark),
+                None => debug!("[unhandled] OSC 133 mark {:?}", String::from_utf8_lossy(kind)),
+            },
+            _ => self.standard_osc(params, bell_terminated),
+        }
+    }
+
+    fn esc_dispatch(&mut self, intermediates: &[u8], _ignore: bool, byte: u8) {
+        let line_attribute = match (byte, intermediates) {
+            (b'3', [b'#']) => LineAttribute::DoubleHeightTop,
+            (b'4', [b'#']) => LineAttribute::DoubleHeightBottom,
+            (b'5', [b'#']) => LineAttribute::Normal,
+            (b'6', [b'#']) => LineAttribute::DoubleWidth,
+            _ => return self.standard_esc(intermediates, byte),
+        };
+
+        self.handler.set_line_attribute(line_attribute);
+    }
+}
+
+/// Convert a raw ANSI mode.
+fn mode(mode: u16) -> Mode {
+    match mode {
+        4 => Mode::Named(NamedMode::Insert),
+        20 => Mode::Named(NamedMode::LineFeedNewLine),
+        _ => Mode::Unknown(mode),
+    }
+}
+
+/// Convert a raw private DEC mode.
+fn private_mode(mode: u16) -> PrivateMode {
+    let named = match mode {
+        1 => NamedPrivateMode::CursorKeys,
+        3 => NamedPrivateMode::ColumnMode,
+        6 => NamedPrivateMode::Origin,
+        7 => NamedPrivateMode::LineWrap,
+        12 => NamedPrivateMode::BlinkingCursor,
+        25 => NamedPrivateMode::ShowCursor,
+        1000 => NamedPrivateMode::ReportMouseClicks,
+        1002 => NamedPrivateMode::ReportCellMouseMotion,
+        1003 => NamedPrivateMode::ReportAllMouseMotion,
+        1004 => NamedPrivateMode::ReportFocusInOut,
+        1005 => NamedPrivateMode::Utf8Mouse,
+        1006 => NamedPrivateMode::SgrMouse,
+        1007 => NamedPrivateMode::AlternateScroll,
+        1042 => NamedPrivateMode::UrgencyHints,
+        1049 => NamedPrivateMode::SwapScreenAndSetRestoreCursor,
+        2004 => NamedPrivateMode::BracketedPaste,
+        2026 => NamedPrivateMode::SyncUpdate,
+        _ => return PrivateMode::Unknown(mode),
+    };
+
+    PrivateMode::Named(named)
+}
+
+#[inline]
+/// Apply the attributes of SGR parameters.
+fn attrs_from_sgr_parameters<H: Handler>(handler: &mut H, params: &mut ParamsIter<'_>) {
+    while let Some(param) = params.next() {
+        let attr = match param {
+            [0] => Some(Attr::Reset),
+            [1] => Some(Attr::Bold),
+            [2] => Some(Attr::Dim),
+            [3] => Some(Attr::Italic),
+            [4, 0] => Some(Attr::CancelUnderline),
+            [4, 2] => Some(Attr::DoubleUnderline),
+            [4, 3] => Some(Attr::Undercurl),
+            [4, 4] => Some(Attr::DottedUnderline),
+            [4, 5] => Some(Attr::DashedUnderline),
+            [4, ..] => Some(Attr::Underline),
+            [5] => Some(Attr::BlinkSlow),
+            [6] => Some(Attr::BlinkFast),
+            [7] => Some(Attr::Reverse),
+            [8] => Some(Attr::Hidden),
+            [9] => Some(Attr::Strike),
+            [21] => Some(Attr::CancelBold),
+            [22] => Some(Attr::CancelBoldDim),
+            [23] => Some(Attr::CancelItalic),
+            [24] => Some(Attr::CancelUnderline),
+            [25] => Some(Attr::CancelBlink),
+            [27] => Some(Attr::CancelReverse),
+            [28] => Some(Attr::CancelHidden),
+            [29] => Some(Attr::CancelStrike),
+            [30] => Some(Attr::Foreground(Color::Named(NamedColor::Black))),
+            [31] => Some(Attr::Foreground(Color::Named(NamedColor::Red))),
+            [32] => Some(Attr::Foreground(Color::Named(NamedColor::Green))),
+            [33] => Some(Attr::Foreground(Color::Named(NamedColor::Yellow))),
+            [34] => Some(Attr::Foreground(Color::Named(NamedColor::Blue))),
+            [35] => Some(Attr::Foreground(Color::Named(NamedColor::Magenta))),
+            [36] => Some(Attr::Foreground(Color::Named(NamedColor::Cyan))),
+            [37] => Some(Attr::Foreground(Color::Named(NamedColor::White))),
+            [38] => {
+                let mut iter = params.map(|param| param[0]);
+                parse_sgr_color(&mut iter).map(Attr::Foreground)
+            },
+            [38, params @ ..] => handle_colon_rgb(params).map(Attr::Foreground),
+            [39] => Some(Attr::Foreground(Color::Named(NamedColor::Foreground))),
+            [40] => Some(Attr::Background(Color::Named(NamedColor::Black))),
+            [41] => Some(Attr::Background(Color::Named(NamedColor::Red))),
+            [42] => Some(Attr::Background(Color::Named(NamedColor::Green))),
+            [43] => Some(Attr::Background(Color::Named(NamedColor::Yellow))),
+            [44] => Some(Attr::Background(Color::Named(NamedColor::Blue))),
+            [45] => Some(Attr::Background(Color::Named(NamedColor::Magenta))),
+            [46] => Some(Attr::Background(Color::Named(NamedColor::Cyan))),
+            [47] => Some(Attr::Background(Color::Named(NamedColor::White))),
+            [48] => {
+                let mut iter = params.map(|param| param[0]);
+                parse_sgr_color(&mut iter).map(Attr::Background)
+            },
+            [48, params @ ..] => handle_colon_rgb(params).map(Attr::Background),
+            [49] => Some(Attr::Background(Color::Named(NamedColor::Background))),
+            [58] => {
+                let mut iter = params.map(|param| param[0]);
+                parse_sgr_color(&mut iter).map(|color| Attr::UnderlineColor(Some(color)))
+            },
+            [58, params @ ..] => {
+                handle_colon_rgb(params).map(|color| Attr::UnderlineColor(Some(color)))
+            },
+            [59] => Some(Attr::UnderlineColor(None)),
+            [90] => Some(Attr::Foreground(Color::Named(NamedColor::BrightBlack))),
+            [91] => Some(Attr::Foreground(Color::Named(NamedColor::BrightRed))),
+            [92] => Some(Attr::Foreground(Color::Named(NamedColor::BrightGreen))),
+            [93] => Some(Attr::Foreground(Color::Named(NamedColor::BrightYellow))),
+            [94] => Some(Attr::Foreground(Color::Named(NamedColor::BrightBlue))),
+            [95] => Some(Attr::Foreground(Color::Named(NamedColor::BrightMagenta))),
+            [96] => Some(Attr::Foreground(Color::Named(NamedColor::BrightCyan))),
+            [97] => Some(Attr::Foreground(Color::Named(NamedColor::BrightWhite))),
+            [100] => Some(Attr::Background(Color::Named(NamedColor::BrightBlack))),
+            [101] => Some(Attr::Background(Color::Named(NamedColor::BrightRed))),
+            [102] => Some(Attr::Background(Color::Named(NamedColor::BrightGreen))),
+            [103] => Some(Attr::Background(Color::Named(NamedColor::BrightYellow))),
+            [104] => Some(Attr::Background(Color::Named(NamedColor::BrightBlue))),
+            [105] => Some(Attr::Background(Color::Named(NamedColor::BrightMagenta))),
+            [106] => Some(Attr::Background(Color::Named(NamedColor::BrightCyan))),
+            [107] => Some(Attr::Background(Color::Named(NamedColor::BrightWhite))),
+            _ => None,
+        };
+
+        match attr {
+            Some(attr) => handler.terminal_attribute(attr),
+            None => debug!("[unhandled] SGR parameter {param:?}"),
+        }
+    }
+}
+
+/// Handle colon separated rgb color escape sequence.
+#[inline]
+fn handle_colon_rgb(params: &[u16]) -> Option<Color> {
+    let rgb_start = if params.len() > 4 { 2 } else { 1 };
+    let rgb_iter = params[rgb_start..].iter().copied();
+    let mut iter = iter::once(params[0]).chain(rgb_iter);
+
+    parse_sgr_color(&mut iter)
+}
+
+/// Parse a color specifier from list of attributes.
+fn parse_sgr_color(params: &mut dyn Iterator<Item = u16>) -> Option<Color> {
+    match params.next() {
+        Some(2) => Some(Color::Spec(Rgb {
+            r: u8::try_from(params.next()?).ok()?,
+            g: u8::try_from(params.next()?).ok()?,
+            b: u8::try_from(params.next()?).ok()?,
+        })),
+        Some(5) => Some(Color::Indexed(u8::try_from(params.next()?).ok()?)),
+        _ => None,
+    }
+}
+
+/// Parse colors in XParseColor format.
+fn xparse_color(color: &[u8]) -> Option<Rgb> {
+    if !color.is_empty() && color[0] == b'#' {
+        parse_legacy_color(&color[1..])
+    } else if color.len() >= 4 && &color[..4] == b"rgb:" {
+        parse_rgb_color(&color[4..])
+    } else {
+        None
+    }
+}
+
+/// Parse colors in `rgb:r(rrr)/g(ggg)/b(bbb)` format.
+fn parse_rgb_color(color: &[u8]) -> Option<Rgb> {
+    let colors = str::from_utf8(color).ok()?.split('/').collect::<Vec<_>>();
+
+    if colors.len() != 3 {
+        return None;
+    }
+
+    // Scale values instead of filling with `0`s.
+    let scale = |input: &str| {
+        if input.len() > 4 {
+            None
+        } else {
+            let max = u32::pow(16, input.len() as u32) - 1;
+            let value = u32::from_str_radix(input, 16).ok()?;
+            Some((255 * value / max) as u8)
+        }
+    };
+
+    Some(Rgb { r: scale(colors[0])?, g: scale(colors[1])?, b: scale(colors[2])? })
+}
+
+/// Parse colors in `#r(rrr)g(ggg)b(bbb)` format.
+fn parse_legacy_color(color: &[u8]) -> Option<Rgb> {
+    let item_len = color.len() / 3;
+
+    // Truncate/Fill to two byte precision.
+    let color_from_slice = |slice: &[u8]| {
+        let col = usize::from_str_radix(str::from_utf8(slice).ok()?, 16).ok()? << 4;
+        Some((col >> (4 * slice.len().saturating_sub(1))) as u8)
+    };
+
+    Some(Rgb {
+        r: color_from_slice(&color[0..item_len])?,
+        g: color_from_slice(&color[item_len..item_len * 2])?,
+        b: color_from_slice(&color[item_len * 2..])?,
+    })
+}
+
+/// Parse a decimal number which fits into a `u8`.
+fn parse_number(input: &[u8]) -> Option<u8> {
+    if input.is_empty() {
+        return None;
+    }
+    let mut num: u8 = 0;
+    for c in input {
+        let c = *c as char;
+        if let Some(digit) = c.to_digit(10) {
+            num = num.checked_mul(10).and_then(|v| v.checked_add(digit as u8))?;
+        } else {
+            return None;
+        }
+    }
+    Some(num)
+}
+
+/// Decode a string of hexadecimal digit pairs.
+fn decode_hex(hex: &[u8]) -> Option<String> {
+    if hex.is_empty() || hex.len() % 2 != 0 {
+        return None;
+    }
+
+    let bytes = hex
+        .chunks(2)
+        .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+
+    String::from_utf8(bytes).ok()
+}
+
+/// Convert a DECCARA parameter to the attribute it changes.
+fn rect_attr(param: u16) -> Option<Attr> {
+    let attr = match param {
+        0 => Attr::Reset,
+        1 => Attr::Bold,
+        4 => Attr::Underline,
+        5 => Attr::BlinkSlow,
+        7 => Attr::Reverse,
+        8 => Attr::Hidden,
+        22 => Attr::CancelBold,
+        24 => Attr::CancelUnderline,
+        25 => Attr::CancelBlink,
+        27 => Attr::CancelReverse,
+        28 => Attr::CancelHidden,
+        _ => {
+            debug!("[unhandled] DECCARA attribute {param}");
+            return None;
+        },
+    };
+
+    Some(attr)
+}
+
+#[inline]
+fn unhandled(params: &Params, intermediates: &[u8], action: char) {
+    debug!(
+        "[Unhandled extended CSI] action={:?}, params={:?}, intermediates={:?}",
+        action, params, intermediates
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct MockHandler {
+        copied: Option<(RectangularArea, usize, usize)>,
+        filled: Option<(char, RectangularArea)>,
+        erased: Vec<(RectangularArea, bool)>,
+        attrs: Vec<Attr>,
+        extent: Option<RectangleExtent>,
+        protected: Option<bool>,
+        line_attributes: Vec<LineAttribute>,
+        status_strings: Vec<Option<StatusString>>,
+        capabilities: Vec<String>,
+        version_reports: usize,
+        shell_marks: Vec<ShellMark>,
+        input: String,
+        protected_after: Option<String>,
+    }
+
+    impl Handler for MockHandler {
+        fn input(&mut self, c: char) {
+            self.input.push(c);
+        }
+    }
+
+    impl ExtendedHandler for MockHandler {
+        fn copy_rectangular_area(&mut self, source: RectangularArea, top: usize, left: usize) {
+            self.copied = Some((source, top, left));
+        }
+
+        fn fill_rectangular_area(&mut self, c: char, area: RectangularArea) {
+            self.filled = Some((c, area));
+        }
+
+        fn erase_rectangular_area(&mut self, area: RectangularArea) {
+            self.erased.push((area, false));
+        }
+
+        fn selective_erase_rectangular_area(&mut self, area: RectangularArea) {
+            self.erased.push((area, true));
+        }
+
+        fn change_rectangular_area_attributes(&mut self, _area: RectangularArea, attrs: Vec<Attr>) {
+            self.attrs = attrs;
+        }
+
+        fn set_attribute_change_extent(&mut self, extent: RectangleExtent) {
+            self.extent = Some(extent);
+        }
+
+        fn set_character_protection(&mut self, protected: bool) {
+            self.protected = Some(protected);
+            self.protected_after = Some(self.input.clone());
+        }
+
+        fn set_line_attribute(&mut self, line_attribute: LineAttribute) {
+            self.line_attributes.push(line_attribute);
+        }
+
+        fn request_status_string(&mut self, setting: Option<StatusString>) {
+            self.status_strings.push(setting);
+        }
+
+        fn request_terminfo_capabilities(&mut self, names: Vec<String>) {
+            self.capabilities.extend(names);
+        }
+
+        fn report_version(&mut self) {
+            self.version_reports += 1;
+        }
+
+        fn shell_mark(&mut self, mark: ShellMark) {
+            self.shell_marks.push(mark);
+        }
+    }
+
+    fn parse(bytes: &[u8]) -> MockHandler {
+        let mut parser: Processor = Processor::new();
+        let mut handler = MockHandler::default();
+
+        for byte in bytes {
+            parser.advance(&mut handler, *byte);
+        }
+
+        handler
+    }
+
+    #[test]
+    fn parse_deccra() {
+        let handler = parse(b"\x1b[2;3;4;5;1;7;8;1$v");
+
+        let area = RectangularArea { top: Some(2), left: Some(3), bottom: Some(4), right: Some(5) };
+        assert_eq!(handler.copied, Some((area, 7, 8)));
+    }
+
+    #[test]
+    fn parse_decfra_defaults() {
+        let handler = parse(b"\x1b[88;;;3$x");
+
+        let area = RectangularArea { bottom: Some(3), ..Default::default() };
+        assert_eq!(handler.filled, Some(('X', area)));
+    }
+
+    #[test]
+    fn parse_decfra_invalid_char() {
+        let handler = parse(b"\x1b[10;1;1;2;2$x");
+
+        assert_eq!(handler.filled, None);
+    }
+
+    #[test]
+    fn parse_erase() {
+        let handler = parse(b"\x1b[1;1;2;2$z\x1b[$\x7b");
+
+        let area = RectangularArea { top: Some(1), left: Some(1), bottom: Some(2), right: Some(2) };
+        assert_eq!(handler.erased, vec![(area, false), (RectangularArea::default(), true)]);
+    }
+
+    #[test]
+    fn parse_deccara() {
+        let handler = parse(b"\x1b[1;1;2;2;1;4;27$r");
+
+        assert_eq!(handler.attrs, vec![Attr::Bold, Attr::Underline, Attr::CancelReverse]);
+    }
+
+    #[test]
+    fn parse_decsace_and_decsca() {
+        let handler = parse(b"\x1b[2*x\x1b[1\"q");
+
+        assert_eq!(handler.extent, Some(RectangleExtent::Rectangle));
+        assert_eq!(handler.protected, Some(true));
+    }
+
+    #[test]
+    fn parse_line_attributes() {
+        let handler = parse(b"\x1b#3\x1b#4\x1b#5\x1b#6\x1b#8");
+
+        assert_eq!(
+            handler.line_attributes,
+            vec![
+                LineAttribute::DoubleHeightTop,
+                LineAttribute::DoubleHeightBottom,
+                LineAttribute::Normal,
+                LineAttribute::DoubleWidth,
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_decrqss() {
+        let handler = parse(b"\x1bP$qm\x1b\\\x1bP$q q\x1b\\\x1bP$qr\x1b\\\x1bP$qx\x1b\\");
+
+        assert_eq!(
+            handler.status_strings,
+            vec![
+                Some(StatusString::GraphicRendition),
+                Some(StatusString::CursorStyle),
+                Some(StatusString::ScrollingRegion),
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_xtgettcap() {
+        let handler = parse(b"\x1bP+q544e;436f;zz\x1b\\");
+
+        assert_eq!(handler.capabilities, vec![String::from("TN"), String::from("Co")]);
+    }
+
+    #[test]
+    fn parse_xtversion() {
+        let handler = parse(b"\x1b[>q\x1b[>0q\x1b[>1q");
+
+        assert_eq!(handler.version_reports, 2);
+    }
+
+    #[test]
+    fn parse_osc133() {
+        let handler =
+            parse(b"\x1b]133;A\x07\x1b]133;B\x07\x1b]133;C\x1b\\\x1b]133;D;0\x07\x1b]133;D\x07");
+
+        assert_eq!(
+            handler.shell_marks,
+            vec![
+                ShellMark::PromptStart,
+                ShellMark::CommandStart,
+                ShellMark::OutputStart,
+                ShellMark::CommandEnd(Some(0)),
+                ShellMark::CommandEnd(None),
+            ]
+        );
+    }
+
+    #[test]
+    fn extended_sequences_during_sync() {
+        let handler = parse(b"\x1b[?2026h\x1b[1\"q");
+        assert_eq!(handler.protected, None);
+
+        let handler = parse(b"\x1b[?2026h\x1b[1\"q\x1b[?2026l");
+        assert_eq!(handler.protected, Some(true));
+
+        let handler = parse(b"\x1b[?2026hab\x1b[1\"qcd\x1b[?2026l");
+        assert_eq!(handler.protected_after.as_deref(), Some("ab"));
+        assert_eq!(handler.input, "abcd");
+    }
+
+    #[test]
+    fn standard_sequences_in_order() {
+        let handler = parse(b"a\x1b[2bb\x1b[1\"q\x1b[31mc");
+
+        assert_eq!(handler.protected_after.as_deref(), Some("aaab"));
+        assert_eq!(handler.input, "aaabc");
+    }
+}
@@ -5,9 +5,10 @@ use bitflags::bitflags;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+use crate::grid::spill::{self, SpillCell};
 use crate::grid::{self, GridCell};
 use crate::index::Column;
-use crate::vte::ansi::{Color, Hyperlink as VteHyperlink, NamedColor};
+use crate::vte::ansi::{Color, Hyperlink as VteHyperlink, NamedColor, Rgb};
 
 bitflags! {
     #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -30,6 +31,7 @@ bitflags! {
         const UNDERCURL                 = 0b0001_0000_0000_0000;
         const DOTTED_UNDERLINE          = 0b0010_0000_0000_0000;
         const DASHED_UNDERLINE          = 0b0100_0000_0000_0000;
+        const PROTECTED                 = 0b1000_0000_0000_0000;
         const ALL_UNDERLINES            = Self::UNDERLINE.bits() | Self::DOUBLE_UNDERLINE.bits()
                                         | Self::UNDERCURL.bits() | Self::DOTTED_UNDERLINE.bits()
                                         | Self::DASHED_UNDERLINE.bits();
@@ -253,6 +255,156 @@ impl GridCell for Cell {
     fn reset(&mut self, template: &Self) {
         *self = Cell { bg: template.bg, ..Cell::default() };
     }
+
+    #[inline]
+    fn split(&self) -> (char, Self) {
+        (self.c, Cell { c: ' ', ..self.clone() })
+    }
+
+    #[inline]
+    fn join(c: char, attributes: &Self) -> Self {
+        Cell { c, ..attributes.clone() }
+    }
+}
+
+impl SpillCell for Cell {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        spill::write_u32(buf, self.c as u32);
+        encode_color(self.fg, buf);
+        encode_color(self.bg, buf);
+        buf.extend_from_slice(&self.flags.bits().to_le_bytes());
+
+        let extra = match &self.extra {
+            Some(extra) => extra,
+            None => {
+                buf.push(0);
+                return;
+            },
+        };
+        buf.push(1);
+
+        spill::write_u32(buf, extra.zerowidth.len() as u32);
+        for c in &extra.zerowidth {
+            spill::write_u32(buf, *c as u32);
+        }
+
+        match extra.underline_color {
+            Some(color) => {
+                buf.push(1);
+                encode_color(color, buf);
+            },
+            None => buf.push(0),
+        }
+
+        match &extra.hyperlink {
+            Some(hyperlink) => {
+                buf.push(1);
+                for text in [hyperlink.id(), hyperlink.uri()] {
+                    spill::write_u32(buf, text.len() as u32);
+                    buf.extend_from_slice(text.as_bytes());
+                }
+            },
+            None => buf.push(0),
+        }
+    }
+
+    fn decode(bytes: &mut &[u8]) -> Option<Self> {
+        let c = char::from_u32(spill::read_u32(bytes)?)?;
+        let fg = decode_color(bytes)?;
+        let bg = decode_color(bytes)?;
+        let flags = spill::read_bytes(bytes, 2)?;
+        let flags = Flags::from_bits_retain(u16::from_le_bytes([flags[0], flags[1]]));
+
+        let mut cell = Cell { c, fg, bg, flags, extra: None };
+        if spill::read_u8(bytes)? == 0 {
+            return Some(cell);
+        }
+
+        let mut extra = CellExtra::default();
+
+        let zerowidth_len = spill::read_u32(bytes)?;
+        for _ in 0..zerowidth_len {
+            extra.zerowidth.push(char::from_u32(spill::read_u32(bytes)?)?);
+        }
+
+        if spill::read_u8(bytes)? == 1 {
+            extra.underline_color = Some(decode_color(bytes)?);
+        }
+
+        if spill::read_u8(bytes)? == 1 {
+            let mut text = || {
+                let len = spill::read_u32(bytes)? as usize;
+                String::from_utf8(spill::read_bytes(bytes, len)?.to_vec()).ok()
+            };
+            let id = text()?;
+            let uri = text()?;
+            extra.hyperlink = Some(Hyperlink::new(Some(id), uri));
+        }
+
+        cell.extra = Some(Arc::new(extra));
+
+        Some(cell)
+    }
+}
+
+/// All named colors, used for decoding spilled cells.
+const NAMED_COLORS: [NamedColor; 29] = [
+    NamedColor::Black,
+    NamedColor::Red,
+    NamedColor::Green,
+    NamedColor::Yellow,
+    NamedColor::Blue,
+    NamedColor::Magenta,
+    NamedColor::Cyan,
+    NamedColor::White,
+    NamedColor::BrightBlack,
+    NamedColor::BrightRed,
+    NamedColor::BrightGreen,
+    NamedColor::BrightYellow,
+    NamedColor::BrightBlue,
+    NamedColor::BrightMagenta,
+    NamedColor::BrightCyan,
+    NamedColor::BrightWhite,
+    NamedColor::Foreground,
+    NamedColor::Background,
+    NamedColor::Cursor,
+    NamedColor::DimBlack,
+    NamedColor::DimRed,
+    NamedColor::DimGreen,
+    NamedColor::DimYellow,
+    NamedColor::DimBlue,
+    NamedColor::DimMagenta,
+    NamedColor::DimCyan,
+    NamedColor::DimWhite,
+    NamedColor::BrightForeground,
+    NamedColor::DimForeground,
+];
+
+fn encode_color(color: Color, buf: &mut Vec<u8>) {
+    match color {
+        Color::Named(named) => {
+            buf.push(0);
+            buf.extend_from_slice(&(named as u16).to_le_bytes());
+        },
+        Color::Spec(Rgb { r, g, b }) => buf.extend_from_slice(&[1, r, g, b]),
+        Color::Indexed(index) => buf.extend_from_slice(&[2, index]),
+    }
+}
+
+fn decode_color(bytes: &mut &[u8]) -> Option<Color> {
+    match spill::read_u8(bytes)? {
+        0 => {
+            let named = spill::read_bytes(bytes, 2)?;
+            let named = u16::from_le_bytes([named[0], named[1]]);
+            NAMED_COLORS.iter().find(|color| **color as u16 == named).map(|c| Color::Named(*c))
+        },
+        1 => {
+            let rgb = spill::read_bytes(bytes, 3)?;
+            Some(Color::Spec(Rgb { r: rgb[0], g: rgb[1], b: rgb[2] }))
+        },
+        2 => Some(Color::Indexed(spill::read_u8(bytes)?)),
+        _ => None,
+    }
 }
 
 impl From<Color> for Cell {
@@ -0,0 +1,559 @@
+//! Export of terminal content as plain text, ANSI escape sequences or HTML.
+
+use std::cmp::{max, min};
+use std::fmt::Write;
+
+use crate::grid::{Dimensions, Grid, LineAttribute, Row};
+use crate::index::{Column, Line};
+use crate::selection::SelectionRange;
+use crate::term::cell::{Cell, Flags, Hyperlink, LineLength};
+use crate::term::color::COUNT;
+use crate::term::Term;
+use crate::vte::ansi::{Color, NamedColor, Rgb};
+
+/// Factor for dimming truecolor foregrounds.
+const DIM_FACTOR: f32 = 0.66;
+
+/// Cell flags which affect the appearance of the exported text.
+const STYLE_FLAGS: Flags = Flags::INVERSE
+    .union(Flags::BOLD)
+    .union(Flags::ITALIC)
+    .union(Flags::DIM)
+    .union(Flags::HIDDEN)
+    .union(Flags::STRIKEOUT)
+    .union(Flags::ALL_UNDERLINES);
+
+/// Output format of an export.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Text without any formatting.
+    #[default]
+    Plain,
+
+    /// Text with SGR and OSC 8 escape sequences.
+    Ansi,
+
+    /// Self-contained HTML document.
+    Html,
+}
+
+impl ExportFormat {
+    /// File extension commonly used for the format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Plain => "txt",
+            Self::Ansi => "ans",
+            Self::Html => "html",
+        }
+    }
+}
+
+/// Export the terminal content within `range`.
+///
+/// Soft-wrapped lines are joined, so the output reflows like the terminal would. Plain text
+/// matches the text copied from a selection. The `palette` is only used to resolve colors for
+/// HTML output.
+pub fn export<T>(
+    term: &Term<T>,
+    range: SelectionRange,
+    format: ExportFormat,
+    palette: &[Rgb; COUNT],
+) -> String {
+    match format {
+        ExportFormat::Plain if range.is_block => {
+            term.block_to_string(range.start, range.end) + "\n"
+        },
+        ExportFormat::Plain => term.bounds_to_string(range.start, range.end) + "\n",
+        ExportFormat::Ansi => write_range(term.grid(), range, AnsiWriter::default()),
+        ExportFormat::Html => write_range(term.grid(), range, HtmlWriter::new(palette)),
+    }
+}
+
+/// Feed all cells within `range` to `writer`.
+fn write_range<W: Writer>(grid: &Grid<Cell>, range: SelectionRange, mut writer: W) -> String {
+    let last_column = Column(grid.columns() - 1);
+
+    let mut wrapped = false;
+    let mut text = String::new();
+    for line in (range.start.line.0..=range.end.line.0).map(Line::from) {
+        let row = grid.row(line);
+
+        let mut start =
+            if line == range.start.line || range.is_block { range.start.column } else { Column(0) };
+        let end =
+            if line == range.end.line || range.is_block { range.end.column } else { last_column };
+
+        // Include wide char when its trailing spacer is the first cell.
+        if start.0 > 0 && row[start].flags.contains(Flags::WIDE_CHAR_SPACER) {
+            start -= 1;
+        }
+
+        if !wrapped {
+            writer.line_start(row.line_attribute());
+        }
+
+        let length = min(content_length(&row), end + 1);
+        for column in (start.0..length.0).map(Column::from) {
+            let cell = &row[column];
+            if cell.flags.intersects(Flags::WIDE_CHAR_SPACER | Flags::LEADING_WIDE_CHAR_SPACER) {
+                continue;
+            }
+
+            text.clear();
+            text.push(cell.c);
+            for c in cell.zerowidth().into_iter().flatten() {
+                text.push(*c);
+            }
+
+            writer.cell(cell, &text);
+        }
+
+        wrapped = !range.is_block
+            && line != range.end.line
+            && row[last_column].flags.contains(Flags::WRAPLINE);
+        if !wrapped {
+            writer.line_end();
+        }
+    }
+
+    writer.finish()
+}
+
+/// Number of columns in a row which should be exported.
+///
+/// Trailing blank cells are still included when their background is visible.
+fn content_length(row: &Row<Cell>) -> Column {
+    let length = row.line_length();
+    let visible = row[..]
+        .iter()
+        .rposition(|cell| {
+            cell.bg != Color::Named(NamedColor::Background) || cell.flags.contains(Flags::INVERSE)
+        })
+        .map_or(0, |index| index + 1);
+
+    max(length, Column(visible))
+}
+
+/// Output target for exported cells.
+trait Writer {
+    /// Called at the beginning of every line that isn't a soft-wrapped continuation.
+    fn line_start(&mut self, _line_attribute: LineAttribute) {}
+
+    /// Append a cell, with `text` being its character and zerowidth characters.
+    fn cell(&mut self, cell: &Cell, text: &str);
+
+    /// Called at the end of every line that isn't soft-wrapped.
+    fn line_end(&mut self);
+
+    /// Complete the output.
+    fn finish(self) -> String;
+}
+
+/// Attributes of a cell which are preserved in styled output.
+#[derive(Clone, PartialEq, Eq)]
+struct Style {
+    fg: Color,
+    bg: Color,
+    underline_color: Option<Color>,
+    flags: Flags,
+    hyperlink: Option<Hyperlink>,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Self::new(&Cell::default())
+    }
+}
+
+impl Style {
+    fn new(cell: &Cell) -> Self {
+        Self {
+            fg: cell.fg,
+            bg: cell.bg,
+            underline_color: cell.underline_color(),
+            flags: cell.flags & STYLE_FLAGS,
+            hyperlink: cell.hyperlink(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct AnsiWriter {
+    output: String,
+    style: Style,
+}
+
+impl AnsiWriter {
+    /// Switch to a new style, emitting only the required escapes.
+    fn set_style(&mut self, style: Style) {
+        if style.hyperlink != self.style.hyperlink {
+            match &style.hyperlink {
+                Some(hyperlink) => {
+                    let _ = write!(
+                        self.output,
+                        "\x1b]8;id={};{}\x1b\\",
+                        hyperlink.id(),
+                        hyperlink.uri()
+                    );
+                },
+                None => self.output.push_str("\x1b]8;;\x1b\\"),
+            }
+        }
+
+        if style.fg != self.style.fg
+            || style.bg != self.style.bg
+            || style.underline_color != self.style.underline_color
+            || style.flags != self.style.flags
+        {
+            self.output.push_str("\x1b[0");
+
+            let flags = style.flags;
+            for (flag, sgr) in [
+                (Flags::BOLD, "1"),
+                (Flags::DIM, "2"),
+                (Flags::ITALIC, "3"),
+                (Flags::UNDERLINE, "4"),
+                (Flags::DOUBLE_UNDERLINE, "4:2"),
+                (Flags::UNDERCURL, "4:3"),
+                (Flags::DOTTED_UNDERLINE, "4:4"),
+                (Flags::DASHED_UNDERLINE, "4:5"),
+                (Flags::INVERSE, "7"),
+                (Flags::HIDDEN, "8"),
+                (Flags::STRIKEOUT, "9"),
+            ] {
+                if flags.contains(flag) {
+                    self.output.push(';');
+                    self.output.push_str(sgr);
+                }
+            }
+
+            write_sgr_color(&mut self.output, style.fg, 30);
+            write_sgr_color(&mut self.output, style.bg, 40);
+            if let Some(color) = style.underline_color {
+                write_sgr_color(&mut self.output, color, 50);
+            }
+
+            self.output.push('m');
+        }
+
+        self.style = style;
+    }
+}
+
+impl Writer for AnsiWriter {
+    fn line_start(&mut self, line_attribute: LineAttribute) {
+        match line_attribute {
+            LineAttribute::Normal => (),
+            LineAttribute::DoubleWidth => self.output.push_str("\x1b#6"),
+            LineAttribute::DoubleHeightTop => self.output.push_str("\x1b#3"),
+            LineAttribute::DoubleHeightBottom => self.output.push_str("\x1b#4"),
+        }
+    }
+
+    fn cell(&mut self, cell: &Cell, text: &str) {
+        let style = Style::new(cell);
+        if style != self.style {
+            self.set_style(style);
+        }
+
+        self.output.push_str(text);
+    }
+
+    fn line_end(&mut self) {
+        // Reset attributes, so every line can be displayed on its own.
+        self.set_style(Style::default());
+        self.output.push('\n');
+    }
+
+    fn finish(mut self) -> String {
+        self.set_style(Style::default());
+        self.output
+    }
+}
+
+/// Append a color parameter to an SGR sequence.
+///
+/// The `base` is the parameter for black, `30` for foreground and `40` for background colors.
+/// Underline colors use `50`, since they don't have any short form.
+fn write_sgr_color(output: &mut String, color: Color, base: u8) {
+    let index = match color {
+        Color::Spec(Rgb { r, g, b }) => {
+            let _ = write!(output, ";{};2;{r};{g};{b}", base + 8);
+            return;
+        },
+        Color::Indexed(index) => index as usize,
+        Color::Named(named) => match named as usize {
+            index @ 0..=15 => index,
+            index
+                if (NamedColor::DimBlack as usize..=NamedColor::DimWhite as usize)
+                    .contains(&index) =>
+            {
+                index - NamedColor::DimBlack as usize
+            },
+            // Foreground, background and their variations are the default colors.
+            _ => return,
+        },
+    };
+
+    match index {
+        0..=7 if base != 50 => {
+            let _ = write!(output, ";{}", base as usize + index);
+        },
+        8..=15 if base != 50 => {
+            let _ = write!(output, ";{}", base as usize + 60 + index - 8);
+        },
+        _ => {
+            let _ = write!(output, ";{};5;{index}", base + 8);
+        },
+    }
+}
+
+struct HtmlWriter<'a> {
+    output: String,
+    palette: &'a [Rgb; COUNT],
+    style: Option<Style>,
+    default_style: Style,
+}
+
+impl<'a> HtmlWriter<'a> {
+    fn new(palette: &'a [Rgb; COUNT]) -> Self {
+        let mut output = String::from(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>LaraShell</title>\n\
+             </head>\n",
+        );
+
+        let fg = palette[NamedColor::Foreground as usize];
+        let bg = palette[NamedColor::Background as usize];
+        let _ = write!(
+            output,
+            "<body style=\"margin: 0; background-color: {};\">\n<pre style=\"margin: 0; \
+             padding: 1em; color: {}; background-color: {}; white-space: pre-wrap; \
+             font-family: monospace;\">",
+            css_color(bg),
+            css_color(fg),
+            css_color(bg),
+        );
+
+        Self { output, palette, style: None, default_style: Style::default() }
+    }
+
+    /// Close the currently open span and link.
+    fn close(&mut self) {
+        match self.style.take() {
+            Some(style) if style != self.default_style => {
+                self.output.push_str("</span>");
+                if style.hyperlink.is_some() {
+                    self.output.push_str("</a>");
+                }
+            },
+            _ => (),
+        }
+    }
+
+    /// Open a span with the CSS for `style`.
+    ///
+    /// Cells without any attributes are written as-is, since they use the `pre` element's style.
+    fn open(&mut self, style: Style) {
+        if style == self.default_style {
+            self.style = Some(style);
+            return;
+        }
+
+        if let Some(hyperlink) = &style.hyperlink {
+            self.output.push_str("<a href=\"");
+            escape(&mut self.output, hyperlink.uri());
+            self.output.push_str("\">");
+        }
+
+        let flags = style.flags;
+        let mut fg = self.foreground(style.fg, flags);
+        let mut bg = self.color(style.bg);
+        if flags.contains(Flags::INVERSE) {
+            (fg, bg) = (bg, fg);
+        }
+        if flags.contains(Flags::HIDDEN) {
+            fg = bg;
+        }
+
+        let _ = write!(self.output, "<span style=\"color: {};", css_color(fg));
+        if bg != self.palette[NamedColor::Background as usize] {
+            let _ = write!(self.output, " background-color: {};", css_color(bg));
+        }
+        if flags.contains(Flags::BOLD) {
+            self.output.push_str(" font-weight: bold;");
+        }
+        if flags.contains(Flags::ITALIC) {
+            self.output.push_str(" font-style: italic;");
+        }
+
+        let underline = if flags.contains(Flags::DOUBLE_UNDERLINE) {
+            Some("underline double")
+        } else if flags.contains(Flags::UNDERCURL) {
+            Some("underline wavy")
+        } else if flags.contains(Flags::DOTTED_UNDERLINE) {
+            Some("underline dotted")
+        } else if flags.contains(Flags::DASHED_UNDERLINE) {
+            Some("underline dashed")
+        } else if flags.contains(Flags::UNDERLINE) || style.hyperlink.is_some() {
+            Some("underline")
+        } else {
+            None
+        };
+        match (underline, flags.contains(Flags::STRIKEOUT)) {
+            (Some(underline), true) => {
+                let _ = write!(self.output, " text-decoration: {underline} line-through;");
+            },
+            (Some(underline), false) => {
+                let _ = write!(self.output, " text-decoration: {underline};");
+            },
+            (None, true) => self.output.push_str(" text-decoration: line-through;"),
+            (None, false) => (),
+        }
+        if let Some(color) = style.underline_color.filter(|_| underline.is_some()) {
+            let _ =
+                write!(self.output, " text-decoration-color: {};", css_color(self.color(color)));
+        }
+
+        self.output.push_str("\">");
+        self.style = Some(style);
+    }
+
+    /// Resolve a foreground color, applying the dim attribute.
+    fn foreground(&self, color: Color, flags: Flags) -> Rgb {
+        if !flags.contains(Flags::DIM) {
+            return self.color(color);
+        }
+
+        match color {
+            Color::Spec(rgb) => rgb * DIM_FACTOR,
+            Color::Named(named) => self.palette[named.to_dim() as usize],
+            Color::Indexed(index @ 0..=7) => {
+                self.palette[NamedColor::DimBlack as usize + index as usize]
+            },
+            Color::Indexed(index) => self.palette[index as usize],
+        }
+    }
+
+    /// Resolve a color using the palette.
+    fn color(&self, color: Color) -> Rgb {
+        match color {
+            Color::Spec(rgb) => rgb,
+            Color::Named(named) => self.palette[named as usize],
+            Color::Indexed(index) => self.palette[index as usize],
+        }
+    }
+}
+
+impl<'a> Writer for HtmlWriter<'a> {
+    fn cell(&mut self, cell: &Cell, text: &str) {
+        let style = Style::new(cell);
+        if self.style.as_ref() != Some(&style) {
+            self.close();
+            self.open(style);
+        }
+
+        escape(&mut self.output, text);
+    }
+
+    fn line_end(&mut self) {
+        self.close();
+        self.output.push('\n');
+    }
+
+    fn finish(mut self) -> String {
+        self.close();
+        self.output.push_str("</pre>\n</body>\n</html>\n");
+        self.output
+    }
+}
+
+/// Format a color for use in CSS.
+fn css_color(color: Rgb) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b)
+}
+
+/// Append text with HTML special characters escaped.
+fn escape(output: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            '"' => output.push_str("&quot;"),
+            c => output.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::index::Point;
+    use crate::term::test::mock_term;
+
+    fn full_range(grid: &Grid<Cell>) -> SelectionRange {
+        let end = Point::new(Line(grid.screen_lines() as i32 - 1), Column(grid.columns() - 1));
+        SelectionRange::new(Point::new(Line(0), Column(0)), end, false)
+    }
+
+    #[test]
+    fn plain_joins_wrapped_lines() {
+        let term = mock_term("hello\nworld\r\n:)");
+        let palette = [Rgb::default(); COUNT];
+        let text = export(&term, full_range(term.grid()), ExportFormat::Plain, &palette);
+        assert_eq!(text, "helloworld\n:)\n");
+    }
+
+    #[test]
+    fn plain_block_selection() {
+        let term = mock_term("hello\nworld\r\n:)");
+
+        let range = SelectionRange::new(
+            Point::new(Line(0), Column(1)),
+            Point::new(Line(1), Column(2)),
+            true,
+        );
+        let palette = [Rgb::default(); COUNT];
+        let text = export(&term, range, ExportFormat::Plain, &palette);
+        assert_eq!(text, "el\nor\n");
+    }
+
+    #[test]
+    fn ansi_attributes() {
+        let mut term = mock_term("abc\r\ndef");
+        let grid = term.grid_mut();
+        grid[Line(0)][Column(1)].fg = Color::Named(NamedColor::Red);
+        grid[Line(0)][Column(1)].flags.insert(Flags::BOLD);
+        grid[Line(0)][Column(2)].bg = Color::Indexed(200);
+        grid[Line(1)][Column(0)].fg = Color::Spec(Rgb { r: 1, g: 2, b: 3 });
+        let hyperlink = Hyperlink::new(Some("1"), String::from("https://example.org"));
+        grid[Line(1)][Column(1)].set_hyperlink(Some(hyperlink));
+        grid[Line(1)].set_line_attribute(LineAttribute::DoubleWidth);
+
+        let palette = [Rgb::default(); COUNT];
+        let text = export(&term, full_range(term.grid()), ExportFormat::Ansi, &palette);
+        assert_eq!(
+            text,
+            "a\x1b[0;1;31mb\x1b[0;48;5;200mc\x1b[0m\n\x1b#6\x1b[0;38;2;1;2;3md\
+             \x1b]8;id=1;https://example.org\x1b\\\x1b[0me\x1b]8;;\x1b\\f\n"
+        );
+    }
+
+    #[test]
+    fn html_escapes_and_colors() {
+        let mut term = mock_term("<&>");
+        term.grid_mut()[Line(0)][Column(1)].fg = Color::Named(NamedColor::Red);
+
+        let mut palette = [Rgb::default(); COUNT];
+        palette[NamedColor::Red as usize] = Rgb { r: 0xff, g: 0, b: 0 };
+        palette[NamedColor::Background as usize] = Rgb { r: 0x10, g: 0x20, b: 0x30 };
+
+        let html = export(&term, full_range(term.grid()), ExportFormat::Html, &palette);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("background-color: #102030;"));
+        assert!(html.contains(
+            "font-family: monospace;\">&lt;<span style=\"color: #ff0000;\">&amp;</span>&gt;\n</pre>"
+        ));
+    }
+}
@@ -1,5 +1,7 @@
 //! Exports the `Term` type which is a high-level API for the Grid.
 
+#[cfg(feature = "serde")]
+use std::borrow::Cow;
 use std::ops::{Index, IndexMut, Range};
 use std::sync::Arc;
 use std::{cmp, mem, ptr, slice, str};
@@ -11,11 +13,15 @@ use base64::engine::general_purpose::STANDARD as Base64;
 use base64::Engine;
 use bitflags::bitflags;
 use log::{debug, trace};
+use unicode_segmentation::UnicodeSegmentation;
 use unicode_width::UnicodeWidthChar;
 
-use crate::event::{Event, EventListener};
-use crate::grid::{Dimensions, Grid, GridIterator, Scroll};
+use crate::event::{Event, EventListener, WindowSize};
+use crate::grid::{
+    Dimensions, Grid, GridIterator, LineAttribute, PromptMark, Rectangle, RectangleExtent, Scroll,
+};
 use crate::index::{self, Boundary, Column, Direction, Line, Point, Side};
+use crate::parser::{ExtendedHandler, RectangularArea, ShellMark, StatusString};
 use crate::selection::{Selection, SelectionRange, SelectionType};
 use crate::term::cell::{Cell, Flags, LineLength};
 use crate::term::color::Colors;
@@ -28,6 +34,8 @@ use crate::vte::ansi::{
 
 pub mod cell;
 pub mod color;
+pub mod export;
+pub mod prompt;
 pub mod search;
 
 /// Minimum number of columns.
@@ -50,6 +58,22 @@ const KEYBOARD_MODE_STACK_MAX_DEPTH: usize = TITLE_STACK_MAX_DEPTH;
 /// Default tab interval, corresponding to terminfo `it` value.
 const INITIAL_TABSTOPS: usize = 8;
 
+/// Private mode for grapheme cluster segmentation.
+const GRAPHEME_CLUSTER_MODE: u16 = 2027;
+
+/// Terminfo capabilities reported through XTGETTCAP.
+const TERMINFO_CAPABILITIES: &[(&str, &str)] = &[
+    ("TN", "larashell"),
+    ("name", "larashell"),
+    ("Co", "256"),
+    ("colors", "256"),
+    ("RGB", "8/8/8"),
+    ("Smulx", "\x1b[4:%p1%dm"),
+    ("Ss", "\x1b[%p1%d q"),
+    ("Se", "\x1b[0 q"),
+    ("Sync", "\x1b[?2026%?%p1%{1}%-%tl%eh%;"),
+];
+
 bitflags! {
     #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
     pub struct TermMode: u32 {
@@ -78,6 +102,7 @@ bitflags! {
         const REPORT_ALTERNATE_KEYS   = 0b0001_0000_0000_0000_0000_0000;
         const REPORT_ALL_KEYS_AS_ESC  = 0b0010_0000_0000_0000_0000_0000;
         const REPORT_ASSOCIATED_TEXT  = 0b0100_0000_0000_0000_0000_0000;
+        const GRAPHEME_CLUSTERS       = 0b1000_0000_0000_0000_0000_0000;
         const KITTY_KEYBOARD_PROTOCOL = Self::DISAMBIGUATE_ESC_CODES.bits()
                                       | Self::REPORT_EVENT_TYPES.bits()
                                       | Self::REPORT_ALTERNATE_KEYS.bits()
@@ -325,6 +350,15 @@ pub struct Term<T> {
     /// Information about damaged cells.
     damage: TermDamageState,
 
+    /// Area affected by rectangular attribute changes.
+    attribute_change_extent: RectangleExtent,
+
+    /// Whether the shell reported prompt positions through OSC 133.
+    shell_integration: bool,
+
+    /// Buffer reused for grapheme cluster segmentation.
+    grapheme_buffer: String,
+
     /// Config directly for the terminal.
     config: Config,
 }
@@ -335,6 +369,15 @@ pub struct Config {
     /// The maximum amount of scrolling history.
     pub scrolling_history: usize,
 
+    /// Maximum memory in bytes used for compressed scrolling history.
+    ///
+    /// Lines exceeding [`Config::scrolling_history`] are compressed and kept until this limit is
+    /// reached. Compression is disabled when this is zero.
+    pub scrolling_history_memory: usize,
+
+    /// Store history exceeding [`Config::scrolling_history_memory`] in a temporary file.
+    pub scrolling_history_spill: bool,
+
     /// Default cursor style to reset the cursor to.
     pub default_cursor_style: CursorStyle,
 
@@ -357,6 +400,8 @@ impl Default for Config {
     fn default() -> Self {
         Self {
             scrolling_history: 10000,
+            scrolling_history_memory: 0,
+            scrolling_history_spill: false,
             semantic_escape_chars: SEMANTIC_ESCAPE_CHARS.to_owned(),
             default_cursor_style: Default::default(),
             vi_mode_cursor_style: Default::default(),
@@ -384,6 +429,64 @@ pub enum Osc52 {
     CopyPaste,
 }
 
+/// Serializable terminal state, used for restoring sessions.
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TermSnapshot<'a> {
+    /// Currently active grid.
+    pub grid: Cow<'a, Grid<Cell>>,
+
+    /// Cursor position in the active grid.
+    pub cursor: Point,
+
+    /// Currently inactive grid.
+    pub inactive_grid: Cow<'a, Grid<Cell>>,
+
+    /// Cursor position in the inactive grid.
+    pub inactive_cursor: Point,
+
+    /// Raw bits of the terminal mode.
+    pub mode: u32,
+
+    /// Window title.
+    #[serde(default)]
+    pub title: Option<String>,
+}
+
+/// Terminal dimensions in cells.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TermSize {
+    pub columns: usize,
+    pub screen_lines: usize,
+}
+
+impl TermSize {
+    pub fn new(columns: usize, screen_lines: usize) -> Self {
+        Self { columns, screen_lines }
+    }
+}
+
+impl Dimensions for TermSize {
+    fn total_lines(&self) -> usize {
+        self.screen_lines()
+    }
+
+    fn screen_lines(&self) -> usize {
+        self.screen_lines
+    }
+
+    fn columns(&self) -> usize {
+        self.columns
+    }
+}
+
+impl From<WindowSize> for TermSize {
+    fn from(window_size: WindowSize) -> Self {
+        Self::new(window_size.num_cols.into(), window_size.num_lines.into())
+    }
+}
+
 impl<T> Term<T> {
     #[inline]
     pub fn scroll_display(&mut self, scroll: Scroll)
@@ -412,7 +515,9 @@ impl<T> Term<T> {
         let num_lines = dimensions.screen_lines();
 
         let history_size = options.scrolling_history;
-        let grid = Grid::new(num_lines, num_cols, history_size);
+        let mut grid = Grid::new(num_lines, num_cols, history_size);
+        grid.update_compressed_history(options.scrolling_history_memory);
+        grid.update_history_spill(options.scrolling_history_spill);
         let alt = Grid::new(num_lines, num_cols, 0);
 
         let tabs = TabStops::new(grid.columns());
@@ -440,6 +545,9 @@ impl<T> Term<T> {
             inactive_keyboard_mode_stack: Default::default(),
             selection: None,
             damage,
+            attribute_change_extent: Default::default(),
+            shell_integration: Default::default(),
+            grapheme_buffer: Default::default(),
             config: options,
         }
     }
@@ -495,24 +603,41 @@ impl<T> Term<T> {
         self.damage.full = true;
     }
 
-    /// Set new options for the [`Term`].
-    pub fn set_options(&mut self, options: Config)
+    /// Current title set by the application.
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    /// Send the current title to the event listener.
+    pub fn refresh_title(&self)
     where
         T: EventListener,
     {
-        let old_config = mem::replace(&mut self.config, options);
-
         let title_event = match &self.title {
             Some(title) => Event::Title(title.clone()),
             None => Event::ResetTitle,
         };
 
         self.event_proxy.send_event(title_event);
+    }
+
+    /// Set new options for the [`Term`].
+    pub fn set_options(&mut self, options: Config)
+    where
+        T: EventListener,
+    {
+        let old_config = mem::replace(&mut self.config, options);
+
+        self.refresh_title();
 
         if self.mode.contains(TermMode::ALT_SCREEN) {
             self.inactive_grid.update_history(self.config.scrolling_history);
+            self.inactive_grid.update_compressed_history(self.config.scrolling_history_memory);
+            self.inactive_grid.update_history_spill(self.config.scrolling_history_spill);
         } else {
             self.grid.update_history(self.config.scrolling_history);
+            self.grid.update_compressed_history(self.config.scrolling_history_memory);
+            self.grid.update_history_spill(self.config.scrolling_history_spill);
         }
 
         if self.config.kitty_keyboard != old_config.kitty_keyboard {
@@ -530,30 +655,32 @@ impl<T> Term<T> {
         let selection_range = self.selection.as_ref().and_then(|s| s.to_range(self))?;
         let SelectionRange { start, end, .. } = selection_range;
 
-        let mut res = String::new();
-
-        match self.selection.as_ref() {
-            Some(Selection { ty: SelectionType::Block, .. }) => {
-                for line in (start.line.0..end.line.0).map(Line::from) {
-                    res += self
-                        .line_to_string(line, start.column..end.column, start.column.0 != 0)
-                        .trim_end();
-                    res += "\n";
-                }
-
-                res += self.line_to_string(end.line, start.column..end.column, true).trim_end();
-            },
+        let res = match self.selection.as_ref() {
+            Some(Selection { ty: SelectionType::Block, .. }) => self.block_to_string(start, end),
             Some(Selection { ty: SelectionType::Lines, .. }) => {
-                res = self.bounds_to_string(start, end) + "\n";
-            },
-            _ => {
-                res = self.bounds_to_string(start, end);
+                self.bounds_to_string(start, end) + "\n"
             },
-        }
+            _ => self.bounds_to_string(start, end),
+        };
 
         Some(res)
     }
 
+    /// Convert the rectangle between two points to a String.
+    pub fn block_to_string(&self, start: Point, end: Point) -> String {
+        let mut res = String::new();
+
+        for line in (start.line.0..end.line.0).map(Line::from) {
+            res +=
+                self.line_to_string(line, start.column..end.column, start.column.0 != 0).trim_end();
+            res += "\n";
+        }
+
+        res += self.line_to_string(end.line, start.column..end.column, true).trim_end();
+
+        res
+    }
+
     /// Convert range between two points to a String.
     pub fn bounds_to_string(&self, start: Point, end: Point) -> String {
         let mut res = String::new();
@@ -577,7 +704,7 @@ impl<T> Term<T> {
     ) -> String {
         let mut text = String::new();
 
-        let grid_line = &self.grid[line];
+        let grid_line = self.grid.row(line);
         let line_length = cmp::min(grid_line.line_length(), cols.end + 1);
 
         // Include wide char when trailing spacer is selected.
@@ -614,8 +741,7 @@ impl<T> Term<T> {
         }
 
         if cols.end >= self.columns() - 1
-            && (line_length.0 == 0
-                || !self.grid[line][line_length - 1].flags.contains(Flags::WRAPLINE))
+            && (line_length.0 == 0 || !grid_line[line_length - 1].flags.contains(Flags::WRAPLINE))
         {
             text.push('\n');
         }
@@ -651,6 +777,104 @@ impl<T> Term<T> {
         &mut self.grid
     }
 
+    /// Serializable view of the terminal state.
+    #[cfg(feature = "serde")]
+    pub fn snapshot(&self) -> TermSnapshot<'_> {
+        TermSnapshot {
+            grid: Cow::Borrowed(&self.grid),
+            cursor: self.grid.cursor.point,
+            inactive_grid: Cow::Borrowed(&self.inactive_grid),
+            inactive_cursor: self.inactive_grid.cursor.point,
+            mode: self.mode.bits(),
+            title: self.title.clone(),
+        }
+    }
+
+    /// Restore the primary screen content of a snapshot as scrollback history.
+    ///
+    /// All lines up to the snapshot's cursor are placed in the history above an empty viewport,
+    /// ready for new output.
+    #[cfg(feature = "serde")]
+    pub fn restore_scrollback(&mut self, snapshot: TermSnapshot<'_>) {
+        // While the alternate screen is active, the primary screen is the inactive grid.
+        let mode = TermMode::from_bits_truncate(snapshot.mode);
+        let (grid, cursor) = if mode.contains(TermMode::ALT_SCREEN) {
+            (snapshot.inactive_grid, snapshot.inactive_cursor)
+        } else {
+            (snapshot.grid, snapshot.cursor)
+        };
+
+        let mut grid = grid.into_owned();
+        if grid.columns() == 0 || grid.screen_lines() == 0 {
+            return;
+        }
+
+        // Reflow the content to the new width, keeping track of the cursor line.
+        grid.cursor.point.line = cmp::max(cmp::min(cursor.line, grid.bottommost_line()), Line(0));
+        grid.resize(true, grid.screen_lines(), self.columns());
+
+        // Exclude the cursor line when it's empty, since it doesn't contain a prompt.
+        let mut end = grid.cursor.point.line;
+        if grid[end].is_clear() {
+            end -= 1;
+        }
+
+        let screen_lines = self.screen_lines();
+        let region = Line(0)..Line(screen_lines as i32);
+        let mut filled = 0;
+        for line in (grid.topmost_line().0..=end.0).map(Line::from) {
+            if filled == screen_lines {
+                self.grid.scroll_up(&region, 1);
+                filled -= 1;
+            }
+
+            self.grid[Line(filled as i32)] = grid[line].clone();
+            filled += 1;
+        }
+
+        // Move restored lines from the viewport into history.
+        self.grid.scroll_up(&region, filled);
+        self.grid.reindex_marks();
+        self.grid.cursor.point = Point::default();
+
+        self.mark_fully_damaged();
+    }
+
+    /// Restore the exact terminal state of a snapshot.
+    ///
+    /// The snapshot's content is resized to the terminal's dimensions, if they don't match.
+    #[cfg(feature = "serde")]
+    pub fn restore(&mut self, snapshot: TermSnapshot<'_>) {
+        let size = TermSize::new(self.columns(), self.screen_lines());
+
+        self.grid = snapshot.grid.into_owned();
+        self.grid.cursor.point = snapshot.cursor;
+        self.inactive_grid = snapshot.inactive_grid.into_owned();
+        self.inactive_grid.cursor.point = snapshot.inactive_cursor;
+        self.mode = TermMode::from_bits_truncate(snapshot.mode);
+        self.title = snapshot.title;
+
+        // Restored compressed history is only limited once the configured limits are applied.
+        let primary_grid = if self.mode.contains(TermMode::ALT_SCREEN) {
+            &mut self.inactive_grid
+        } else {
+            &mut self.grid
+        };
+        primary_grid.update_history_spill(self.config.scrolling_history_spill);
+        primary_grid.update_compressed_history(self.config.scrolling_history_memory);
+
+        // Restored marks are not part of the index yet.
+        self.grid.reindex_marks();
+        self.inactive_grid.reindex_marks();
+
+        self.selection = None;
+        self.vi_mode_cursor = ViModeCursor::new(self.grid.cursor.point);
+        self.scroll_region = Line(0)..Line(self.screen_lines() as i32);
+
+        self.resize(size);
+        self.mark_fully_damaged();
+    }
+
     /// Resize terminal to new dimensions.
     pub fn resize<S: Dimensions>(&mut self, size: S) {
         let old_cols = self.columns();
@@ -960,7 +1184,11 @@ impl<T> Term<T> {
 
         trace!("Wrapping input");
 
-        self.grid.cursor_cell().flags.insert(Flags::WRAPLINE);
+        // Double width lines are never reflowed, so they cannot be soft-wrapped.
+        let line = self.grid.cursor.point.line;
+        if !self.grid[line].line_attribute().is_double_width() {
+            self.grid.cursor_cell().flags.insert(Flags::WRAPLINE);
+        }
 
         if self.grid.cursor.point.line + 1 >= self.scroll_region.end {
             self.linefeed();
@@ -974,6 +1202,113 @@ impl<T> Term<T> {
         self.damage_cursor();
     }
 
+    /// Append `c` to the grapheme cluster in front of the cursor.
+    ///
+    /// Returns `false` without modifying the grid if `c` starts a new grapheme cluster.
+    fn extend_grapheme_cluster(&mut self, c: char) -> bool
+    where
+        T: EventListener,
+    {
+        // Find the cell containing the previous grapheme cluster.
+        let mut point = self.grid.cursor.point;
+        if !self.grid.cursor.input_needs_wrap {
+            if point.column == 0 {
+                return false;
+            }
+            point.column -= 1;
+        }
+        if self.grid[point].flags.contains(Flags::WIDE_CHAR_SPACER) {
+            point.column -= 1;
+        }
+
+        let cell = &self.grid[point];
+
+        // ASCII characters never continue a cluster ending in ASCII, so skip segmentation.
+        let last = cell.zerowidth().and_then(|zerowidth| zerowidth.last()).unwrap_or(&cell.c);
+        if c.is_ascii() && last.is_ascii() {
+            return false;
+        }
+
+        let mut cluster = mem::take(&mut self.grapheme_buffer);
+        cluster.clear();
+        cluster.push(cell.c);
+        cluster.extend(cell.zerowidth().into_iter().flatten());
+        cluster.push(c);
+
+        let extends = cluster.graphemes(true).nth(1).is_none();
+        let width = if cell.flags.contains(Flags::WIDE_CHAR) { 2 } else { 1 };
+        let widen = extends && cluster_width(&cluster) > width;
+        self.grapheme_buffer = cluster;
+
+        if !extends {
+            return false;
+        }
+
+        self.grid[point].push_zerowidth(c);
+
+        if widen {
+            self.widen_grapheme_cluster(point);
+        }
+
+        true
+    }
+
+    /// Turn the single width grapheme cluster at `point` into a fullwidth one.
+    ///
+    /// This expects the cursor to be right after the grapheme cluster.
+    fn widen_grapheme_cluster(&mut self, mut point: Point)
+    where
+        T: EventListener,
+    {
+        let columns = self.grid[point.line].usable_len();
+        if columns < 2 {
+            return;
+        }
+
+        let mut cell = self.grid[point].clone();
+
+        // Move the cluster to the next line if it doesn't fit into this one.
+        if point.column + 1 >= columns {
+            if !self.mode.contains(TermMode::LINE_WRAP) {
+                return;
+            }
+
+            let leading_spacer = &mut self.grid[point];
+            leading_spacer.clear_wide();
+            leading_spacer.flags.insert(Flags::LEADING_WIDE_CHAR_SPACER);
+            self.damage.damage_line(point.line.0 as usize, point.column.0, point.column.0);
+
+            self.wrapline();
+            point = self.grid.cursor.point;
+        }
+
+        // Clear fullwidth characters overwritten by the spacer.
+        let spacer_point = Point::new(point.line, point.column + 1);
+        if self.grid[spacer_point].flags.contains(Flags::WIDE_CHAR)
+            && spacer_point.column + 1 < columns
+        {
+            let next_column = spacer_point.column + 1;
+            self.grid[point.line][next_column].flags.remove(Flags::WIDE_CHAR_SPACER);
+        }
+
+        cell.flags.insert(Flags::WIDE_CHAR);
+        let mut spacer = cell.clone();
+        spacer.clear_wide();
+        spacer.flags.insert(Flags::WIDE_CHAR_SPACER);
+
+        self.grid[point] = cell;
+        self.grid[spacer_point] = spacer;
+        self.damage.damage_line(point.line.0 as usize, point.column.0, spacer_point.column.0);
+
+        if spacer_point.column + 1 < columns {
+            self.grid.cursor.point.column = spacer_point.column + 1;
+            self.grid.cursor.input_needs_wrap = false;
+        } else {
+            self.grid.cursor.point.column = spacer_point.column;
+            self.grid.cursor.input_needs_wrap = true;
+        }
+    }
+
     /// Write `c` to the cell at the cursor position.
     #[inline(always)]
     fn write_at_cursor(&mut self, c: char) {
@@ -1020,6 +1355,12 @@ impl<T> Term<T> {
         self.damage.damage_point(point);
     }
 
+    /// Number of columns which can hold characters in the cursor's line.
+    #[inline]
+    fn cursor_line_columns(&self) -> usize {
+        self.grid[self.grid.cursor.point.line].usable_len()
+    }
+
     #[inline]
     fn set_keyboard_mode(&mut self, mode: TermMode, apply: KeyboardModesApplyBehavior) {
         let active_mode = self.mode & TermMode::KITTY_KEYBOARD_PROTOCOL;
@@ -1032,6 +1373,42 @@ impl<T> Term<T> {
         trace!("Setting keyboard mode to {new_mode:?}");
         self.mode |= new_mode;
     }
+
+    /// Convert the area of a rectangular area operation to grid coordinates.
+    ///
+    /// This will return `None` if the area is empty. With the stream extent, areas spanning
+    /// multiple lines may end left of their start.
+    fn rectangle(&self, area: RectangularArea, extent: RectangleExtent) -> Option<Rectangle> {
+        let (y_offset, max_y) = if self.mode.contains(TermMode::ORIGIN) {
+            (self.scroll_region.start, self.scroll_region.end - 1)
+        } else {
+            (Line(0), self.bottommost_line())
+        };
+
+        let top = y_offset + (area.top.unwrap_or(1) - 1);
+        let bottom = area.bottom.map_or(max_y, |bottom| cmp::min(y_offset + (bottom - 1), max_y));
+        let left = Column(area.left.unwrap_or(1) - 1);
+        let right = area
+            .right
+            .map_or(self.last_column(), |right| cmp::min(Column(right - 1), self.last_column()));
+
+        let stream = extent == RectangleExtent::Stream && top != bottom;
+        if top > bottom || (left > right && !stream) {
+            return None;
+        }
+
+        Some(Rectangle::new(top, left, bottom, right))
+    }
+
+    /// Damage a modified rectangle and clear selections intersecting it.
+    fn damage_rectangle(&mut self, rect: Rectangle) {
+        for line in rect.top.0..=rect.bottom.0 {
+            self.damage.damage_line(line as usize, rect.left.0, rect.right.0);
+        }
+
+        let range = rect.top..=rect.bottom;
+        self.selection = self.selection.take().filter(|s| !s.intersects_range(range));
+    }
 }
 
 impl<T> Dimensions for Term<T> {
@@ -1055,6 +1432,11 @@ impl<T: EventListener> Handler for Term<T> {
     /// A character to be displayed.
     #[inline(never)]
     fn input(&mut self, c: char) {
+        // Add characters to the previous grapheme cluster, instead of creating a new cell.
+        if self.mode.contains(TermMode::GRAPHEME_CLUSTERS) && self.extend_grapheme_cluster(c) {
+            return;
+        }
+
         // Number of cells the char will occupy.
         let width = match c.width() {
             Some(width) => width,
@@ -1084,8 +1466,12 @@ impl<T: EventListener> Handler for Term<T> {
             self.wrapline();
         }
 
+        // Keep the cursor inside the usable part of double width lines.
+        let columns = self.cursor_line_columns();
+        self.grid.cursor.point.column =
+            cmp::min(self.grid.cursor.point.column, Column(columns - 1));
+
         // If in insert mode, first shift cells to the right.
-        let columns = self.columns();
         if self.mode.contains(TermMode::INSERT) && self.grid.cursor.point.column + width < columns {
             let line = self.grid.cursor.point.line;
             let col = self.grid.cursor.point.column;
@@ -1125,7 +1511,7 @@ impl<T: EventListener> Handler for Term<T> {
             self.grid.cursor.template.flags.remove(Flags::WIDE_CHAR_SPACER);
         }
 
-        if self.grid.cursor.point.column + 1 < columns {
+        if self.grid.cursor.point.column + 1 < self.cursor_line_columns() {
             self.grid.cursor.point.column += 1;
         } else {
             self.grid.cursor.input_needs_wrap = true;
@@ -1137,6 +1523,7 @@ impl<T: EventListener> Handler for Term<T> {
         trace!("Decalnning");
 
         for line in (0..self.screen_lines()).map(Line::from) {
+            self.grid[line].set_line_attribute(LineAttribute::Normal);
             for column in 0..self.columns() {
                 let cell = &mut self.grid[line][Column(column)];
                 *cell = Cell::default();
@@ -1161,7 +1548,7 @@ impl<T: EventListener> Handler for Term<T> {
 
         self.damage_cursor();
         self.grid.cursor.point.line = cmp::max(cmp::min(line + y_offset, max_y), Line(0));
-        self.grid.cursor.point.column = cmp::min(col, self.last_column());
+        self.grid.cursor.point.column = cmp::min(col, Column(self.cursor_line_columns() - 1));
         self.damage_cursor();
         self.grid.cursor.input_needs_wrap = false;
     }
@@ -1227,7 +1614,8 @@ impl<T: EventListener> Handler for Term<T> {
     #[inline]
     fn move_forward(&mut self, cols: usize) {
         trace!("Moving forward: {}", cols);
-        let last_column = cmp::min(self.grid.cursor.point.column + cols, self.last_column());
+        let last_column =
+            cmp::min(self.grid.cursor.point.column + cols, Column(self.cursor_line_columns() - 1));
 
         let cursor_line = self.grid.cursor.point.line.0 as usize;
         self.damage.damage_line(cursor_line, self.grid.cursor.point.column.0, last_column.0);
@@ -1809,6 +2197,7 @@ impl<T: EventListener> Handler for Term<T> {
         self.cursor_style = None;
         self.grid.reset();
         self.inactive_grid.reset();
+        self.shell_integration = false;
         self.scroll_region = Line(0)..Line(self.screen_lines() as i32);
         self.tabs = TabStops::new(self.columns());
         self.title_stack = Vec::new();
@@ -1817,6 +2206,7 @@ impl<T: EventListener> Handler for Term<T> {
         self.vi_mode_cursor = Default::default();
         self.keyboard_mode_stack = Default::default();
         self.inactive_keyboard_mode_stack = Default::default();
+        self.attribute_change_extent = Default::default();
 
         // Preserve vi mode across resets.
         self.mode &= TermMode::VI;
@@ -1857,7 +2247,8 @@ impl<T: EventListener> Handler for Term<T> {
             Attr::Reset => {
                 cursor.template.fg = Color::Named(NamedColor::Foreground);
                 cursor.template.bg = Color::Named(NamedColor::Background);
-                cursor.template.flags = Flags::empty();
+                // Character protection is not a graphic rendition.
+                cursor.template.flags &= Flags::PROTECTED;
                 cursor.template.set_underline_color(None);
             },
             Attr::Reverse => cursor.template.flags.insert(Flags::INVERSE),
@@ -1903,6 +2294,11 @@ impl<T: EventListener> Handler for Term<T> {
     fn set_private_mode(&mut self, mode: PrivateMode) {
         let mode = match mode {
             PrivateMode::Named(mode) => mode,
+            PrivateMode::Unknown(GRAPHEME_CLUSTER_MODE) => {
+                trace!("Setting private mode: GraphemeClusters");
+                self.mode.insert(TermMode::GRAPHEME_CLUSTERS);
+                return;
+            },
             PrivateMode::Unknown(mode) => {
                 debug!("Ignoring unknown mode {} in set_private_mode", mode);
                 return;
@@ -1963,6 +2359,11 @@ impl<T: EventListener> Handler for Term<T> {
     fn unset_private_mode(&mut self, mode: PrivateMode) {
         let mode = match mode {
             PrivateMode::Named(mode) => mode,
+            PrivateMode::Unknown(GRAPHEME_CLUSTER_MODE) => {
+                trace!("Unsetting private mode: GraphemeClusters");
+                self.mode.remove(TermMode::GRAPHEME_CLUSTERS);
+                return;
+            },
             PrivateMode::Unknown(mode) => {
                 debug!("Ignoring unknown mode {} in unset_private_mode", mode);
                 return;
@@ -2050,6 +2451,9 @@ impl<T: EventListener> Handler for Term<T> {
                 NamedPrivateMode::SyncUpdate => ModeState::Reset,
                 NamedPrivateMode::ColumnMode => ModeState::NotSupported,
             },
+            PrivateMode::Unknown(GRAPHEME_CLUSTER_MODE) => {
+                self.mode.contains(TermMode::GRAPHEME_CLUSTERS).into()
+            },
             PrivateMode::Unknown(_) => ModeState::NotSupported,
         };
 
@@ -2238,6 +2642,254 @@ impl<T: EventListener> Handler for Term<T> {
     }
 }
 
+impl<T: EventListener> ExtendedHandler for Term<T> {
+    #[inline]
+    fn copy_rectangular_area(&mut self, source: RectangularArea, top: usize, left: usize) {
+        trace!("Copying rectangular area {:?} to ({};{})", source, top, left);
+
+        let mut source = match self.rectangle(source, RectangleExtent::Rectangle) {
+            Some(source) => source,
+            None => return,
+        };
+
+        let area = RectangularArea { top: Some(top), left: Some(left), ..Default::default() };
+        let destination = match self.rectangle(area, RectangleExtent::Rectangle) {
+            Some(destination) => destination,
+            None => return,
+        };
+
+        // Clip the source to the available space at the destination.
+        let height = cmp::min(source.height(), destination.height());
+        let width = cmp::min(source.width(), destination.width());
+        source.bottom = source.top + (height - 1);
+        source.right = source.left + (width - 1);
+
+        let point = Point::new(destination.top, destination.left);
+        self.grid.copy_rectangle(source, point);
+
+        let bottom = destination.top + (height - 1);
+        let right = destination.left + (width - 1);
+        self.damage_rectangle(Rectangle::new(destination.top, destination.left, bottom, right));
+    }
+
+    #[inline]
+    fn fill_rectangular_area(&mut self, c: char, area: RectangularArea) {
+        trace!("Filling rectangular area {:?} with {:?}", area, c);
+
+        let rect = match self.rectangle(area, RectangleExtent::Rectangle) {
+            Some(rect) => rect,
+            None => return,
+        };
+
+        let template = &self.grid.cursor.template;
+        let cell = Cell {
+            c,
+            fg: template.fg,
+            bg: template.bg,
+            flags: template.flags,
+            extra: template.extra.clone(),
+        };
+        self.grid.fill_rectangle(rect, &cell);
+
+        self.damage_rectangle(rect);
+    }
+
+    #[inline]
+    fn erase_rectangular_area(&mut self, area: RectangularArea) {
+        trace!("Erasing rectangular area {:?}", area);
+
+        let rect = match self.rectangle(area, RectangleExtent::Rectangle) {
+            Some(rect) => rect,
+            None => return,
+        };
+
+        // Cleared cells have current background color set.
+        let bg = self.grid.cursor.template.bg;
+        self.grid.fill_rectangle(rect, &bg.into());
+
+        self.damage_rectangle(rect);
+    }
+
+    #[inline]
+    fn selective_erase_rectangular_area(&mut self, area: RectangularArea) {
+        trace!("Selectively erasing rectangular area {:?}", area);
+
+        let rect = match self.rectangle(area, RectangleExtent::Rectangle) {
+            Some(rect) => rect,
+            None => return,
+        };
+
+        self.grid.selective_erase_rectangle(rect);
+
+        self.damage_rectangle(rect);
+    }
+
+    #[inline]
+    fn change_rectangular_area_attributes(&mut self, area: RectangularArea, attrs: Vec<Attr>) {
+        trace!("Changing attributes of rectangular area {:?}: {:?}", area, attrs);
+
+        let extent = self.attribute_change_extent;
+        let rect = match self.rectangle(area, extent) {
+            Some(rect) => rect,
+            None => return,
+        };
+
+        self.grid.change_rectangle_flags(rect, extent, |flags| {
+            for attr in &attrs {
+                match attr {
+                    Attr::Reset => {
+                        flags.remove(Flags::BOLD | Flags::ALL_UNDERLINES | Flags::INVERSE);
+                        flags.remove(Flags::HIDDEN);
+                    },
+                    Attr::Bold => flags.insert(Flags::BOLD),
+                    Attr::CancelBold => flags.remove(Flags::BOLD),
+                    Attr::Underline => {
+                        flags.remove(Flags::ALL_UNDERLINES);
+                        flags.insert(Flags::UNDERLINE);
+                    },
+                    Attr::CancelUnderline => flags.remove(Flags::ALL_UNDERLINES),
+                    Attr::Reverse => flags.insert(Flags::INVERSE),
+                    Attr::CancelReverse => flags.remove(Flags::INVERSE),
+                    Attr::Hidden => flags.insert(Flags::HIDDEN),
+                    Attr::CancelHidden => flags.remove(Flags::HIDDEN),
+                    _ => (),
+                }
+            }
+        });
+
+        // Stream extent changes everything between the first and last line.
+        let rect = match extent {
+            RectangleExtent::Stream if rect.top != rect.bottom => {
+                Rectangle::new(rect.top, Column(0), rect.bottom, self.last_column())
+            },
+            _ => rect,
+        };
+        self.damage_rectangle(rect);
+    }
+
+    #[inline]
+    fn set_attribute_change_extent(&mut self, extent: RectangleExtent) {
+        trace!("Setting attribute change extent to {:?}", extent);
+        self.attribute_change_extent = extent;
+    }
+
+    #[inline]
+    fn set_character_protection(&mut self, protected: bool) {
+        trace!("Setting character protection: {}", protected);
+        self.grid.cursor.template.flags.set(Flags::PROTECTED, protected);
+    }
+
+    #[inline]
+    fn set_line_attribute(&mut self, line_attribute: LineAttribute) {
+        trace!("Setting line attribute: {:?}", line_attribute);
+
+        let line = self.grid.cursor.point.line;
+        let bg = self.grid.cursor.template.bg;
+        let columns = self.columns();
+
+        let row = &mut self.grid[line];
+        row.set_line_attribute(line_attribute);
+
+        // Discard everything which no longer fits into the line.
+        let usable_columns = row.usable_len();
+        if usable_columns < columns {
+            if row[Column(usable_columns - 1)].flags.contains(Flags::WIDE_CHAR) {
+                row[Column(usable_columns - 1)].clear_wide();
+            }
+
+            for cell in &mut row[Column(usable_columns)..] {
+                *cell = bg.into();
+            }
+        }
+
+        // Double width lines can neither wrap nor be wrapped into.
+        row[Column(columns - 1)].flags.remove(Flags::WRAPLINE);
+        if line > self.topmost_line() {
+            self.grid[line - 1i32][Column(columns - 1)].flags.remove(Flags::WRAPLINE);
+        }
+
+        self.grid.cursor.point.column =
+            cmp::min(self.grid.cursor.point.column, Column(usable_columns - 1));
+        self.grid.cursor.input_needs_wrap = false;
+
+        self.damage.damage_line(line.0 as usize, 0, columns - 1);
+        self.selection = self.selection.take().filter(|s| !s.intersects_range(line..=line));
+    }
+
+    #[inline]
+    fn request_status_string(&mut self, setting: Option<StatusString>) {
+        trace!("Reporting status string {:?}", setting);
+
+        let response = setting.map(|setting| match setting {
+            StatusString::GraphicRendition => {
+                format!("{}m", sgr_parameters(&self.grid.cursor.template))
+            },
+            StatusString::CursorStyle => {
+                let style = self.cursor_style.unwrap_or(self.config.default_cursor_style);
+                let shape = match style.shape {
+                    CursorShape::Underline => 3,
+                    CursorShape::Beam => 5,
+                    CursorShape::Block | CursorShape::HollowBlock | CursorShape::Hidden => 1,
+                };
+                format!("{} q", shape + usize::from(!style.blinking))
+            },
+            StatusString::ScrollingRegion => {
+                format!("{};{}r", self.scroll_region.start + 1, self.scroll_region.end)
+            },
+        });
+
+        let text = match response {
+            Some(response) => format!("\x1bP1$r{response}\x1b\\"),
+            None => String::from("\x1bP0$r\x1b\\"),
+        };
+        self.event_proxy.send_event(Event::PtyWrite(text));
+    }
+
+    #[inline]
+    fn request_terminfo_capabilities(&mut self, names: Vec<String>) {
+        for name in names {
+            trace!("Reporting terminfo capability {}", name);
+
+            let value = TERMINFO_CAPABILITIES.iter().find(|(key, _)| *key == name);
+            let text = match value {
+                Some((_, value)) => {
+                    format!("\x1bP1+r{}={}\x1b\\", encode_hex(&name), encode_hex(value))
+                },
+                None => format!("\x1bP0+r{}\x1b\\", encode_hex(&name)),
+            };
+            self.event_proxy.send_event(Event::PtyWrite(text));
+        }
+    }
+
+    #[inline]
+    fn report_version(&mut self) {
+        trace!("Reporting terminal version");
+        let text = format!("\x1bP>|LaraShell({})\x1b\\", env!("CARGO_PKG_VERSION"));
+        self.event_proxy.send_event(Event::PtyWrite(text));
+    }
+
+    #[inline]
+    fn shell_mark(&mut self, mark: ShellMark) {
+        trace!("Setting shell mark {:?}", mark);
+
+        let line = self.grid.cursor.point.line;
+        match mark {
+            ShellMark::PromptStart => {
+                self.shell_integration = true;
+                self.grid.set_prompt_mark(line, Some(PromptMark::Prompt));
+            },
+            // Empty output ends on the line of the next prompt, which takes precedence.
+            ShellMark::OutputStart if self.grid[line].prompt_mark() != Some(PromptMark::Prompt) => {
+                self.grid.set_prompt_mark(line, Some(PromptMark::Output));
+            },
+            ShellMark::CommandEnd(exit_code) => {
+                self.event_proxy.send_event(Event::CommandFinished(exit_code));
+            },
+            _ => (),
+        }
+    }
+}
+
 /// The state of the [`Mode`] and [`PrivateMode`].
 #[repr(u8)]
 #[derive(Debug, Clone, Copy)]
@@ -2281,6 +2933,80 @@ fn version_number(mut version: &str) -> usize {
     version_number
 }
 
+/// Number of columns occupied by a grapheme cluster.
+fn cluster_width(cluster: &str) -> usize {
+    // Emoji presentation selector always makes the cluster fullwidth.
+    if cluster.contains('\u{fe0f}') {
+        return 2;
+    }
+
+    // Flags are made up of two regional indicators, which are single width on their own.
+    let mut chars = cluster.chars();
+    if chars.next().is_some_and(is_regional_indicator) && chars.next().is_some() {
+        return 2;
+    }
+
+    cluster.chars().filter_map(|c| c.width()).max().unwrap_or(1).clamp(1, 2)
+}
+
+#[inline]
+fn is_regional_indicator(c: char) -> bool {
+    ('\u{1f1e6}'..='\u{1f1ff}').contains(&c)
+}
+
+/// SGR parameters which reproduce the attributes of a cell.
+fn sgr_parameters(cell: &Cell) -> String {
+    let mut params = vec![String::from("0")];
+
+    let flag_params = [
+        (Flags::BOLD, "1"),
+        (Flags::DIM, "2"),
+        (Flags::ITALIC, "3"),
+        (Flags::UNDERLINE, "4"),
+        (Flags::DOUBLE_UNDERLINE, "4:2"),
+        (Flags::UNDERCURL, "4:3"),
+        (Flags::DOTTED_UNDERLINE, "4:4"),
+        (Flags::DASHED_UNDERLINE, "4:5"),
+        (Flags::INVERSE, "7"),
+        (Flags::HIDDEN, "8"),
+        (Flags::STRIKEOUT, "9"),
+    ];
+    for (flag, param) in flag_params {
+        if cell.flags.contains(flag) {
+            params.push(param.into());
+        }
+    }
+
+    params.extend(color_parameter(cell.fg, Some((30, 90)), 38));
+    params.extend(color_parameter(cell.bg, Some((40, 100)), 48));
+    if let Some(color) = cell.underline_color() {
+        params.extend(color_parameter(color, None, 58));
+    }
+
+    params.join(";")
+}
+
+/// SGR parameter for a color, or `None` if it is the default color.
+///
+/// The `named` parameters are the base values for normal and bright named colors.
+fn color_parameter(color: Color, named: Option<(u8, u8)>, extended: u8) -> Option<String> {
+    match (color, named) {
+        (Color::Named(color), Some((normal, bright))) => match color as u8 {
+            index @ 0..=7 => Some((normal + index).to_string()),
+            index @ 8..=15 => Some((bright + index - 8).to_string()),
+            _ => None,
+        },
+        (Color::Named(_), None) => None,
+        (Color::Indexed(index), _) => Some(format!("{extended};5;{index}")),
+        (Color::Spec(Rgb { r, g, b }), _) => Some(format!("{extended};2;{r};{g};{b}")),
+    }
+}
+
+/// Encode a string as pairs of uppercase hexadecimal digits.
+fn encode_hex(text: &str) -> String {
+    text.bytes().map(|byte| format!("{byte:02X}")).collect()
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ClipboardType {
     Clipboard,
@@ -2387,36 +3113,9 @@ impl<'a> RenderableContent<'a> {
 pub mod test {
     use super::*;
 
-    #[cfg(feature = "serde")]
-    use serde::{Deserialize, Serialize};
-
     use crate::event::VoidListener;
 
-    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-    pub struct TermSize {
-        pub columns: usize,
-        pub screen_lines: usize,
-    }
-
-    impl TermSize {
-        pub fn new(columns: usize, screen_lines: usize) -> Self {
-            Self { columns, screen_lines }
-        }
-    }
-
-    impl Dimensions for TermSize {
-        fn total_lines(&self) -> usize {
-            self.screen_lines()
-        }
-
-        fn screen_lines(&self) -> usize {
-            self.screen_lines
-        }
-
-        fn columns(&self) -> usize {
-            self.columns
-        }
-    }
+    pub use super::TermSize;
 
     /// Construct a terminal from its content as string.
     ///
@@ -2486,9 +3185,45 @@ mod tests {
     use crate::index::{Column, Point, Side};
     use crate::selection::{Selection, SelectionType};
     use crate::term::cell::{Cell, Flags};
+    use crate::term::search::RegexSearch;
     use crate::term::test::TermSize;
     use crate::vte::ansi::{self, CharsetIndex, Handler, StandardCharset};
 
+    #[test]
+    fn spilled_history_is_readable() {
+        let size = TermSize::new(10, 2);
+        let config = Config {
+            scrolling_history: 2,
+            scrolling_history_memory: 1,
+            scrolling_history_spill: true,
+            ..Config::default()
+        };
+        let mut term = Term::new(config, &size, VoidListener);
+
+        for i in 0..50 {
+            for c in format!("line {i}").chars() {
+                term.input(c);
+            }
+            term.carriage_return();
+            term.linefeed();
+        }
+
+        assert_eq!(term.grid().history_size(), 49);
+
+        let text = term
+            .bounds_to_string(Point::new(Line(-49), Column(0)), Point::new(Line(-49), Column(5)));
+        assert_eq!(text, "line 0");
+
+        let mut regex = RegexSearch::new("line 17").unwrap();
+        let origin = Point::new(Line(1), Column(9));
+        let search_match = term.search_next(&mut regex, origin, Direction::Left, Side::Left, None);
+        assert_eq!(search_match.map(|m| *m.start()), Some(Point::new(Line(-32), Column(0))));
+
+        term.scroll_display(Scroll::Top);
+        assert_eq!(term.grid().display_offset(), 49);
+        assert_eq!(term.grid()[Line(-49)][Column(5)].c, '0');
+    }
+
     #[test]
     fn scroll_display_page_up() {
         let size = TermSize::new(5, 10);
@@ -2717,6 +3452,68 @@ mod tests {
         assert_eq!(deserialized, grid);
     }
 
+    #[test]
+    #[cfg(feature = "serde")]
+    fn grid_serde_compressed_history() {
+        let mut grid: Grid<Cell> = Grid::new(1, 4, 0);
+        grid.update_compressed_history(1 << 20);
+        for c in ['a', 'b'] {
+            grid[Line(0)][Column(0)].c = c;
+            grid.scroll_up(&(Line(0)..Line(1)), 1);
+        }
+
+        let serialized = serde_json::to_string(&grid).expect("ser");
+        let deserialized = serde_json::from_str::<Grid<Cell>>(&serialized).expect("de");
+
+        assert_eq!(deserialized.history_size(), 2);
+        assert_eq!(deserialized[Line(-2)][Column(0)].c, 'a');
+        assert_eq!(deserialized[Line(-1)][Column(0)].c, 'b');
+        assert_eq!(deserialized, grid);
+    }
+
+    #[test]
+    fn restore_scrollback() {
+        let mut term = test::mock_term("one\r\ntwo\r\nthree\r\n");
+        term.grid.cursor.point = Point::new(Line(3), Column(0));
+
+        let serialized = serde_json::to_string(&term.snapshot()).unwrap();
+        let snapshot: TermSnapshot<'_> = serde_json::from_str(&serialized).unwrap();
+
+        let size = TermSize::new(10, 2);
+        let mut term = Term::new(Config::default(), &size, VoidListener);
+        term.restore_scrollback(snapshot);
+
+        assert_eq!(term.history_size(), 3);
+        let start = Point::new(Line(-3), Column(0));
+        let end = Point::new(Line(1), Column(9));
+        assert_eq!(term.bounds_to_string(start, end), "one\ntwo\nthree\n\n");
+        assert_eq!(term.grid.cursor.point, Point::new(Line(0), Column(0)));
+    }
+
+    #[test]
+    fn restore() {
+        let mut term = test::mock_term("one\r\ntwo\r\nthree");
+        term.grid.cursor.point = Point::new(Line(2), Column(3));
+        term.mode.insert(TermMode::BRACKETED_PASTE);
+        term.swap_alt();
+
+        let serialized = serde_json::to_string(&term.snapshot()).unwrap();
+        let snapshot: TermSnapshot<'_> = serde_json::from_str(&serialized).unwrap();
+
+        let size = TermSize::new(5, 3);
+        let mut restored = Term::new(Config::default(), &size, VoidListener);
+        restored.restore(snapshot);
+
+        assert!(restored.mode.contains(TermMode::ALT_SCREEN | TermMode::BRACKETED_PASTE));
+        assert_eq!(restored.grid.cursor.point, term.grid.cursor.point);
+
+        restored.swap_alt();
+        let start = Point::new(Line(0), Column(0));
+        let end = Point::new(Line(2), Column(4));
+        assert_eq!(restored.bounds_to_string(start, end), "one\ntwo\nthree");
+        assert_eq!(restored.grid.cursor.point, Point::new(Line(2), Column(3)));
+    }
+
     #[test]
     fn input_line_drawing_character() {
         let size = TermSize::new(7, 17);
@@ -2728,6 +3525,223 @@ mod tests {
         assert_eq!(term.grid()[cursor].c, '▒');
     }
 
+    /// Event listener collecting all text written to the PTY.
+    #[derive(Default, Clone)]
+    struct PtyWriteListener(std::rc::Rc<std::cell::RefCell<Vec<String>>>);
+
+    impl EventListener for PtyWriteListener {
+        fn send_event(&self, event: Event) {
+            if let Event::PtyWrite(text) = event {
+                self.0.borrow_mut().push(text);
+            }
+        }
+    }
+
+    impl PtyWriteListener {
+        fn take(&self) -> Vec<String> {
+            mem::take(&mut *self.0.borrow_mut())
+        }
+    }
+
+    #[test]
+    fn decrqss_reports() {
+        let size = TermSize::new(10, 10);
+        let listener = PtyWriteListener::default();
+        let mut term = Term::new(Config::default(), &size, listener.clone());
+
+        term.terminal_attribute(Attr::Bold);
+        term.terminal_attribute(Attr::Underline);
+        term.terminal_attribute(Attr::Foreground(Color::Named(NamedColor::BrightRed)));
+        term.terminal_attribute(Attr::Background(Color::Indexed(100)));
+        term.terminal_attribute(Attr::UnderlineColor(Some(Color::Spec(Rgb { r: 1, g: 2, b: 3 }))));
+        term.request_status_string(Some(StatusString::GraphicRendition));
+
+        term.set_scrolling_region(2, Some(5));
+        term.request_status_string(Some(StatusString::ScrollingRegion));
+
+        term.set_cursor_style(Some(CursorStyle { shape: CursorShape::Beam, blinking: false }));
+        term.request_status_string(Some(StatusString::CursorStyle));
+
+        term.request_status_string(None);
+
+        assert_eq!(
+            listener.take(),
+            vec![
+                String::from("\x1bP1$r0;1;4;91;48;5;100;58;2;1;2;3m\x1b\\"),
+                String::from("\x1bP1$r2;5r\x1b\\"),
+                String::from("\x1bP1$r6 q\x1b\\"),
+                String::from("\x1bP0$r\x1b\\"),
+            ]
+        );
+    }
+
+    #[test]
+    fn xtgettcap_reports() {
+        let size = TermSize::new(10, 10);
+        let listener = PtyWriteListener::default();
+        let mut term = Term::new(Config::default(), &size, listener.clone());
+
+        term.request_terminfo_capabilities(vec![String::from("RGB"), String::from("xx")]);
+
+        assert_eq!(
+            listener.take(),
+            vec![
+                String::from("\x1bP1+r524742=382F382F38\x1b\\"),
+                String::from("\x1bP0+r7878\x1b\\"),
+            ]
+        );
+    }
+
+    /// Grapheme clusters with their expected width in columns.
+    const GRAPHEME_CLUSTERS: &[(&str, usize)] = &[
+        // Combining acute accent.
+        ("e\u{301}", 1),
+        // Multiple combining marks.
+        ("a\u{308}\u{304}", 1),
+        // Family, joined with ZWJ.
+        ("\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}\u{200d}\u{1f466}", 2),
+        // Flag made of regional indicators.
+        ("\u{1f1e9}\u{1f1ea}", 2),
+        // Text default character with emoji presentation selector.
+        ("\u{2764}\u{fe0f}", 2),
+        // Keycap sequence.
+        ("1\u{fe0f}\u{20e3}", 2),
+        // Rainbow flag.
+        ("\u{1f3f3}\u{fe0f}\u{200d}\u{1f308}", 2),
+        // Skin tone modifier.
+        ("\u{1f44d}\u{1f3fd}", 2),
+        // Hangul syllable made of conjoining jamo.
+        ("\u{1100}\u{1161}\u{11a8}", 2),
+    ];
+
+    #[test]
+    fn grapheme_cluster_widths() {
+        for (cluster, width) in GRAPHEME_CLUSTERS {
+            let size = TermSize::new(10, 1);
+            let mut term = Term::new(Config::default(), &size, VoidListener);
+            term.set_private_mode(PrivateMode::Unknown(GRAPHEME_CLUSTER_MODE));
+
+            for c in cluster.chars() {
+                term.input(c);
+            }
+            term.input('x');
+
+            let mut chars = cluster.chars();
+            let cell = &term.grid()[Line(0)][Column(0)];
+            assert_eq!(cell.c, chars.next().unwrap(), "{cluster:?}");
+            assert_eq!(cell.zerowidth().unwrap(), chars.collect::<Vec<_>>(), "{cluster:?}");
+            assert_eq!(cell.flags.contains(Flags::WIDE_CHAR), *width == 2, "{cluster:?}");
+            assert_eq!(term.grid()[Line(0)][Column(*width)].c, 'x', "{cluster:?}");
+        }
+    }
+
+    #[test]
+    fn grapheme_clusters_split_without_mode() {
+        let size = TermSize::new(10, 1);
+        let mut term = Term::new(Config::default(), &size, VoidListener);
+
+        for c in "\u{1f1e9}\u{1f1ea}".chars() {
+            term.input(c);
+        }
+
+        assert_eq!(term.grid()[Line(0)][Column(0)].zerowidth(), None);
+        assert_ne!(term.grid.cursor.point.column, Column(0));
+        assert_ne!(term.grid()[Line(0)][Column(1)].c, ' ');
+    }
+
+    #[test]
+    fn grapheme_cluster_widened_at_line_end() {
+        let size = TermSize::new(3, 2);
+        let mut term = Term::new(Config::default(), &size, VoidListener);
+        term.set_private_mode(PrivateMode::Unknown(GRAPHEME_CLUSTER_MODE));
+
+        for c in "ab\u{2764}\u{fe0f}".chars() {
+            term.input(c);
+        }
+
+        let leading_spacer = &term.grid()[Line(0)][Column(2)];
+        assert!(leading_spacer.flags.contains(Flags::LEADING_WIDE_CHAR_SPACER));
+        assert!(leading_spacer.flags.contains(Flags::WRAPLINE));
+
+        let cell = &term.grid()[Line(1)][Column(0)];
+        assert_eq!(cell.c, '\u{2764}');
+        assert!(cell.flags.contains(Flags::WIDE_CHAR));
+        assert!(term.grid()[Line(1)][Column(1)].flags.contains(Flags::WIDE_CHAR_SPACER));
+        assert_eq!(term.grid.cursor.point, Point::new(Line(1), Column(2)));
+    }
+
+    #[test]
+    fn grapheme_cluster_mode_report() {
+        let size = TermSize::new(10, 1);
+        let listener = PtyWriteListener::default();
+        let mut term = Term::new(Config::default(), &size, listener.clone());
+
+        term.report_private_mode(PrivateMode::Unknown(GRAPHEME_CLUSTER_MODE));
+        term.set_private_mode(PrivateMode::Unknown(GRAPHEME_CLUSTER_MODE));
+        term.report_private_mode(PrivateMode::Unknown(GRAPHEME_CLUSTER_MODE));
+
+        assert_eq!(
+            listener.take(),
+            vec![String::from("\x1b[?2027;2$y"), String::from("\x1b[?2027;1$y")]
+        );
+    }
+
+    #[test]
+    fn double_width_line_wraps_at_half_width() {
+        let size = TermSize::new(10, 3);
+        let mut term = Term::new(Config::default(), &size, VoidListener);
+
+        term.set_line_attribute(LineAttribute::DoubleWidth);
+        for c in "abcdefg".chars() {
+            term.input(c);
+        }
+
+        assert_eq!(term.grid()[Line(0)][Column(4)].c, 'e');
+        assert_eq!(term.grid()[Line(1)][Column(0)].c, 'f');
+        assert!(!term.grid()[Line(0)][Column(9)].flags.contains(Flags::WRAPLINE));
+
+        // Cursor movement is limited to the left half of the line.
+        term.goto(0, 9);
+        assert_eq!(term.grid.cursor.point, Point::new(Line(0), Column(4)));
+    }
+
+    #[test]
+    fn double_width_line_clears_right_half() {
+        let size = TermSize::new(6, 3);
+        let mut term = Term::new(Config::default(), &size, VoidListener);
+
+        for c in "abcde".chars() {
+            term.input(c);
+        }
+        term.set_line_attribute(LineAttribute::DoubleHeightTop);
+
+        assert_eq!(term.grid()[Line(0)].line_attribute(), LineAttribute::DoubleHeightTop);
+        assert_eq!(term.grid()[Line(0)][Column(2)].c, 'c');
+        assert_eq!(term.grid()[Line(0)][Column(3)].c, ' ');
+        assert_eq!(term.grid.cursor.point.column, Column(2));
+
+        // DECALN resets all lines to single width.
+        term.decaln();
+        assert_eq!(term.grid()[Line(0)].line_attribute(), LineAttribute::Normal);
+    }
+
+    #[test]
+    fn double_width_line_is_not_reflowed() {
+        let size = TermSize::new(6, 3);
+        let mut term = Term::new(Config::default(), &size, VoidListener);
+
+        term.set_line_attribute(LineAttribute::DoubleWidth);
+        for c in "abc".chars() {
+            term.input(c);
+        }
+
+        term.resize(TermSize::new(2, 3));
+
+        assert_eq!(term.grid()[Line(0)].line_attribute(), LineAttribute::DoubleWidth);
+        assert_eq!(term.grid()[Line(0)][Column(1)].c, 'b');
+        assert_eq!(term.grid()[Line(1)][Column(0)].c, ' ');
+    }
+
     #[test]
     fn clearing_viewport_keeps_history_position() {
         let size = TermSize::new(10, 20);
@@ -0,0 +1,261 @@
+//! Navigation between shell prompts and the output of their commands.
+//!
+//! Prompts are located using OSC 133 marks when the shell emits them. Otherwise lines starting
+//! with a match of the prompt regex are treated as prompts, which also applies to the output of
+//! a running command, since it might have started another shell without shell integration.
+
+use std::cmp::max;
+use std::ops::RangeInclusive;
+
+use crate::grid::{Dimensions, PromptMark};
+use crate::index::{Column, Direction, Line, Point};
+use crate::term::search::RegexSearch;
+use crate::term::Term;
+
+impl<T> Term<T> {
+    /// Check if the shell reported prompt positions through OSC 133.
+    #[inline]
+    pub fn shell_integration(&self) -> bool {
+        self.shell_integration
+    }
+
+    /// Check if a line is the first line of a shell prompt.
+    pub fn is_prompt(&self, line: Line, regex: Option<&mut RegexSearch>) -> bool {
+        if self.regex_prompts_start().map_or(true, |start| line < start) {
+            return self.grid.prompt_mark(line) == Some(PromptMark::Prompt);
+        }
+
+        self.matches_prompt(line, regex)
+    }
+
+    /// Find the closest prompt above or below a line.
+    ///
+    /// Prompts reported through OSC 133 are looked up in the index of marked lines, so only
+    /// lines which are not covered by shell integration are matched against the regex.
+    pub fn prompt_line(
+        &self,
+        line: Line,
+        direction: Direction,
+        mut regex: Option<&mut RegexSearch>,
+    ) -> Option<Line> {
+        let regex_start = self.regex_prompts_start();
+        let mut matches = |line: &Line| self.matches_prompt(*line, regex.as_deref_mut());
+        match direction {
+            Direction::Left => regex_start
+                .filter(|start| *start < line)
+                .and_then(|start| (start.0..line.0).rev().map(Line::from).find(&mut matches))
+                .or_else(|| self.marked_prompt(line, direction)),
+            Direction::Right => self.marked_prompt(line, direction).or_else(|| {
+                let start = max(line + 1, regex_start?);
+                (start.0..=self.bottommost_line().0).map(Line::from).find(matches)
+            }),
+        }
+    }
+
+    /// First line which is matched against the prompt regex instead of using OSC 133 marks.
+    ///
+    /// Shells started by a command which is still running, like through `ssh`, might not report
+    /// their prompts. So without a prompt after the last command's output, its output is
+    /// matched against the prompt regex.
+    fn regex_prompts_start(&self) -> Option<Line> {
+        if !self.shell_integration {
+            return Some(self.topmost_line());
+        }
+
+        let end = Line(self.screen_lines() as i32);
+        match self.grid.next_prompt_mark(end, Direction::Left) {
+            Some((line, PromptMark::Output)) => Some(line),
+            _ => None,
+        }
+    }
+
+    /// Find the closest prompt reported through OSC 133 above or below a line.
+    fn marked_prompt(&self, mut line: Line, direction: Direction) -> Option<Line> {
+        if !self.shell_integration {
+            return None;
+        }
+
+        loop {
+            match self.grid.next_prompt_mark(line, direction)? {
+                (prompt, PromptMark::Prompt) => return Some(prompt),
+                (output, PromptMark::Output) => line = output,
+            }
+        }
+    }
+
+    /// Check if a line starts with a match of the prompt regex.
+    fn matches_prompt(&self, line: Line, regex: Option<&mut RegexSearch>) -> bool {
+        let regex = match regex {
+            Some(regex) => regex,
+            None => return false,
+        };
+
+        // Wrapped lines can't start a prompt.
+        let start = Point::new(line, Column(0));
+        if self.line_search_left(start) != start {
+            return false;
+        }
+
+        let end = Point::new(line, self.last_column());
+        self.regex_search_right(regex, start, end)
+            .is_some_and(|regex_match| regex_match.start().column == Column(0))
+    }
+
+    /// Lines holding the output of the command at the closest prompt above `line`.
+    ///
+    /// The output of commands which are still running ends at the terminal cursor.
+    pub fn command_output(
+        &self,
+        line: Line,
+        mut regex: Option<&mut RegexSearch>,
+    ) -> Option<RangeInclusive<Line>> {
+        let prompt = if self.is_prompt(line, regex.as_deref_mut()) {
+            line
+        } else {
+            self.prompt_line(line, Direction::Left, regex.as_deref_mut())?
+        };
+
+        let cursor = self.grid.cursor.point;
+        let end = match self.prompt_line(prompt, Direction::Right, regex) {
+            Some(next_prompt) => next_prompt - 1,
+            None if cursor.column == 0 => cursor.line - 1,
+            None => cursor.line,
+        };
+
+        // Output starts at its OSC 133 mark, or below the command entered at the prompt.
+        let command_end = self.line_search_right(Point::new(prompt, Column(0))).line;
+        let start = match self.grid.next_prompt_mark(prompt, Direction::Right) {
+            Some((output, PromptMark::Output)) if output <= end => output,
+            _ => command_end + 1,
+        };
+
+        (start <= end).then_some(start..=end)
+    }
+
+    /// Lines holding the output of the most recent command.
+    ///
+    /// This skips the prompt at the terminal cursor, since its command has not been run yet.
+    pub fn last_command_output(
+        &self,
+        mut regex: Option<&mut RegexSearch>,
+    ) -> Option<RangeInclusive<Line>> {
+        let cursor_line = self.grid.cursor.point.line;
+        let prompt = if self.is_prompt(cursor_line, regex.as_deref_mut()) {
+            Some(cursor_line)
+        } else {
+            self.prompt_line(cursor_line, Direction::Left, regex.as_deref_mut())
+        };
+
+        let prompt = prompt.filter(|&prompt| {
+            self.line_search_right(Point::new(prompt, Column(0))).line >= cursor_line
+        });
+
+        let line = match prompt {
+            Some(prompt) if prompt <= self.topmost_line() => return None,
+            Some(prompt) => prompt - 1,
+            None => cursor_line,
+        };
+
+        self.command_output(line, regex)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::parser::{ExtendedHandler, ShellMark};
+    use crate::term::test::mock_term;
+    use crate::vte::ansi::Handler;
+
+    #[test]
+    fn regex_prompts() {
+        #[rustfmt::skip]
+        let mut term = mock_term("\
+            $ ls\r\n\
+            foo\r\n\
+            bar\r\n\
+            $ true\r\n\
+            $ echo\r\n\
+            baz\r\n\
+            $ \
+        ");
+        term.grid.cursor.point = Point::new(Line(6), Column(2));
+
+        let mut regex = RegexSearch::new("\\$ ").unwrap();
+
+        assert!(term.is_prompt(Line(0), Some(&mut regex)));
+        assert!(!term.is_prompt(Line(0), None));
+        assert_eq!(term.prompt_line(Line(6), Direction::Left, Some(&mut regex)), Some(Line(4)));
+        assert_eq!(term.prompt_line(Line(0), Direction::Right, Some(&mut regex)), Some(Line(3)));
+
+        assert_eq!(term.command_output(Line(2), Some(&mut regex)), Some(Line(1)..=Line(2)));
+        assert_eq!(term.command_output(Line(3), Some(&mut regex)), None);
+        assert_eq!(term.last_command_output(Some(&mut regex)), Some(Line(5)..=Line(5)));
+    }
+
+    #[test]
+    fn shell_integration_prompts() {
+        #[rustfmt::skip]
+        let mut term = mock_term("\
+            $ echo a\r\n\
+            a\r\n\
+            $ true\r\n\
+            $ sleep\r\n\
+            b\
+        ");
+
+        let set_mark = |term: &mut Term<_>, line, mark| {
+            term.grid.cursor.point = Point::new(Line(line), Column(0));
+            term.shell_mark(mark);
+        };
+        set_mark(&mut term, 0, ShellMark::PromptStart);
+        set_mark(&mut term, 1, ShellMark::OutputStart);
+        set_mark(&mut term, 2, ShellMark::CommandEnd(None));
+        set_mark(&mut term, 2, ShellMark::PromptStart);
+        set_mark(&mut term, 3, ShellMark::OutputStart);
+        set_mark(&mut term, 3, ShellMark::CommandEnd(None));
+        set_mark(&mut term, 3, ShellMark::PromptStart);
+        set_mark(&mut term, 4, ShellMark::OutputStart);
+        term.grid.cursor.point = Point::new(Line(4), Column(1));
+
+        // The regex is ignored once the shell reports its prompts.
+        let mut regex = RegexSearch::new("a").unwrap();
+        assert!(!term.is_prompt(Line(1), Some(&mut regex)));
+
+        assert_eq!(term.command_output(Line(1), None), Some(Line(1)..=Line(1)));
+        assert_eq!(term.command_output(Line(2), None), None);
+        assert_eq!(term.last_command_output(None), Some(Line(4)..=Line(4)));
+    }
+
+    #[test]
+    fn nested_shell_prompts() {
+        #[rustfmt::skip]
+        let mut term = mock_term("\
+            $ ssh\r\n\
+            > ls\r\n\
+            a\r\n\
+            > \
+        ");
+
+        term.grid.cursor.point = Point::new(Line(0), Column(0));
+        term.shell_mark(ShellMark::PromptStart);
+        term.grid.cursor.point = Point::new(Line(1), Column(0));
+        term.shell_mark(ShellMark::OutputStart);
+        term.grid.cursor.point = Point::new(Line(3), Column(2));
+
+        // Prompts of the remote shell are matched within the output of `ssh`.
+        let mut regex = RegexSearch::new("> ").unwrap();
+        assert!(term.is_prompt(Line(0), Some(&mut regex)));
+        assert!(term.is_prompt(Line(1), Some(&mut regex)));
+        assert!(!term.is_prompt(Line(2), Some(&mut regex)));
+        assert_eq!(term.prompt_line(Line(3), Direction::Left, Some(&mut regex)), Some(Line(1)));
+        assert_eq!(term.prompt_line(Line(1), Direction::Left, Some(&mut regex)), Some(Line(0)));
+        assert_eq!(term.prompt_line(Line(0), Direction::Right, Some(&mut regex)), Some(Line(1)));
+        assert_eq!(term.last_command_output(Some(&mut regex)), Some(Line(2)..=Line(2)));
+
+        // Resetting the terminal disables shell integration.
+        term.reset_state();
+        assert!(!term.shell_integration());
+    }
+}
@@ -1,3 +1,4 @@
+use std::borrow::Cow;
 use std::cmp::max;
 use std::error::Error;
 use std::mem;
@@ -10,7 +11,7 @@ use regex_automata::nfa::thompson::Config as ThompsonConfig;
 use regex_automata::util::syntax::Config as SyntaxConfig;
 use regex_automata::{Anchored, Input, MatchKind};
 
-use crate::grid::{BidirectionalIterator, Dimensions, GridIterator, Indexed};
+use crate::grid::{BidirectionalIterator, Dimensions, Grid, Row};
 use crate::index::{Boundary, Column, Direction, Point, Side};
 use crate::term::cell::{Cell, Flags};
 use crate::term::Term;
@@ -29,15 +30,72 @@ pub struct RegexSearch {
     right_fdfa: LazyDfa,
 }
 
+/// Case sensitivity of a regex search.
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+pub enum CaseSensitivity {
+    /// Ignore case unless the search contains an uppercase character.
+    #[default]
+    Smart,
+    /// Always match case.
+    Sensitive,
+    /// Never match case.
+    Insensitive,
+}
+
+impl CaseSensitivity {
+    /// Cycle to the next case sensitivity mode.
+    pub fn next(self) -> Self {
+        match self {
+            Self::Smart => Self::Sensitive,
+            Self::Sensitive => Self::Insensitive,
+            Self::Insensitive => Self::Smart,
+        }
+    }
+}
+
+/// Options controlling how the search text is matched.
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+pub struct SearchOptions {
+    /// Case sensitivity of the search.
+    pub case_sensitivity: CaseSensitivity,
+
+    /// Only match whole words.
+    pub whole_word: bool,
+
+    /// Match the search text literally instead of as a regex.
+    pub literal: bool,
+}
+
 impl RegexSearch {
     /// Build the forward and backward search DFAs.
     pub fn new(search: &str) -> Result<RegexSearch, Box<BuildError>> {
+        Self::with_options(search, SearchOptions::default())
+    }
+
+    /// Build the forward and backward search DFAs with custom matching options.
+    pub fn with_options(
+        search: &str,
+        options: SearchOptions,
+    ) -> Result<RegexSearch, Box<BuildError>> {
+        let case_insensitive = match options.case_sensitivity {
+            CaseSensitivity::Smart => !search.chars().any(|c| c.is_uppercase()),
+            CaseSensitivity::Sensitive => false,
+            CaseSensitivity::Insensitive => true,
+        };
+
+        let mut pattern =
+            if options.literal { regex_syntax::escape(search) } else { search.to_owned() };
+        if options.whole_word {
+            // Unicode word boundaries are not supported by the lazy DFA.
+            pattern = format!("(?-u:\\b)(?:{pattern})(?-u:\\b)");
+        }
+        let search = pattern.as_str();
+
         // Setup configs for both DFA directions.
         //
         // Bounds are based on Regex's meta engine:
         // https://github.com/rust-lang/regex/blob/061ee815ef2c44101dba7b0b124600fcb03c1912/regex-automata/src/meta/wrappers.rs#L581-L599
-        let has_uppercase = search.chars().any(|c| c.is_uppercase());
-        let syntax_config = SyntaxConfig::new().case_insensitive(!has_uppercase);
+        let syntax_config = SyntaxConfig::new().case_insensitive(case_insensitive);
         let config =
             Config::new().minimum_cache_clear_count(Some(3)).minimum_bytes_per_state(Some(10));
         let max_size = config.get_cache_capacity();
@@ -283,8 +341,8 @@ impl<T> Term<T> {
 
         // Advance the iterator.
         let next = match regex.direction {
-            Direction::Right => GridIterator::next,
-            Direction::Left => GridIterator::prev,
+            Direction::Right => CellCursor::next,
+            Direction::Left => CellCursor::prev,
         };
 
         // Get start state for the DFA.
@@ -292,14 +350,12 @@ impl<T> Term<T> {
         let input = Input::new(&[]).anchored(regex_anchored);
         let mut state = regex.dfa.start_state_forward(&mut regex.cache, &input).unwrap();
 
-        let mut iter = self.grid.iter_from(start);
+        let mut iter = CellCursor::new(&self.grid, start);
         let mut last_wrapped = false;
         let mut regex_match = None;
         let mut done = false;
 
-        let mut cell = iter.cell();
-        self.skip_fullwidth(&mut iter, &mut cell, regex.direction);
-        let mut c = cell.c;
+        let (mut c, _) = self.skip_fullwidth(&mut iter, regex.direction);
 
         let mut point = iter.point();
         let mut last_point = point;
@@ -385,24 +441,20 @@ impl<T> Term<T> {
             }
 
             // Advance grid cell iterator.
-            let mut cell = match next(&mut iter) {
-                Some(Indexed { cell, .. }) => cell,
-                None => {
-                    // Wrap around to other end of the scrollback buffer.
-                    let line = topmost_line - point.line + screen_lines - 1;
-                    let start = Point::new(line, last_column - point.column);
-                    iter = self.grid.iter_from(start);
-                    iter.cell()
-                },
-            };
+            if !next(&mut iter) {
+                // Wrap around to other end of the scrollback buffer.
+                let line = topmost_line - point.line + screen_lines - 1;
+                let start = Point::new(line, last_column - point.column);
+                iter = CellCursor::new(&self.grid, start);
+            }
 
             // Check for completion before potentially skipping over fullwidth characters.
             done = iter.point() == end;
 
-            self.skip_fullwidth(&mut iter, &mut cell, regex.direction);
+            let (cell_c, flags) = self.skip_fullwidth(&mut iter, regex.direction);
 
-            let wrapped = cell.flags.contains(Flags::WRAPLINE);
-            c = cell.c;
+            let wrapped = flags.contains(Flags::WRAPLINE);
+            c = cell_c;
 
             last_point = mem::replace(&mut point, iter.point());
 
@@ -432,40 +484,46 @@ impl<T> Term<T> {
         Ok(regex_match)
     }
 
-    /// Advance a grid iterator over fullwidth characters.
-    fn skip_fullwidth<'a>(
-        &self,
-        iter: &'a mut GridIterator<'_, Cell>,
-        cell: &mut &'a Cell,
-        direction: Direction,
-    ) {
+    /// Advance a cell cursor over fullwidth characters.
+    ///
+    /// Returns the character and flags of the cell which should be passed to the regex.
+    fn skip_fullwidth(&self, iter: &mut CellCursor<'_>, direction: Direction) -> (char, Flags) {
+        let cell = iter.cell();
+        let (mut c, mut flags) = (cell.c, cell.flags);
+
         match direction {
             // In the alternate screen buffer there might not be a wide char spacer after a wide
             // char, so we only advance the iterator when the wide char is not in the last column.
             Direction::Right
-                if cell.flags.contains(Flags::WIDE_CHAR)
-                    && iter.point().column < self.last_column() =>
+                if flags.contains(Flags::WIDE_CHAR) && iter.point().column < self.last_column() =>
             {
                 iter.next();
             },
-            Direction::Right if cell.flags.contains(Flags::LEADING_WIDE_CHAR_SPACER) => {
-                if let Some(Indexed { cell: new_cell, .. }) = iter.next() {
-                    *cell = new_cell;
+            Direction::Right if flags.contains(Flags::LEADING_WIDE_CHAR_SPACER) => {
+                if iter.next() {
+                    (c, flags) = (iter.cell().c, iter.cell().flags);
                 }
                 iter.next();
             },
-            Direction::Left if cell.flags.contains(Flags::WIDE_CHAR_SPACER) => {
-                if let Some(Indexed { cell: new_cell, .. }) = iter.prev() {
-                    *cell = new_cell;
+            Direction::Left if flags.contains(Flags::WIDE_CHAR_SPACER) => {
+                if iter.prev() {
+                    (c, flags) = (iter.cell().c, iter.cell().flags);
                 }
 
                 let prev = iter.point().sub(self, Boundary::Grid, 1);
-                if self.grid[prev].flags.contains(Flags::LEADING_WIDE_CHAR_SPACER) {
+                let prev_flags = if prev.line == iter.point().line {
+                    iter.row[prev.column].flags
+                } else {
+                    self.grid.row(prev.line)[prev.column].flags
+                };
+                if prev_flags.contains(Flags::LEADING_WIDE_CHAR_SPACER) {
                     iter.prev();
                 }
             },
             _ => (),
         }
+
+        (c, flags)
     }
 
     /// Find next matching bracket.
@@ -609,6 +667,67 @@ impl<T> Term<T> {
     }
 }
 
+/// Cursor over all cells of the grid, reading one line at a time.
+///
+/// Unlike the [`GridIterator`](crate::grid::GridIterator), this does not keep rows of the compressed history inflated, so
+/// searching the entire history doesn't inflate all of it at once.
+struct CellCursor<'a> {
+    grid: &'a Grid<Cell>,
+    point: Point,
+    row: Cow<'a, Row<Cell>>,
+}
+
+impl<'a> CellCursor<'a> {
+    fn new(grid: &'a Grid<Cell>, point: Point) -> Self {
+        Self { grid, point, row: grid.row(point.line) }
+    }
+
+    /// Current cursor position.
+    fn point(&self) -> Point {
+        self.point
+    }
+
+    /// Cell at the current cursor position.
+    fn cell(&self) -> &Cell {
+        &self.row[self.point.column]
+    }
+
+    /// Move to the next cell, returning `false` at the end of the grid.
+    fn next(&mut self) -> bool {
+        let last_column = self.grid.last_column();
+        if self.point >= Point::new(self.grid.bottommost_line(), last_column) {
+            return false;
+        }
+
+        if self.point.column == last_column {
+            self.point.column = Column(0);
+            self.point.line += 1;
+            self.row = self.grid.row(self.point.line);
+        } else {
+            self.point.column += 1;
+        }
+
+        true
+    }
+
+    /// Move to the previous cell, returning `false` at the start of the grid.
+    fn prev(&mut self) -> bool {
+        if self.point == Point::new(self.grid.topmost_line(), Column(0)) {
+            return false;
+        }
+
+        if self.point.column == 0 {
+            self.point.column = self.grid.last_column();
+            self.point.line -= 1;
+            self.row = self.grid.row(self.point.line);
+        } else {
+            self.point.column -= 1;
+        }
+
+        true
+    }
+}
+
 /// Iterator over regex matches.
 pub struct RegexIter<'a, T> {
     point: Point,
@@ -1135,6 +1254,64 @@ mod tests {
         assert_eq!(term.regex_search_right(&mut regex, start, end), Some(start..=end));
     }
 
+    #[test]
+    fn case_sensitivity() {
+        let term = mock_term("Rust rust RUST");
+        let start = Point::new(Line(0), Column(0));
+        let end = Point::new(Line(0), Column(13));
+
+        let count = |search: &str, case_sensitivity| {
+            let options = SearchOptions { case_sensitivity, ..SearchOptions::default() };
+            let mut regex = RegexSearch::with_options(search, options).unwrap();
+            RegexIter::new(start, end, Direction::Right, &term, &mut regex).count()
+        };
+
+        assert_eq!(count("rust", CaseSensitivity::Smart), 3);
+        assert_eq!(count("Rust", CaseSensitivity::Smart), 1);
+        assert_eq!(count("rust", CaseSensitivity::Sensitive), 1);
+        assert_eq!(count("Rust", CaseSensitivity::Insensitive), 3);
+    }
+
+    #[test]
+    fn literal_search() {
+        let term = mock_term("a.c abc a.c");
+        let start = Point::new(Line(0), Column(0));
+        let end = Point::new(Line(0), Column(10));
+
+        let options = SearchOptions { literal: true, ..SearchOptions::default() };
+        let mut regex = RegexSearch::with_options("a.c", options).unwrap();
+        let matches: Vec<_> =
+            RegexIter::new(start, end, Direction::Right, &term, &mut regex).collect();
+        assert_eq!(matches, vec![
+            Point::new(Line(0), Column(0))..=Point::new(Line(0), Column(2)),
+            Point::new(Line(0), Column(8))..=Point::new(Line(0), Column(10)),
+        ]);
+
+        // Invalid regexes are valid literals.
+        assert!(RegexSearch::new("(").is_err());
+        assert!(RegexSearch::with_options("(", options).is_ok());
+    }
+
+    #[test]
+    fn whole_word_search() {
+        let term = mock_term("test testing test");
+        let start = Point::new(Line(0), Column(0));
+        let end = Point::new(Line(0), Column(16));
+
+        let options = SearchOptions { whole_word: true, ..SearchOptions::default() };
+        let mut regex = RegexSearch::with_options("test", options).unwrap();
+        let matches: Vec<_> =
+            RegexIter::new(start, end, Direction::Right, &term, &mut regex).collect();
+        assert_eq!(matches, vec![
+            Point::new(Line(0), Column(0))..=Point::new(Line(0), Column(3)),
+            Point::new(Line(0), Column(13))..=Point::new(Line(0), Column(16)),
+        ]);
+
+        // Search from the right.
+        let match_start = Point::new(Line(0), Column(13));
+        assert_eq!(term.regex_search_left(&mut regex, end, start), Some(match_start..=end));
+    }
+
     #[test]
     fn newline_breaking_semantic() {
         #[rustfmt::skip]
@@ -9,10 +9,10 @@ use std::path::Path;
 use larashell_terminal::event::{Event, EventListener};
 use larashell_terminal::grid::{Dimensions, Grid};
 use larashell_terminal::index::{Column, Line};
+use larashell_terminal::parser::Processor;
 use larashell_terminal::term::cell::Cell;
 use larashell_terminal::term::test::TermSize;
 use larashell_terminal::term::{Config, Term};
-use larashell_terminal::vte::ansi;
 
 macro_rules! ref_tests {
     ($($name:ident)*) => {
@@ -72,6 +72,13 @@ ref_tests! {
     zsh_tab_completion
     erase_in_line
     scroll_in_region_up_preserves_history
+    deccra
+    decfra
+    decera
+    decsera
+    deccara
+    deccara_stream
+    decdwl
 }
 
 fn read_u8<P>(path: P) -> Vec<u8>
@@ -110,7 +117,7 @@ fn ref_test(dir: &Path) {
         Config { scrolling_history: ref_config.history_size as usize, ..Default::default() };
 
     let mut terminal = Term::new(options, &size, Mock);
-    let mut parser: ansi::Processor = ansi::Processor::new();
+    let mut parser: Processor = Processor::new();
 
     for byte in recording {
         parser.advance(&mut terminal, byte);